@@ -4,15 +4,32 @@
 
 use super::ai_service::AIService;
 use super::codex_auth::CodexAuthState;
+use super::file_watcher;
+use super::lsp_commands::LspState;
+use super::workspace;
+use crate::diagnostics;
+use crate::error::{ErrorCode, VoidDeskError};
+use crate::git;
+use crate::lsp::LspManager;
+use crate::metrics;
+#[cfg(feature = "local_inference")]
+use crate::sdk::LocalModel;
 use crate::sdk::{
-    AgentEvent, AgentRunHandle, ErrorCategory, InlineImageAttachment, Message, SdkError,
+    Agent, AgentEvent, AgentRunHandle, ErrorCategory, FimFormat, FimProvider,
+    InlineImageAttachment, Message, SdkError,
 };
+use crate::secrets;
+use crate::settings;
 use anyhow::Error;
 use futures::StreamExt;
+use lsp_types::FileChangeType;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use tauri::{ipc::Channel, State};
+use std::time::Instant;
+use tauri::{ipc::Channel, Manager, State, WebviewWindow};
 use tokio::sync::{OnceCell, RwLock};
 
 const DEFAULT_CONTEXT_WINDOW_TOKENS: usize = 32_000;
@@ -22,7 +39,8 @@ static ACTIVE_RUNS: OnceCell<Arc<RwLock<ActiveRunRegistry>>> = OnceCell::const_n
 
 #[derive(Clone)]
 struct ActiveRunEntry {
-    session_id: String,
+    /// None for runs outside a chat, such as inline completions
+    session_id: Option<String>,
     handle: AgentRunHandle,
 }
 
@@ -49,6 +67,8 @@ pub struct ToolOperation {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AIResponseChunk {
+    /// The request this chunk belongs to, so overlapping streams can be told apart
+    pub request_id: String,
     pub content: Option<String>,
     pub tool_call: Option<String>,
     pub tool_operation: Option<ToolOperation>,
@@ -75,7 +95,7 @@ pub async fn test_ai_connection(
     base_url: String,
     model_id: String,
     codex_auth: State<'_, CodexAuthState>,
-) -> Result<String, String> {
+) -> Result<String, VoidDeskError> {
     let provider_type = provider_type
         .as_deref()
         .unwrap_or("openai_compatible")
@@ -84,11 +104,11 @@ pub async fn test_ai_connection(
     let model_id = model_id.trim();
 
     if provider_type != "codex_subscription" && api_key.is_empty() {
-        return Err("API key is required".to_string());
+        return Err(VoidDeskError::invalid_input("API key is required"));
     }
 
     if model_id.is_empty() {
-        return Err("Model ID is required".to_string());
+        return Err(VoidDeskError::invalid_input("Model ID is required"));
     }
 
     let agent = AIService::create_agent(
@@ -99,20 +119,41 @@ pub async fn test_ai_connection(
         None,
         Some(codex_auth.auth_path()),
     )
-    .map_err(|e| format!("Failed to create agent: {}", e))?;
+    .map_err(|e| VoidDeskError::from(e).context("Failed to create agent"))?;
 
     let result = agent
         .run("Say 'Connection Successful'".to_string(), Vec::new())
         .await
-        .map_err(|e| format!("Connection test failed: {}", e))?;
+        .map_err(|e| VoidDeskError::from(e).context("Connection test failed"))?;
 
     if result.text.is_empty() {
-        Err("No response from API".to_string())
+        Err(VoidDeskError::new(
+            ErrorCode::Provider,
+            "No response from API",
+        ))
     } else {
         Ok("Connection successful! API is responsive.".to_string())
     }
 }
 
+/// The key a window's conversation is kept under. The main window keeps the key every
+/// conversation used before they were per window, so its existing session carries over.
+fn session_owner(window: &str) -> &str {
+    if window == workspace::MAIN_WINDOW {
+        "default_user"
+    } else {
+        window
+    }
+}
+
+/// The id the caller chose for a request, or a new one
+fn resolve_request_id(request_id: Option<String>) -> String {
+    request_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Stream a chat turn; returns the request id, which every chunk also carries
 #[tauri::command]
 pub async fn ask_ai_stream(
     message: String,
@@ -128,29 +169,35 @@ pub async fn ask_ai_stream(
     on_event: Channel<AIResponseChunk>,
     service: State<'_, AIService>,
     codex_auth: State<'_, CodexAuthState>,
-) -> Result<(), String> {
+    window: WebviewWindow,
+) -> Result<String, VoidDeskError> {
+    let request_id = resolve_request_id(request_id);
+    // Each window keeps its own conversation
     let session_id = service
-        .get_or_create_session("default_user")
+        .get_or_create_session(session_owner(window.label()))
         .await
         .map_err(|e| format!("Failed to create session: {}", e))?;
 
     let req = StreamRequest {
         message,
         history_messages,
-        provider_type: provider_type.unwrap_or_else(|| "openai_compatible".to_string()),
+        provider_type: provider_type.unwrap_or_default(),
         api_key,
         base_url,
         model_id,
         context_window_tokens,
         active_path,
         debug_raw_stream,
-        request_id,
+        request_id: request_id.clone(),
         image_attachments: None,
         session_id,
         on_event,
         codex_auth_path: codex_auth.auth_path(),
+        lsp_manager: window.state::<LspState>().manager(window.label()),
+        window: window.label().to_string(),
     };
-    process_ai_stream(req, service.inner()).await
+    process_ai_stream(req, service.inner()).await?;
+    Ok(request_id)
 }
 
 fn total_inline_image_bytes(attachments: &[InlineImageAttachment]) -> usize {
@@ -165,8 +212,9 @@ fn total_inline_image_bytes(attachments: &[InlineImageAttachment]) -> usize {
         .sum()
 }
 
+/// Cancel a chat turn or inline completion by its request id; false if none is running
 #[tauri::command]
-pub async fn cancel_ai_stream(request_id: String) -> Result<bool, String> {
+pub async fn cancel_ai_stream(request_id: String) -> Result<bool, VoidDeskError> {
     if request_id.trim().is_empty() {
         return Ok(false);
     }
@@ -188,18 +236,202 @@ pub async fn cancel_ai_stream(request_id: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub async fn reset_ai_conversation(service: State<'_, AIService>) -> Result<(), String> {
-    service.reset_session("default_user").await;
+pub async fn reset_ai_conversation(
+    service: State<'_, AIService>,
+    window: WebviewWindow,
+) -> Result<(), VoidDeskError> {
+    service.reset_session(session_owner(window.label())).await;
     Ok(())
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InlineCompletionChunk {
+    pub request_id: String,
     pub text: String,
     pub done: bool,
     pub error: Option<String>,
 }
 
+/// Optional settings for one inline completion
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineCompletionOptions {
+    /// FIM format name, or "off"; see fim_format
+    pub fim: Option<String>,
+    /// Chunks carry this id and cancel_ai_stream takes it; a new one when None
+    pub request_id: Option<String>,
+}
+
+/// An inline completion in flight: tags its chunks with the request id and stops sending
+/// once cancel_ai_stream cancels it
+#[derive(Clone)]
+struct InlineRequest {
+    id: String,
+    handle: AgentRunHandle,
+    on_event: Channel<InlineCompletionChunk>,
+}
+
+impl InlineRequest {
+    /// Send a piece of the completion; false once cancelled or nobody is listening
+    fn send(&self, text: String) -> bool {
+        !self.handle.is_cancelled()
+            && self
+                .on_event
+                .send(InlineCompletionChunk {
+                    request_id: self.id.clone(),
+                    text,
+                    done: false,
+                    error: None,
+                })
+                .is_ok()
+    }
+
+    fn finish(&self, error: Option<String>) -> Result<(), String> {
+        self.on_event
+            .send(InlineCompletionChunk {
+                request_id: self.id.clone(),
+                text: String::new(),
+                done: true,
+                error,
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Code before the cursor sent for a fill-in-the-middle completion
+const FIM_PREFIX_MAX_BYTES: usize = 8_000;
+/// Code after the cursor sent for a fill-in-the-middle completion
+const FIM_SUFFIX_MAX_BYTES: usize = 2_000;
+const FIM_MAX_TOKENS: u32 = 128;
+
+/// The FIM format to use: `fim` names one, "off" turns FIM off, and by default the
+/// model id decides. Only OpenAI-compatible servers offer the completions endpoint; the
+/// local model always completes in FIM.
+fn fim_format(provider_type: &str, model_id: &str, fim: Option<&str>) -> Option<FimFormat> {
+    if !matches!(provider_type, "openai_compatible" | "local") {
+        return None;
+    }
+    match fim.map(str::trim).filter(|fim| !fim.is_empty()) {
+        None | Some("auto") => FimFormat::detect(model_id),
+        Some("off") => None,
+        Some(name) => serde_json::from_value(serde_json::Value::String(name.to_string())).ok(),
+    }
+}
+
+/// Keep the `max_bytes` of `text` nearest the cursor, cutting at a line break
+fn near_cursor(text: &str, max_bytes: usize, before_cursor: bool) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    if before_cursor {
+        let mut start = text.len() - max_bytes;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        let start = text[start..]
+            .find('\n')
+            .map_or(start, |newline| start + newline + 1);
+        &text[start..]
+    } else {
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let end = text[..end].rfind('\n').map_or(end, |newline| newline + 1);
+        &text[..end]
+    }
+}
+
+/// Stream a FIM completion; Err when the request could not start, so the caller can
+/// fall back to the chat prompt
+async fn stream_fim_completion(
+    provider: &FimProvider,
+    before: &str,
+    after: &str,
+    request: &InlineRequest,
+) -> Result<(), String> {
+    let prefix = near_cursor(before, FIM_PREFIX_MAX_BYTES, true);
+    let suffix = near_cursor(after, FIM_SUFFIX_MAX_BYTES, false);
+    let stream = provider
+        .stream(prefix, suffix, FIM_MAX_TOKENS)
+        .await
+        .map_err(|e| e.to_string())?;
+    futures::pin_mut!(stream);
+
+    let mut error = None;
+    while let Some(text) = stream.next().await {
+        match text {
+            Ok(text) => {
+                if !request.send(text) {
+                    break;
+                }
+            }
+            Err(err) => {
+                error = Some(format!("Stream error: {}", err));
+                break;
+            }
+        }
+    }
+    request.finish(error)
+}
+
+/// Complete with the GGUF model in the `ai.local_model_path` setting, run in-process
+#[cfg(feature = "local_inference")]
+async fn stream_local_completion(
+    file_path: &str,
+    fim: Option<&str>,
+    before: &str,
+    after: &str,
+    request: &InlineRequest,
+) -> Result<(), String> {
+    // Smaller budgets than a server gets: the model has a short context and runs on the CPU
+    const LOCAL_PREFIX_MAX_BYTES: usize = 4_000;
+    const LOCAL_SUFFIX_MAX_BYTES: usize = 1_000;
+
+    let model_path = settings::for_path(Some(file_path))
+        .ai
+        .local_model_path
+        .ok_or("Set ai.local_model_path to a GGUF model to use local completions")?;
+    let name = Path::new(&model_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (prompt, stop) = fim_format("local", &name, fim)
+        .and_then(|format| {
+            let prompt = format.prompt(
+                near_cursor(before, LOCAL_PREFIX_MAX_BYTES, true),
+                near_cursor(after, LOCAL_SUFFIX_MAX_BYTES, false),
+            )?;
+            Some((prompt, format.stop()))
+        })
+        .ok_or_else(|| format!("No fill-in-the-middle format is known for {}", name))?;
+
+    let request = request.clone();
+    tokio::task::spawn_blocking(move || {
+        let model = LocalModel::shared(Path::new(&model_path)).map_err(|e| e.to_string())?;
+        model
+            .complete(&prompt, stop, FIM_MAX_TOKENS as usize, |text| {
+                request.send(text.to_string())
+            })
+            .map_err(|e| e.to_string())?;
+        request.finish(None)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "local_inference"))]
+async fn stream_local_completion(
+    _file_path: &str,
+    _fim: Option<&str>,
+    _before: &str,
+    _after: &str,
+    _request: &InlineRequest,
+) -> Result<(), String> {
+    Err("Local completions need a build with the local_inference feature".to_string())
+}
+
+/// Stream an inline completion; returns the request id, which every chunk also carries
 #[tauri::command]
 pub async fn get_inline_completion(
     content: String,
@@ -210,9 +442,11 @@ pub async fn get_inline_completion(
     api_key: String,
     base_url: String,
     model_id: String,
+    options: Option<InlineCompletionOptions>,
     on_event: Channel<InlineCompletionChunk>,
     codex_auth: State<'_, CodexAuthState>,
-) -> Result<(), String> {
+) -> Result<String, VoidDeskError> {
+    let InlineCompletionOptions { fim, request_id } = options.unwrap_or_default();
     let provider_type = provider_type
         .as_deref()
         .unwrap_or("openai_compatible")
@@ -220,30 +454,56 @@ pub async fn get_inline_completion(
     let api_key = api_key.trim();
     let model_id = model_id.trim();
 
-    if provider_type != "codex_subscription" && api_key.is_empty() {
-        on_event
-            .send(InlineCompletionChunk {
-                text: String::new(),
-                done: true,
-                error: Some("API key is required".to_string()),
-            })
-            .map_err(|e| e.to_string())?;
-        return Ok(());
-    }
-
-    let before = if cursor_pos <= content.len() {
-        &content[..cursor_pos]
-    } else {
-        &content
-    };
-    let after = if cursor_pos < content.len() {
-        &content[cursor_pos..]
-    } else {
-        ""
+    let request = InlineRequest {
+        id: resolve_request_id(request_id),
+        handle: AgentRunHandle::default(),
+        on_event,
     };
+    register_request_run(&request.id, request.handle.clone()).await?;
+
+    let completed: Result<(), String> = async {
+        if !matches!(provider_type, "codex_subscription" | "local") && api_key.is_empty() {
+            request.finish(Some("API key is required".to_string()))?;
+            return Ok(());
+        }
+
+        let before = if cursor_pos <= content.len() {
+            &content[..cursor_pos]
+        } else {
+            &content
+        };
+        let after = if cursor_pos < content.len() {
+            &content[cursor_pos..]
+        } else {
+            ""
+        };
+
+        if provider_type == "local" {
+            let completed =
+                stream_local_completion(&file_path, fim.as_deref(), before, after, &request).await;
+            if let Err(error) = completed {
+                request.finish(Some(error))?;
+            }
+            return Ok(());
+        }
+
+        if let Some(format) = fim_format(provider_type, model_id, fim.as_deref()) {
+            let started = match FimProvider::new(api_key, &base_url, model_id, format) {
+                Ok(provider) => stream_fim_completion(&provider, before, after, &request).await,
+                Err(e) => Err(e.to_string()),
+            };
+            match started {
+                Ok(()) => return Ok(()),
+                Err(error) => tracing::warn!(
+                    "FIM completion with {} failed, using the chat prompt: {}",
+                    model_id,
+                    error
+                ),
+            }
+        }
 
-    let prompt = format!(
-        r#"You are an inline code completion assistant. Generate ONLY the code that should be inserted at the cursor position. Do not include explanations, markdown, or code blocks.
+        let prompt = format!(
+            r#"You are an inline code completion assistant. Generate ONLY the code that should be inserted at the cursor position. Do not include explanations, markdown, or code blocks.
 
 Language: {language}
 File: {file_path}
@@ -259,65 +519,160 @@ Code after cursor:
 ```
 
 Generate a short, contextually appropriate completion (1-3 lines max). Output ONLY the raw code to insert, nothing else."#,
-        language = language,
-        file_path = file_path,
-        before = before,
-        after = after
-    );
+            language = language,
+            file_path = file_path,
+            before = before,
+            after = after
+        );
 
-    let agent = AIService::create_agent(
-        provider_type,
-        api_key,
-        &base_url,
-        model_id,
-        None,
-        Some(codex_auth.auth_path()),
-    )
-    .map_err(|e| format!("Failed to create agent: {}", e))?
-    .with_max_iterations(1);
+        let agent = AIService::create_agent(
+            provider_type,
+            api_key,
+            &base_url,
+            model_id,
+            None,
+            Some(codex_auth.auth_path()),
+        )
+        .map_err(|e| format!("Failed to create agent: {}", e))?
+        .with_max_iterations(1);
 
-    let mut stream = agent
-        .run_streaming(prompt, Vec::new())
-        .await
-        .map_err(|e| format!("Failed to run agent: {}", e))?;
-
-    while let Some(event) = stream.next().await {
-        match event {
-            Ok(AgentEvent::TextDelta(text)) => {
-                if !text.is_empty() {
-                    on_event
-                        .send(InlineCompletionChunk {
-                            text,
-                            done: false,
-                            error: None,
-                        })
-                        .map_err(|e| e.to_string())?;
+        let mut stream = agent
+            .run_streaming(prompt, Vec::new())
+            .await
+            .map_err(|e| format!("Failed to run agent: {}", e))?;
+
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(AgentEvent::TextDelta(text)) => {
+                    if !text.is_empty() && !request.send(text) {
+                        break;
+                    }
+                }
+                Ok(AgentEvent::Done(_)) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    request.finish(Some(format!("Stream error: {}", err)))?;
+                    return Ok(());
                 }
-            }
-            Ok(AgentEvent::Done(_)) => break,
-            Ok(_) => {}
-            Err(err) => {
-                on_event
-                    .send(InlineCompletionChunk {
-                        text: String::new(),
-                        done: true,
-                        error: Some(format!("Stream error: {}", err)),
-                    })
-                    .map_err(|e| e.to_string())?;
-                return Ok(());
             }
         }
+
+        request.finish(None)?;
+        Ok(())
     }
+    .await;
+    cleanup_run(&request.id).await;
+    completed?;
+    Ok(request.id)
+}
 
-    on_event
-        .send(InlineCompletionChunk {
-            text: String::new(),
-            done: true,
-            error: None,
-        })
-        .map_err(|e| e.to_string())?;
+/// Staged diffs beyond this are cut; the file summary before the patch is always sent
+const COMMIT_DIFF_MAX_BYTES: usize = 24_000;
 
-    Ok(())
+const COMMIT_MESSAGE_PROMPT: &str = r#"You write git commit messages. You are given a summary of the staged files followed by the staged diff.
+
+Write a Conventional Commits message:
+- Subject line: `type(scope): summary`, where type is one of feat, fix, refactor, perf, docs, test, build, ci, style, chore. The scope is optional. Imperative mood, lowercase summary, no trailing period, at most 72 characters.
+- If the change needs more explanation, add a blank line and a short body wrapped at 72 characters explaining what changed and why.
+- Describe only what the diff shows. If the diff was truncated, base the message on the file summary as well.
+
+Output ONLY the commit message, without code fences, quotes or commentary."#;
+
+/// Model output as a commit message: no surrounding code fence or blank lines
+fn clean_commit_message(text: &str) -> String {
+    let text = text.trim();
+    let text = match text.strip_prefix("```") {
+        Some(fenced) => {
+            let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end().strip_suffix("```").unwrap_or(body)
+        }
+        None => text,
+    };
+    text.trim().to_string()
+}
+
+/// The provider and model a one-shot request goes to
+#[derive(Debug, Clone)]
+pub struct ModelChoice {
+    pub provider_type: Option<String>,
+    pub api_key: String,
+    pub base_url: String,
+    pub model_id: String,
+}
+
+/// Run one prompt through the chosen model, without tools or history, and return its text
+pub(crate) async fn complete_once(
+    model: &ModelChoice,
+    codex_auth: &CodexAuthState,
+    system_prompt: &str,
+    prompt: String,
+) -> Result<String, VoidDeskError> {
+    let provider_type = model
+        .provider_type
+        .as_deref()
+        .unwrap_or("openai_compatible")
+        .trim();
+    let api_key = model.api_key.trim();
+    if provider_type != "codex_subscription" && api_key.is_empty() {
+        return Err(VoidDeskError::invalid_input("API key is required"));
+    }
+
+    let provider = AIService::create_provider(
+        provider_type,
+        api_key,
+        &model.base_url,
+        model.model_id.trim(),
+        Some(codex_auth.auth_path()),
+    )
+    .map_err(|e| VoidDeskError::from(e).context("Failed to create provider"))?;
+    let agent = Agent::builder(provider)
+        .with_system_prompt(system_prompt.to_string())
+        .with_max_iterations(1)
+        .build();
+    let result = agent.run(prompt, Vec::new()).await?;
+    Ok(result.text)
+}
+
+/// The JSON object in a model's reply, which may be wrapped in a code fence or prose
+pub(crate) fn json_reply<T: DeserializeOwned>(output: &str) -> Option<T> {
+    let start = output.find('{')?;
+    let end = output.rfind('}')?;
+    serde_json::from_str(output.get(start..=end)?).ok()
+}
+
+/// Draft a commit message for what is staged in the repository containing `root`
+#[tauri::command]
+pub async fn generate_commit_message(
+    root: String,
+    provider_type: Option<String>,
+    api_key: String,
+    base_url: String,
+    model_id: String,
+    codex_auth: State<'_, CodexAuthState>,
+) -> Result<String, VoidDeskError> {
+    let model = ModelChoice {
+        provider_type,
+        api_key,
+        base_url,
+        model_id,
+    };
+
+    let diff = tokio::task::spawn_blocking(move || git::staged_patch(&root, COMMIT_DIFF_MAX_BYTES))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let text = complete_once(&model, &codex_auth, COMMIT_MESSAGE_PROMPT, diff)
+        .await
+        .map_err(|e| e.context("Failed to generate commit message"))?;
+
+    let message = clean_commit_message(&text);
+    if message.is_empty() {
+        return Err(VoidDeskError::new(
+            ErrorCode::Provider,
+            "The model returned an empty commit message",
+        ));
+    }
+    Ok(message)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -333,7 +688,7 @@ pub struct SessionMetadata {
 pub async fn create_chat_session(
     name: String,
     service: State<'_, AIService>,
-) -> Result<String, String> {
+) -> Result<String, VoidDeskError> {
     let session = service.session_store().create(None, Some(name)).await;
     Ok(session.id)
 }
@@ -341,7 +696,7 @@ pub async fn create_chat_session(
 #[tauri::command]
 pub async fn list_chat_sessions(
     service: State<'_, AIService>,
-) -> Result<Vec<SessionMetadata>, String> {
+) -> Result<Vec<SessionMetadata>, VoidDeskError> {
     let sessions = service.session_store().list().await;
     let metadata = sessions
         .into_iter()
@@ -360,7 +715,7 @@ pub async fn list_chat_sessions(
 pub async fn delete_chat_session(
     session_id: String,
     service: State<'_, AIService>,
-) -> Result<(), String> {
+) -> Result<(), VoidDeskError> {
     service.delete_session(&session_id).await;
     Ok(())
 }
@@ -370,7 +725,7 @@ pub async fn rename_chat_session(
     session_id: String,
     name: String,
     service: State<'_, AIService>,
-) -> Result<(), String> {
+) -> Result<(), VoidDeskError> {
     service
         .session_store()
         .set_name(&session_id, Some(name))
@@ -395,10 +750,12 @@ pub async fn ask_ai_stream_with_session(
     on_event: Channel<AIResponseChunk>,
     service: State<'_, AIService>,
     codex_auth: State<'_, CodexAuthState>,
-) -> Result<(), String> {
+    window: WebviewWindow,
+) -> Result<String, VoidDeskError> {
+    let request_id = resolve_request_id(request_id);
     let session_id = if session_id.trim().is_empty() {
         service
-            .get_or_create_session("default_user")
+            .get_or_create_session(session_owner(window.label()))
             .await
             .map_err(|e| format!("Failed to create session: {}", e))?
     } else {
@@ -411,20 +768,23 @@ pub async fn ask_ai_stream_with_session(
     let req = StreamRequest {
         message,
         history_messages,
-        provider_type: provider_type.unwrap_or_else(|| "openai_compatible".to_string()),
+        provider_type: provider_type.unwrap_or_default(),
         api_key,
         base_url,
         model_id,
         context_window_tokens,
         active_path,
         debug_raw_stream,
-        request_id,
+        request_id: request_id.clone(),
         image_attachments,
         session_id,
         on_event,
         codex_auth_path: codex_auth.auth_path(),
+        lsp_manager: window.state::<LspState>().manager(window.label()),
+        window: window.label().to_string(),
     };
-    process_ai_stream(req, service.inner()).await
+    process_ai_stream(req, service.inner()).await?;
+    Ok(request_id)
 }
 
 struct StreamRequest {
@@ -437,26 +797,52 @@ struct StreamRequest {
     context_window_tokens: Option<usize>,
     active_path: Option<String>,
     debug_raw_stream: Option<bool>,
-    request_id: Option<String>,
+    request_id: String,
     image_attachments: Option<Vec<InlineImageAttachment>>,
     session_id: String,
     on_event: Channel<AIResponseChunk>,
     codex_auth_path: std::path::PathBuf,
+    lsp_manager: Arc<LspManager>,
+    /// Label of the window that asked, whose active project is the default
+    window: String,
+}
+
+/// Fill in what the request left out: the project from the active workspace, the API key
+/// from the secrets, the rest from the AI settings of the project `active_path` belongs to
+fn apply_ai_settings(req: &mut StreamRequest) {
+    if req.active_path.is_none() {
+        req.active_path =
+            workspace::active_root_of(&req.window).map(|root| root.to_string_lossy().to_string());
+    }
+    if req.api_key.trim().is_empty() {
+        req.api_key = secrets::get(secrets::AI_API_KEY).unwrap_or_default();
+    }
+    let defaults = settings::for_path(req.active_path.as_deref()).ai;
+    if req.provider_type.trim().is_empty() {
+        req.provider_type = defaults
+            .provider_type
+            .unwrap_or_else(|| "openai_compatible".to_string());
+    }
+    if req.base_url.trim().is_empty() {
+        req.base_url = defaults.base_url.unwrap_or_default();
+    }
+    if req.model_id.trim().is_empty() {
+        req.model_id = defaults.model_id.unwrap_or_default();
+    }
+    req.context_window_tokens = req.context_window_tokens.or(defaults.context_window_tokens);
 }
 
-async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<(), String> {
+async fn process_ai_stream(mut req: StreamRequest, service: &AIService) -> Result<(), String> {
+    apply_ai_settings(&mut req);
     let provider_type = req.provider_type.trim();
     let api_key = req.api_key.trim();
     let model_id = req.model_id.trim();
-    let request_id = req
-        .request_id
-        .clone()
-        .filter(|v| !v.trim().is_empty())
-        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let request_id = req.request_id.clone();
 
     if provider_type != "codex_subscription" && api_key.is_empty() {
         send_error_chunk(
             &req.on_event,
+            &request_id,
             "API key is required".to_string(),
             "validation",
             None,
@@ -464,6 +850,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
         )?;
         return Ok(());
     }
+    diagnostics::record_ai_request(&request_id, provider_type, model_id, &req.base_url);
 
     let image_attachments_count = req
         .image_attachments
@@ -482,6 +869,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
             let detail_str = att.detail.as_deref().unwrap_or("none");
             send_debug_chunk(
                 &req.on_event,
+                &request_id,
                 format!(
                     "Image attachment {}: name={}, mimeType={}, dataUrl_len={}, detail={}, sourceBytes={:?}, optimizedBytes={:?}",
                     i,
@@ -499,6 +887,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 
     send_debug_chunk(
         &req.on_event,
+        &request_id,
         format!(
             "Request {} received: model={}, message_chars={}, override_history={}, image_attachments={}, image_bytes={}, raw_stream={}",
             request_id,
@@ -524,6 +913,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
         Err(err) => {
             send_error_chunk(
                 &req.on_event,
+                &request_id,
                 format!("Failed to create agent: {}", err),
                 "internal",
                 None,
@@ -539,6 +929,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 
     send_debug_chunk(
         &req.on_event,
+        &request_id,
         format!(
             "Agent created for request {}. active_path={:?}, model_context_window={:?}, effective_context_window={}",
             request_id,
@@ -571,6 +962,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 
     send_debug_chunk(
         &req.on_event,
+        &request_id,
         format!(
             "History prepared for request {}: stored={}, trimmed_for_run={}, session_id={}, source={}",
             request_id,
@@ -585,6 +977,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
     if let Some(existing_request_id) = active_request_for_session(&req.session_id).await {
         send_error_chunk(
             &req.on_event,
+            &request_id,
             format!(
                 "Session {} already has an active run ({})",
                 req.session_id, existing_request_id
@@ -607,6 +1000,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
             let err_type = classify_error(&err);
             send_error_chunk(
                 &req.on_event,
+                &request_id,
                 format!("Failed to run agent: {}", err),
                 err_type,
                 sdk_error_status(&err),
@@ -621,6 +1015,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
     {
         send_error_chunk(
             &req.on_event,
+            &request_id,
             format!(
                 "Session {} already has an active run ({})",
                 req.session_id, existing_request_id
@@ -634,18 +1029,33 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 
     send_debug_chunk(
         &req.on_event,
+        &request_id,
         format!("Request {} registered as active run", request_id),
         "backend",
     )?;
 
+    // Multi-file edits are reported once the run ends rather than file by file
+    let _watcher_pause = file_watcher::pause_all_watchers();
+
+    let started = Instant::now();
+    let mut awaiting_first_token = true;
     let stream_result: Result<bool, String> = async {
         let mut completed_normally = false;
         while let Some(event) = stream.next().await {
             match event {
                 Ok(AgentEvent::TextDelta(text)) => {
                     if !text.is_empty() {
+                        if awaiting_first_token {
+                            awaiting_first_token = false;
+                            metrics::record_duration(
+                                "ai.first_token_ms",
+                                Some(provider_type),
+                                started.elapsed(),
+                            );
+                        }
                         req.on_event
                             .send(AIResponseChunk {
+                                request_id: request_id.clone(),
                                 content: Some(text),
                                 tool_call: None,
                                 tool_operation: None,
@@ -664,6 +1074,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                 Ok(AgentEvent::ReasoningDelta(reasoning)) => {
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: None,
                             tool_operation: None,
@@ -681,6 +1092,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                 Ok(AgentEvent::UsageDelta(usage)) => {
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: None,
                             tool_operation: None,
@@ -702,6 +1114,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                     let (operation, target) = map_tool_operation(&event.name, &event.input);
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: Some(format!("Calling tool: {}", event.name)),
                             tool_operation: Some(ToolOperation {
@@ -730,14 +1143,40 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                         "streaming_edit_file" => "Edited",
                         "list_directory" => "Listed",
                         "run_command" => "Executed",
+                        "recent_terminal_commands" => "Read",
+                        "get_editor_context" => "Read",
+                        "list_listening_ports" => "Listed",
                         _ => "Completed",
                     };
 
                     let target = extract_target_from_result(&event.result);
                     let details = extract_diff_from_result(&event.result);
 
+                    if event.success
+                        && !target.is_empty()
+                        && matches!(
+                            event.name.as_str(),
+                            "write_file" | "edit_file" | "streaming_edit_file"
+                        )
+                    {
+                        let path = match req.active_path.as_deref() {
+                            Some(root) if Path::new(&target).is_relative() => {
+                                Path::new(root).join(&target).to_string_lossy().to_string()
+                            }
+                            _ => target.clone(),
+                        };
+                        if let Err(e) = req
+                            .lsp_manager
+                            .did_change_watched_files(vec![(path, FileChangeType::CHANGED)])
+                            .await
+                        {
+                            tracing::warn!("Failed to notify LSP about tool edit: {}", e);
+                        }
+                    }
+
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: Some(format!("Tool {} returned", event.name)),
                             tool_operation: Some(ToolOperation {
@@ -760,6 +1199,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                 Ok(AgentEvent::Debug(event)) => {
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: None,
                             tool_operation: None,
@@ -782,6 +1222,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                         .await;
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: None,
                             tool_operation: None,
@@ -806,6 +1247,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                         .await;
                     send_debug_chunk(
                         &req.on_event,
+                        &request_id,
                         format!(
                             "Request {} completed and session {} was updated with {} retained messages",
                             request_id, req.session_id, retained_count
@@ -820,11 +1262,13 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
                     let error_message = format!("Stream error: {}", err);
                     send_debug_chunk(
                         &req.on_event,
+                        &request_id,
                         format!("Request {} terminated with {} error", request_id, err_type),
                         "error",
                     )?;
                     req.on_event
                         .send(AIResponseChunk {
+                            request_id: request_id.clone(),
                             content: None,
                             tool_call: None,
                             tool_operation: None,
@@ -848,11 +1292,13 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
     .await;
 
     cleanup_run(&request_id).await;
+    metrics::record_duration("ai.request_ms", Some(provider_type), started.elapsed());
 
     let completed_normally = stream_result?;
 
     send_debug_chunk(
         &req.on_event,
+        &request_id,
         format!(
             "Request {} cleaned up; completed_normally={}",
             request_id, completed_normally
@@ -866,6 +1312,7 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 
     req.on_event
         .send(AIResponseChunk {
+            request_id: request_id.clone(),
             content: None,
             tool_call: None,
             tool_operation: None,
@@ -890,18 +1337,46 @@ async fn process_ai_stream(req: StreamRequest, service: &AIService) -> Result<()
 async fn cleanup_run(request_id: &str) {
     let runs = active_runs().await;
     let mut registry = runs.write().await;
-    if let Some(entry) = registry.request_runs.remove(request_id) {
+    if let Some(session_id) = registry
+        .request_runs
+        .remove(request_id)
+        .and_then(|entry| entry.session_id)
+    {
         let should_remove_session = registry
             .session_runs
-            .get(&entry.session_id)
+            .get(&session_id)
             .map(|active_request_id| active_request_id == request_id)
             .unwrap_or(false);
         if should_remove_session {
-            registry.session_runs.remove(&entry.session_id);
+            registry.session_runs.remove(&session_id);
         }
     }
 }
 
+/// Track a run outside any chat session so cancel_ai_stream can stop it. A request id
+/// that is already running is rejected, as replacing it would leave that run uncancellable.
+async fn register_request_run(
+    request_id: &str,
+    handle: AgentRunHandle,
+) -> Result<(), VoidDeskError> {
+    let runs = active_runs().await;
+    let mut registry = runs.write().await;
+    if registry.request_runs.contains_key(request_id) {
+        return Err(VoidDeskError::new(
+            ErrorCode::Conflict,
+            format!("Request {} is already running", request_id),
+        ));
+    }
+    registry.request_runs.insert(
+        request_id.to_string(),
+        ActiveRunEntry {
+            session_id: None,
+            handle,
+        },
+    );
+    Ok(())
+}
+
 async fn register_active_run(
     request_id: &str,
     session_id: &str,
@@ -912,11 +1387,14 @@ async fn register_active_run(
     if let Some(existing_request_id) = registry.session_runs.get(session_id).cloned() {
         return Ok(Some(existing_request_id));
     }
+    if registry.request_runs.contains_key(request_id) {
+        return Err(format!("Request {} is already running", request_id));
+    }
 
     registry.request_runs.insert(
         request_id.to_string(),
         ActiveRunEntry {
-            session_id: session_id.to_string(),
+            session_id: Some(session_id.to_string()),
             handle,
         },
     );
@@ -934,6 +1412,7 @@ async fn active_request_for_session(session_id: &str) -> Option<String> {
 
 fn send_error_chunk(
     on_event: &Channel<AIResponseChunk>,
+    request_id: &str,
     message: String,
     error_type: &str,
     error_status: Option<u16>,
@@ -941,6 +1420,7 @@ fn send_error_chunk(
 ) -> Result<(), String> {
     on_event
         .send(AIResponseChunk {
+            request_id: request_id.to_string(),
             content: None,
             tool_call: None,
             tool_operation: None,
@@ -958,11 +1438,13 @@ fn send_error_chunk(
 
 fn send_debug_chunk(
     on_event: &Channel<AIResponseChunk>,
+    request_id: &str,
     message: String,
     debug_type: &str,
 ) -> Result<(), String> {
     on_event
         .send(AIResponseChunk {
+            request_id: request_id.to_string(),
             content: None,
             tool_call: None,
             tool_operation: None,
@@ -1062,6 +1544,15 @@ fn map_tool_operation(name: &str, input: &serde_json::Value) -> (String, String)
                 .unwrap_or("unknown")
                 .to_string(),
         ),
+        "recent_terminal_commands" => ("Checking".to_string(), "terminal history".to_string()),
+        "get_editor_context" => ("Checking".to_string(), "the editor".to_string()),
+        "list_listening_ports" => (
+            "Checking".to_string(),
+            match input.get("port").and_then(|v| v.as_u64()) {
+                Some(port) => format!("port {}", port),
+                None => "listening ports".to_string(),
+            },
+        ),
         _ => ("Calling".to_string(), name.to_string()),
     }
 }
@@ -1181,10 +1672,52 @@ fn estimate_message_tokens(message: &Message) -> usize {
 #[cfg(test)]
 mod tests {
     use super::{
-        resolve_effective_context_window, resolve_request_history, trim_history_to_context_window,
-        ConversationHistoryMessage,
+        cancel_ai_stream, clean_commit_message, cleanup_run, fim_format, near_cursor,
+        register_request_run, resolve_effective_context_window, resolve_request_history,
+        resolve_request_id, trim_history_to_context_window, ConversationHistoryMessage,
     };
-    use crate::sdk::Message;
+    use crate::error::ErrorCode;
+    use crate::sdk::{AgentRunHandle, FimFormat, Message};
+
+    #[test]
+    fn runs_outside_a_session_are_cancelled_by_request_id() {
+        assert_eq!(resolve_request_id(Some("inline-1".to_string())), "inline-1");
+        assert!(!resolve_request_id(Some(" ".to_string())).trim().is_empty());
+
+        futures::executor::block_on(async {
+            let handle = AgentRunHandle::default();
+            register_request_run("inline-1", handle.clone())
+                .await
+                .unwrap();
+            let duplicate = register_request_run("inline-1", AgentRunHandle::default()).await;
+            assert_eq!(duplicate.unwrap_err().code, ErrorCode::Conflict);
+            assert!(cancel_ai_stream("inline-1".to_string()).await.unwrap());
+            assert!(handle.is_cancelled());
+
+            cleanup_run("inline-1").await;
+            assert!(!cancel_ai_stream("inline-1".to_string()).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn fim_uses_the_nearest_whole_lines() {
+        let model = "deepseek-coder-6.7b-base";
+        assert_eq!(
+            fim_format("openai_compatible", model, None),
+            Some(FimFormat::DeepSeek)
+        );
+        assert_eq!(fim_format("openai_compatible", model, Some("off")), None);
+        assert_eq!(fim_format("codex_subscription", model, None), None);
+        assert_eq!(
+            fim_format("openai_compatible", "gpt-4o", Some("qwen")),
+            Some(FimFormat::Qwen)
+        );
+
+        let code = "one\ntwo\nthree\n";
+        assert_eq!(near_cursor(code, 9, true), "three\n");
+        assert_eq!(near_cursor(code, 9, false), "one\ntwo\n");
+        assert_eq!(near_cursor(code, 64, true), code);
+    }
 
     #[test]
     fn provided_history_rehydrates_session_requests() {
@@ -1243,4 +1776,13 @@ mod tests {
             8_000
         );
     }
+
+    #[test]
+    fn commit_message_drops_code_fences() {
+        assert_eq!(
+            clean_commit_message("```text\nfeat(git): add log\n\nBody line\n```\n"),
+            "feat(git): add log\n\nBody line"
+        );
+        assert_eq!(clean_commit_message("  fix: typo \n"), "fix: typo");
+    }
 }
@@ -0,0 +1,229 @@
+// Diagnostic explanations
+// The Problems panel's "Explain" action: the model gets a compiler or language server
+// diagnostic with the code around it and explains it in plain words, suggesting a fix
+// when it sees one. The fix is a replacement of a snippet of the file, located in the
+// file so the editor can preview and apply it; nothing is written here.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::ai_commands::{complete_once, json_reply, ModelChoice};
+use super::codex_auth::CodexAuthState;
+use super::workspace_roots;
+use crate::diff::{self, TextDiff};
+use crate::error::{ErrorCode, VoidDeskError};
+use crate::lsp::manager::{LspDiagnostic, LspPosition, LspRange};
+
+/// Lines of the file shown before and after the diagnostic when the caller sends no code
+const CONTEXT_LINES: u32 = 15;
+
+const EXPLAIN_DIAGNOSTIC_PROMPT: &str = r#"You explain compiler and linter diagnostics to a developer. You are given the diagnostic and the code around it.
+
+Reply with a single JSON object and nothing else:
+{"explanation": "...", "fix": {"description": "...", "old_text": "...", "new_text": "..."}}
+- `explanation`: what the diagnostic means and why this code triggers it, in a few sentences of Markdown.
+- `fix`: the smallest change that resolves it, or null when you cannot tell from the code shown. `old_text` is copied exactly from the code shown, whole lines with their indentation, and `new_text` replaces it. `description` says what the fix does in one sentence."#;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticFix {
+    pub description: String,
+    pub old_text: String,
+    pub new_text: String,
+    /// Where `old_text` is in the file as saved; None when it could not be found there
+    pub range: Option<LspRange>,
+    /// `new_text` against `old_text`
+    pub diff: TextDiff,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticExplanation {
+    /// Markdown
+    pub explanation: String,
+    pub fix: Option<DiagnosticFix>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelFix {
+    #[serde(default)]
+    description: String,
+    old_text: String,
+    new_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelReply {
+    explanation: String,
+    #[serde(default)]
+    fix: Option<ModelFix>,
+}
+
+fn severity_name(severity: Option<u32>) -> &'static str {
+    match severity {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "information",
+        Some(4) => "hint",
+        _ => "problem",
+    }
+}
+
+/// The lines of `text` from CONTEXT_LINES before `range` to CONTEXT_LINES after it, and
+/// the 1-based number of the first
+fn lines_around(text: &str, range: &LspRange) -> (u32, String) {
+    let first = range.start.line.saturating_sub(CONTEXT_LINES);
+    let last = range.end.line.saturating_add(CONTEXT_LINES);
+    let lines: Vec<&str> = text
+        .lines()
+        .skip(first as usize)
+        .take((last - first + 1) as usize)
+        .collect();
+    (first + 1, lines.join("\n"))
+}
+
+/// The 0-based line and UTF-16 column of byte `offset` in `text`
+fn lsp_position(text: &str, offset: usize) -> LspPosition {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    LspPosition {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Where `snippet` occurs in `text`, taking the occurrence nearest to `line` when there
+/// are several
+fn locate(text: &str, snippet: &str, line: u32) -> Option<LspRange> {
+    if snippet.is_empty() {
+        return None;
+    }
+    let start = text
+        .match_indices(snippet)
+        .map(|(offset, _)| offset)
+        .min_by_key(|offset| lsp_position(text, *offset).line.abs_diff(line))?;
+    Some(LspRange {
+        start: lsp_position(text, start),
+        end: lsp_position(text, start + snippet.len()),
+    })
+}
+
+fn explain_prompt(path: &str, diagnostic: &LspDiagnostic, code: &str) -> String {
+    let mut prompt = format!(
+        "File: {}\n{} at line {}, column {}",
+        path,
+        severity_name(diagnostic.severity),
+        diagnostic.range.start.line + 1,
+        diagnostic.range.start.character + 1
+    );
+    if let Some(source) = &diagnostic.source {
+        prompt.push_str(&format!(" from {}", source));
+    }
+    if let Some(code) = &diagnostic.code {
+        prompt.push_str(&format!(" ({})", code));
+    }
+    prompt.push_str(&format!(
+        ":\n{}\n\nCode:\n```\n{}\n```",
+        diagnostic.message, code
+    ));
+    prompt
+}
+
+/// Explain `diagnostic` in `path` and suggest a fix. `surrounding_code` is the code to
+/// show the model, such as the editor's unsaved buffer around it; without it the lines
+/// around the diagnostic are read from the file.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn explain_diagnostic(
+    path: String,
+    diagnostic: LspDiagnostic,
+    surrounding_code: Option<String>,
+    provider_type: Option<String>,
+    api_key: String,
+    base_url: String,
+    model_id: String,
+    codex_auth: State<'_, CodexAuthState>,
+) -> Result<DiagnosticExplanation, VoidDeskError> {
+    let model = ModelChoice {
+        provider_type,
+        api_key,
+        base_url,
+        model_id,
+    };
+    workspace_roots::ensure_in_workspace(&path)?;
+    let (file_text, code) = match (tokio::fs::read_to_string(&path).await, surrounding_code) {
+        (Ok(text), Some(code)) => (Some(text), code),
+        (Ok(text), None) => {
+            let (first_line, lines) = lines_around(&text, &diagnostic.range);
+            let code = format!("(starting at line {})\n{}", first_line, lines);
+            (Some(text), code)
+        }
+        // The caller's code is enough to explain a file that cannot be read
+        (Err(_), Some(code)) => (None, code),
+        (Err(e), None) => {
+            return Err(VoidDeskError::from(e).context(format!("Failed to read {}", path)))
+        }
+    };
+
+    let prompt = explain_prompt(&path, &diagnostic, &code);
+    let output = complete_once(&model, &codex_auth, EXPLAIN_DIAGNOSTIC_PROMPT, prompt)
+        .await
+        .map_err(|e| e.context("Failed to explain the diagnostic"))?;
+    let reply = json_reply::<ModelReply>(&output).ok_or_else(|| {
+        VoidDeskError::new(
+            ErrorCode::Provider,
+            "The model did not return an explanation",
+        )
+    })?;
+
+    let fix = match reply.fix {
+        Some(fix) if fix.old_text != fix.new_text => Some(DiagnosticFix {
+            range: file_text
+                .as_deref()
+                .and_then(|text| locate(text, &fix.old_text, diagnostic.range.start.line)),
+            diff: diff::diff_texts(&fix.old_text, &fix.new_text)?,
+            description: fix.description,
+            old_text: fix.old_text,
+            new_text: fix.new_text,
+        }),
+        _ => None,
+    };
+    Ok(DiagnosticExplanation {
+        explanation: reply.explanation.trim().to_string(),
+        fix,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start_line: u32, end_line: u32) -> LspRange {
+        LspRange {
+            start: LspPosition {
+                line: start_line,
+                character: 0,
+            },
+            end: LspPosition {
+                line: end_line,
+                character: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn shows_nearby_lines_and_locates_the_fix_closest_to_the_problem() {
+        let text: String = (0..40)
+            .map(|n| format!("let v{} = \"é\"; x();\n", n % 20))
+            .collect();
+
+        let (first_line, lines) = lines_around(&text, &range(20, 21));
+        assert_eq!(first_line, 6);
+        assert_eq!(lines.lines().count(), 32);
+        assert!(lines.starts_with("let v5 "));
+
+        // "let v3" is on lines 3 and 23; the diagnostic on line 21 picks the second
+        let found = locate(&text, "x();\nlet v3", 21).unwrap();
+        assert_eq!((found.start.line, found.start.character), (22, 14));
+        assert_eq!((found.end.line, found.end.character), (23, 6));
+        assert!(locate(&text, "missing", 21).is_none());
+    }
+}
@@ -0,0 +1,235 @@
+// Selection edits
+// The inline "Cmd+K" edit: the model sees the selected code, a few lines on each side and
+// the user's instruction, and writes a replacement for the selection alone. Nothing is
+// written here; the editor previews the diff and applies the replacement to its buffer.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+
+use super::ai_commands::{complete_once, ModelChoice};
+use super::codex_auth::CodexAuthState;
+use super::editor_context::EditorPosition;
+use super::workspace_roots;
+use crate::diff::{self, TextDiff};
+use crate::error::{ErrorCode, VoidDeskError};
+
+/// Lines of the file shown to the model before and after the selection
+const CONTEXT_LINES: usize = 20;
+
+/// Larger selections belong in the chat, where the agent can read the file itself
+const MAX_SELECTION_BYTES: usize = 32_000;
+
+const EDIT_SELECTION_PROMPT: &str = r#"You edit a selected region of a source file. You are given the file name, an instruction, the code right before the selection, the selection itself and the code right after it.
+
+Rewrite the selection as the instruction asks. When the selection is empty, write the code to insert at that point.
+- Output ONLY the code that replaces the selection, without code fences, quotes or commentary.
+- Do not repeat the code before or after the selection.
+- Keep the indentation and style of the surrounding code."#;
+
+/// The selected text, as 1-based positions whose columns count UTF-16 code units, the
+/// way the editor reports them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SelectionRange {
+    pub start: EditorPosition,
+    pub end: EditorPosition,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionEdit {
+    pub range: SelectionRange,
+    pub original: String,
+    pub replacement: String,
+    /// The file with the replacement applied, against the file as it was
+    pub diff: TextDiff,
+    /// `diff` as a unified diff, for display
+    pub patch: String,
+}
+
+/// Byte offset of `position` in `text`, clamped to the end of its line
+fn offset_of(text: &str, position: EditorPosition) -> Option<usize> {
+    let line_index = (position.line as usize).checked_sub(1)?;
+    let line_start = if line_index == 0 {
+        0
+    } else {
+        text.match_indices('\n').nth(line_index - 1)?.0 + 1
+    };
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let column = (position.column as usize).saturating_sub(1);
+    let mut units = 0;
+    for (index, ch) in line.char_indices() {
+        if units >= column {
+            return Some(line_start + index);
+        }
+        units += ch.len_utf16();
+    }
+    Some(line_start + line.len())
+}
+
+/// Up to CONTEXT_LINES whole lines before `start` and after `end`, including the rest of
+/// the lines the selection starts and ends in
+fn surrounding(text: &str, start: usize, end: usize) -> (&str, &str) {
+    let line_start = text[..start].rfind('\n').map_or(0, |index| index + 1);
+    let before_start = text[..line_start]
+        .match_indices('\n')
+        .rev()
+        .nth(CONTEXT_LINES)
+        .map_or(0, |(index, _)| index + 1);
+    let line_end = text[end..]
+        .find('\n')
+        .map_or(text.len(), |index| end + index);
+    let after_end = text[line_end..]
+        .match_indices('\n')
+        .nth(CONTEXT_LINES)
+        .map_or(text.len(), |(index, _)| line_end + index);
+    (&text[before_start..start], &text[end..after_end])
+}
+
+/// Model output as a replacement for `original`: no surrounding code fence, and ending
+/// in a newline exactly when the original did
+fn clean_replacement(output: &str, original: &str) -> String {
+    let trimmed = output.trim_matches('\n');
+    let text = match trimmed.strip_prefix("```") {
+        Some(fenced) if trimmed.trim_end().ends_with("```") => {
+            let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end()
+                .strip_suffix("```")
+                .unwrap_or(body)
+                .trim_matches('\n')
+        }
+        _ => trimmed,
+    };
+    let mut text = text.trim_end().to_string();
+    if original.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+fn edit_prompt(
+    path: &str,
+    instruction: &str,
+    before: &str,
+    selection: &str,
+    after: &str,
+) -> String {
+    format!(
+        "File: {}\nInstruction: {}\n\n<before>\n{}</before>\n<selection>\n{}</selection>\n<after>\n{}\n</after>",
+        path, instruction, before, selection, after
+    )
+}
+
+/// Ask the model to rewrite the `range` of `path` as `instruction` says. `content` is the
+/// editor's buffer, with any unsaved edits; the file is read from disk without it.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn ai_edit_selection(
+    path: String,
+    range: SelectionRange,
+    instruction: String,
+    content: Option<String>,
+    provider_type: Option<String>,
+    api_key: String,
+    base_url: String,
+    model_id: String,
+    codex_auth: State<'_, CodexAuthState>,
+) -> Result<SelectionEdit, VoidDeskError> {
+    let model = ModelChoice {
+        provider_type,
+        api_key,
+        base_url,
+        model_id,
+    };
+    let instruction = instruction.trim();
+    if instruction.is_empty() {
+        return Err(VoidDeskError::invalid_input("Describe the edit to make"));
+    }
+
+    let text = match content {
+        Some(content) => content,
+        None => {
+            workspace_roots::ensure_in_workspace(&path)?;
+            tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| VoidDeskError::from(e).context(format!("Failed to read {}", path)))?
+        }
+    };
+    let (start, end) = match (offset_of(&text, range.start), offset_of(&text, range.end)) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => {
+            return Err(VoidDeskError::invalid_input(
+                "The selection is outside the file",
+            ))
+        }
+    };
+    let original = &text[start..end];
+    if original.len() > MAX_SELECTION_BYTES {
+        return Err(VoidDeskError::invalid_input(
+            "The selection is too large for an inline edit; ask in the chat instead",
+        ));
+    }
+    let (before, after) = surrounding(&text, start, end);
+    let file_name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+
+    let prompt = edit_prompt(&file_name, instruction, before, original, after);
+    let output = complete_once(&model, &codex_auth, EDIT_SELECTION_PROMPT, prompt)
+        .await
+        .map_err(|e| e.context("Failed to edit the selection"))?;
+
+    let replacement = clean_replacement(&output, original);
+    if replacement.trim().is_empty() && !original.trim().is_empty() {
+        return Err(VoidDeskError::new(
+            ErrorCode::Provider,
+            "The model returned an empty replacement",
+        ));
+    }
+    let updated = format!("{}{}{}", &text[..start], replacement, &text[end..]);
+    let diff = diff::diff_texts(&text, &updated)?;
+    let patch = diff.unified(&format!("a/{}", file_name), &format!("b/{}", file_name));
+    Ok(SelectionEdit {
+        range,
+        original: original.to_string(),
+        replacement,
+        diff,
+        patch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_selection_and_cleans_the_replacement() {
+        let text: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+        let text = text.replace("line 25", "lïne 25");
+        let at = |line, column| offset_of(&text, EditorPosition { line, column }).unwrap();
+
+        let (start, end) = (at(25, 2), at(26, 1));
+        assert_eq!(&text[start..end], "ïne 25\n");
+        assert_eq!(at(25, 99), at(26, 1) - 1);
+        assert!(offset_of(
+            &text,
+            EditorPosition {
+                line: 40,
+                column: 1
+            }
+        )
+        .is_none());
+
+        let (before, after) = surrounding(&text, start, end);
+        assert!(before.starts_with("line 5\n"));
+        assert!(before.ends_with("line 24\nl"));
+        assert_eq!(after, "line 26\nline 27\nline 28\nline 29\nline 30\n");
+
+        assert_eq!(
+            clean_replacement("```rust\nfn a() {}\n```\n", "fn b() {}\n"),
+            "fn a() {}\n"
+        );
+        assert_eq!(clean_replacement("    x + 1\n\n", "    x"), "    x + 1");
+    }
+}
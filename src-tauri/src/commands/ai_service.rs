@@ -14,6 +14,23 @@ use crate::sdk::provider::{
     Provider,
 };
 use crate::sdk::{Agent, SessionStore, ToolPolicy};
+use crate::settings;
+
+/// The base prompt followed by the project's own rules from its settings
+fn with_project_rules(prompt: &str, rules: &[String]) -> String {
+    if rules.is_empty() {
+        return prompt.to_string();
+    }
+    let rules = rules
+        .iter()
+        .map(|rule| format!("- {}", rule.trim()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "{}\n\n## PROJECT RULES\n\nThe project asks you to follow these rules:\n{}",
+        prompt, rules
+    )
+}
 
 pub struct AgentBuild {
     pub agent: Agent,
@@ -97,9 +114,10 @@ impl AIService {
         let provider =
             Self::create_provider(provider_type, api_key, base_url, model_id, codex_auth_path)?;
         let model_info = provider.model_info();
+        let project_ai = settings::for_path(active_path).ai;
 
         let mut agent_builder = Agent::builder(provider)
-            .with_system_prompt(
+            .with_system_prompt(with_project_rules(
                 r#"You are VoiDesk, a powerful autonomous AI coding assistant embedded in a professional IDE. You pair-program with the user, taking real actions on their codebase through tools. You do not just describe — you do.
 
 ## AUTONOMOUS AGENT RULES
@@ -162,6 +180,24 @@ Execute a shell command in the project root directory.
 
 Use for: builds, tests, installs, git operations, linting, type-checking.
 
+### `recent_terminal_commands`
+List commands the user recently ran in the IDE terminal for this project, newest first, with their exit codes.
+- `query` (string, optional): only return commands containing this text
+- `limit` (integer, optional): maximum number of commands (default 20)
+
+Use to see how the user builds, runs and tests the project before guessing.
+
+### `get_editor_context`
+Shows what the user has open in the editor: the active file, cursor, selected text, the lines around the cursor and the other open files. Takes no arguments. Its text includes edits the user has not saved yet.
+
+Use first when the user says "this function", "the selected code" or "this file" without pasting it.
+
+### `list_listening_ports`
+List TCP ports with a server listening on them and the process holding each.
+- `port` (integer, optional): only report this port, and whether it is free
+
+Use before starting a server, or when one fails with "address already in use".
+
 ## MANDATORY WORKFLOW
 
 **Before touching any file:**
@@ -231,9 +267,9 @@ For tasks requiring many tool calls or multiple steps:
 - Do not nest bullets or create deep hierarchies.
 - Do not show full file contents in the final message — reference the path instead.
 - Do not tell the user to "save the file" — changes are already applied.
-- If there is a logical next step you could help with, ask concisely at the end."#
-                    .to_string(),
-            );
+- If there is a logical next step you could help with, ask concisely at the end."#,
+                &project_ai.rules,
+            ));
 
         let command_allowlist = std::env::var("VOIDESK_COMMAND_ALLOWLIST")
             .ok()
@@ -244,7 +280,8 @@ For tasks requiring many tool calls or multiple steps:
                     .filter(|v| !v.is_empty())
                     .collect::<Vec<_>>()
             })
-            .filter(|v| !v.is_empty());
+            .filter(|v| !v.is_empty())
+            .or(project_ai.command_allowlist);
         let allow_command_tool = std::env::var("VOIDESK_ALLOW_COMMAND_TOOL")
             .ok()
             .map(|value| value.eq_ignore_ascii_case("true"))
@@ -0,0 +1,293 @@
+// Terminal commands from plain language
+// The terminal's "ask AI" input: the model proposes one shell command for what the user
+// describes, with an explanation. Nothing runs here; the terminal shows the proposal and
+// the user decides. How dangerous a command is comes from the model and from our own
+// patterns, whichever is worse, so a model that misjudges a `rm -rf` cannot hide it.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::ai_commands::{complete_once, json_reply, ModelChoice};
+use super::codex_auth::CodexAuthState;
+use super::workspace_roots;
+use crate::error::{ErrorCode, VoidDeskError};
+use crate::terminal;
+
+/// Commands from the terminal history shown to the model as examples of the project's tools
+const HISTORY_EXAMPLES: usize = 10;
+
+const TERMINAL_COMMAND_PROMPT: &str = r#"You turn a request written in plain language into one shell command. You are given the shell, the operating system, the working directory and some commands the user ran there recently.
+
+Reply with a single JSON object and nothing else:
+{"command": "...", "explanation": "...", "danger": "safe" | "caution" | "dangerous", "reason": "..."}
+- `command`: one command line for the given shell. Chain steps with the shell's operators when several are needed.
+- `explanation`: one or two sentences on what the command does.
+- `danger`: "dangerous" if it deletes or overwrites data, rewrites history, changes permissions broadly or needs elevated rights; "caution" if it changes files, processes or remote state; "safe" if it only reads.
+- `reason`: why it is not safe; empty when it is.
+Prefer the tools the recent commands show the project uses."#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandDanger {
+    /// Only reads
+    Safe,
+    /// Changes files, processes or remote state
+    Caution,
+    /// Can lose data or be hard to undo
+    Dangerous,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalCommandSuggestion {
+    pub command: String,
+    pub explanation: String,
+    pub danger: CommandDanger,
+    /// Why the command is not safe; None when it is
+    pub danger_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelReply {
+    command: String,
+    #[serde(default)]
+    explanation: String,
+    #[serde(default)]
+    danger: Option<CommandDanger>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Commands that can lose data or are hard to undo, and why, matched as whole words of
+/// the command in lowercase with its whitespace collapsed
+const DANGEROUS_PATTERNS: &[(&str, &str)] = &[
+    ("rm -r", "Deletes files recursively"),
+    ("rm -fr", "Deletes files recursively"),
+    ("remove-item -recurse", "Deletes files recursively"),
+    ("rd /s", "Deletes files recursively"),
+    ("rmdir /s", "Deletes files recursively"),
+    ("del /s", "Deletes files recursively"),
+    ("sudo", "Runs with elevated rights"),
+    ("mkfs", "Formats a disk"),
+    ("format", "Formats a disk"),
+    ("dd", "Writes raw data to a device or file"),
+    ("chmod -r", "Changes permissions recursively"),
+    ("chown -r", "Changes ownership recursively"),
+    ("git reset --hard", "Discards uncommitted changes"),
+    ("git clean", "Deletes untracked files"),
+    ("git checkout -- ", "Discards uncommitted changes"),
+    ("git push --force", "Rewrites remote history"),
+    ("git push -f", "Rewrites remote history"),
+    ("drop table", "Deletes database data"),
+    ("drop database", "Deletes database data"),
+    ("| sh", "Runs a downloaded script"),
+    ("| bash", "Runs a downloaded script"),
+    ("| iex", "Runs a downloaded script"),
+    (":(){", "Fork bomb"),
+    ("shutdown", "Shuts the machine down"),
+    ("reboot", "Restarts the machine"),
+];
+
+/// Commands that change files, processes or remote state, and how
+const CAUTION_PATTERNS: &[(&str, &str)] = &[
+    ("rm", "Deletes files"),
+    ("remove-item", "Deletes files"),
+    ("del", "Deletes files"),
+    ("mv", "Moves or overwrites files"),
+    ("kill", "Stops processes"),
+    ("pkill", "Stops processes"),
+    ("killall", "Stops processes"),
+    ("taskkill", "Stops processes"),
+    ("git push", "Changes the remote repository"),
+    ("git rebase", "Rewrites history"),
+    ("npm publish", "Publishes a package"),
+    ("cargo publish", "Publishes a package"),
+    ("docker system prune", "Deletes containers and images"),
+    ("chmod", "Changes permissions"),
+];
+
+/// Whether `pattern` occurs in `command` as whole words: where a word or a chained
+/// command starts, and not running on into a longer word unless it ends in a flag. `dd`
+/// matches `dd if=...` but not `git add .`, `| sh` not `| shellcheck`, and `rm -r`
+/// matches `rm -rf`.
+fn contains_words(command: &str, pattern: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    command.match_indices(pattern).any(|(index, _)| {
+        let starts = command[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|before| matches!(before, ' ' | ';' | '|' | '&' | '(' | '`'));
+        let ends = !pattern.ends_with(is_word)
+            || pattern
+                .rsplit(' ')
+                .next()
+                .is_some_and(|last| last.starts_with('-'))
+            || command[index + pattern.len()..]
+                .chars()
+                .next()
+                .is_none_or(|after| !is_word(after));
+        starts && ends
+    })
+}
+
+/// How dangerous `command` looks by the patterns above, and why
+fn classify(command: &str) -> (CommandDanger, Option<&'static str>) {
+    let normalized = command
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let matching = |patterns: &[(&str, &'static str)]| {
+        patterns
+            .iter()
+            .find(|(pattern, _)| contains_words(&normalized, pattern))
+            .map(|(_, reason)| *reason)
+    };
+    if let Some(reason) = matching(DANGEROUS_PATTERNS) {
+        return (CommandDanger::Dangerous, Some(reason));
+    }
+    if let Some(reason) = matching(CAUTION_PATTERNS) {
+        return (CommandDanger::Caution, Some(reason));
+    }
+    // Redirections that truncate a file; not appends, `2>&1` or the null device
+    let overwrites = normalized.match_indices('>').any(|(index, _)| {
+        let target = normalized[index + 1..].trim_start();
+        !normalized[..index].ends_with('>')
+            && !target.starts_with(['>', '&'])
+            && !["/dev/null", "$null", "nul"]
+                .iter()
+                .any(|null| target.starts_with(null))
+    });
+    if overwrites {
+        return (CommandDanger::Caution, Some("Overwrites a file"));
+    }
+    (CommandDanger::Safe, None)
+}
+
+fn command_prompt(request: &str, shell: &str, cwd: Option<&str>, history: &[String]) -> String {
+    let mut prompt = format!(
+        "Shell: {}\nOperating system: {}\nWorking directory: {}\n",
+        shell,
+        std::env::consts::OS,
+        cwd.unwrap_or("unknown")
+    );
+    if !history.is_empty() {
+        prompt.push_str("Recent commands:\n");
+        for command in history {
+            prompt.push_str("  ");
+            prompt.push_str(command);
+            prompt.push('\n');
+        }
+    }
+    prompt.push_str("\nRequest: ");
+    prompt.push_str(request);
+    prompt
+}
+
+/// Propose a command for `shell` (the default shell when None) that does what
+/// `natural_language` describes in `cwd`. It is never run here.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn generate_terminal_command(
+    natural_language: String,
+    shell: Option<String>,
+    cwd: Option<String>,
+    provider_type: Option<String>,
+    api_key: String,
+    base_url: String,
+    model_id: String,
+    codex_auth: State<'_, CodexAuthState>,
+) -> Result<TerminalCommandSuggestion, VoidDeskError> {
+    let request = natural_language.trim();
+    if request.is_empty() {
+        return Err(VoidDeskError::invalid_input(
+            "Describe the command you need",
+        ));
+    }
+    let model = ModelChoice {
+        provider_type,
+        api_key,
+        base_url,
+        model_id,
+    };
+    let shell = shell.unwrap_or_else(terminal::default_shell);
+
+    let root = cwd.as_deref().and_then(workspace_roots::containing_root);
+    let history = match root {
+        Some(root) => {
+            terminal::recent_commands(Some(&root.to_string_lossy()), None, HISTORY_EXAMPLES)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| entry.command)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let prompt = command_prompt(request, &shell, cwd.as_deref(), &history);
+    let output = complete_once(&model, &codex_auth, TERMINAL_COMMAND_PROMPT, prompt)
+        .await
+        .map_err(|e| e.context("Failed to generate a command"))?;
+    let reply = json_reply::<ModelReply>(&output)
+        .filter(|reply| !reply.command.trim().is_empty())
+        .ok_or_else(|| {
+            VoidDeskError::new(ErrorCode::Provider, "The model did not propose a command")
+        })?;
+
+    let command = reply.command.trim().to_string();
+    let model_danger = reply.danger.unwrap_or(CommandDanger::Caution);
+    let model_reason = reply.reason.filter(|reason| !reason.trim().is_empty());
+    let (local_danger, local_reason) = classify(&command);
+    let local_reason = local_reason.map(str::to_string);
+    let danger = model_danger.max(local_danger);
+    let danger_reason = if danger == CommandDanger::Safe {
+        None
+    } else if local_danger == danger {
+        local_reason.or(model_reason)
+    } else {
+        model_reason.or(local_reason)
+    };
+    Ok(TerminalCommandSuggestion {
+        command,
+        explanation: reply.explanation.trim().to_string(),
+        danger,
+        danger_reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_commands_and_parses_fenced_replies() {
+        assert_eq!(classify("ls -la src").0, CommandDanger::Safe);
+        assert_eq!(
+            classify("cargo build 2>&1 | tee log").0,
+            CommandDanger::Safe
+        );
+        assert_eq!(classify("echo done > notes.txt").0, CommandDanger::Caution);
+        assert_eq!(classify("git push origin main").0, CommandDanger::Caution);
+        assert_eq!(
+            classify("git  push  --force origin main"),
+            (CommandDanger::Dangerous, Some("Rewrites remote history"))
+        );
+        assert_eq!(classify("RM -RF target").0, CommandDanger::Dangerous);
+        assert_eq!(classify("git add . && git status").0, CommandDanger::Safe);
+        assert_eq!(
+            classify("cargo fmt -- --check > /dev/null").0,
+            CommandDanger::Safe
+        );
+        assert_eq!(
+            classify("cd build; sudo make install").0,
+            CommandDanger::Dangerous
+        );
+
+        let reply: ModelReply = json_reply(
+            "```json\n{\"command\": \"find . -name '*.rs'\", \"explanation\": \"Lists Rust files\", \"danger\": \"safe\"}\n```",
+        )
+        .unwrap();
+        assert_eq!(reply.command, "find . -name '*.rs'");
+        assert_eq!(reply.danger, Some(CommandDanger::Safe));
+        assert!(json_reply::<ModelReply>("I cannot help with that").is_none());
+    }
+}
@@ -7,10 +7,14 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
 
+use super::editor_context;
+use super::file_commands::{large_file_threshold, save_with_history};
+use crate::diff;
 use crate::sdk::{AgentTool, AgentToolOutput, ToolSchemaFormat};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,6 +67,20 @@ pub struct RunCommandArgs {
     pub command: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecentCommandsArgs {
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListeningPortsArgs {
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListDirectoryArgs {
     pub path: String,
@@ -211,6 +229,23 @@ impl AgentTool for ReadFileTool {
             .ok_or_else(|| anyhow!("No active project path"))?;
         let path = resolve_and_validate_path(&root, &args.path)?;
 
+        let size = fs::metadata(&path)
+            .map_err(|e| anyhow!("Failed to read file '{}': {}", args.path, e))?
+            .len();
+        if size > large_file_threshold() {
+            let (Some(start_line), Some(end_line)) = (args.start_line, args.end_line) else {
+                return Err(anyhow!(
+                    "File '{}' is too large to read whole ({} bytes, limit {} bytes). \
+                     Pass start_line and end_line to read up to {} lines of it.",
+                    args.path,
+                    size,
+                    large_file_threshold(),
+                    MAX_LARGE_FILE_RANGE_LINES
+                ));
+            };
+            return read_large_file_range(&path, &args.path, start_line, end_line);
+        }
+
         let content = fs::read_to_string(&path)
             .map_err(|e| anyhow!("Failed to read file '{}': {}", args.path, e))?;
 
@@ -264,6 +299,74 @@ impl AgentTool for ReadFileTool {
     }
 }
 
+/// Most lines returned per call when reading a file over the large file threshold
+const MAX_LARGE_FILE_RANGE_LINES: u32 = 2000;
+
+/// Read a line range by streaming the file, so files over the large file threshold are
+/// never held in memory whole
+fn read_large_file_range(
+    path: &Path,
+    display_path: &str,
+    start_line: u32,
+    end_line: u32,
+) -> Result<AgentToolOutput> {
+    if start_line < 1 {
+        return Err(anyhow!("start_line must be >= 1"));
+    }
+    if end_line < start_line {
+        return Err(anyhow!("end_line must be >= start_line"));
+    }
+    let requested_end_line = end_line;
+    let end_line = end_line.min(start_line + MAX_LARGE_FILE_RANGE_LINES - 1);
+
+    let file = fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to read file '{}': {}", display_path, e))?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut selected = Vec::new();
+    let mut uses_crlf = false;
+    let mut total_lines = 0u32;
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| anyhow!("Failed to read file '{}': {}", display_path, e))?;
+        if read == 0 {
+            break;
+        }
+        total_lines += 1;
+        if total_lines >= start_line && total_lines <= end_line {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            uses_crlf |= text.ends_with('\r');
+            selected.push(text.strip_suffix('\r').unwrap_or(text).to_string());
+        }
+    }
+
+    if start_line > total_lines.max(1) {
+        return Err(anyhow!(
+            "start_line {} is out of bounds (file has {} lines)",
+            start_line,
+            total_lines
+        ));
+    }
+    let end_line = end_line.min(total_lines.max(1));
+    let line_ending = if uses_crlf { "\r\n" } else { "\n" };
+
+    Ok(AgentToolOutput::new(
+        json!({
+            "success": true,
+            "path": display_path,
+            "content": selected.join(line_ending),
+            "truncated": end_line < requested_end_line.min(total_lines),
+            "start_line": start_line,
+            "end_line": end_line,
+            "total_lines": total_lines
+        })
+        .to_string(),
+    ))
+}
+
 pub struct WriteFileTool {
     root_path: Option<String>,
 }
@@ -346,7 +449,7 @@ impl AgentTool for WriteFileTool {
             }
         }
 
-        fs::write(&path, &args.content)
+        save_with_history(&path, args.content.as_bytes())
             .map_err(|e| anyhow!("Failed to write file '{}': {}", args.path, e))?;
 
         Ok(AgentToolOutput::new(
@@ -508,44 +611,14 @@ impl AgentTool for StreamingEditFileTool {
 struct ResolvedEdit {
     index: usize,
     range: std::ops::Range<usize>,
-    old_text: String,
     new_text: String,
 }
 
-fn build_edits_diff(edits: &[ResolvedEdit]) -> String {
-    let mut diff = String::from("--- original\n+++ updated\n");
-    for (idx, edit) in edits.iter().enumerate() {
-        diff.push_str(&format!("@@ edit {} @@\n", idx + 1));
-        diff.push_str(&format_diff_block('-', &edit.old_text));
-        diff.push_str(&format_diff_block('+', &edit.new_text));
-    }
-    diff
-}
-
-fn build_create_diff(content: &str) -> String {
-    let mut diff = String::from("--- original\n+++ updated\n");
-    diff.push_str(&format_diff_block('+', content));
-    diff
-}
-
-fn build_overwrite_diff(old_content: Option<&str>, new_content: &str) -> String {
-    let mut diff = String::from("--- original\n+++ updated\n");
-    if let Some(old) = old_content {
-        diff.push_str(&format_diff_block('-', old));
-    }
-    diff.push_str(&format_diff_block('+', new_content));
-    diff
-}
-
-fn format_diff_block(prefix: char, text: &str) -> String {
-    let mut out = String::new();
-    let lines: Vec<&str> = text.split('\n').collect();
-    for line in lines {
-        out.push(prefix);
-        out.push_str(line);
-        out.push('\n');
-    }
-    out
+/// Unified diff of a file before and after an edit, for the tool result
+fn edit_diff(old_content: &str, new_content: &str) -> String {
+    diff::diff_texts(old_content, new_content)
+        .map(|diff| diff.unified("original", "updated"))
+        .unwrap_or_default()
 }
 
 fn resolve_edit_range(content: &str, edit: &EditOperation) -> Result<std::ops::Range<usize>> {
@@ -782,6 +855,210 @@ impl AgentTool for RunCommandTool {
     }
 }
 
+pub struct RecentCommandsTool {
+    root_path: Option<String>,
+}
+
+impl RecentCommandsTool {
+    pub fn new(root_path: Option<String>) -> Self {
+        Self { root_path }
+    }
+}
+
+#[async_trait]
+impl AgentTool for RecentCommandsTool {
+    fn name(&self) -> &str {
+        "recent_terminal_commands"
+    }
+
+    fn description(&self) -> &str {
+        "List commands the user recently ran in the IDE terminal for this project."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Only return commands containing this text"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of commands to return (default 20)"
+                }
+            }
+        })
+    }
+
+    fn schema_format(&self) -> ToolSchemaFormat {
+        ToolSchemaFormat::JsonSchema
+    }
+
+    async fn run(&self, input: Value) -> Result<AgentToolOutput> {
+        let args: RecentCommandsArgs = serde_json::from_value(input)?;
+        let root = self
+            .root_path
+            .clone()
+            .ok_or_else(|| anyhow!("No active project path"))?;
+
+        let commands = crate::terminal::recent_commands(
+            Some(&root),
+            args.query.as_deref(),
+            args.limit.unwrap_or(20).min(200),
+        )
+        .map_err(|e| anyhow!(e))?;
+
+        Ok(AgentToolOutput::new(
+            json!({
+                "success": true,
+                "commands": commands,
+                "count": commands.len()
+            })
+            .to_string(),
+        ))
+    }
+}
+
+pub struct ListeningPortsTool;
+
+impl ListeningPortsTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl AgentTool for ListeningPortsTool {
+    fn name(&self) -> &str {
+        "list_listening_ports"
+    }
+
+    fn description(&self) -> &str {
+        "List TCP ports with a server listening on them and the process holding each. \
+         Use it before starting a server, or when one fails with 'address already in use'."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "port": {
+                    "type": "integer",
+                    "description": "Only report this port, and whether it is free"
+                }
+            }
+        })
+    }
+
+    fn schema_format(&self) -> ToolSchemaFormat {
+        ToolSchemaFormat::JsonSchema
+    }
+
+    async fn run(&self, input: Value) -> Result<AgentToolOutput> {
+        let args: ListeningPortsArgs = serde_json::from_value(input)?;
+        let mut ports =
+            tokio::task::spawn_blocking(crate::commands::port_commands::listening_ports)
+                .await?
+                .map_err(|e| anyhow!(e))?;
+        if let Some(port) = args.port {
+            ports.retain(|listening| listening.port == port);
+        }
+
+        Ok(AgentToolOutput::new(
+            json!({
+                "success": true,
+                "free": args.port.map(|_| ports.is_empty()),
+                "ports": ports,
+                "count": ports.len()
+            })
+            .to_string(),
+        ))
+    }
+}
+
+pub struct GetEditorContextTool {
+    root_path: Option<String>,
+}
+
+impl GetEditorContextTool {
+    pub fn new(root_path: Option<String>) -> Self {
+        Self { root_path }
+    }
+
+    /// `path` relative to the project root when it lies inside it
+    fn display_path(&self, path: &str) -> String {
+        self.root_path
+            .as_deref()
+            .and_then(|root| Path::new(path).strip_prefix(root).ok())
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+#[async_trait]
+impl AgentTool for GetEditorContextTool {
+    fn name(&self) -> &str {
+        "get_editor_context"
+    }
+
+    fn description(&self) -> &str {
+        "Get what the user is looking at in the editor: the active file, cursor position, \
+         selected text, the lines around the cursor and the other open files. Use it when \
+         the user refers to 'this function', 'the selected code' or 'this file'. The text \
+         comes from the editor, so it includes edits the user has not saved."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    fn schema_format(&self) -> ToolSchemaFormat {
+        ToolSchemaFormat::JsonSchema
+    }
+
+    async fn run(&self, _input: Value) -> Result<AgentToolOutput> {
+        let Some(context) = editor_context::context_for_root(self.root_path.as_deref()) else {
+            return Ok(AgentToolOutput::new(
+                json!({
+                    "success": true,
+                    "available": false,
+                    "message": "No editor is open on this project"
+                })
+                .to_string(),
+            ));
+        };
+
+        let active_dirty = context
+            .open_files
+            .iter()
+            .any(|file| Some(file.path.as_str()) == context.active_file.as_deref() && file.dirty);
+        let open_files: Vec<Value> = context
+            .open_files
+            .iter()
+            .map(|file| json!({ "path": self.display_path(&file.path), "unsaved": file.dirty }))
+            .collect();
+
+        Ok(AgentToolOutput::new(
+            json!({
+                "success": true,
+                "available": true,
+                "active_file": context.active_file.as_deref().map(|path| self.display_path(path)),
+                "unsaved_changes": active_dirty,
+                "language": context.language,
+                "cursor": context.cursor,
+                "selection": context.selection,
+                "excerpt": context.excerpt,
+                "open_files": open_files
+            })
+            .to_string(),
+        ))
+    }
+}
+
 pub fn get_all_tools(root_path: Option<&str>) -> Vec<Arc<dyn AgentTool>> {
     let root = root_path.map(|s| s.to_string());
     vec![
@@ -790,7 +1067,10 @@ pub fn get_all_tools(root_path: Option<&str>) -> Vec<Arc<dyn AgentTool>> {
         Arc::new(EditFileTool::new(root.clone())),
         Arc::new(StreamingEditFileTool::new(root.clone())),
         Arc::new(ListDirectoryTool::new(root.clone())),
-        Arc::new(RunCommandTool::new(root)),
+        Arc::new(RunCommandTool::new(root.clone())),
+        Arc::new(RecentCommandsTool::new(root.clone())),
+        Arc::new(GetEditorContextTool::new(root)),
+        Arc::new(ListeningPortsTool::new()),
     ]
 }
 
@@ -798,9 +1078,7 @@ fn execute_edit_file(args: EditFileArgs, root: &str) -> Result<AgentToolOutput>
     let path = resolve_and_validate_path(root, &args.path)?;
     ensure_not_sensitive(&path, args.allow_sensitive.unwrap_or(false))?;
 
-    let mut diff = String::new();
-
-    match args.mode {
+    let diff = match args.mode {
         EditFileMode::Create => {
             if path.exists() {
                 return Err(anyhow!("File already exists: '{}'", args.path));
@@ -814,9 +1092,9 @@ fn execute_edit_file(args: EditFileArgs, root: &str) -> Result<AgentToolOutput>
                         .map_err(|e| anyhow!("Failed to create directories: {}", e))?;
                 }
             }
-            fs::write(&path, &content)
+            save_with_history(&path, content.as_bytes())
                 .map_err(|e| anyhow!("Failed to write file '{}': {}", args.path, e))?;
-            diff = build_create_diff(&content);
+            edit_diff("", &content)
         }
         EditFileMode::Overwrite => {
             let content = args
@@ -829,9 +1107,9 @@ fn execute_edit_file(args: EditFileArgs, root: &str) -> Result<AgentToolOutput>
                         .map_err(|e| anyhow!("Failed to create directories: {}", e))?;
                 }
             }
-            fs::write(&path, &content)
+            save_with_history(&path, content.as_bytes())
                 .map_err(|e| anyhow!("Failed to write file '{}': {}", args.path, e))?;
-            diff = build_overwrite_diff(old_content.as_deref(), &content);
+            edit_diff(old_content.as_deref().unwrap_or(""), &content)
         }
         EditFileMode::Edit => {
             if !path.exists() {
@@ -860,7 +1138,6 @@ fn execute_edit_file(args: EditFileArgs, root: &str) -> Result<AgentToolOutput>
                 resolved_edits.push(ResolvedEdit {
                     index,
                     range,
-                    old_text: edit.old_text.clone(),
                     new_text: edit.new_text.clone(),
                 });
             }
@@ -884,13 +1161,11 @@ fn execute_edit_file(args: EditFileArgs, root: &str) -> Result<AgentToolOutput>
                 updated.replace_range(edit.range.clone(), &edit.new_text);
             }
 
-            fs::write(&path, &updated)
+            save_with_history(&path, updated.as_bytes())
                 .map_err(|e| anyhow!("Failed to write file '{}': {}", args.path, e))?;
-            let mut diff_edits = resolved_edits.clone();
-            diff_edits.sort_by_key(|edit| edit.index);
-            diff = build_edits_diff(&diff_edits);
+            edit_diff(&content, &updated)
         }
-    }
+    };
 
     Ok(AgentToolOutput::new(
         json!({
@@ -0,0 +1,194 @@
+// Conversation export
+// Renders an agent session as Markdown to share or keep with the project: each turn in
+// order, every tool call with its arguments, and the diffs of the edits it made. System
+// prompts stay out, as do images, which are only noted.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+
+use super::ai_service::AIService;
+use super::file_commands::write_atomic;
+use super::workspace_roots;
+use crate::error::VoidDeskError;
+use crate::sdk::{MessageContent, MessagePart, Session};
+
+/// Tool output beyond this is cut; diffs are always kept whole
+const TOOL_OUTPUT_MAX_CHARS: usize = 4_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationExport {
+    pub markdown: String,
+    /// Where the document was saved, when a path was given
+    pub path: Option<String>,
+}
+
+/// A code fence longer than any run of backticks in `text`, so it cannot end early
+fn fenced(text: &str, language: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}\n",
+        fence,
+        language,
+        text.trim_end_matches('\n'),
+        fence
+    )
+}
+
+fn content_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::Plain(text) => text.clone(),
+        MessageContent::Multipart(parts) => parts
+            .iter()
+            .map(|part| match part {
+                MessagePart::Text { text } => text.clone(),
+                MessagePart::Image { .. } => "_[image]_".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    }
+}
+
+fn tool_arguments(arguments: &str) -> String {
+    serde_json::from_str::<Value>(arguments)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| arguments.to_string())
+}
+
+/// A tool's output, with the diff of an edit shown as one
+fn tool_output(output: &str) -> String {
+    let value = serde_json::from_str::<Value>(output).ok();
+    if let Some(diff) = value.as_ref().and_then(|value| value.get("diff")?.as_str()) {
+        if !diff.is_empty() {
+            return fenced(diff, "diff");
+        }
+    }
+    let mut text = match value {
+        Some(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| output.into()),
+        None => output.to_string(),
+    };
+    if let Some((cut, _)) = text.char_indices().nth(TOOL_OUTPUT_MAX_CHARS) {
+        text.truncate(cut);
+        text.push_str("\n… (truncated)");
+    }
+    fenced(&text, "")
+}
+
+pub(crate) fn render_markdown(session: &Session) -> String {
+    let title = session.name.as_deref().unwrap_or("Untitled conversation");
+    let mut markdown = format!(
+        "# {}\n\n_Exported from VoiDesk on {}_\n",
+        title,
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    for message in &session.messages {
+        let text = message
+            .content
+            .as_ref()
+            .map(content_text)
+            .unwrap_or_default();
+        match message.role.as_str() {
+            "user" => markdown.push_str(&format!("\n## User\n\n{}\n", text.trim())),
+            "assistant" => {
+                markdown.push_str("\n## Assistant\n");
+                if !text.trim().is_empty() {
+                    markdown.push_str(&format!("\n{}\n", text.trim()));
+                }
+                for call in message.tool_calls.iter().flatten() {
+                    tool_names.insert(&call.id, &call.function.name);
+                    markdown.push_str(&format!(
+                        "\n### Tool call: `{}`\n\n{}",
+                        call.function.name,
+                        fenced(&tool_arguments(&call.function.arguments), "json")
+                    ));
+                }
+            }
+            "tool" => {
+                let name = message
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| tool_names.get(id))
+                    .unwrap_or(&"tool");
+                markdown.push_str(&format!(
+                    "\n**Result of `{}`**\n\n{}",
+                    name,
+                    tool_output(&text)
+                ));
+            }
+            _ => {}
+        }
+    }
+    markdown
+}
+
+/// Render the session as Markdown, saving it to `path` in the workspace when one is given
+#[tauri::command]
+pub async fn export_conversation_markdown(
+    session_id: String,
+    path: Option<String>,
+    service: State<'_, AIService>,
+) -> Result<ConversationExport, VoidDeskError> {
+    let session = service
+        .session_store()
+        .get(&session_id)
+        .await
+        .ok_or_else(|| VoidDeskError::not_found(format!("Session not found: {}", session_id)))?;
+    let markdown = render_markdown(&session);
+
+    if let Some(path) = &path {
+        workspace_roots::ensure_in_workspace(path)?;
+        write_atomic(Path::new(path), markdown.as_bytes())?;
+    }
+    Ok(ConversationExport { markdown, path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::{Message, ToolCall};
+
+    #[test]
+    fn renders_turns_tool_calls_and_diffs() {
+        let now = chrono::Utc::now();
+        let edit = serde_json::json!({
+            "success": true,
+            "path": "src/main.rs",
+            "diff": "--- original\n+++ updated\n-old\n+new\n",
+        });
+        let session = Session {
+            id: "s1".to_string(),
+            name: Some("Rename".to_string()),
+            messages: vec![
+                Message::system("You are an agent".to_string()),
+                Message::user("Use `new` instead".to_string()),
+                Message::assistant_with_tool_calls(
+                    None,
+                    vec![ToolCall::new(
+                        "call_1".to_string(),
+                        "edit_file".to_string(),
+                        r#"{"path":"src/main.rs"}"#.to_string(),
+                    )],
+                ),
+                Message::tool_result("call_1".to_string(), edit.to_string()),
+                Message::tool_result("call_2".to_string(), "```\nls output\n```".to_string()),
+                Message::assistant_text("Done.".to_string()),
+            ],
+            created_at: now,
+            updated_at: now,
+        };
+
+        let markdown = render_markdown(&session);
+        assert!(markdown.starts_with("# Rename\n"));
+        assert!(!markdown.contains("You are an agent"));
+        assert!(markdown.contains("## User\n\nUse `new` instead\n"));
+        assert!(markdown.contains("### Tool call: `edit_file`\n\n```json\n{\n  \"path\""));
+        assert!(markdown.contains("**Result of `edit_file`**\n\n```diff\n--- original"));
+        assert!(markdown.contains("**Result of `tool`**\n\n````\n```\nls output\n```\n````\n"));
+        assert!(markdown.trim_end().ends_with("## Assistant\n\nDone."));
+    }
+}
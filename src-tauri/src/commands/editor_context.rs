@@ -0,0 +1,196 @@
+// Editor context
+// What each window's editor shows: the open files, the active one, the cursor, the
+// selection and the lines around the cursor. The frontend reports it as it changes so the
+// agent can answer "explain this function" or "fix the selected code" from what the user
+// is looking at, unsaved edits included, without the code being pasted into the chat.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use tauri::WebviewWindow;
+
+use super::workspace;
+use crate::error::VoidDeskError;
+
+/// Selections and excerpts longer than this are cut short
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorSelection {
+    pub start: EditorPosition,
+    pub end: EditorPosition,
+    pub text: String,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Lines of the active file's buffer around the cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorExcerpt {
+    pub start_line: u32,
+    pub text: String,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenEditorFile {
+    pub path: String,
+    /// Has edits that are not saved yet
+    pub dirty: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditorContext {
+    #[serde(default)]
+    pub open_files: Vec<OpenEditorFile>,
+    pub active_file: Option<String>,
+    pub language: Option<String>,
+    pub cursor: Option<EditorPosition>,
+    /// None when nothing is selected
+    pub selection: Option<EditorSelection>,
+    pub excerpt: Option<EditorExcerpt>,
+}
+
+struct WindowContext {
+    context: EditorContext,
+    /// Orders reports across windows; the highest was reported last
+    revision: u64,
+}
+
+static CONTEXTS: OnceLock<RwLock<HashMap<String, WindowContext>>> = OnceLock::new();
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+fn contexts() -> &'static RwLock<HashMap<String, WindowContext>> {
+    CONTEXTS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Cut `text` to MAX_TEXT_CHARS characters; true if anything was dropped
+fn cap_text(text: &mut String) -> bool {
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => {
+            text.truncate(end);
+            true
+        }
+        None => false,
+    }
+}
+
+fn store(window: &str, mut context: EditorContext) -> Result<(), String> {
+    if let Some(selection) = context.selection.as_mut() {
+        selection.truncated |= cap_text(&mut selection.text);
+    }
+    if let Some(excerpt) = context.excerpt.as_mut() {
+        excerpt.truncated |= cap_text(&mut excerpt.text);
+    }
+    let revision = REVISION.fetch_add(1, Ordering::Relaxed) + 1;
+    contexts()
+        .write()
+        .map_err(|e| e.to_string())?
+        .insert(window.to_string(), WindowContext { context, revision });
+    Ok(())
+}
+
+/// Drop what `window` reported, once it has closed
+pub fn forget_window(window: &str) {
+    if let Ok(mut contexts) = contexts().write() {
+        contexts.remove(window);
+    }
+}
+
+/// The editor context reported last by a window working in `root`: one whose active
+/// folder is `root` or whose active file lies under it. Any window when `root` is None.
+pub fn context_for_root(root: Option<&str>) -> Option<EditorContext> {
+    let contexts = contexts().read().ok()?;
+    contexts
+        .iter()
+        .filter(|(window, entry)| {
+            let Some(root) = root else {
+                return true;
+            };
+            let root = Path::new(root);
+            workspace::active_root_of(window).is_some_and(|active| active == root)
+                || entry
+                    .context
+                    .active_file
+                    .as_deref()
+                    .is_some_and(|file| Path::new(file).starts_with(root))
+        })
+        .max_by_key(|(_, entry)| entry.revision)
+        .map(|(_, entry)| entry.context.clone())
+}
+
+/// Record what the calling window's editor shows, for the agent's get_editor_context tool
+#[tauri::command]
+pub async fn update_editor_context(
+    context: EditorContext,
+    window: WebviewWindow,
+) -> Result<(), VoidDeskError> {
+    Ok(store(window.label(), context)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_with_file(path: &str) -> EditorContext {
+        EditorContext {
+            active_file: Some(path.to_string()),
+            cursor: Some(EditorPosition { line: 3, column: 1 }),
+            selection: Some(EditorSelection {
+                start: EditorPosition { line: 1, column: 1 },
+                end: EditorPosition { line: 1, column: 1 },
+                text: "x".repeat(MAX_TEXT_CHARS + 10),
+                truncated: false,
+            }),
+            ..EditorContext::default()
+        }
+    }
+
+    #[test]
+    fn picks_the_latest_window_working_in_the_root() {
+        store(
+            "editor-test-a",
+            context_with_file("/editor-test/one/src/main.rs"),
+        )
+        .unwrap();
+        store(
+            "editor-test-b",
+            context_with_file("/editor-test/two/lib.rs"),
+        )
+        .unwrap();
+        store(
+            "editor-test-c",
+            context_with_file("/editor-test/one/README.md"),
+        )
+        .unwrap();
+
+        let context = context_for_root(Some("/editor-test/one")).unwrap();
+        assert_eq!(
+            context.active_file.as_deref(),
+            Some("/editor-test/one/README.md")
+        );
+        let selection = context.selection.unwrap();
+        assert!(selection.truncated);
+        assert_eq!(selection.text.len(), MAX_TEXT_CHARS);
+
+        forget_window("editor-test-c");
+        let context = context_for_root(Some("/editor-test/one")).unwrap();
+        assert_eq!(
+            context.active_file.as_deref(),
+            Some("/editor-test/one/src/main.rs")
+        );
+        assert!(context_for_root(Some("/editor-test/three")).is_none());
+        forget_window("editor-test-a");
+        forget_window("editor-test-b");
+    }
+}
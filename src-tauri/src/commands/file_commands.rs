@@ -1,69 +1,664 @@
+use base64::Engine;
 use std::fs;
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::file_encoding::{self, EncodedFile, FileEncoding};
+use super::file_watcher;
+use super::local_history;
+use super::workspace_index::{self, ContentHasher};
+use super::workspace_roots;
+use crate::error::{ErrorCode, VoidDeskError};
+use crate::lsp::protocol::language_id_from_extension;
+
+/// Files above this size are not loaded wholesale; see set_large_file_threshold
+const DEFAULT_LARGE_FILE_THRESHOLD: u64 = 20 * 1024 * 1024;
+
+static LARGE_FILE_THRESHOLD: AtomicU64 = AtomicU64::new(DEFAULT_LARGE_FILE_THRESHOLD);
+
+/// Commands that can still read a file that is too large to open
+const CHUNKED_READ_COMMANDS: &[&str] = &["probe_file", "read_file_range", "read_file_lines"];
+
+pub fn large_file_threshold() -> u64 {
+    LARGE_FILE_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Refuse files above the large file threshold before reading them into memory; the
+/// error's details name the commands that can read it in chunks instead
+fn ensure_not_too_large(path: &str) -> Result<(), VoidDeskError> {
+    let size = fs::metadata(path)?.len();
+    let limit = large_file_threshold();
+    if size > limit {
+        return Err(VoidDeskError::new(
+            ErrorCode::TooLarge,
+            format!(
+                "File is too large to open ({} bytes, limit {} bytes)",
+                size, limit
+            ),
+        )
+        .with_details(serde_json::json!({
+            "size": size,
+            "limit": limit,
+            "chunked_read_commands": CHUNKED_READ_COMMANDS,
+        })));
+    }
+    Ok(())
+}
 
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path).map_err(|e| e.to_string())
+pub fn get_large_file_threshold() -> u64 {
+    large_file_threshold()
 }
 
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<(), String> {
-    // Create parent directories if they don't exist
+pub fn set_large_file_threshold(bytes: u64) -> Result<(), VoidDeskError> {
+    if bytes == 0 {
+        return Err(VoidDeskError::invalid_input(
+            "Large file threshold must be greater than zero",
+        ));
+    }
+    LARGE_FILE_THRESHOLD.store(bytes, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn read_file(path: String) -> Result<String, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    ensure_not_too_large(&path)?;
+    let bytes = fs::read(&path)?;
+    let decoded = file_encoding::decode(&bytes);
+    file_encoding::remember(&path, decoded.encoding);
+    Ok(decoded.content)
+}
+
+/// Read a file as UTF-8 text, detecting its encoding unless one is given
+#[tauri::command]
+pub async fn read_file_with_encoding(
+    path: String,
+    encoding: Option<String>,
+) -> Result<EncodedFile, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    ensure_not_too_large(&path)?;
+    let bytes = fs::read(&path)?;
+    let decoded = match encoding {
+        Some(label) => file_encoding::decode_with(
+            &bytes,
+            file_encoding::lookup(&label).map_err(VoidDeskError::invalid_input)?,
+        ),
+        None => file_encoding::decode(&bytes),
+    };
+    file_encoding::remember(&path, decoded.encoding);
+    Ok(decoded.into())
+}
+
+/// Largest chunk read_file_range returns in one call
+const MAX_READ_CHUNK_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(serde::Serialize)]
+pub struct FileProbe {
+    pub size: u64,
+    pub line_count: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct FileChunk {
+    pub content: String,
+    /// Where the chunk actually starts; moved forward past a split character
+    pub offset: u64,
+    /// Offset to pass to the next call
+    pub next_offset: u64,
+    pub total_size: u64,
+    pub eof: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct FileLines {
+    pub lines: Vec<String>,
+    /// 0-based index of the first returned line
+    pub start: u64,
+    pub has_more: bool,
+}
+
+/// Size and line count of a file, so the editor can decide how to load it
+#[tauri::command]
+pub async fn probe_file(path: String) -> Result<FileProbe, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let mut file = fs::File::open(&path)?;
+    let size = file.metadata()?.len();
+    let line_count = count_lines(&mut file, |_| {})?;
+    Ok(FileProbe { size, line_count })
+}
+
+/// Lines in everything `reader` yields, passing each chunk read to `inspect` on the way
+fn count_lines(reader: &mut impl Read, mut inspect: impl FnMut(&[u8])) -> io::Result<u64> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut newlines = 0u64;
+    let mut last_byte = None;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        inspect(chunk);
+        newlines += chunk.iter().filter(|byte| **byte == b'\n').count() as u64;
+        last_byte = Some(chunk[read - 1]);
+    }
+
+    // A final line without a trailing newline still counts
+    Ok(match last_byte {
+        None => 0,
+        Some(b'\n') => newlines,
+        Some(_) => newlines + 1,
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct FileStats {
+    pub size: u64,
+    pub line_count: u64,
+    /// Language id as used for LSP, "plaintext" when unknown
+    pub language: String,
+    /// SHA-256 of the contents, hex encoded. Not BLAKE3: it is the hash get_file_version
+    /// and the workspace index already use, so one value compares across all three.
+    pub hash: String,
+    pub is_binary: bool,
+}
+
+/// Bytes checked for NULs when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+fn detect_language(path: &Path) -> &'static str {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "dockerfile" => "dockerfile",
+        "makefile" => "makefile",
+        _ => {
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_string())
+                .unwrap_or_default();
+            language_id_from_extension(&extension)
+        }
+    }
+}
+
+/// Line count, language, hash and binary check in one pass over the file. The hash
+/// also lets callers tell whether a file changed since they last looked at it.
+pub fn file_stats(path: &Path) -> Result<FileStats, VoidDeskError> {
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = ContentHasher::default();
+    let mut sniffed = 0usize;
+    let mut is_binary = false;
+    let line_count = count_lines(&mut file, |chunk| {
+        hasher.update(chunk);
+        if sniffed < BINARY_SNIFF_BYTES {
+            let end = chunk.len().min(BINARY_SNIFF_BYTES - sniffed);
+            is_binary |= chunk[..end].contains(&0);
+            sniffed += end;
+        }
+    })?;
+
+    Ok(FileStats {
+        size,
+        line_count,
+        language: detect_language(path).to_string(),
+        hash: hasher.finish(),
+        is_binary,
+    })
+}
+
+/// Stats for the status bar
+#[tauri::command]
+pub async fn get_file_stats(path: String) -> Result<FileStats, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    tokio::task::spawn_blocking(move || file_stats(Path::new(&path)))
+        .await
+        .map_err(|e| VoidDeskError::internal(e.to_string()))?
+}
+
+/// Read up to `length` bytes starting at `offset`, never splitting a UTF-8 character
+#[tauri::command]
+pub async fn read_file_range(
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<FileChunk, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let mut file = fs::File::open(&path)?;
+    let total_size = file.metadata()?.len();
+    let offset = offset.min(total_size);
+    // Read a few extra bytes so a character cut at either end can be completed
+    let length = length.min(MAX_READ_CHUNK_BYTES);
+    let end = (offset + length + 3).min(total_size);
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = vec![0u8; (end - offset) as usize];
+    file.read_exact(&mut bytes)?;
+
+    // Skip continuation bytes of a character that started before `offset`
+    let skip = bytes
+        .iter()
+        .take(3)
+        .take_while(|byte| (**byte & 0b1100_0000) == 0b1000_0000)
+        .count();
+    let mut take = (length as usize).min(bytes.len()).max(skip);
+    // Extend to the end of a character cut at the end of the range
+    while take < bytes.len() && (bytes[take] & 0b1100_0000) == 0b1000_0000 {
+        take += 1;
+    }
+
+    let content = String::from_utf8_lossy(&bytes[skip..take]).to_string();
+    let next_offset = offset + take as u64;
+    Ok(FileChunk {
+        content,
+        offset: offset + skip as u64,
+        next_offset,
+        total_size,
+        eof: next_offset >= total_size,
+    })
+}
+
+/// Read `count` lines starting at the 0-based line `start`
+#[tauri::command]
+pub async fn read_file_lines(
+    path: String,
+    start: u64,
+    count: u64,
+) -> Result<FileLines, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let file = fs::File::open(&path)?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut index = 0u64;
+    let mut lines = Vec::new();
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(FileLines {
+                lines,
+                start,
+                has_more: false,
+            });
+        }
+
+        if index >= start {
+            if lines.len() as u64 == count {
+                return Ok(FileLines {
+                    lines,
+                    start,
+                    has_more: true,
+                });
+            }
+            let text = String::from_utf8_lossy(&line);
+            lines.push(text.trim_end_matches(['\n', '\r']).to_string());
+        }
+        index += 1;
+    }
+}
+
+/// Largest file read_file_binary sends over IPC
+const MAX_BINARY_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(serde::Serialize)]
+pub struct BinaryFile {
+    /// Base64 encoded contents
+    pub data: String,
+    pub mime_type: String,
+    pub size: u64,
+}
+
+/// Guess the MIME type from magic bytes, falling back to the extension
+pub(crate) fn sniff_mime(bytes: &[u8], path: &Path) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x00asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+    ];
+
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" {
+        match &bytes[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            _ => {}
+        }
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return mime;
+    }
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "json" => "application/json",
+        "txt" | "md" | "log" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Read a file as base64 so images and other assets survive the trip to the frontend
+#[tauri::command]
+pub async fn read_file_binary(path: String) -> Result<BinaryFile, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let size = fs::metadata(&path)?.len();
+    if size > MAX_BINARY_FILE_BYTES {
+        return Err(VoidDeskError::new(
+            ErrorCode::TooLarge,
+            format!(
+                "File is too large to load ({} bytes, limit {} bytes)",
+                size, MAX_BINARY_FILE_BYTES
+            ),
+        ));
+    }
+
+    let bytes = fs::read(&path)?;
+    Ok(BinaryFile {
+        mime_type: sniff_mime(&bytes, Path::new(&path)).to_string(),
+        size: bytes.len() as u64,
+        data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+    })
+}
+
+#[tauri::command]
+pub async fn write_file_binary(path: String, data: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| VoidDeskError::invalid_input(format!("Invalid base64 data: {}", e)))?;
     if let Some(parent) = Path::new(&path).parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        fs::create_dir_all(parent)?;
+    }
+    Ok(write_atomic(Path::new(&path), &bytes)?)
+}
+
+/// What the editor last saw of a file on disk
+#[derive(serde::Serialize)]
+pub struct FileVersion {
+    /// SHA-256 of the contents, as used by the workspace index
+    pub hash: String,
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime: u64,
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn file_version(path: &Path) -> Result<Option<FileVersion>, VoidDeskError> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(FileVersion {
+        hash: workspace_index::hash_file(path)?,
+        mtime: modified_ms(&metadata),
+    }))
+}
+
+/// Hash and mtime of a file, to pass back to write_file when saving
+#[tauri::command]
+pub async fn get_file_version(path: String) -> Result<FileVersion, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    file_version(Path::new(&path))?
+        .ok_or_else(|| VoidDeskError::not_found(format!("File not found: {}", path)))
+}
+
+/// Compare the file on disk with what the caller expects; the hash wins when both are given.
+/// A conflict's details carry what is on disk now, with a null hash when it was deleted.
+fn check_for_conflict(
+    path: &Path,
+    expected_hash: Option<&str>,
+    expected_mtime: Option<u64>,
+) -> Result<(), VoidDeskError> {
+    if expected_hash.is_none() && expected_mtime.is_none() {
+        return Ok(());
     }
-    fs::write(&path, content).map_err(|e| e.to_string())
+
+    let current = file_version(path)?;
+    let unchanged = match (&current, expected_hash) {
+        (Some(current), Some(expected_hash)) => current.hash == expected_hash,
+        (Some(current), None) => Some(current.mtime) == expected_mtime,
+        (None, _) => false,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    Err(VoidDeskError::new(
+        ErrorCode::Conflict,
+        format!("{} was changed on disk", path.display()),
+    )
+    .with_details(serde_json::json!({
+        "current_hash": current.as_ref().map(|current| current.hash.clone()),
+        "current_mtime": current.map(|current| current.mtime),
+    })))
 }
 
+/// Save a file. With `expected_hash` or `expected_mtime` the write is refused with a
+/// conflict error when the file changed on disk since the editor loaded it.
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), String> {
+pub async fn write_file(
+    path: String,
+    content: String,
+    expected_hash: Option<String>,
+    expected_mtime: Option<u64>,
+) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    check_for_conflict(Path::new(&path), expected_hash.as_deref(), expected_mtime)?;
+    let encoding = file_encoding::remembered(&path);
+    write_encoded(&path, &content, encoding)?;
+    Ok(())
+}
+
+/// Save in a chosen encoding; omitted fields keep what the file was read with
+#[tauri::command]
+pub async fn write_file_with_encoding(
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    bom: Option<bool>,
+) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let remembered = file_encoding::remembered(&path);
+    let encoding = FileEncoding {
+        encoding: match encoding {
+            Some(label) => file_encoding::lookup(&label).map_err(VoidDeskError::invalid_input)?,
+            None => remembered.encoding,
+        },
+        bom: bom.unwrap_or(remembered.bom),
+    };
+    write_encoded(&path, &content, encoding)?;
+    file_encoding::remember(&path, encoding);
+    Ok(())
+}
+
+pub(crate) fn write_encoded(
+    path: &str,
+    content: &str,
+    encoding: FileEncoding,
+) -> Result<(), VoidDeskError> {
+    let bytes = file_encoding::encode(content, encoding).map_err(VoidDeskError::invalid_input)?;
+    // Create parent directories if they don't exist
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(save_with_history(Path::new(path), &bytes)?)
+}
+
+/// Save over a file atomically, keeping the old and new contents in local history
+pub fn save_with_history(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    local_history::record_version(path);
+    write_atomic(path, bytes)?;
+    local_history::record_version(path);
+    Ok(())
+}
+
+/// Replace a file's contents without ever leaving it half written: write a temp file
+/// next to it, fsync, then rename it over the target, keeping the original permissions.
+/// Symlinks are followed so the link itself survives.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    replace_file(path, bytes, false)
+}
+
+/// Like `write_atomic`, but only the owner can ever read the file: on Unix the temp file
+/// is created with mode 0600 instead of taking on the original permissions
+pub fn write_atomic_private(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    replace_file(path, bytes, true)
+}
+
+fn replace_file(path: &Path, bytes: &[u8], private: bool) -> io::Result<()> {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink());
+    let target = if is_symlink {
+        fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    let directory = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let temp_path = directory.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+    let permissions = fs::metadata(&target)
+        .ok()
+        .filter(|_| !private)
+        .map(|metadata| metadata.permissions());
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp_path)?;
+        file.write_all(bytes)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Persist the rename itself; not every platform can open a directory for this
+    #[cfg(unix)]
+    if let Ok(directory) = fs::File::open(&directory) {
+        let _ = directory.sync_all();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_file(path: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
     let path = Path::new(&path);
     if path.is_dir() {
-        fs::remove_dir_all(path).map_err(|e| e.to_string())
+        fs::remove_dir_all(path)?;
     } else {
-        fs::remove_file(path).map_err(|e| e.to_string())
+        fs::remove_file(path)?;
     }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn create_directory(path: String) -> Result<(), String> {
-    fs::create_dir_all(&path).map_err(|e| e.to_string())
+pub async fn create_directory(path: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    Ok(fs::create_dir_all(&path)?)
 }
 
 #[tauri::command]
-pub async fn move_file(from: String, to: String) -> Result<(), String> {
-    fs::rename(from, to).map_err(|e| e.to_string())
+pub async fn move_file(from: String, to: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&from)?;
+    workspace_roots::ensure_in_workspace(&to)?;
+    Ok(fs::rename(from, to)?)
 }
 
 #[tauri::command]
-pub async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
-    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())
+pub async fn rename_file(old_path: String, new_path: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&old_path)?;
+    workspace_roots::ensure_in_workspace(&new_path)?;
+    Ok(fs::rename(&old_path, &new_path)?)
 }
 
 #[derive(serde::Serialize)]
 pub struct BatchOperationResult {
     pub path: String,
     pub success: bool,
-    pub error: Option<String>,
+    pub error: Option<VoidDeskError>,
 }
 
 #[tauri::command]
-pub async fn batch_delete_files(paths: Vec<String>) -> Result<Vec<BatchOperationResult>, String> {
+pub async fn batch_delete_files(
+    paths: Vec<String>,
+) -> Result<Vec<BatchOperationResult>, VoidDeskError> {
+    let _pause = file_watcher::pause_all_watchers();
     let mut results = Vec::new();
 
     for path in paths {
-        let path_obj = Path::new(&path);
-        let result = if path_obj.is_dir() {
-            fs::remove_dir_all(path_obj)
-        } else {
-            fs::remove_file(path_obj)
-        };
+        let result = workspace_roots::ensure_in_workspace(&path).and_then(|_| {
+            let path_obj = Path::new(&path);
+            let removed = if path_obj.is_dir() {
+                fs::remove_dir_all(path_obj)
+            } else {
+                fs::remove_file(path_obj)
+            };
+            removed.map_err(VoidDeskError::from)
+        });
 
         results.push(BatchOperationResult {
             path: path.clone(),
             success: result.is_ok(),
-            error: result.err().map(|e| e.to_string()),
+            error: result.err(),
         });
     }
 
@@ -79,33 +674,302 @@ pub struct BatchMoveOperation {
 #[tauri::command]
 pub async fn batch_move_files(
     operations: Vec<BatchMoveOperation>,
-) -> Result<Vec<BatchOperationResult>, String> {
+) -> Result<Vec<BatchOperationResult>, VoidDeskError> {
+    let _pause = file_watcher::pause_all_watchers();
     let mut results = Vec::new();
 
     for op in operations {
-        let result = fs::rename(&op.from, &op.to);
+        let result = workspace_roots::ensure_in_workspace(&op.from)
+            .and_then(|_| workspace_roots::ensure_in_workspace(&op.to))
+            .and_then(|_| fs::rename(&op.from, &op.to).map_err(VoidDeskError::from));
 
         results.push(BatchOperationResult {
             path: op.from,
             success: result.is_ok(),
-            error: result.err().map(|e| e.to_string()),
+            error: result.err(),
         });
     }
 
     Ok(results)
 }
 
+/// What to do when the destination of a copy already exists
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Replace files; directories are merged
+    Overwrite,
+    /// Copy to "name copy.ext", "name copy 2.ext", ...
+    #[default]
+    Rename,
+    Skip,
+}
+
+#[derive(serde::Serialize)]
+pub struct CopyResult {
+    /// Where the copy ended up
+    pub path: String,
+    /// False when the copy was skipped because the destination exists
+    pub copied: bool,
+}
+
+/// First free "name copy.ext" / "name copy N.ext" next to `path`
+fn unique_copy_path(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let is_dir = path.is_dir();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Directories and dotfiles keep their whole name as the stem
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(index) if index > 0 && !is_dir => (&file_name[..index], &file_name[index..]),
+        _ => (file_name.as_str(), ""),
+    };
+
+    let mut counter = 1;
+    loop {
+        let candidate = if counter == 1 {
+            parent.join(format!("{} copy{}", stem, extension))
+        } else {
+            parent.join(format!("{} copy {}{}", stem, counter, extension))
+        };
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Apply the conflict policy; `None` means the copy should be skipped
+fn resolve_copy_destination(to: &Path, conflict: ConflictPolicy) -> Option<PathBuf> {
+    if !to.exists() {
+        return Some(to.to_path_buf());
+    }
+    match conflict {
+        ConflictPolicy::Overwrite => Some(to.to_path_buf()),
+        ConflictPolicy::Rename => Some(unique_copy_path(to)),
+        ConflictPolicy::Skip => None,
+    }
+}
+
+fn copy_directory_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            copy_directory_recursive(&source, &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&source, &target)?;
+        } else {
+            fs::copy(&source, &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link = fs::read_link(source)?;
+    if fs::symlink_metadata(target).is_ok() {
+        fs::remove_file(target)?;
+    }
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    // Creating symlinks needs extra privileges on Windows, so copy what they point to
+    if source.is_dir() {
+        copy_directory_recursive(source, target)
+    } else {
+        fs::copy(source, target).map(|_| ())
+    }
+}
+
+fn copy_path(
+    from: &Path,
+    to: &Path,
+    conflict: ConflictPolicy,
+) -> Result<CopyResult, VoidDeskError> {
+    if !from.exists() {
+        return Err(VoidDeskError::not_found(format!(
+            "Path does not exist: {}",
+            from.display()
+        )));
+    }
+
+    let Some(destination) = resolve_copy_destination(to, conflict) else {
+        return Ok(CopyResult {
+            path: to.to_string_lossy().to_string(),
+            copied: false,
+        });
+    };
+
+    // Overwriting a path with itself would truncate it
+    if destination.exists() && fs::canonicalize(&destination).ok() == fs::canonicalize(from).ok() {
+        return Err(VoidDeskError::invalid_input(
+            "Source and destination are the same",
+        ));
+    }
+
+    if from.is_dir() {
+        // Copying a directory into itself would never finish
+        let source = fs::canonicalize(from)?;
+        let target_parent = destination
+            .parent()
+            .and_then(|parent| fs::canonicalize(parent).ok());
+        if target_parent.is_some_and(|parent| parent.starts_with(&source)) {
+            return Err(VoidDeskError::invalid_input(
+                "Cannot copy a folder into itself",
+            ));
+        }
+        copy_directory_recursive(from, &destination)?;
+    } else {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, &destination)?;
+    }
+
+    Ok(CopyResult {
+        path: destination.to_string_lossy().to_string(),
+        copied: true,
+    })
+}
+
+#[tauri::command]
+pub async fn copy_file(
+    from: String,
+    to: String,
+    conflict: Option<ConflictPolicy>,
+) -> Result<CopyResult, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&from)?;
+    workspace_roots::ensure_in_workspace(&to)?;
+    let from = Path::new(&from);
+    if from.is_dir() {
+        return Err(VoidDeskError::invalid_input(
+            "Use copy_directory to copy folders",
+        ));
+    }
+    copy_path(from, Path::new(&to), conflict.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn copy_directory(
+    from: String,
+    to: String,
+    conflict: Option<ConflictPolicy>,
+) -> Result<CopyResult, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&from)?;
+    workspace_roots::ensure_in_workspace(&to)?;
+    let from = Path::new(&from);
+    if !from.is_dir() {
+        return Err(VoidDeskError::invalid_input(format!(
+            "Not a directory: {}",
+            from.display()
+        )));
+    }
+    copy_path(from, Path::new(&to), conflict.unwrap_or_default())
+}
+
+/// Copy a file or folder next to itself as "name copy"; returns the new path
+#[tauri::command]
+pub async fn duplicate_path(path: String) -> Result<String, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let source = Path::new(&path);
+    let result = copy_path(source, source, ConflictPolicy::Rename)?;
+    Ok(result.path)
+}
+
+#[derive(serde::Serialize)]
+pub struct FilePermissions {
+    pub readonly: bool,
+    /// Unix permission bits such as 0o755; None on Windows
+    pub mode: Option<u32>,
+    pub executable: bool,
+}
+
+#[cfg(unix)]
+fn describe_permissions(_path: &Path, metadata: &fs::Metadata) -> FilePermissions {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = metadata.permissions().mode() & 0o7777;
+    FilePermissions {
+        readonly: mode & 0o222 == 0,
+        mode: Some(mode),
+        executable: !metadata.is_dir() && mode & 0o111 != 0,
+    }
+}
+
+#[cfg(not(unix))]
+fn describe_permissions(path: &Path, metadata: &fs::Metadata) -> FilePermissions {
+    // Windows has no executable bit; go by the extensions the shell will run
+    let executable = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ["exe", "bat", "cmd", "com", "ps1"]
+                .iter()
+                .any(|candidate| extension.eq_ignore_ascii_case(candidate))
+        });
+    FilePermissions {
+        readonly: metadata.permissions().readonly(),
+        mode: None,
+        executable,
+    }
+}
+
+#[cfg(unix)]
+fn apply_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn apply_mode(path: &Path, mode: u32) -> io::Result<()> {
+    // Only the owner write bit maps onto Windows, as the readonly attribute
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    fs::set_permissions(path, permissions)
+}
+
+#[tauri::command]
+pub async fn get_permissions(path: String) -> Result<FilePermissions, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let path = Path::new(&path);
+    let metadata = fs::metadata(path)?;
+    Ok(describe_permissions(path, &metadata))
+}
+
+/// chmod on Unix, e.g. 0o755 to make a script executable. On Windows only the
+/// readonly flag is changed, following the owner write bit.
+#[tauri::command]
+pub async fn set_permissions(path: String, mode: u32) -> Result<FilePermissions, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let path = Path::new(&path);
+    apply_mode(path, mode)?;
+    let metadata = fs::metadata(path)?;
+    Ok(describe_permissions(path, &metadata))
+}
+
 /// Reveal a file or folder in the system's file explorer
 /// Windows: opens explorer with the file selected
 /// macOS: uses open -R to reveal in Finder
 /// Linux: opens the parent directory with xdg-open
 #[tauri::command]
-pub async fn reveal_in_file_explorer(path: String) -> Result<(), String> {
-    let path = fs::canonicalize(&path).map_err(|e| e.to_string())?;
+pub async fn reveal_in_file_explorer(path: String) -> Result<(), VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let path = fs::canonicalize(&path)?;
     let path = path.as_path();
 
     if !path.exists() {
-        return Err("Path does not exist".to_string());
+        return Err(VoidDeskError::not_found("Path does not exist"));
     }
 
     let result = if cfg!(target_os = "windows") {
@@ -115,21 +979,16 @@ pub async fn reveal_in_file_explorer(path: String) -> Result<(), String> {
             Command::new("explorer")
                 .arg("/select,")
                 .arg(&windows_path)
-                .spawn()
-                .map_err(|e| e.to_string())?;
+                .spawn()?;
         } else {
-            Command::new("explorer")
-                .arg(&windows_path)
-                .spawn()
-                .map_err(|e| e.to_string())?;
+            Command::new("explorer").arg(&windows_path).spawn()?;
         }
         Ok(())
     } else if cfg!(target_os = "macos") {
         // macOS: use open -R to reveal in Finder
         Command::new("open")
             .args(["-R", &path.to_string_lossy()])
-            .spawn()
-            .map_err(|e| e.to_string())?;
+            .spawn()?;
         Ok(())
     } else {
         // Linux: open parent directory with xdg-open
@@ -139,13 +998,154 @@ pub async fn reveal_in_file_explorer(path: String) -> Result<(), String> {
             Some(path)
         };
         if let Some(parent) = parent {
-            Command::new("xdg-open")
-                .arg(parent)
-                .spawn()
-                .map_err(|e| e.to_string())?;
+            Command::new("xdg-open").arg(parent).spawn()?;
         }
         Ok(())
     };
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "voidesk-file-commands-{}-{}",
+            label,
+            uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn reports_file_stats() {
+        let dir = temp_dir("stats");
+        let script = dir.join("Dockerfile");
+        fs::write(&script, "FROM rust\nRUN cargo build").unwrap();
+        let stats = file_stats(&script).unwrap();
+        assert_eq!(stats.line_count, 2);
+        assert_eq!(stats.language, "dockerfile");
+        assert!(!stats.is_binary);
+
+        let blob = dir.join("blob.rs");
+        fs::write(&blob, b"\x00\x01\x02").unwrap();
+        let blob_stats = file_stats(&blob).unwrap();
+        assert!(blob_stats.is_binary);
+        assert_ne!(blob_stats.hash, stats.hash);
+        assert_eq!(stats.hash, workspace_index::hash_file(&script).unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sniffs_mime_from_content_before_extension() {
+        assert_eq!(
+            sniff_mime(b"\x89PNG\r\n\x1a\n\x00\x00", Path::new("logo.jpg")),
+            "image/png"
+        );
+        assert_eq!(
+            sniff_mime(b"RIFF\x10\x00\x00\x00WEBPVP8 ", Path::new("a.bin")),
+            "image/webp"
+        );
+        assert_eq!(sniff_mime(b"<svg", Path::new("icon.svg")), "image/svg+xml");
+    }
+
+    #[test]
+    fn detects_files_changed_on_disk() {
+        let dir = std::env::temp_dir().join(format!("voidesk-conflict-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.rs");
+        fs::write(&path, "fn main() {}").unwrap();
+        let loaded = file_version(&path).unwrap().unwrap();
+
+        assert!(check_for_conflict(&path, Some(&loaded.hash), None).is_ok());
+        assert!(check_for_conflict(&path, None, None).is_ok());
+
+        fs::write(&path, "fn main() { changed() }").unwrap();
+        let conflict = check_for_conflict(&path, Some(&loaded.hash), Some(loaded.mtime));
+        let conflict = conflict.unwrap_err();
+        assert_eq!(conflict.code, ErrorCode::Conflict);
+        let current_hash = &conflict.details.unwrap()["current_hash"];
+        assert!(current_hash.is_string() && *current_hash != loaded.hash.as_str());
+
+        fs::remove_file(&path).unwrap();
+        let deleted = check_for_conflict(&path, Some(&loaded.hash), None).unwrap_err();
+        assert_eq!(deleted.code, ErrorCode::Conflict);
+        assert!(deleted.details.unwrap()["current_hash"].is_null());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_writes_keep_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("voidesk-atomic-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("run.sh");
+        fs::write(&script, "echo old").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let link = dir.join("link.sh");
+        std::os::unix::fs::symlink(&script, &link).unwrap();
+
+        write_atomic(&link, b"echo new").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&script).unwrap(), "echo new");
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        write_atomic_private(&script, b"echo secret").unwrap();
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copies_with_conflict_policies() {
+        let dir = temp_dir("copy");
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("src/nested/lib.rs"), "").unwrap();
+
+        let renamed =
+            copy_path(&dir.join("src"), &dir.join("src"), ConflictPolicy::Rename).unwrap();
+        assert_eq!(Path::new(&renamed.path), dir.join("src copy"));
+        assert!(dir.join("src copy/nested/lib.rs").is_file());
+
+        let skipped = copy_path(
+            &dir.join("src/main.rs"),
+            &dir.join("src copy/main.rs"),
+            ConflictPolicy::Skip,
+        )
+        .unwrap();
+        assert!(!skipped.copied);
+
+        let duplicate = copy_path(
+            &dir.join("src/main.rs"),
+            &dir.join("src/main.rs"),
+            ConflictPolicy::Rename,
+        )
+        .unwrap();
+        assert_eq!(Path::new(&duplicate.path), dir.join("src/main copy.rs"));
+        assert_eq!(
+            unique_copy_path(&dir.join("src/main.rs")),
+            dir.join("src/main copy 2.rs")
+        );
+
+        assert!(copy_path(
+            &dir.join("src"),
+            &dir.join("src/nested/inner"),
+            ConflictPolicy::Rename
+        )
+        .is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
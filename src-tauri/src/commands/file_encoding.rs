@@ -0,0 +1,231 @@
+// File encodings
+// Detects BOMs and legacy encodings (Latin-1, Shift-JIS, UTF-16...) so such files open
+// in the editor as UTF-8 and are saved back in the encoding they were read with
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// How much of a file is inspected when guessing UTF-16 without a BOM
+const UTF16_SNIFF_BYTES: usize = 4096;
+
+/// Encoding a file was read with, reused when it is written back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+pub struct DecodedText {
+    pub content: String,
+    pub encoding: FileEncoding,
+    /// Some bytes were not valid in the encoding and became U+FFFD
+    pub had_errors: bool,
+}
+
+#[derive(Serialize)]
+pub struct EncodedFile {
+    pub content: String,
+    /// WHATWG name such as "UTF-8", "Shift_JIS" or "windows-1252"
+    pub encoding: String,
+    pub bom: bool,
+    pub had_errors: bool,
+}
+
+impl From<DecodedText> for EncodedFile {
+    fn from(decoded: DecodedText) -> Self {
+        Self {
+            content: decoded.content,
+            encoding: decoded.encoding.encoding.name().to_string(),
+            bom: decoded.encoding.bom,
+            had_errors: decoded.had_errors,
+        }
+    }
+}
+
+fn remembered_encodings() -> &'static Mutex<HashMap<String, FileEncoding>> {
+    static ENCODINGS: OnceLock<Mutex<HashMap<String, FileEncoding>>> = OnceLock::new();
+    ENCODINGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Remember how `path` was read; plain UTF-8 is the default and isn't stored
+pub fn remember(path: &str, encoding: FileEncoding) {
+    let mut encodings = remembered_encodings().lock().unwrap();
+    if encoding == FileEncoding::default() {
+        encodings.remove(path);
+    } else {
+        encodings.insert(path.to_string(), encoding);
+    }
+}
+
+pub fn remembered(path: &str) -> FileEncoding {
+    remembered_encodings()
+        .lock()
+        .unwrap()
+        .get(path)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Resolve a user-facing label like "latin1" or "Shift-JIS"
+pub fn lookup(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))
+}
+
+/// Decode bytes, trusting a BOM first, then UTF-16 and UTF-8 checks, then a statistical guess
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return decode_without_bom(&bytes[bom_length..], encoding, true);
+    }
+    // ASCII in UTF-16 is also valid UTF-8, so this has to come first
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return decode_without_bom(bytes, encoding, false);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return decode_without_bom(bytes, UTF_8, false);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    decode_without_bom(bytes, detector.guess(None, true), false)
+}
+
+/// Decode with an encoding the user picked, still skipping a matching BOM
+pub fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> DecodedText {
+    match Encoding::for_bom(bytes) {
+        Some((bom_encoding, bom_length)) if bom_encoding == encoding => {
+            decode_without_bom(&bytes[bom_length..], encoding, true)
+        }
+        _ => decode_without_bom(bytes, encoding, false),
+    }
+}
+
+fn decode_without_bom(bytes: &[u8], encoding: &'static Encoding, bom: bool) -> DecodedText {
+    let (content, had_errors) = encoding.decode_without_bom_handling(bytes);
+    DecodedText {
+        content: content.into_owned(),
+        encoding: FileEncoding { encoding, bom },
+        had_errors,
+    }
+}
+
+/// BOM-less UTF-16 shows up as NUL bytes in every other position of ASCII text
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SNIFF_BYTES)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 || !sample.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let even_nuls = sample.iter().step_by(2).filter(|byte| **byte == 0).count();
+    let odd_nuls = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|byte| **byte == 0)
+        .count();
+    if odd_nuls * 10 >= pairs * 4 && even_nuls * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_nuls * 10 >= pairs * 4 && odd_nuls * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Encode editor text back into `encoding`, refusing characters it can't represent
+pub fn encode(text: &str, encoding: FileEncoding) -> Result<Vec<u8>, String> {
+    let FileEncoding { encoding, bom } = encoding;
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+
+    // encoding_rs only encodes UTF-16 as UTF-8, so those two are done by hand
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little_endian = encoding == UTF_16LE;
+        if bom {
+            bytes.extend_from_slice(if little_endian {
+                b"\xff\xfe"
+            } else {
+                b"\xfe\xff"
+            });
+        }
+        for unit in text.encode_utf16() {
+            let pair = if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend_from_slice(&pair);
+        }
+        return Ok(bytes);
+    }
+
+    if bom && encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xef\xbb\xbf");
+    }
+    let (encoded, _, had_unmappable) = encoding.encode(text);
+    if had_unmappable {
+        let character = text
+            .chars()
+            .find(|c| {
+                let mut buffer = [0; 4];
+                encoding.encode(c.encode_utf8(&mut buffer)).2
+            })
+            .unwrap_or('\u{FFFD}');
+        return Err(format!(
+            "The character '{}' can't be saved as {}",
+            character,
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    #[test]
+    fn round_trips_boms_and_legacy_encodings() {
+        let utf16 = decode(b"\xff\xfeh\x00i\x00");
+        assert_eq!(utf16.content, "hi");
+        assert_eq!(
+            utf16.encoding,
+            FileEncoding {
+                encoding: UTF_16LE,
+                bom: true
+            }
+        );
+        assert_eq!(encode("hi", utf16.encoding).unwrap(), b"\xff\xfeh\x00i\x00");
+
+        let bomless = decode(b"a\x00b\x00c\x00d\x00");
+        assert_eq!(bomless.encoding.encoding, UTF_16LE);
+        assert_eq!(bomless.content, "abcd");
+
+        let latin1 = decode_with(b"caf\xe9", WINDOWS_1252);
+        assert_eq!(latin1.content, "café");
+        assert_eq!(encode("café", latin1.encoding).unwrap(), b"caf\xe9");
+
+        let japanese = FileEncoding {
+            encoding: SHIFT_JIS,
+            bom: false,
+        };
+        let bytes = encode("日本語", japanese).unwrap();
+        assert_eq!(decode_with(&bytes, SHIFT_JIS).content, "日本語");
+
+        assert!(encode("日本", latin1.encoding).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+// Quick open
+// Finds files by name in the workspace index, which the file watcher keeps current,
+// using either a glob or fuzzy subsequence matching
+
+use glob::{MatchOptions, Pattern};
+use serde::Serialize;
+
+use super::workspace_index;
+use super::workspace_roots;
+use crate::error::VoidDeskError;
+
+const DEFAULT_FIND_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct FileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub score: i64,
+    /// Char indices into `relative_path` that matched, for highlighting
+    pub positions: Vec<usize>,
+}
+
+// Fuzzy scoring weights, loosely modelled on fzf
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 24;
+const BONUS_BOUNDARY: i64 = 20;
+const BONUS_FILE_NAME: i64 = 12;
+const PENALTY_GAP: i64 = 1;
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+fn is_boundary(previous: Option<char>, current: char) -> bool {
+    match previous {
+        None => true,
+        Some(previous) => {
+            matches!(previous, '/' | '\\' | '_' | '-' | '.' | ' ')
+                || (previous.is_lowercase() && current.is_uppercase())
+        }
+    }
+}
+
+/// Score `query` as a subsequence of `candidate`; None when some character is missing.
+/// Matching prefers word boundaries, runs of consecutive characters and the file name.
+pub(crate) fn fuzzy_score(query: &[char], candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let file_name_start = chars
+        .iter()
+        .rposition(|c| *c == '/' || *c == '\\')
+        .map_or(0, |position| position + 1);
+
+    // Match from the back so the query lands in the file name whenever it can
+    let mut positions = Vec::with_capacity(query.len());
+    let mut index = chars.len();
+    for wanted in query.iter().rev() {
+        let found = chars[..index]
+            .iter()
+            .rposition(|c| c.to_lowercase().eq(wanted.to_lowercase()))?;
+        positions.push(found);
+        index = found;
+    }
+    positions.reverse();
+
+    let mut score = 0;
+    let mut previous_position: Option<usize> = None;
+    for &position in &positions {
+        score += SCORE_MATCH;
+        let previous_char = position.checked_sub(1).map(|i| chars[i]);
+        if is_boundary(previous_char, chars[position]) {
+            score += BONUS_BOUNDARY;
+        }
+        if position >= file_name_start {
+            score += BONUS_FILE_NAME;
+        }
+        match previous_position {
+            Some(previous) if previous + 1 == position => score += BONUS_CONSECUTIVE,
+            Some(previous) => score -= PENALTY_GAP * (position - previous - 1) as i64,
+            None => {}
+        }
+        previous_position = Some(position);
+    }
+    // Shorter paths win ties
+    score -= chars.len() as i64 / 8;
+
+    Some((score, positions))
+}
+
+fn find_indexed_files(
+    root: &str,
+    pattern: &str,
+    limit: usize,
+) -> Result<Vec<FileMatch>, VoidDeskError> {
+    let pattern = pattern.trim();
+    let mut matches = Vec::new();
+
+    if is_glob(pattern) {
+        let glob = Pattern::new(pattern)
+            .map_err(|e| VoidDeskError::invalid_input(format!("Invalid glob: {}", e)))?;
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        // Patterns without a slash match file names anywhere, like `*.rs`
+        let match_name = !pattern.contains('/');
+        workspace_index::for_each_indexed_file(root, |relative_path, path| {
+            let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            let target = if match_name { name } else { relative_path };
+            if glob.matches_with(target, options) {
+                matches.push(FileMatch {
+                    path: path.to_string(),
+                    relative_path: relative_path.to_string(),
+                    score: -(relative_path.len() as i64),
+                    positions: Vec::new(),
+                });
+            }
+        })?;
+    } else {
+        let query: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        workspace_index::for_each_indexed_file(root, |relative_path, path| {
+            if let Some((score, positions)) = fuzzy_score(&query, relative_path) {
+                matches.push(FileMatch {
+                    path: path.to_string(),
+                    relative_path: relative_path.to_string(),
+                    score,
+                    positions,
+                });
+            }
+        })?;
+    }
+
+    matches.sort_by(|left, right| {
+        right
+            .score
+            .cmp(&left.score)
+            .then_with(|| left.relative_path.cmp(&right.relative_path))
+    });
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Quick-open lookup: globs such as `src/**/*.ts` or fuzzy queries such as `fcmd`
+#[tauri::command]
+pub async fn find_files(
+    root: String,
+    pattern: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileMatch>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    let limit = limit.unwrap_or(DEFAULT_FIND_LIMIT);
+    tokio::task::spawn_blocking(move || find_indexed_files(&root, &pattern, limit)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> Option<i64> {
+        let query: Vec<char> = query.chars().collect();
+        fuzzy_score(&query, candidate).map(|(score, _)| score)
+    }
+
+    #[test]
+    fn ranks_file_name_and_boundary_matches_first() {
+        let query = "fcmd";
+        let name_hit = score(query, "src-tauri/src/commands/file_commands.rs").unwrap();
+        let scattered = score(query, "src/features/chat/models/data.ts").unwrap();
+        assert!(name_hit > scattered);
+
+        assert!(
+            score("main", "src/main.rs").unwrap() > score("main", "src/domain/index.ts").unwrap()
+        );
+        assert_eq!(score("xyz", "src/main.rs"), None);
+
+        let (_, positions) = fuzzy_score(&['m', 'r'], "src/main.rs").unwrap();
+        assert_eq!(positions, vec![4, 9]);
+    }
+}
@@ -1,50 +1,540 @@
 //! File system watcher for VoiDesk
-//! Watches the project directory and emits events when files change
+//! Watches project directories and emits events when files change. Several folders can
+//! be watched at once; each watcher has an id that is included in its events.
+//! Paths matched by the project's ignore rules (node_modules, target, .gitignore
+//! entries, `.voidesk/ignore` overrides, ...) are neither watched nor reported. A paused watcher keeps collecting
+//! changes and reports them as one batch when resumed.
+//! Watcher failures are reported as `watcher-error`, and lost events (an overflowed OS
+//! queue) as `watcher-resync-needed`, after which the frontend should reload the tree.
+//! Network drives and some WSL mounts send no native events; watchers can poll instead.
+//! Each watcher belongs to the window that started it: its events go to that window only,
+//! and stopping or listing "all" watchers means all of the calling window's.
 
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::Mutex;
+use lsp_types::FileChangeType;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tokio::sync::{mpsc, watch};
 
+use super::ignore_engine::{is_rules_file, IgnoreRules};
+use super::lsp_commands::LspState;
 use super::workspace_index;
+use super::workspace_roots;
+use crate::error::VoidDeskError;
+use crate::metrics;
+use crate::settings::{self, PROJECT_CONFIG_FILE};
+use crate::syntax::symbol_index;
 
-// Global watcher state
-static WATCHER: std::sync::OnceLock<Mutex<Option<WatcherState>>> = std::sync::OnceLock::new();
+// Global watcher state, keyed by watcher id
+static WATCHERS: std::sync::OnceLock<Mutex<HashMap<String, WatcherState>>> =
+    std::sync::OnceLock::new();
+
+/// inotify watches are per directory and limited, so on Linux each folder is watched on
+/// its own and ignored folders are skipped. Other platforms watch the tree natively.
+const WATCH_PER_DIRECTORY: bool = cfg!(target_os = "linux");
+
+const DEFAULT_DEBOUNCE_MS: u64 = 500;
+
+/// How often a polling watcher rescans the tree unless the caller picks an interval
+const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
+
+/// Native (inotify, FSEvents, ReadDirectoryChangesW) or polling watcher
+type DynWatcher = Box<dyn Watcher + Send>;
 
 struct WatcherState {
-    _watcher: RecommendedWatcher,
-    #[allow(dead_code)]
+    _watcher: Arc<Mutex<DynWatcher>>,
     watched_path: String,
+    /// Label of the window that started the watcher
+    window: String,
+    polling: bool,
+    pauses: watch::Sender<Pauses>,
+    health: Arc<Mutex<WatcherHealth>>,
+    /// Closed once the debounce task has exited
+    events: mpsc::Sender<Event>,
+}
+
+/// Active pauses of one watcher; events are held back while there are any. Pauses nest,
+/// so a batch operation running during an AI edit doesn't resume the watcher early, and
+/// the frontend's are counted apart from the backend's so neither can end the other's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Pauses {
+    /// From pause_file_watcher
+    requested: usize,
+    /// From WatcherPause guards
+    guards: usize,
+}
+
+impl Pauses {
+    fn active(&self) -> bool {
+        self.requested > 0 || self.guards > 0
+    }
+}
+
+#[derive(Debug, Default)]
+struct WatcherHealth {
+    /// Unix timestamp in milliseconds
+    last_event_at: Option<i64>,
+    last_error: Option<String>,
+    error_count: u64,
+    resync_count: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WatcherErrorEvent {
+    pub watcher_id: String,
+    pub message: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WatcherResyncEvent {
+    pub watcher_id: String,
+    pub reason: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WatcherStatus {
+    pub id: String,
+    pub path: String,
+    /// False when the debounce task has stopped and no more events will arrive
+    pub running: bool,
+    pub paused: bool,
+    pub polling: bool,
+    pub last_event_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub error_count: u64,
+    /// Times events were lost and a full refresh was requested
+    pub resync_count: u64,
+}
+
+fn get_watchers() -> &'static Mutex<HashMap<String, WatcherState>> {
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Apply `change` to the pauses of one watcher, or of every watcher of `window` when no
+/// id is given
+fn update_pauses(
+    window: &str,
+    id: Option<&str>,
+    change: impl Fn(&mut Pauses),
+) -> Result<(), String> {
+    let watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    match id {
+        Some(id) => {
+            let state = watchers
+                .get(id)
+                .ok_or_else(|| format!("No file watcher with id {}", id))?;
+            state.pauses.send_modify(&change);
+        }
+        None => watchers
+            .values()
+            .filter(|state| state.window == window)
+            .for_each(|state| state.pauses.send_modify(&change)),
+    }
+    Ok(())
+}
+
+/// Holds the watchers that were running when it was taken paused until dropped, so a
+/// batch of writes is reported once at the end instead of as a stream of events
+pub struct WatcherPause(Vec<String>);
+
+impl Drop for WatcherPause {
+    fn drop(&mut self) {
+        let Ok(watchers) = get_watchers().lock() else {
+            return;
+        };
+        for state in self.0.iter().filter_map(|id| watchers.get(id)) {
+            state
+                .pauses
+                .send_modify(|pauses| pauses.guards = pauses.guards.saturating_sub(1));
+        }
+    }
+}
+
+pub fn pause_all_watchers() -> WatcherPause {
+    let Ok(watchers) = get_watchers().lock() else {
+        return WatcherPause(Vec::new());
+    };
+    for state in watchers.values() {
+        state.pauses.send_modify(|pauses| pauses.guards += 1);
+    }
+    WatcherPause(watchers.keys().cloned().collect())
+}
+
+/// The project's ignore rules, reloaded when its .gitignore or overrides change
+struct WatchFilter {
+    root: PathBuf,
+    ignore_rules: IgnoreRules,
+}
+
+impl WatchFilter {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ignore_rules: IgnoreRules::load(root),
+        }
+    }
+
+    /// True when the path or any folder above it (below the root) is ignored.
+    /// Dotfiles such as .env stay visible so open files still reload.
+    fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(rel_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        self.ignore_rules
+            .is_ignored_path(&rel_path.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// Watch `dir` and every folder below it that isn't ignored, one directory at a time
+fn watch_directory_tree(watcher: &mut dyn Watcher, dir: &Path, filter: &WatchFilter) {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        if let Err(e) = watcher.watch(&current, RecursiveMode::NonRecursive) {
+            tracing::warn!("Failed to watch {}: {}", current.display(), e);
+            continue;
+        }
+        let Ok(read_dir) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            // Symlinked folders are not followed, so link cycles can't recurse forever
+            let is_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false);
+            let path = entry.path();
+            if is_dir && !filter.is_ignored(&path) {
+                pending.push(path);
+            }
+        }
+    }
+}
+
+/// What happened to a path over one debounce window
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Create,
+    Modify,
+    Remove,
 }
 
-fn get_watcher_state() -> &'static Mutex<Option<WatcherState>> {
-    WATCHER.get_or_init(|| Mutex::new(None))
+impl ChangeKind {
+    /// Combine an earlier change to a path with a later one; None when they cancel out,
+    /// like a temp file created and removed within the same window
+    fn then(self, later: ChangeKind) -> Option<ChangeKind> {
+        match (self, later) {
+            (ChangeKind::Create, ChangeKind::Remove) => None,
+            (ChangeKind::Create, _) => Some(ChangeKind::Create),
+            (ChangeKind::Remove, ChangeKind::Create) => Some(ChangeKind::Modify),
+            (_, later) => Some(later),
+        }
+    }
+}
+
+/// Stat taken when the change is reported; empty for removed paths
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct PathMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_dir: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Unix timestamp in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<i64>,
+}
+
+impl PathMetadata {
+    fn read(path: &Path) -> Self {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Self::default();
+        };
+        Self {
+            is_dir: Some(metadata.is_dir()),
+            size: metadata.is_file().then_some(metadata.len()),
+            mtime: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_millis() as i64),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct PathChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(flatten)]
+    pub metadata: PathMetadata,
 }
 
 #[derive(Clone, serde::Serialize)]
 pub struct FileChangeEvent {
-    pub event_type: String, // "create", "modify", "remove"
+    pub watcher_id: String,
+    pub event_type: String, // "change" or "rename"
+    /// Every path in `changes`, or the two paths of a rename
     pub paths: Vec<String>,
+    /// Typed change per path, in the order they were first seen; empty for renames
+    pub changes: Vec<PathChange>,
+    /// Old and new path, set for "rename" events only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+/// What one debounce window of raw notify events amounts to
+#[derive(Debug, Default)]
+struct DebouncedChanges {
+    changes: Vec<PathChange>,
+    renames: Vec<(String, String)>,
+    /// Created or renamed-to paths, which may be folders that need watching
+    new_paths: Vec<PathBuf>,
+    /// .gitignore, the ignore overrides or the project config changed
+    rules_changed: bool,
+    /// The project's .voidesk/config.json changed
+    config_changed: bool,
+}
+
+fn path_string(path: &Path) -> Option<String> {
+    path.to_str().map(str::to_string)
+}
+
+fn push_change(changes: &mut DebouncedChanges, path: &Path, kind: ChangeKind) {
+    let Some(path) = path_string(path) else {
+        return;
+    };
+    match changes
+        .changes
+        .iter()
+        .position(|change| change.path == path)
+    {
+        Some(index) => match changes.changes[index].kind.then(kind) {
+            Some(merged) => changes.changes[index].kind = merged,
+            None => {
+                changes.changes.remove(index);
+            }
+        },
+        None => changes.changes.push(PathChange {
+            path,
+            kind,
+            metadata: PathMetadata::default(),
+        }),
+    }
+}
+
+/// Merge a window of events, pairing the two halves of a rename. inotify links them
+/// with a cookie (the "tracker"); Windows reports them back to back without one.
+/// A half whose partner is outside the watched tree stays a create or remove.
+fn collect_changes(events: impl IntoIterator<Item = Event>, root: &Path) -> DebouncedChanges {
+    let mut changes = DebouncedChanges::default();
+    let mut pending_from: Option<(Option<usize>, PathBuf)> = None;
+    let mut paired_trackers = Vec::new();
+
+    for event in events {
+        if event.paths.iter().any(|path| is_rules_file(root, path)) {
+            changes.rules_changed = true;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|path| *path == root.join(PROJECT_CONFIG_FILE))
+        {
+            changes.config_changed = true;
+        }
+
+        let tracker = event.attrs.tracker();
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                if let Some((_, unpaired)) = pending_from.take() {
+                    push_change(&mut changes, &unpaired, ChangeKind::Remove);
+                }
+                if let Some(path) = event.paths.into_iter().next() {
+                    pending_from = Some((tracker, path));
+                }
+                continue;
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let Some(to) = event.paths.into_iter().next() else {
+                    continue;
+                };
+                match pending_from.take() {
+                    Some((from_tracker, from)) if from_tracker == tracker => {
+                        if let (Some(from), Some(to_string)) =
+                            (path_string(&from), path_string(&to))
+                        {
+                            changes.renames.push((from, to_string));
+                        }
+                        paired_trackers.extend(tracker);
+                        changes.new_paths.push(to);
+                    }
+                    unrelated => {
+                        pending_from = unrelated;
+                        push_change(&mut changes, &to, ChangeKind::Create);
+                        changes.new_paths.push(to);
+                    }
+                }
+                continue;
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                // inotify also sends From and To for the same cookie
+                if tracker.is_some_and(|tracker| paired_trackers.contains(&tracker)) {
+                    continue;
+                }
+                if let (Some(from), Some(to)) =
+                    (path_string(&event.paths[0]), path_string(&event.paths[1]))
+                {
+                    changes.renames.push((from, to));
+                }
+                changes.new_paths.push(event.paths[1].clone());
+                continue;
+            }
+            EventKind::Create(_) => changes.new_paths.extend(event.paths.iter().cloned()),
+            EventKind::Remove(_) | EventKind::Modify(_) => {}
+            _ => continue,
+        }
+
+        let kind = match event.kind {
+            EventKind::Create(_) => ChangeKind::Create,
+            EventKind::Remove(_) => ChangeKind::Remove,
+            _ => ChangeKind::Modify,
+        };
+        for path in &event.paths {
+            push_change(&mut changes, path, kind);
+        }
+    }
+
+    // A rename whose other half never arrived moved the path out of the tree
+    if let Some((_, from)) = pending_from {
+        push_change(&mut changes, &from, ChangeKind::Remove);
+    }
+    changes
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WatcherInfo {
+    pub id: String,
+    pub path: String,
+    pub paused: bool,
+    pub polling: bool,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherOptions {
+    /// How long to wait for more events before emitting; defaults to 500ms
+    pub debounce_ms: Option<u64>,
+    /// Scan for changes on an interval instead of relying on OS notifications
+    #[serde(default)]
+    pub poll: bool,
+    /// Time between scans when polling; defaults to 2s
+    pub poll_interval_ms: Option<u64>,
 }
 
+/// Start watching `path` alongside any other watchers; returns the new watcher's id
 #[tauri::command]
-pub async fn start_file_watcher(app: AppHandle, path: String) -> Result<(), String> {
-    // Stop any existing watcher first
-    stop_file_watcher_internal()?;
+pub async fn start_file_watcher(
+    app: AppHandle,
+    window: WebviewWindow,
+    path: String,
+    options: Option<WatcherOptions>,
+) -> Result<String, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let options = options.unwrap_or_default();
+    let debounce_ms = options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS);
+    let polling = options.poll;
+    // A poller scans the whole tree itself, so there are no per-directory watch limits
+    let per_directory = WATCH_PER_DIRECTORY && !polling;
 
+    let watcher_id = uuid::Uuid::new_v4().to_string();
+    let window = window.label().to_string();
     let watch_path = path.clone();
     let index_root = watch_path.clone();
+    let filter = Arc::new(RwLock::new(WatchFilter::new(Path::new(&watch_path))));
 
     // Create a channel for debouncing
     let (tx, mut rx) = mpsc::channel::<Event>(100);
 
+    // Create the watcher; events under ignored paths are dropped before debouncing
+    let tx_clone = tx.clone();
+    let filter_for_events = Arc::clone(&filter);
+    let health = Arc::new(Mutex::new(WatcherHealth::default()));
+    let health_for_events = Arc::clone(&health);
+    let app_for_events = app.clone();
+    let window_for_events = window.clone();
+    let id_for_events = watcher_id.clone();
+    let handler = move |res: Result<Event, notify::Error>| match res {
+        Ok(mut event) => {
+            let need_rescan = event.need_rescan();
+            if let Ok(mut health) = health_for_events.lock() {
+                health.last_event_at = Some(chrono::Utc::now().timestamp_millis());
+                if need_rescan {
+                    health.resync_count += 1;
+                }
+            }
+            if need_rescan {
+                tracing::warn!("Events were dropped, requesting a resync");
+                let _ = app_for_events.emit_to(
+                    &window_for_events,
+                    "watcher-resync-needed",
+                    WatcherResyncEvent {
+                        watcher_id: id_for_events.clone(),
+                        reason: "The file system event queue overflowed".to_string(),
+                    },
+                );
+            }
+
+            if let Ok(filter) = filter_for_events.read() {
+                event.paths.retain(|path| !filter.is_ignored(path));
+            }
+            if !event.paths.is_empty() {
+                let _ = tx_clone.blocking_send(event);
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Watch error: {}", e);
+            if let Ok(mut health) = health_for_events.lock() {
+                health.last_error = Some(e.to_string());
+                health.error_count += 1;
+            }
+            let _ = app_for_events.emit_to(
+                &window_for_events,
+                "watcher-error",
+                WatcherErrorEvent {
+                    watcher_id: id_for_events.clone(),
+                    message: e.to_string(),
+                },
+            );
+        }
+    };
+    let watcher: DynWatcher = if polling {
+        let interval = options.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS);
+        let config = Config::default().with_poll_interval(Duration::from_millis(interval));
+        Box::new(
+            PollWatcher::new(handler, config)
+                .map_err(|e| format!("Failed to create watcher: {}", e))?,
+        )
+    } else {
+        Box::new(
+            RecommendedWatcher::new(handler, Config::default())
+                .map_err(|e| format!("Failed to create watcher: {}", e))?,
+        )
+    };
+    let watcher = Arc::new(Mutex::new(watcher));
+
     // Spawn debounce task
     let app_for_emit = app.clone();
+    let window_for_emit = window.clone();
+    // Only a weak handle, so dropping the watcher closes the channel and ends the task
+    let watcher_for_task = Arc::downgrade(&watcher);
+    let filter_for_task = Arc::clone(&filter);
+    let id_for_task = watcher_id.clone();
+    let (pauses, mut pauses_for_task) = watch::channel(Pauses::default());
     tokio::spawn(async move {
         let mut pending_events: Vec<Event> = Vec::new();
-        let debounce_duration = Duration::from_millis(500);
+        let debounce_duration = Duration::from_millis(debounce_ms);
 
         loop {
             tokio::select! {
@@ -54,80 +544,384 @@ pub async fn start_file_watcher(app: AppHandle, path: String) -> Result<(), Stri
                         None => break,
                     }
                 }
-                _ = tokio::time::sleep(debounce_duration), if !pending_events.is_empty() => {
+                // Wakes the loop on resume so held-back events go out
+                Ok(()) = pauses_for_task.changed() => {}
+                _ = tokio::time::sleep(debounce_duration),
+                    if !pending_events.is_empty() && !pauses_for_task.borrow().active() => {
                     // Process accumulated events
-                    let mut paths: Vec<String> = Vec::new();
-                    let mut event_type = "modify".to_string();
-
-                    for event in pending_events.drain(..) {
-                        match event.kind {
-                            EventKind::Create(_) => event_type = "create".to_string(),
-                            EventKind::Remove(_) => event_type = "remove".to_string(),
-                            EventKind::Modify(_) => {},
-                            _ => continue,
+                    metrics::increment("watcher.events", None, pending_events.len() as u64);
+                    metrics::increment("watcher.batches", None, 1);
+                    let mut changes = collect_changes(pending_events.drain(..), Path::new(&index_root));
+
+                    if changes.rules_changed {
+                        if let Ok(mut filter) = filter_for_task.write() {
+                            *filter = WatchFilter::new(Path::new(&index_root));
                         }
+                    }
+                    if changes.config_changed {
+                        settings::project_config_changed(&app_for_emit, Path::new(&index_root))
+                            .await;
+                    }
 
-                        for path in event.paths {
-                            if let Some(p) = path.to_str() {
-                                if !paths.contains(&p.to_string()) {
-                                    paths.push(p.to_string());
-                                }
+                    // New folders need their own watches when watching per directory
+                    if per_directory {
+                        let watcher = watcher_for_task.upgrade();
+                        for created in changes.new_paths.iter().filter(|path| path.is_dir()) {
+                            if let (Some(Ok(mut watcher)), Ok(filter)) = (
+                                watcher.as_ref().map(|watcher| watcher.lock()),
+                                filter_for_task.read(),
+                            ) {
+                                watch_directory_tree(&mut **watcher, created, &filter);
                             }
                         }
                     }
 
-                    if !paths.is_empty() {
-                        let _ = workspace_index::apply_file_changes(&index_root, &paths);
-                        let _ = app_for_emit.emit("file-change", FileChangeEvent {
-                            event_type,
-                            paths,
-                        });
+                    for change in &mut changes.changes {
+                        if change.kind != ChangeKind::Remove {
+                            change.metadata = PathMetadata::read(Path::new(&change.path));
+                        }
+                    }
+
+                    let paths: Vec<String> =
+                        changes.changes.iter().map(|change| change.path.clone()).collect();
+                    let mut index_paths = paths.clone();
+                    let mut lsp_changes: Vec<(String, FileChangeType)> = changes
+                        .changes
+                        .iter()
+                        // A folder's mtime changes whenever its entries do; that's no news
+                        .filter(|change| {
+                            change.kind != ChangeKind::Modify || change.metadata.is_dir != Some(true)
+                        })
+                        .map(|change| {
+                            let change_type = match change.kind {
+                                ChangeKind::Create => FileChangeType::CREATED,
+                                ChangeKind::Remove => FileChangeType::DELETED,
+                                ChangeKind::Modify => FileChangeType::CHANGED,
+                            };
+                            (change.path.clone(), change_type)
+                        })
+                        .collect();
+
+                    for (from, to) in &changes.renames {
+                        index_paths.push(from.clone());
+                        index_paths.push(to.clone());
+                        lsp_changes.push((from.clone(), FileChangeType::DELETED));
+                        lsp_changes.push((to.clone(), FileChangeType::CREATED));
+                        let _ = app_for_emit.emit_to(
+                            &window_for_emit,
+                            "file-change",
+                            FileChangeEvent {
+                                watcher_id: id_for_task.clone(),
+                                event_type: "rename".to_string(),
+                                paths: vec![from.clone(), to.clone()],
+                                changes: Vec::new(),
+                                from: Some(from.clone()),
+                                to: Some(to.clone()),
+                            },
+                        );
+                    }
+
+                    if !changes.changes.is_empty() {
+                        let _ = app_for_emit.emit_to(
+                            &window_for_emit,
+                            "file-change",
+                            FileChangeEvent {
+                                watcher_id: id_for_task.clone(),
+                                event_type: "change".to_string(),
+                                paths,
+                                changes: changes.changes,
+                                from: None,
+                                to: None,
+                            },
+                        );
+                    }
+
+                    if !index_paths.is_empty() {
+                        let _ = workspace_index::apply_file_changes(&index_root, &index_paths);
+                        symbol_index::update_files(&index_root, &index_paths);
+                        let lsp_manager = app_for_emit
+                            .try_state::<LspState>()
+                            .map(|state| state.manager(&window_for_emit));
+                        if let Some(manager) = lsp_manager {
+                            if let Err(e) = manager.did_change_watched_files(lsp_changes).await {
+                                tracing::warn!("Failed to notify LSP: {}", e);
+                            }
+                        }
                     }
                 }
             }
         }
     });
 
-    // Create the watcher
-    let tx_clone = tx.clone();
-    let mut watcher = RecommendedWatcher::new(
-        move |res: Result<Event, notify::Error>| {
-            if let Ok(event) = res {
-                let _ = tx_clone.blocking_send(event);
-            }
-        },
-        Config::default(),
-    )
-    .map_err(|e| format!("Failed to create watcher: {}", e))?;
-
     // Start watching
-    watcher
-        .watch(Path::new(&watch_path), RecursiveMode::Recursive)
-        .map_err(|e| format!("Failed to watch path: {}", e))?;
+    {
+        let mut watcher = watcher.lock().map_err(|e| e.to_string())?;
+        if per_directory {
+            watcher
+                .watch(Path::new(&watch_path), RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch path: {}", e))?;
+            let filter = filter.read().map_err(|e| e.to_string())?;
+            watch_directory_tree(&mut **watcher, Path::new(&watch_path), &filter);
+        } else {
+            watcher
+                .watch(Path::new(&watch_path), RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch path: {}", e))?;
+        }
+    }
 
     // Store the watcher
-    let mut state = get_watcher_state().lock().map_err(|e| e.to_string())?;
-    *state = Some(WatcherState {
-        _watcher: watcher,
-        watched_path: path,
-    });
+    let mut watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    watchers.insert(
+        watcher_id.clone(),
+        WatcherState {
+            _watcher: watcher,
+            watched_path: path,
+            window,
+            polling,
+            pauses,
+            health,
+            events: tx,
+        },
+    );
 
+    Ok(watcher_id)
+}
+
+/// Stop one watcher, or every watcher of the calling window when no id is given
+#[tauri::command]
+pub async fn stop_file_watcher(
+    window: WebviewWindow,
+    id: Option<String>,
+) -> Result<(), VoidDeskError> {
+    match id {
+        Some(id) => {
+            get_watchers()
+                .lock()
+                .map_err(|e| e.to_string())?
+                .remove(&id);
+        }
+        None => stop_window_watchers(window.label())?,
+    }
     Ok(())
 }
 
-fn stop_file_watcher_internal() -> Result<(), String> {
-    let mut state = get_watcher_state().lock().map_err(|e| e.to_string())?;
-    *state = None;
+/// Stop every watcher a window started, for a window that has closed
+pub fn stop_window_watchers(window: &str) -> Result<(), String> {
+    let mut watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    watchers.retain(|_, state| state.window != window);
     Ok(())
 }
 
+/// Stop a window's watchers of `root` and of folders inside it, for a closed workspace
+pub fn stop_watchers_under(window: &str, root: &Path) -> Result<(), String> {
+    let mut watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    watchers.retain(|_, state| {
+        if state.window != window {
+            return true;
+        }
+        let watched = Path::new(&state.watched_path);
+        let watched = watched
+            .canonicalize()
+            .unwrap_or_else(|_| watched.to_path_buf());
+        !watched.starts_with(root)
+    });
+    Ok(())
+}
+
+/// Whether the given watcher is running, or any of the calling window's when no id is
+/// given
+#[tauri::command]
+pub async fn is_watching(window: WebviewWindow, id: Option<String>) -> Result<bool, VoidDeskError> {
+    let watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    Ok(match id {
+        Some(id) => watchers.contains_key(&id),
+        None => watchers
+            .values()
+            .any(|state| state.window == window.label()),
+    })
+}
+
+/// The calling window's watchers
+#[tauri::command]
+pub async fn list_file_watchers(window: WebviewWindow) -> Result<Vec<WatcherInfo>, VoidDeskError> {
+    let watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    Ok(watchers
+        .iter()
+        .filter(|(_, state)| state.window == window.label())
+        .map(|(id, state)| WatcherInfo {
+            id: id.clone(),
+            path: state.watched_path.clone(),
+            paused: state.pauses.borrow().active(),
+            polling: state.polling,
+        })
+        .collect())
+}
+
+fn status_of(id: String, state: &WatcherState) -> Result<WatcherStatus, String> {
+    let health = state.health.lock().map_err(|e| e.to_string())?;
+    Ok(WatcherStatus {
+        id,
+        path: state.watched_path.clone(),
+        running: !state.events.is_closed(),
+        paused: state.pauses.borrow().active(),
+        polling: state.polling,
+        last_event_at: health.last_event_at,
+        last_error: health.last_error.clone(),
+        error_count: health.error_count,
+        resync_count: health.resync_count,
+    })
+}
+
+/// Status of every watcher, sorted by id
+pub fn watcher_statuses() -> Result<Vec<WatcherStatus>, String> {
+    let watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    let mut statuses = watchers
+        .iter()
+        .map(|(id, state)| status_of(id.clone(), state))
+        .collect::<Result<Vec<_>, _>>()?;
+    statuses.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(statuses)
+}
+
 #[tauri::command]
-pub async fn stop_file_watcher() -> Result<(), String> {
-    stop_file_watcher_internal()
+pub async fn watcher_status(id: String) -> Result<WatcherStatus, VoidDeskError> {
+    let watchers = get_watchers().lock().map_err(|e| e.to_string())?;
+    let state = watchers
+        .get(&id)
+        .ok_or_else(|| VoidDeskError::not_found(format!("No file watcher with id {}", id)))?;
+    Ok(status_of(id, state)?)
 }
 
+/// Hold back events from a watcher (every watcher of the calling window when no id is
+/// given) until resume_file_watcher is called; changes made meanwhile are reported as one
+/// batch
 #[tauri::command]
-pub async fn is_watching() -> Result<bool, String> {
-    let state = get_watcher_state().lock().map_err(|e| e.to_string())?;
-    Ok(state.is_some())
+pub async fn pause_file_watcher(
+    window: WebviewWindow,
+    id: Option<String>,
+) -> Result<(), VoidDeskError> {
+    Ok(update_pauses(window.label(), id.as_deref(), |pauses| {
+        pauses.requested += 1
+    })?)
+}
+
+/// Undo one pause_file_watcher; pauses held by backend operations stay in place
+#[tauri::command]
+pub async fn resume_file_watcher(
+    window: WebviewWindow,
+    id: Option<String>,
+) -> Result<(), VoidDeskError> {
+    Ok(update_pauses(window.label(), id.as_deref(), |pauses| {
+        pauses.requested = pauses.requested.saturating_sub(1)
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_ignores_paths_inside_ignored_folders() {
+        let filter = WatchFilter {
+            root: PathBuf::from("/repo"),
+            ignore_rules: IgnoreRules {
+                rules: vec!["node_modules".to_string(), "*.log".to_string()],
+                exceptions: Vec::new(),
+            },
+        };
+        assert!(filter.is_ignored(Path::new("/repo/node_modules/react/index.js")));
+        assert!(filter.is_ignored(Path::new("/repo/logs/server.log")));
+        assert!(!filter.is_ignored(Path::new("/repo/src/main.rs")));
+        assert!(!filter.is_ignored(Path::new("/repo/.env")));
+        assert!(!filter.is_ignored(Path::new("/elsewhere/node_modules")));
+    }
+
+    fn rename_event(mode: RenameMode, paths: &[&str], tracker: Option<usize>) -> Event {
+        let mut event = Event::new(EventKind::Modify(ModifyKind::Name(mode)));
+        for path in paths {
+            event = event.add_path(PathBuf::from(path));
+        }
+        match tracker {
+            Some(tracker) => event.set_tracker(tracker),
+            None => event,
+        }
+    }
+
+    #[test]
+    fn pairs_rename_halves_into_one_rename() {
+        let root = Path::new("/repo");
+        // inotify: From and To share a cookie, followed by a Both event
+        let inotify = collect_changes(
+            vec![
+                rename_event(RenameMode::From, &["/repo/a.rs"], Some(7)),
+                rename_event(RenameMode::To, &["/repo/b.rs"], Some(7)),
+                rename_event(RenameMode::Both, &["/repo/a.rs", "/repo/b.rs"], Some(7)),
+            ],
+            root,
+        );
+        assert_eq!(
+            inotify.renames,
+            vec![("/repo/a.rs".to_string(), "/repo/b.rs".to_string())]
+        );
+        assert!(inotify.changes.is_empty());
+
+        // Windows: back to back without a cookie
+        let windows = collect_changes(
+            vec![
+                rename_event(RenameMode::From, &["/repo/old"], None),
+                rename_event(RenameMode::To, &["/repo/new"], None),
+            ],
+            root,
+        );
+        assert_eq!(windows.renames.len(), 1);
+
+        // Moved out of the watched tree
+        let moved_out = collect_changes(
+            vec![rename_event(RenameMode::From, &["/repo/gone.rs"], Some(3))],
+            root,
+        );
+        assert!(moved_out.renames.is_empty());
+        assert_eq!(
+            moved_out.changes,
+            vec![PathChange {
+                path: "/repo/gone.rs".to_string(),
+                kind: ChangeKind::Remove,
+                metadata: PathMetadata::default(),
+            }]
+        );
+    }
+
+    #[test]
+    fn keeps_a_typed_change_per_path() {
+        let event = |kind: EventKind, path: &str| Event::new(kind).add_path(PathBuf::from(path));
+        let create = EventKind::Create(notify::event::CreateKind::File);
+        let modify = EventKind::Modify(ModifyKind::Any);
+        let remove = EventKind::Remove(notify::event::RemoveKind::File);
+
+        let changes = collect_changes(
+            vec![
+                event(create, "/repo/new.rs"),
+                event(modify, "/repo/new.rs"),
+                event(remove, "/repo/old.rs"),
+                event(create, "/repo/tmp.swp"),
+                event(remove, "/repo/tmp.swp"),
+                event(remove, "/repo/saved.rs"),
+                event(create, "/repo/saved.rs"),
+            ],
+            Path::new("/repo"),
+        );
+        let kinds: Vec<(&str, ChangeKind)> = changes
+            .changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("/repo/new.rs", ChangeKind::Create),
+                ("/repo/old.rs", ChangeKind::Remove),
+                ("/repo/saved.rs", ChangeKind::Modify),
+            ]
+        );
+    }
 }
@@ -0,0 +1,394 @@
+// Formatting
+// Runs the usual command-line formatter for a file (rustfmt, prettier, black or gofmt)
+// on the editor's text, so format-on-save works where the language server can't format.
+// Each formatter runs from the file's folder and finds the project's own config there;
+// only files in the open workspace are formatted, with tools found inside it.
+
+use regex::Regex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use super::workspace_roots;
+
+/// A formatter still running after this long is stopped
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Formatter {
+    Rustfmt,
+    Prettier,
+    Black,
+    Gofmt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatError {
+    pub message: String,
+    /// 1-based, when the formatter reported where
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatResult {
+    pub formatter: &'static str,
+    /// The formatted text; None when the formatter failed
+    pub content: Option<String>,
+    pub changed: bool,
+    pub errors: Vec<FormatError>,
+}
+
+impl Formatter {
+    fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        let formatter = match extension.as_str() {
+            "rs" => Self::Rustfmt,
+            "py" | "pyi" => Self::Black,
+            "go" => Self::Gofmt,
+            "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" | "json" | "jsonc"
+            | "css" | "scss" | "less" | "html" | "vue" | "md" | "mdx" | "yaml" | "yml"
+            | "graphql" => Self::Prettier,
+            _ => return None,
+        };
+        Some(formatter)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Rustfmt => "rustfmt",
+            Self::Prettier => "prettier",
+            Self::Black => "black",
+            Self::Gofmt => "gofmt",
+        }
+    }
+
+    /// Where a project keeps its own copy, relative to a folder above the file
+    fn project_binaries(self) -> &'static [&'static str] {
+        match (self, cfg!(windows)) {
+            (Self::Prettier, false) => &["node_modules/.bin/prettier"],
+            (Self::Prettier, true) => &["node_modules/.bin/prettier.cmd"],
+            (Self::Black, false) => &[".venv/bin/black", "venv/bin/black"],
+            (Self::Black, true) => &[".venv/Scripts/black.exe", "venv/Scripts/black.exe"],
+            _ => &[],
+        }
+    }
+
+    fn program(self, dir: &Path, root: &Path) -> PathBuf {
+        project_program(dir, root, self.project_binaries(), self.name())
+    }
+
+    fn args(self, path: &Path, dir: &Path, root: &Path) -> Vec<String> {
+        let path = path.to_string_lossy().to_string();
+        match self {
+            Self::Rustfmt => {
+                let mut args = vec!["--emit".to_string(), "stdout".to_string()];
+                if let Some(edition) = cargo_edition(dir, root) {
+                    args.extend(["--edition".to_string(), edition]);
+                }
+                args
+            }
+            // The file path lets prettier pick the parser and find .prettierrc and
+            // .prettierignore
+            Self::Prettier => vec!["--stdin-filepath".to_string(), path],
+            Self::Black => vec![
+                "--quiet".to_string(),
+                "--stdin-filename".to_string(),
+                path,
+                "-".to_string(),
+            ],
+            Self::Gofmt => Vec::new(),
+        }
+    }
+}
+
+/// `dir` and the folders above it, stopping at the workspace `root`
+pub(crate) fn folders_up_to<'a>(dir: &'a Path, root: &'a Path) -> impl Iterator<Item = &'a Path> {
+    dir.ancestors()
+        .take_while(move |folder| folder.starts_with(root))
+}
+
+/// The project's copy of a tool, the first of `binaries` under a folder from `dir` up to
+/// `root`, else `name` from PATH
+pub(crate) fn project_program(dir: &Path, root: &Path, binaries: &[&str], name: &str) -> PathBuf {
+    folders_up_to(dir, root)
+        .flat_map(|folder| binaries.iter().map(move |binary| folder.join(binary)))
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Run `program` in `dir` and collect its output, writing `input` to its stdin. `name`
+/// is the tool's name for errors; it is stopped after `timeout`.
+pub(crate) async fn run_tool(
+    name: &str,
+    program: &Path,
+    args: &[String],
+    dir: &Path,
+    input: Option<String>,
+    timeout: Duration,
+) -> Result<Output, String> {
+    // npm installs tools as .cmd scripts on Windows, which only cmd can start
+    let mut command = if cfg!(windows) && !program.to_string_lossy().ends_with(".exe") {
+        let mut command = tokio::process::Command::new("cmd");
+        command.arg("/C").arg(program);
+        command
+    } else {
+        tokio::process::Command::new(program)
+    };
+    let mut child = command
+        .args(args)
+        .current_dir(dir)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("{} is not installed or not on PATH", name),
+            _ => format!("Could not start {}: {}", name, e),
+        })?;
+
+    // Write while the output is read, so a large file can't fill both pipes
+    let writer = match (input, child.stdin.take()) {
+        (Some(input), Some(mut stdin)) => Some(tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        })),
+        _ => None,
+    };
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| {
+            format!(
+                "{} did not finish within {} seconds",
+                name,
+                timeout.as_secs()
+            )
+        })?
+        .map_err(|e| e.to_string())?;
+    if let Some(writer) = writer {
+        let _ = writer.await;
+    }
+    Ok(output)
+}
+
+/// The edition of the nearest Cargo.toml, which rustfmt can't see when reading stdin
+fn cargo_edition(dir: &Path, root: &Path) -> Option<String> {
+    static EDITION: OnceLock<Regex> = OnceLock::new();
+    let pattern = EDITION.get_or_init(|| Regex::new(r#"(?m)^\s*edition\s*=\s*"(\d{4})""#).unwrap());
+    let manifest = folders_up_to(dir, root)
+        .map(|folder| folder.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())?;
+    let content = std::fs::read_to_string(manifest).ok()?;
+    Some(pattern.captures(&content)?[1].to_string())
+}
+
+/// Line, column and message of each error the formatter printed
+fn parse_errors(formatter: Formatter, stderr: &str) -> Vec<FormatError> {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [rustfmt_location, prettier, black, gofmt] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"^\s*--> .*:(\d+):(\d+)$").unwrap(),
+            Regex::new(r"^\[error\] (?:[^:]+: )?(.*?) \((\d+):(\d+)\)$").unwrap(),
+            Regex::new(r"Cannot parse[^:]*: (\d+):(\d+): (.*)$").unwrap(),
+            Regex::new(r"^<standard input>:(\d+):(\d+): (.*)$").unwrap(),
+        ]
+    });
+    let number = |text: &str| text.parse::<u32>().ok();
+
+    let mut errors: Vec<FormatError> = Vec::new();
+    for line in stderr.lines() {
+        match formatter {
+            Formatter::Rustfmt => {
+                if let Some(message) = line.strip_prefix("error") {
+                    let message = message.split_once(": ").map_or(message, |(_, rest)| rest);
+                    errors.push(FormatError {
+                        message: message.trim().to_string(),
+                        line: None,
+                        column: None,
+                    });
+                } else if let (Some(captures), Some(error)) =
+                    (rustfmt_location.captures(line), errors.last_mut())
+                {
+                    if error.line.is_none() {
+                        error.line = number(&captures[1]);
+                        error.column = number(&captures[2]);
+                    }
+                }
+            }
+            Formatter::Prettier => {
+                if let Some(captures) = prettier.captures(line) {
+                    errors.push(FormatError {
+                        message: captures[1].to_string(),
+                        line: number(&captures[2]),
+                        column: number(&captures[3]),
+                    });
+                }
+            }
+            Formatter::Black | Formatter::Gofmt => {
+                let pattern = if formatter == Formatter::Black {
+                    black
+                } else {
+                    gofmt
+                };
+                if let Some(captures) = pattern.captures(line) {
+                    errors.push(FormatError {
+                        message: captures[3].to_string(),
+                        line: number(&captures[1]),
+                        column: number(&captures[2]),
+                    });
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() && !stderr.trim().is_empty() {
+        errors.push(FormatError {
+            message: stderr.trim().to_string(),
+            line: None,
+            column: None,
+        });
+    }
+    errors
+}
+
+async fn run_formatter(
+    formatter: Formatter,
+    path: &Path,
+    root: &Path,
+    content: &str,
+) -> Result<FormatResult, String> {
+    // A new file's folder may not exist yet; the nearest one that does still has the config
+    let dir = path
+        .ancestors()
+        .skip(1)
+        .find(|folder| folder.is_dir())
+        .and_then(|folder| folder.canonicalize().ok())
+        .unwrap_or_else(|| root.to_path_buf());
+    let output = run_tool(
+        formatter.name(),
+        &formatter.program(&dir, root),
+        &formatter.args(path, &dir, root),
+        &dir,
+        Some(content.to_string()),
+        FORMAT_TIMEOUT,
+    )
+    .await?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Ok(FormatResult {
+            formatter: formatter.name(),
+            content: None,
+            changed: false,
+            errors: parse_errors(formatter, &stderr),
+        });
+    }
+    let formatted = String::from_utf8(output.stdout)
+        .map_err(|_| format!("{} printed invalid UTF-8", formatter.name()))?;
+    Ok(FormatResult {
+        formatter: formatter.name(),
+        changed: formatted != content,
+        content: Some(formatted),
+        errors: Vec::new(),
+    })
+}
+
+/// Format `content`, or the file on disk when none is given, without writing anything.
+/// Syntax errors come back in `errors`; a missing formatter is an error.
+#[tauri::command]
+pub async fn format_file(path: String, content: Option<String>) -> Result<FormatResult, String> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let root = workspace_roots::containing_root(&path)
+        .ok_or_else(|| format!("{} is outside the open workspace", path))?;
+    let path = PathBuf::from(path);
+    let formatter = Formatter::for_path(&path)
+        .ok_or_else(|| format!("No formatter is known for {}", path.display()))?;
+    let content = match content {
+        Some(content) => content,
+        None => tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+    run_formatter(formatter, &path, &root, &content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn finds_project_formatters_and_reads_their_errors() {
+        let dir = std::env::temp_dir().join(format!("voidesk-format-{}", uuid::Uuid::new_v4()));
+        let nested = dir.join("crates/app/src");
+        fs::create_dir_all(&nested).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let nested = nested.canonicalize().unwrap();
+        fs::write(
+            dir.join("crates/app/Cargo.toml"),
+            "[package]\nname = \"app\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        assert_eq!(cargo_edition(&nested, &dir).as_deref(), Some("2021"));
+        assert_eq!(
+            Formatter::for_path(Path::new("src/App.tsx")),
+            Some(Formatter::Prettier)
+        );
+        assert_eq!(Formatter::for_path(Path::new("notes.txt")), None);
+
+        let bin = dir.join(Formatter::Prettier.project_binaries()[0]);
+        fs::create_dir_all(bin.parent().unwrap()).unwrap();
+        fs::write(&bin, "").unwrap();
+        assert_eq!(Formatter::Prettier.program(&nested, &dir), bin);
+        assert_eq!(
+            Formatter::Black.program(&nested, &dir),
+            PathBuf::from("black")
+        );
+        // Nothing above the workspace root is run
+        assert_eq!(
+            Formatter::Prettier.program(&nested, &dir.join("crates")),
+            PathBuf::from("prettier")
+        );
+
+        let errors = parse_errors(
+            Formatter::Rustfmt,
+            "error: expected one of `,` or `}`, found `fn`\n --> <stdin>:3:5\n  |\n",
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "expected one of `,` or `}`, found `fn`");
+        assert_eq!((errors[0].line, errors[0].column), (Some(3), Some(5)));
+
+        let errors = parse_errors(
+            Formatter::Prettier,
+            "[error] src/a.ts: SyntaxError: ';' expected. (2:9)\n[error]   1 | let a\n",
+        );
+        assert_eq!(errors[0].message, "SyntaxError: ';' expected.");
+        assert_eq!(errors[0].line, Some(2));
+
+        let errors = parse_errors(
+            Formatter::Black,
+            "error: cannot format -: Cannot parse for target version Python 3.12: 1:6: def f(:\n",
+        );
+        assert_eq!((errors[0].line, errors[0].column), (Some(1), Some(6)));
+        assert_eq!(errors[0].message, "def f(:");
+
+        let errors = parse_errors(
+            Formatter::Gofmt,
+            "<standard input>:4:1: expected '}', found 'EOF'\n",
+        );
+        assert_eq!(errors[0].message, "expected '}', found 'EOF'");
+
+        let errors = parse_errors(Formatter::Gofmt, "something else went wrong\n");
+        assert_eq!(errors[0].message, "something else went wrong");
+        assert_eq!(errors[0].line, None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
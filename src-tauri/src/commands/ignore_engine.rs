@@ -0,0 +1,204 @@
+// Ignore engine
+// The one definition of "ignored" for the explorer, the watcher, search and the workspace
+// index: built-in defaults, the `files.exclude` setting, the root .gitignore and the
+// project's own overrides in `.voidesk/ignore`, where `!pattern` brings back something
+// the others hide.
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::workspace_roots;
+use crate::settings::{self, PROJECT_CONFIG_FILE};
+
+/// Per-project overrides, in .gitignore syntax
+pub const OVERRIDES_FILE: &str = ".voidesk/ignore";
+
+const DEFAULT_IGNORE_RULES: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".next",
+    "__pycache__",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoreRules {
+    /// Patterns hiding what they match
+    pub rules: Vec<String>,
+    /// Negated patterns; whatever they match stays visible, dotfiles included
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+fn push_lines(rules: &mut IgnoreRules, content: &str) {
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        match trimmed.strip_prefix('!') {
+            Some(exception) => rules
+                .exceptions
+                .push(exception.trim_start_matches('/').to_string()),
+            None => rules
+                .rules
+                .push(trimmed.trim_start_matches('/').to_string()),
+        }
+    }
+}
+
+fn matches_rule(rule: &str, rel_path: &str, file_name: &str) -> bool {
+    let normalized_rule = rule.trim_matches('/');
+    if normalized_rule.is_empty() {
+        return false;
+    }
+
+    if normalized_rule.contains('*')
+        || normalized_rule.contains('?')
+        || normalized_rule.contains('[')
+    {
+        if let Ok(pattern) = Pattern::new(normalized_rule) {
+            return pattern.matches(rel_path) || pattern.matches(file_name);
+        }
+    }
+
+    if rule.ends_with('/') || normalized_rule.contains('/') {
+        return rel_path == normalized_rule
+            || rel_path.starts_with(&format!("{}/", normalized_rule));
+    }
+
+    file_name == normalized_rule
+}
+
+impl IgnoreRules {
+    /// The built-in rules plus the lines of a .gitignore-style `extra`
+    pub fn with_defaults(extra: &str) -> Self {
+        let mut rules = IgnoreRules {
+            rules: DEFAULT_IGNORE_RULES
+                .iter()
+                .map(|rule| (*rule).to_string())
+                .collect(),
+            exceptions: Vec::new(),
+        };
+        push_lines(&mut rules, extra);
+        rules
+    }
+
+    /// The rules for the project at `root`
+    pub fn load(root: &Path) -> Self {
+        let excludes = settings::effective(root.canonicalize().ok().as_deref())
+            .files
+            .exclude;
+        let mut rules = Self::with_defaults(&excludes.join("\n"));
+        for file in [".gitignore", OVERRIDES_FILE] {
+            if let Ok(content) = fs::read_to_string(root.join(file)) {
+                push_lines(&mut rules, &content);
+            }
+        }
+        rules
+    }
+
+    fn is_excepted(&self, rel_path: &str, file_name: &str) -> bool {
+        self.exceptions
+            .iter()
+            .any(|exception| matches_rule(exception, rel_path, file_name))
+    }
+
+    /// True when a rule matches the entry itself; dotfiles are not ignored by this
+    pub fn is_ignored(&self, rel_path: &str, file_name: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches_rule(rule, rel_path, file_name))
+            && !self.is_excepted(rel_path, file_name)
+    }
+
+    /// Whether the explorer and the index leave the entry out: ignored, or a dotfile
+    pub fn is_hidden(&self, rel_path: &str, file_name: &str) -> bool {
+        if file_name.starts_with('.') && !self.is_excepted(rel_path, file_name) {
+            return true;
+        }
+        self.is_ignored(rel_path, file_name)
+    }
+
+    /// True when `rel_path` or any folder above it is ignored, for paths met without
+    /// walking down to them, like watcher events
+    pub fn is_ignored_path(&self, rel_path: &str) -> bool {
+        let mut prefix = String::new();
+        for name in rel_path.split('/').filter(|name| !name.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(name);
+            if self.is_ignored(&prefix, name) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Exclusion globs for ripgrep. A rule with an exception of its own is left out, as
+    /// ripgrep cannot bring back part of what a glob excludes.
+    pub fn ripgrep_globs(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|rule| {
+                let rule = rule.trim_matches('/');
+                !self
+                    .exceptions
+                    .iter()
+                    .any(|exception| exception.trim_matches('/') == rule)
+            })
+            .map(|rule| format!("!{}", rule))
+            .collect()
+    }
+}
+
+/// Whether `path` is one of the files the rules of `root` are read from
+pub fn is_rules_file(root: &Path, path: &Path) -> bool {
+    [".gitignore", OVERRIDES_FILE, PROJECT_CONFIG_FILE]
+        .iter()
+        .any(|file| path == root.join(file))
+}
+
+/// The effective ignore rules of the project at `root`
+#[tauri::command]
+pub async fn get_ignore_rules(root: String) -> Result<IgnoreRules, String> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    Ok(IgnoreRules::load(Path::new(&root)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_add_rules_and_exceptions() {
+        let root = std::env::temp_dir().join(format!("voidesk-ignore-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join(".voidesk")).unwrap();
+        fs::write(root.join(".gitignore"), "/coverage\n*.log\n# comment\n").unwrap();
+        fs::write(root.join(OVERRIDES_FILE), "!build\n!.github\ngenerated/\n").unwrap();
+        let rules = IgnoreRules::load(&root);
+
+        assert!(rules.is_ignored("node_modules", "node_modules"));
+        assert!(rules.is_ignored("coverage", "coverage"));
+        assert!(rules.is_ignored("logs/server.log", "server.log"));
+        assert!(rules.is_ignored("generated/api.rs", "api.rs"));
+        assert!(!rules.is_ignored("build", "build"));
+        assert!(!rules.is_ignored(".env", ".env"));
+
+        assert!(rules.is_hidden(".env", ".env"));
+        assert!(!rules.is_hidden(".github", ".github"));
+        assert!(rules.is_ignored_path("packages/app/node_modules/react/index.js"));
+        assert!(!rules.is_ignored_path("src/main.rs"));
+
+        let globs = rules.ripgrep_globs();
+        assert!(globs.contains(&"!node_modules".to_string()));
+        assert!(!globs.contains(&"!build".to_string()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,229 @@
+// Image preview
+// Dimensions and small previews of image files, so the explorer and editor can show
+// assets without sending multi-megabyte originals to the webview. Raster formats are
+// decoded here; SVGs are read for their declared size and sent as they are.
+
+use base64::Engine;
+use image::{ImageFormat, ImageReader};
+use regex::Regex;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::workspace_roots;
+
+const MIN_THUMBNAIL_SIZE: u32 = 16;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// SVGs are previewed as they are, up to this size
+const MAX_SVG_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInfo {
+    /// None for an SVG that declares no size
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// e.g. "png", "jpeg" or "svg"
+    pub format: String,
+    pub mime_type: String,
+    /// File size in bytes
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageThumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub mime_type: String,
+    /// Base64 encoded image
+    pub data: String,
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+/// Width and height from the root element's attributes, else from its viewBox
+fn svg_size(source: &str) -> Option<(u32, u32)> {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [root, width, height, view_box] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"(?s)<svg\b[^>]*>").unwrap(),
+            Regex::new(r#"\swidth\s*=\s*["']\s*([\d.]+)\s*(?:px)?\s*["']"#).unwrap(),
+            Regex::new(r#"\sheight\s*=\s*["']\s*([\d.]+)\s*(?:px)?\s*["']"#).unwrap(),
+            Regex::new(
+                r#"\sviewBox\s*=\s*["']\s*[-\d.]+[\s,]+[-\d.]+[\s,]+([\d.]+)[\s,]+([\d.]+)"#,
+            )
+            .unwrap(),
+        ]
+    });
+    let root = root.find(source)?.as_str();
+    let number = |text: &str| text.parse::<f64>().ok().map(|value| value.round() as u32);
+    let attributes = width
+        .captures(root)
+        .zip(height.captures(root))
+        .and_then(|(width, height)| number(&width[1]).zip(number(&height[1])));
+    attributes.or_else(|| {
+        let captures = view_box.captures(root)?;
+        number(&captures[1]).zip(number(&captures[2]))
+    })
+}
+
+fn reader(path: &Path) -> Result<ImageReader<std::io::BufReader<std::fs::File>>, String> {
+    ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())
+}
+
+fn format_name(format: ImageFormat) -> String {
+    format
+        .extensions_str()
+        .first()
+        .map(|extension| extension.to_string())
+        .unwrap_or_else(|| format!("{:?}", format).to_lowercase())
+}
+
+fn image_info(path: &Path) -> Result<ImageInfo, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if is_svg(path) {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let dimensions = svg_size(&source);
+        return Ok(ImageInfo {
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+            format: "svg".to_string(),
+            mime_type: "image/svg+xml".to_string(),
+            size,
+        });
+    }
+
+    let reader = reader(path)?;
+    let format = reader
+        .format()
+        .ok_or_else(|| format!("{} is not a supported image", path.display()))?;
+    // Only the header is read
+    let (width, height) = reader.into_dimensions().map_err(|e| e.to_string())?;
+    Ok(ImageInfo {
+        width: Some(width),
+        height: Some(height),
+        format: format_name(format),
+        mime_type: format.to_mime_type().to_string(),
+        size,
+    })
+}
+
+fn thumbnail(path: &Path, max_size: u32) -> Result<ImageThumbnail, String> {
+    let max_size = max_size.clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE);
+    if is_svg(path) {
+        let info = image_info(path)?;
+        if info.size > MAX_SVG_PREVIEW_BYTES {
+            return Err(format!("{} is too large to preview", path.display()));
+        }
+        let (width, height) = info.width.zip(info.height).unwrap_or((max_size, max_size));
+        let scale = (max_size as f64 / width.max(height).max(1) as f64).min(1.0);
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        return Ok(ImageThumbnail {
+            width: ((width as f64 * scale).round() as u32).max(1),
+            height: ((height as f64 * scale).round() as u32).max(1),
+            mime_type: info.mime_type,
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        });
+    }
+
+    let image = reader(path)?.decode().map_err(|e| e.to_string())?;
+    let preview = if image.width() > max_size || image.height() > max_size {
+        image.thumbnail(max_size, max_size)
+    } else {
+        image
+    };
+
+    // PNG keeps transparency; JPEG is much smaller for photos
+    let mut bytes = Cursor::new(Vec::new());
+    let mime_type = if preview.color().has_alpha() {
+        preview
+            .write_to(&mut bytes, ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+        "image/png"
+    } else {
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY);
+        preview
+            .to_rgb8()
+            .write_with_encoder(encoder)
+            .map_err(|e| e.to_string())?;
+        "image/jpeg"
+    };
+    Ok(ImageThumbnail {
+        width: preview.width(),
+        height: preview.height(),
+        mime_type: mime_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes.into_inner()),
+    })
+}
+
+/// Dimensions, format and size of an image, reading only its header
+#[tauri::command]
+pub async fn get_image_info(path: String) -> Result<ImageInfo, String> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    tokio::task::spawn_blocking(move || image_info(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The image scaled down to fit `max_size` pixels on its longer side; smaller images
+/// keep their size
+#[tauri::command]
+pub async fn get_image_thumbnail(path: String, max_size: u32) -> Result<ImageThumbnail, String> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    tokio::task::spawn_blocking(move || thumbnail(Path::new(&path), max_size))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_sizes_and_scales_previews_down() {
+        let dir = std::env::temp_dir().join(format!("voidesk-image-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Named .bin to show the format comes from the content
+        let photo = dir.join("photo.bin");
+        image::RgbImage::from_pixel(400, 200, image::Rgb([200, 80, 40]))
+            .save_with_format(&photo, ImageFormat::Png)
+            .unwrap();
+
+        let info = image_info(&photo).unwrap();
+        assert_eq!((info.width, info.height), (Some(400), Some(200)));
+        assert_eq!(info.format, "png");
+        assert_eq!(info.mime_type, "image/png");
+
+        let preview = thumbnail(&photo, 100).unwrap();
+        assert_eq!((preview.width, preview.height), (100, 50));
+        assert_eq!(preview.mime_type, "image/jpeg");
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&preview.data)
+            .unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (100, 50));
+
+        let icon = dir.join("icon.svg");
+        std::fs::write(
+            &icon,
+            "<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"\n  \
+             viewBox=\"0 0 48 24\"><rect width=\"4\" height=\"4\"/></svg>",
+        )
+        .unwrap();
+        let info = image_info(&icon).unwrap();
+        assert_eq!((info.width, info.height), (Some(48), Some(24)));
+        let preview = thumbnail(&icon, 16).unwrap();
+        assert_eq!((preview.width, preview.height), (16, 8));
+        assert_eq!(preview.mime_type, "image/svg+xml");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,484 @@
+// Linting
+// Runs eslint, clippy or ruff on a file or a whole project and turns their JSON output
+// into the diagnostics language servers publish, so the Problems panel can show lint
+// results next to compiler errors. Only workspace folders are linted, with tools and
+// configs found inside the workspace.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::format_commands::{folders_up_to, project_program, run_tool};
+use super::workspace_roots;
+use crate::lsp::manager::{LspDiagnostic, LspPosition, LspRange};
+
+/// Clippy builds the crate first, which can take a while
+const CLIPPY_TIMEOUT: Duration = Duration::from_secs(300);
+const LINT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// LSP severities
+const SEVERITY_ERROR: u32 = 1;
+const SEVERITY_WARNING: u32 = 2;
+const SEVERITY_INFORMATION: u32 = 3;
+const SEVERITY_HINT: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Linter {
+    Eslint,
+    Clippy,
+    Ruff,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintReport {
+    pub linter: &'static str,
+    pub diagnostics: Vec<LspDiagnostic>,
+    /// Set when the linter could not run, e.g. because it is not installed
+    pub error: Option<String>,
+}
+
+impl Linter {
+    fn for_file(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        let linter = match extension.as_str() {
+            "rs" => Self::Clippy,
+            "py" | "pyi" => Self::Ruff,
+            "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" | "vue" => Self::Eslint,
+            _ => return None,
+        };
+        Some(linter)
+    }
+
+    /// The linters a project folder is set up for
+    fn for_project(dir: &Path) -> Vec<Self> {
+        let has = |names: &[&str]| names.iter().any(|name| dir.join(name).is_file());
+        let mut linters = Vec::new();
+        if has(&["package.json"])
+            && has(&[
+                "eslint.config.js",
+                "eslint.config.mjs",
+                "eslint.config.cjs",
+                "eslint.config.ts",
+                ".eslintrc",
+                ".eslintrc.js",
+                ".eslintrc.cjs",
+                ".eslintrc.json",
+                ".eslintrc.yml",
+                ".eslintrc.yaml",
+            ])
+        {
+            linters.push(Self::Eslint);
+        }
+        if has(&["Cargo.toml"]) {
+            linters.push(Self::Clippy);
+        }
+        if has(&["ruff.toml", ".ruff.toml", "pyproject.toml"]) {
+            linters.push(Self::Ruff);
+        }
+        linters
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Eslint => "eslint",
+            Self::Clippy => "clippy",
+            Self::Ruff => "ruff",
+        }
+    }
+
+    fn program(self, dir: &Path, root: &Path) -> PathBuf {
+        let binaries: &[&str] = match (self, cfg!(windows)) {
+            (Self::Eslint, false) => &["node_modules/.bin/eslint"],
+            (Self::Eslint, true) => &["node_modules/.bin/eslint.cmd"],
+            (Self::Ruff, false) => &[".venv/bin/ruff", "venv/bin/ruff"],
+            (Self::Ruff, true) => &[".venv/Scripts/ruff.exe", "venv/Scripts/ruff.exe"],
+            (Self::Clippy, _) => &[],
+        };
+        let name = if self == Self::Clippy {
+            "cargo"
+        } else {
+            self.name()
+        };
+        project_program(dir, root, binaries, name)
+    }
+
+    /// Clippy always checks the whole package; the others just `target`
+    fn args(self, target: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            Self::Eslint => &["--format", "json", target],
+            Self::Clippy => &["clippy", "--message-format=json", "--quiet"],
+            Self::Ruff => &["check", "--output-format", "json", "--no-fix", target],
+        };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn timeout(self) -> Duration {
+        match self {
+            Self::Clippy => CLIPPY_TIMEOUT,
+            _ => LINT_TIMEOUT,
+        }
+    }
+}
+
+/// A 0-based position from the 1-based line and column linters print
+fn position(line: u32, column: u32) -> LspPosition {
+    LspPosition {
+        line: line.saturating_sub(1),
+        character: column.saturating_sub(1),
+    }
+}
+
+fn diagnostic(
+    linter: Linter,
+    path: String,
+    message: String,
+    severity: u32,
+    code: Option<String>,
+    range: LspRange,
+) -> LspDiagnostic {
+    LspDiagnostic {
+        path,
+        message,
+        severity: Some(severity),
+        source: Some(linter.name().to_string()),
+        code,
+        range,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EslintFile {
+    file_path: String,
+    messages: Vec<EslintMessage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EslintMessage {
+    rule_id: Option<String>,
+    severity: u32,
+    message: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+}
+
+fn parse_eslint(output: &str) -> Result<Vec<LspDiagnostic>, String> {
+    let files: Vec<EslintFile> = serde_json::from_str(output).map_err(|e| e.to_string())?;
+    let mut diagnostics = Vec::new();
+    for file in files {
+        for message in file.messages {
+            let start = position(message.line.unwrap_or(1), message.column.unwrap_or(1));
+            let end = match (message.end_line, message.end_column) {
+                (Some(line), Some(column)) => position(line, column),
+                _ => start.clone(),
+            };
+            let severity = if message.severity >= 2 {
+                SEVERITY_ERROR
+            } else {
+                SEVERITY_WARNING
+            };
+            diagnostics.push(diagnostic(
+                Linter::Eslint,
+                file.file_path.clone(),
+                message.message,
+                severity,
+                message.rule_id,
+                LspRange { start, end },
+            ));
+        }
+    }
+    Ok(diagnostics)
+}
+
+#[derive(Deserialize)]
+struct RuffLocation {
+    row: u32,
+    column: u32,
+}
+
+#[derive(Deserialize)]
+struct RuffMessage {
+    code: Option<String>,
+    message: String,
+    filename: String,
+    location: RuffLocation,
+    end_location: RuffLocation,
+}
+
+fn parse_ruff(output: &str) -> Result<Vec<LspDiagnostic>, String> {
+    let messages: Vec<RuffMessage> = serde_json::from_str(output).map_err(|e| e.to_string())?;
+    Ok(messages
+        .into_iter()
+        .map(|message| {
+            // Ruff has no severities; only syntax errors come without a rule code
+            let severity = if message.code.is_some() {
+                SEVERITY_WARNING
+            } else {
+                SEVERITY_ERROR
+            };
+            let range = LspRange {
+                start: position(message.location.row, message.location.column),
+                end: position(message.end_location.row, message.end_location.column),
+            };
+            diagnostic(
+                Linter::Ruff,
+                message.filename,
+                message.message,
+                severity,
+                message.code,
+                range,
+            )
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    level: String,
+    code: Option<CompilerCode>,
+    spans: Vec<CompilerSpan>,
+}
+
+#[derive(Deserialize)]
+struct CompilerCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: String,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+}
+
+/// Cargo prints one JSON message per line. Span file names are relative to the
+/// workspace root, which may be any folder above `dir`.
+fn parse_clippy(output: &str, dir: &Path) -> Vec<LspDiagnostic> {
+    let resolve = |file_name: &str| {
+        let relative = Path::new(file_name);
+        if relative.is_absolute() {
+            return relative.to_path_buf();
+        }
+        dir.ancestors()
+            .map(|folder| folder.join(relative))
+            .find(|candidate| candidate.is_file())
+            .unwrap_or_else(|| dir.join(relative))
+    };
+
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-message")
+        .filter_map(|message| {
+            let message = message.message?;
+            // Summaries such as "aborting due to 2 previous errors" have no span
+            let span = message.spans.iter().find(|span| span.is_primary)?;
+            let severity = match message.level.as_str() {
+                "error" | "error: internal compiler error" => SEVERITY_ERROR,
+                "warning" => SEVERITY_WARNING,
+                "note" => SEVERITY_INFORMATION,
+                _ => SEVERITY_HINT,
+            };
+            let range = LspRange {
+                start: position(span.line_start, span.column_start),
+                end: position(span.line_end, span.column_end),
+            };
+            Some(diagnostic(
+                Linter::Clippy,
+                resolve(&span.file_name).to_string_lossy().to_string(),
+                message.message,
+                severity,
+                message.code.map(|code| code.code),
+                range,
+            ))
+        })
+        .collect()
+}
+
+/// Whether two paths name the same file, however they are spelled
+fn same_file(left: &str, right: &Path) -> bool {
+    match (Path::new(left).canonicalize(), right.canonicalize()) {
+        (Ok(left), Ok(right)) => left == right,
+        _ => Path::new(left) == right,
+    }
+}
+
+async fn run(
+    linter: Linter,
+    dir: &Path,
+    root: &Path,
+    target: &str,
+) -> Result<Vec<LspDiagnostic>, String> {
+    let output = run_tool(
+        linter.name(),
+        &linter.program(dir, root),
+        &linter.args(target),
+        dir,
+        None,
+        linter.timeout(),
+    )
+    .await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Finding problems is a failing exit too, so only a lack of output means the
+    // linter itself failed
+    let failed = |error: String| {
+        let stderr = stderr.trim();
+        if output.status.success() || stderr.is_empty() {
+            error
+        } else {
+            stderr.to_string()
+        }
+    };
+    match linter {
+        Linter::Eslint => parse_eslint(&stdout).map_err(failed),
+        Linter::Ruff => parse_ruff(&stdout).map_err(failed),
+        Linter::Clippy => {
+            let diagnostics = parse_clippy(&stdout, dir);
+            if diagnostics.is_empty() && !output.status.success() {
+                return Err(failed("cargo clippy failed".to_string()));
+            }
+            Ok(diagnostics)
+        }
+    }
+}
+
+async fn report(
+    linter: Linter,
+    dir: &Path,
+    root: &Path,
+    target: &str,
+    file: Option<&Path>,
+) -> LintReport {
+    match run(linter, dir, root, target).await {
+        Ok(mut diagnostics) => {
+            if let Some(file) = file {
+                diagnostics.retain(|diagnostic| same_file(&diagnostic.path, file));
+            }
+            LintReport {
+                linter: linter.name(),
+                diagnostics,
+                error: None,
+            }
+        }
+        Err(error) => LintReport {
+            linter: linter.name(),
+            diagnostics: Vec::new(),
+            error: Some(error),
+        },
+    }
+}
+
+/// Lint a file with the linter for its language, or a project folder with every linter
+/// it is configured for. Each linter runs from the folder so it finds the project's
+/// config; one that fails to run reports its error without hiding the others.
+#[tauri::command]
+pub async fn run_linter(path: String) -> Result<Vec<LintReport>, String> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let root = workspace_roots::containing_root(&path)
+        .ok_or_else(|| format!("{} is outside the open workspace", path))?;
+    let target = tokio::fs::canonicalize(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let metadata = tokio::fs::metadata(&target)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if metadata.is_dir() {
+        let linters = Linter::for_project(&target);
+        if linters.is_empty() {
+            return Err(format!("No linter is configured in {}", path));
+        }
+        let mut reports = Vec::new();
+        for linter in linters {
+            reports.push(report(linter, &target, &root, ".", None).await);
+        }
+        return Ok(reports);
+    }
+
+    let linter = Linter::for_file(&target)
+        .ok_or_else(|| format!("No linter is known for {}", target.display()))?;
+    let parent = target.parent().unwrap_or_else(|| Path::new("."));
+    // Clippy runs on the package the file belongs to
+    let dir = match linter {
+        Linter::Clippy => folders_up_to(parent, &root)
+            .find(|folder| folder.join("Cargo.toml").is_file())
+            .ok_or_else(|| format!("{} is not part of a Cargo package", path))?,
+        _ => parent,
+    };
+    Ok(vec![report(linter, dir, &root, &path, Some(&target)).await])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_linter_output_into_diagnostics() {
+        let eslint = r#"[{"filePath":"/p/src/a.ts","messages":[
+            {"ruleId":"no-unused-vars","severity":1,"message":"'x' is unused","line":3,
+             "column":7,"endLine":3,"endColumn":8},
+            {"ruleId":null,"severity":2,"fatal":true,"message":"Parsing error","line":9,
+             "column":1}]}]"#;
+        let diagnostics = parse_eslint(eslint).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Some(SEVERITY_WARNING));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(
+            (
+                diagnostics[0].range.start.line,
+                diagnostics[0].range.start.character
+            ),
+            (2, 6)
+        );
+        assert_eq!(diagnostics[1].severity, Some(SEVERITY_ERROR));
+        assert_eq!(diagnostics[1].range.end.line, 8);
+
+        let ruff = r#"[{"code":"F401","message":"`os` imported but unused",
+            "filename":"/p/main.py","location":{"row":1,"column":8},
+            "end_location":{"row":1,"column":10},"fix":null,"url":null}]"#;
+        let diagnostics = parse_ruff(ruff).unwrap();
+        assert_eq!(diagnostics[0].source.as_deref(), Some("ruff"));
+        assert_eq!(diagnostics[0].range.end.character, 9);
+
+        let dir = std::env::temp_dir().join(format!("voidesk-lint-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+        let clippy = [
+            r#"{"reason":"compiler-artifact","target":{}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"this `if` has identical blocks","level":"warning","code":{"code":"clippy::if_same_then_else"},"spans":[{"file_name":"src/lib.rs","line_start":4,"line_end":6,"column_start":5,"column_end":6,"is_primary":true}]}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"1 warning emitted","level":"warning","code":null,"spans":[]}}"#,
+        ]
+        .join("\n");
+        let diagnostics = parse_clippy(&clippy, &dir.join("src"));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(same_file(&diagnostics[0].path, &dir.join("src/lib.rs")));
+        assert_eq!(
+            diagnostics[0].code.as_deref(),
+            Some("clippy::if_same_then_else")
+        );
+        assert_eq!(diagnostics[0].range.start.line, 3);
+
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join("pyproject.toml"), "").unwrap();
+        assert_eq!(
+            Linter::for_project(&dir),
+            vec![Linter::Clippy, Linter::Ruff]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
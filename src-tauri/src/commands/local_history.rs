@@ -0,0 +1,214 @@
+// Local history
+// Keeps timestamped copies of saved files in the app data directory so a bad AI edit
+// or an accidental save can be rolled back
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::file_commands::write_atomic;
+use super::workspace_roots;
+use crate::error::VoidDeskError;
+
+static LOCAL_HISTORY_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Versions kept per file; the oldest are pruned first
+const MAX_VERSIONS_PER_FILE: usize = 50;
+
+/// Larger files are not copied into the history
+const MAX_VERSION_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Holds the original path, since version folders are named by its hash
+const SOURCE_FILE_NAME: &str = "source";
+
+#[derive(Debug, Serialize)]
+pub struct FileHistoryEntry {
+    pub id: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    let history_dir = LOCAL_HISTORY_DIR.get_or_init(|| app_data_dir.join("local-history"));
+    fs::create_dir_all(history_dir).map_err(|e| e.to_string())
+}
+
+fn versions_dir(history_dir: &Path, path: &Path) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().replace('\\', "/").as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    history_dir.join(&digest[..32])
+}
+
+/// Version ids are "<timestamp>-<suffix>"; anything else could escape the folder
+fn parse_version_id(id: &str) -> Option<u64> {
+    let (timestamp, suffix) = id.split_once('-')?;
+    if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    timestamp.parse().ok()
+}
+
+fn list_versions(history_dir: &Path, path: &Path) -> Result<Vec<FileHistoryEntry>, String> {
+    let dir = versions_dir(history_dir, path);
+    let Ok(read_dir) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut versions = Vec::new();
+    for entry in read_dir.flatten() {
+        let id = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = parse_version_id(&id) else {
+            continue;
+        };
+        let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        versions.push(FileHistoryEntry {
+            id,
+            timestamp,
+            size,
+        });
+    }
+    // Newest first
+    versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.id.cmp(&a.id)));
+    Ok(versions)
+}
+
+/// Copy the file's current contents into its history unless they match the newest version
+fn snapshot(history_dir: &Path, path: &Path) -> Result<(), String> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_VERSION_BYTES => metadata,
+        _ => return Ok(()),
+    };
+    let content = fs::read(path).map_err(|e| e.to_string())?;
+
+    let dir = versions_dir(history_dir, path);
+    let versions = list_versions(history_dir, path)?;
+    if let Some(newest) = versions.first() {
+        if newest.size == metadata.len()
+            && fs::read(dir.join(&newest.id)).ok().as_deref() == Some(&content[..])
+        {
+            return Ok(());
+        }
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::write(
+        dir.join(SOURCE_FILE_NAME),
+        path.to_string_lossy().as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = format!(
+        "{}-{}",
+        chrono::Utc::now().timestamp_millis(),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    fs::write(dir.join(id), content).map_err(|e| e.to_string())?;
+
+    for stale in versions.iter().skip(MAX_VERSIONS_PER_FILE - 1) {
+        let _ = fs::remove_file(dir.join(&stale.id));
+    }
+    Ok(())
+}
+
+/// Record the file as it is on disk; history problems never block a save
+pub fn record_version(path: &Path) {
+    let Some(history_dir) = LOCAL_HISTORY_DIR.get() else {
+        return;
+    };
+    if let Err(e) = snapshot(history_dir, path) {
+        tracing::warn!(
+            "Failed to record local history for {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+fn history_dir() -> Result<&'static PathBuf, String> {
+    LOCAL_HISTORY_DIR
+        .get()
+        .ok_or_else(|| "Local history has not been initialized".to_string())
+}
+
+fn version_path(history_dir: &Path, path: &Path, id: &str) -> Result<PathBuf, VoidDeskError> {
+    if parse_version_id(id).is_none() {
+        return Err(VoidDeskError::invalid_input(format!(
+            "Invalid version id: {}",
+            id
+        )));
+    }
+    let version = versions_dir(history_dir, path).join(id);
+    if !version.is_file() {
+        return Err(VoidDeskError::not_found(format!(
+            "Version not found: {}",
+            id
+        )));
+    }
+    Ok(version)
+}
+
+#[tauri::command]
+pub async fn list_file_history(path: String) -> Result<Vec<FileHistoryEntry>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    Ok(list_versions(history_dir()?, Path::new(&path))?)
+}
+
+#[tauri::command]
+pub async fn read_file_version(path: String, id: String) -> Result<String, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let version = version_path(history_dir()?, Path::new(&path), &id)?;
+    let bytes = fs::read(version)?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Put a saved version back on disk and return its contents. What was on disk is
+/// recorded first, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_file_version(path: String, id: String) -> Result<String, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let history_dir = history_dir()?;
+    let target = Path::new(&path);
+    let bytes = fs::read(version_path(history_dir, target, &id)?)?;
+
+    record_version(target);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(target, &bytes)?;
+    record_version(target);
+
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_distinct_versions_newest_first() {
+        let base = std::env::temp_dir().join(format!("voidesk-history-{}", uuid::Uuid::new_v4()));
+        let history_dir = base.join("history");
+        let file = base.join("notes.txt");
+        fs::create_dir_all(&base).unwrap();
+
+        fs::write(&file, "first").unwrap();
+        snapshot(&history_dir, &file).unwrap();
+        snapshot(&history_dir, &file).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        fs::write(&file, "second!").unwrap();
+        snapshot(&history_dir, &file).unwrap();
+
+        let versions = list_versions(&history_dir, &file).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].size, 7);
+        let oldest = version_path(&history_dir, &file, &versions[1].id).unwrap();
+        assert_eq!(fs::read_to_string(oldest).unwrap(), "first");
+
+        assert!(version_path(&history_dir, &file, "../../etc-passwd").is_err());
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,280 @@
+// Log tailing
+// Follows a growing file like `tail -f`: the watcher says when it changed, and the read
+// offset is tracked here so only appended lines are sent
+
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tokio::sync::{mpsc, oneshot};
+
+use super::workspace_roots;
+
+static TAILS: OnceLock<Mutex<HashMap<String, TailHandle>>> = OnceLock::new();
+
+/// Re-checked on this interval too, in case the watcher misses a write
+const TAIL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most bytes read per change, so a sudden flood doesn't stall the event stream
+const MAX_TAIL_READ_BYTES: u64 = 1024 * 1024;
+
+/// How far back from the end to look for the initial lines
+const INITIAL_LINES_WINDOW_BYTES: u64 = 64 * 1024;
+
+struct TailHandle {
+    _watcher: RecommendedWatcher,
+    cancel: oneshot::Sender<()>,
+}
+
+fn tails() -> &'static Mutex<HashMap<String, TailHandle>> {
+    TAILS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TailEvent {
+    Lines {
+        lines: Vec<String>,
+    },
+    /// The file shrank, e.g. it was truncated or rotated; reading restarts at the top
+    Truncated,
+    Removed,
+    Error {
+        message: String,
+    },
+}
+
+/// Read position in the followed file plus any trailing partial line
+struct TailReader {
+    path: PathBuf,
+    offset: u64,
+    partial: Vec<u8>,
+    removed: bool,
+}
+
+impl TailReader {
+    fn new(path: PathBuf, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            partial: Vec::new(),
+            removed: false,
+        }
+    }
+
+    /// Everything appended since the last call, as events to send
+    fn poll(&mut self) -> Vec<TailEvent> {
+        let mut events = Vec::new();
+        let size = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                if !self.removed {
+                    self.removed = true;
+                    events.push(TailEvent::Removed);
+                }
+                return events;
+            }
+        };
+        if self.removed || size < self.offset {
+            // Recreated or truncated: follow the new contents from the start
+            self.removed = false;
+            self.offset = 0;
+            self.partial.clear();
+            events.push(TailEvent::Truncated);
+        }
+        if size == self.offset {
+            return events;
+        }
+
+        let mut chunk = Vec::new();
+        let read = fs::File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(self.offset))?;
+            file.take(MAX_TAIL_READ_BYTES).read_to_end(&mut chunk)
+        });
+        match read {
+            Ok(read) => self.offset += read as u64,
+            Err(e) => {
+                events.push(TailEvent::Error {
+                    message: e.to_string(),
+                });
+                return events;
+            }
+        }
+
+        self.partial.extend_from_slice(&chunk);
+        let Some(last_newline) = self.partial.iter().rposition(|byte| *byte == b'\n') else {
+            return events;
+        };
+        let complete: Vec<u8> = self.partial.drain(..=last_newline).collect();
+        let lines = String::from_utf8_lossy(&complete[..complete.len() - 1])
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line).to_string())
+            .collect();
+        events.push(TailEvent::Lines { lines });
+        events
+    }
+}
+
+/// The last `count` complete lines of the file, read from its tail only
+fn last_lines(path: &Path, size: u64, count: usize) -> Result<Vec<String>, String> {
+    if count == 0 || size == 0 {
+        return Ok(Vec::new());
+    }
+    let start = size.saturating_sub(INITIAL_LINES_WINDOW_BYTES);
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    file.take(size - start)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    // The first line is probably cut off when the window doesn't reach the top
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..]
+        .iter()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line).to_string())
+        .collect())
+}
+
+/// Follow `path`, sending appended lines through `on_event` until stop_tail is called
+/// with the returned id. `initial_lines` sends the current end of the file first.
+#[tauri::command]
+pub async fn tail_file(
+    path: String,
+    initial_lines: Option<usize>,
+    on_event: Channel<TailEvent>,
+) -> Result<String, String> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let file_path = PathBuf::from(&path);
+    let size = fs::metadata(&file_path).map_err(|e| e.to_string())?.len();
+    if !file_path.is_file() {
+        return Err(format!("Path is not a file: {}", path));
+    }
+
+    let lines = last_lines(&file_path, size, initial_lines.unwrap_or(0))?;
+    if !lines.is_empty() {
+        let _ = on_event.send(TailEvent::Lines { lines });
+    }
+
+    // Watch the folder rather than the file so a rotated log is picked up again
+    let (changed_tx, mut changed_rx) = mpsc::channel::<()>(1);
+    let watched_file = file_path.clone();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<Event, notify::Error>| {
+            if let Ok(event) = res {
+                if event.paths.contains(&watched_file) {
+                    let _ = changed_tx.try_send(());
+                }
+            }
+        },
+        Config::default(),
+    )
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    let parent = file_path
+        .parent()
+        .ok_or_else(|| format!("Invalid path: {}", path))?;
+    watcher
+        .watch(parent, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch path: {}", e))?;
+
+    let tail_id = uuid::Uuid::new_v4().to_string();
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    tails().lock().map_err(|e| e.to_string())?.insert(
+        tail_id.clone(),
+        TailHandle {
+            _watcher: watcher,
+            cancel: cancel_tx,
+        },
+    );
+
+    let task_id = tail_id.clone();
+    tokio::spawn(async move {
+        let mut reader = TailReader::new(file_path, size);
+        loop {
+            tokio::select! {
+                _ = &mut cancel_rx => break,
+                _ = changed_rx.recv() => {}
+                _ = tokio::time::sleep(TAIL_POLL_INTERVAL) => {}
+            }
+
+            let events = reader.poll();
+            if events
+                .into_iter()
+                .any(|event| on_event.send(event).is_err())
+            {
+                // The panel went away without calling stop_tail
+                if let Ok(mut tails) = tails().lock() {
+                    tails.remove(&task_id);
+                }
+                break;
+            }
+        }
+    });
+
+    Ok(tail_id)
+}
+
+#[tauri::command]
+pub async fn stop_tail(tail_id: String) -> Result<(), String> {
+    let handle = tails().lock().map_err(|e| e.to_string())?.remove(&tail_id);
+    if let Some(handle) = handle {
+        let _ = handle.cancel.send(());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn reads_appended_lines_and_restarts_after_truncation() {
+        let path = std::env::temp_dir().join(format!("voidesk-tail-{}.log", uuid::Uuid::new_v4()));
+        fs::write(&path, "old 1\nold 2\nold 3\n").unwrap();
+        let size = fs::metadata(&path).unwrap().len();
+        assert_eq!(last_lines(&path, size, 2).unwrap(), vec!["old 2", "old 3"]);
+
+        let mut reader = TailReader::new(path.clone(), size);
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"new 1\r\nnew 2\npart").unwrap();
+        assert_eq!(
+            reader.poll(),
+            vec![TailEvent::Lines {
+                lines: vec!["new 1".to_string(), "new 2".to_string()]
+            }]
+        );
+        file.write_all(b"ial\n").unwrap();
+        assert_eq!(
+            reader.poll(),
+            vec![TailEvent::Lines {
+                lines: vec!["partial".to_string()]
+            }]
+        );
+
+        fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(
+            reader.poll(),
+            vec![
+                TailEvent::Truncated,
+                TailEvent::Lines {
+                    lines: vec!["fresh".to_string()]
+                }
+            ]
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+}
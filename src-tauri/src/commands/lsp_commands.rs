@@ -1,127 +1,211 @@
 // LSP Tauri Commands
 
+use crate::error::{ErrorCode, VoidDeskError};
 use crate::lsp::LspManager;
-use crate::lsp::manager::{LspDiagnostic, LspLocation, RenameResult};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::sync::Arc;
-use tauri::State;
+use crate::lsp::manager::{CompletionItem, HoverResult, LspDiagnostic, LspLocation, RenameResult};
+use crate::lsp::transport::{RequestTimeouts, REQUEST_TIMED_OUT};
+use crate::settings;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, State, WebviewWindow};
 
+/// Language servers per window, so each project window has its own servers, root and
+/// diagnostics
 pub struct LspState {
-    pub manager: Arc<LspManager>,
+    managers: Mutex<HashMap<String, Arc<LspManager>>>,
+    app_handle: AppHandle,
 }
 
 impl LspState {
-    pub fn new() -> Self {
+    pub fn new(app_handle: AppHandle) -> Self {
         Self {
-            manager: Arc::new(LspManager::new()),
+            managers: Mutex::new(HashMap::new()),
+            app_handle,
+        }
+    }
+
+    /// The manager of `window`, created with the global LSP settings on first use
+    pub fn manager(&self, window: &str) -> Arc<LspManager> {
+        let mut managers = self.managers.lock().unwrap();
+        let manager = managers.entry(window.to_string()).or_insert_with(|| {
+            let manager = LspManager::for_window(self.app_handle.clone(), window);
+            let timeouts = settings::effective(None).lsp.request_timeouts();
+            if let Err(error) = manager.set_request_timeouts(timeouts) {
+                tracing::warn!("Could not apply the LSP timeouts: {}", error);
+            }
+            manager
+        });
+        Arc::clone(manager)
+    }
+
+    pub fn managers(&self) -> Vec<Arc<LspManager>> {
+        self.managers.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Stop the servers of a window that has closed
+    pub async fn remove(&self, window: &str) {
+        let manager = self.managers.lock().unwrap().remove(window);
+        if let Some(manager) = manager {
+            manager.shutdown().await;
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct CompletionItem {
-    pub label: String,
-    pub kind: Option<String>,
-    pub detail: Option<String>,
-    pub insert_text: Option<String>,
+/// Language servers report errors as messages; pick out the ones the editor treats apart
+fn lsp_error(message: String) -> VoidDeskError {
+    let code = if message.starts_with(REQUEST_TIMED_OUT) {
+        ErrorCode::Timeout
+    } else if message == "Request cancelled" {
+        ErrorCode::Cancelled
+    } else {
+        ErrorCode::Internal
+    };
+    VoidDeskError::new(code, message)
 }
 
 #[tauri::command]
-pub async fn lsp_list_diagnostics(state: State<'_, LspState>) -> Result<Vec<LspDiagnostic>, String> {
-    Ok(state.manager.list_diagnostics().await)
+pub async fn lsp_list_diagnostics(
+    state: State<'_, LspState>,
+    window: WebviewWindow,
+) -> Result<Vec<LspDiagnostic>, VoidDeskError> {
+    Ok(state.manager(window.label()).list_diagnostics().await)
 }
 
-#[tauri::command]
-pub async fn lsp_set_root(state: State<'_, LspState>, root_path: String) -> Result<(), String> {
-    state.manager.set_root_path(root_path).await;
+/// Point the language servers at the workspace root `root_path`, with its timeouts
+pub async fn set_root(manager: &LspManager, root_path: String) -> Result<(), String> {
+    let lsp_settings = settings::for_path(Some(&root_path)).lsp;
+    manager.set_request_timeouts(lsp_settings.request_timeouts())?;
+    manager.set_root_path(root_path).await;
     Ok(())
 }
 
+#[tauri::command]
+pub async fn lsp_get_request_timeouts(
+    state: State<'_, LspState>,
+    window: WebviewWindow,
+) -> Result<RequestTimeouts, VoidDeskError> {
+    Ok(state.manager(window.label()).request_timeouts())
+}
+
+#[tauri::command]
+pub async fn lsp_set_request_timeouts(
+    state: State<'_, LspState>,
+    window: WebviewWindow,
+    timeouts: RequestTimeouts,
+) -> Result<(), VoidDeskError> {
+    state
+        .manager(window.label())
+        .set_request_timeouts(timeouts)
+        .map_err(lsp_error)
+}
+
 #[tauri::command]
 pub async fn lsp_did_open(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     content: String,
     language: String,
-) -> Result<(), String> {
-    state.manager.did_open(&language, &path, &content).await
+) -> Result<(), VoidDeskError> {
+    state
+        .manager(window.label())
+        .did_open(&language, &path, &content)
+        .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_completion(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     line: u32,
     character: u32,
     language: String,
-) -> Result<Value, String> {
+) -> Result<Vec<CompletionItem>, VoidDeskError> {
     state
-        .manager
+        .manager(window.label())
         .completion(&language, &path, line, character)
         .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_hover(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     line: u32,
     character: u32,
     language: String,
-) -> Result<Value, String> {
-    state.manager.hover(&language, &path, line, character).await
+) -> Result<Option<HoverResult>, VoidDeskError> {
+    state
+        .manager(window.label())
+        .hover(&language, &path, line, character)
+        .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_did_change(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     content: String,
     language: String,
-) -> Result<(), String> {
-    state.manager.did_change(&language, &path, &content).await
+) -> Result<(), VoidDeskError> {
+    state
+        .manager(window.label())
+        .did_change(&language, &path, &content)
+        .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_definition(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     line: u32,
     character: u32,
     language: String,
-) -> Result<Vec<LspLocation>, String> {
+) -> Result<Vec<LspLocation>, VoidDeskError> {
     state
-        .manager
+        .manager(window.label())
         .definition(&language, &path, line, character)
         .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_references(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     line: u32,
     character: u32,
     language: String,
-) -> Result<Vec<LspLocation>, String> {
+) -> Result<Vec<LspLocation>, VoidDeskError> {
     state
-        .manager
+        .manager(window.label())
         .references(&language, &path, line, character)
         .await
+        .map_err(lsp_error)
 }
 
 #[tauri::command]
 pub async fn lsp_rename(
     state: State<'_, LspState>,
+    window: WebviewWindow,
     path: String,
     line: u32,
     character: u32,
     language: String,
     new_name: String,
-) -> Result<RenameResult, String> {
+) -> Result<RenameResult, VoidDeskError> {
     state
-        .manager
+        .manager(window.label())
         .rename(&language, &path, line, character, &new_name)
         .await
+        .map_err(lsp_error)
 }
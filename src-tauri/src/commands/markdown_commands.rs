@@ -0,0 +1,205 @@
+// Markdown
+// Renders Markdown to sanitized HTML for the README preview and the AI chat, so both
+// show tables, task lists and highlighted code the same way. Relative images are read
+// from the workspace and inlined, since the webview can't load local files itself.
+
+use base64::Engine;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use super::file_commands::sniff_mime;
+use super::workspace_roots;
+use crate::syntax::highlight_html;
+
+/// Larger images are left as links so the HTML stays small enough to send over IPC
+const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A fenced code block, highlighted when there is a grammar for its language
+fn code_block(language: &str, code: &str) -> String {
+    let language = language.split_whitespace().next().unwrap_or_default();
+    let body = highlight_html(language, code).unwrap_or_else(|| escape(code));
+    if language.is_empty() {
+        format!("<pre><code>{}</code></pre>\n", body)
+    } else {
+        format!(
+            "<pre><code class=\"language-{}\">{}</code></pre>\n",
+            escape(language),
+            body
+        )
+    }
+}
+
+/// The file a relative image refers to: `/`-rooted ones from the workspace root, others
+/// from the Markdown file's folder, or the first workspace root for text with no file
+fn image_path(source: &str, document: Option<&Path>) -> Option<PathBuf> {
+    if source.is_empty() || source.starts_with('#') || reqwest::Url::parse(source).is_ok() {
+        return None;
+    }
+    let source = source.split(['?', '#']).next().unwrap_or(source);
+    let root = || match document {
+        Some(document) => workspace_roots::containing_root(&document.to_string_lossy()),
+        None => workspace_roots::list().ok()?.into_iter().next(),
+    };
+    match source.strip_prefix('/') {
+        Some(rooted) => Some(root()?.join(rooted)),
+        None => match document {
+            Some(document) => Some(document.parent()?.join(source)),
+            None => Some(root()?.join(source)),
+        },
+    }
+}
+
+/// A data URI for an image inside an open workspace; None for anything else
+fn inline_image(path: &Path) -> Option<String> {
+    workspace_roots::ensure_in_workspace(&path.to_string_lossy()).ok()?;
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_INLINE_IMAGE_BYTES {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    let mime = sniff_mime(&bytes, path);
+    if !mime.starts_with("image/") {
+        return None;
+    }
+    let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+    Some(format!("data:{};base64,{}", mime, data))
+}
+
+fn sanitize(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("span", ["class"])
+        .add_url_schemes(["data"])
+        // Data URIs only as the source of an image
+        .attribute_filter(|element, attribute, value| {
+            let is_data = value.trim_start().to_lowercase().starts_with("data:");
+            let is_image =
+                element == "img" && attribute == "src" && value.starts_with("data:image/");
+            if is_data && !is_image {
+                None
+            } else {
+                Some(Cow::Borrowed(value))
+            }
+        })
+        .clean(html)
+        .to_string()
+}
+
+fn render(text: &str, document: Option<&Path>) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(text, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => language.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, body)) = code.as_mut() {
+                    body.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((language, body)) = code.take() {
+                    events.push(Event::Html(code_block(&language, &body).into()));
+                }
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let dest_url = image_path(&dest_url, document)
+                    .and_then(|path| inline_image(&path))
+                    .map(Into::into)
+                    .unwrap_or(dest_url);
+                events.push(Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }));
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::with_capacity(text.len() * 2);
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    sanitize(&html)
+}
+
+/// Markdown as sanitized HTML. `path` is the Markdown file, when there is one, for
+/// resolving its relative images.
+#[tauri::command]
+pub async fn render_markdown(text: String, path: Option<String>) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || render(&text, path.as_deref().map(Path::new)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_sanitized_html_with_highlighted_code_and_inline_images() {
+        let dir = std::env::temp_dir().join(format!("voidesk-markdown-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("logo.png"), b"\x89PNG\r\n\x1a\nrest").unwrap();
+        let root = workspace_roots::register(&dir.to_string_lossy()).unwrap();
+
+        let text = "\
+# Title
+
+- [x] done
+
+```rust
+let x = 1;
+```
+
+![logo](/logo.png) ![up](../logo.png?raw=true) ![web](https://example.com/a.png)
+
+<script>alert(1)</script><a href=\"javascript:alert(1)\">x</a>
+[data](data:text/html;base64,PHNjcmlwdD4=)
+";
+        let readme = root.join("docs/README.md");
+        let html = render(text, Some(&readme));
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("type=\"checkbox\""));
+        assert!(html
+            .contains("<code class=\"language-rust\"><span class=\"tok-keyword\">let</span> x = "));
+        assert_eq!(html.matches("src=\"data:image/png;base64,").count(), 2);
+        assert!(html.contains("src=\"https://example.com/a.png\""));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("data:text/html"));
+
+        // Outside the workspace nothing is read
+        let outside = render("![x](../../logo.png)", Some(&readme));
+        assert!(outside.contains("src=\"../../logo.png\""));
+
+        workspace_roots::unregister(&root.to_string_lossy()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,14 +1,34 @@
 pub mod ai_commands;
 pub mod ai_debug;
+pub mod ai_diagnostic;
+pub mod ai_edit;
 pub mod ai_service;
+pub mod ai_terminal;
 pub mod ai_tools;
 pub mod attachment_commands;
 pub mod chat_storage;
 pub mod codex_auth;
+pub mod conversation_export;
+pub mod editor_context;
 pub mod file_commands;
+pub mod file_encoding;
+pub mod file_finder;
 pub mod file_watcher;
+pub mod format_commands;
+pub mod ignore_engine;
+pub mod image_commands;
+pub mod lint_commands;
+pub mod local_history;
+pub mod log_tail;
 pub mod lsp_commands;
 pub mod lsp_runtime;
+pub mod markdown_commands;
+pub mod notification_commands;
+pub mod port_commands;
 pub mod project_commands;
+pub mod recent_items;
+pub mod scaffold;
 pub mod search_commands;
+pub mod workspace;
 pub mod workspace_index;
+pub mod workspace_roots;
@@ -0,0 +1,116 @@
+// Notifications
+// Lets long-running work (agent runs, background builds) tell the user it is done. While
+// one of our windows has focus the notice goes to the frontend as a `user-notification`
+// event to show in-app; otherwise it goes through the OS notification system.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Payload of the `user-notification` event
+#[derive(Debug, Clone, Serialize)]
+pub struct UserNotification {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub kind: NotificationKind,
+    /// Unix timestamp in milliseconds
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationDelivery {
+    System,
+    InApp,
+}
+
+/// The OS shows no kind, so non-info kinds lead the title
+fn system_title(title: &str, kind: NotificationKind) -> String {
+    match kind {
+        NotificationKind::Info => title.to_string(),
+        NotificationKind::Success => format!("✓ {}", title),
+        NotificationKind::Warning => format!("⚠ {}", title),
+        NotificationKind::Error => format!("✗ {}", title),
+    }
+}
+
+fn any_window_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+/// Notify the user, in-app when they are looking at us and through the OS otherwise
+pub(crate) fn notify(
+    app: &AppHandle,
+    title: &str,
+    body: &str,
+    kind: NotificationKind,
+) -> NotificationDelivery {
+    if !any_window_focused(app) {
+        let shown = app
+            .notification()
+            .builder()
+            .title(system_title(title, kind))
+            .body(body)
+            .show();
+        match shown {
+            Ok(()) => return NotificationDelivery::System,
+            // Falls back to the event, which the frontend shows once it has focus again
+            Err(e) => tracing::warn!("System notification failed: {}", e),
+        }
+    }
+
+    let notification = UserNotification {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: title.to_string(),
+        body: body.to_string(),
+        kind,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let _ = app.emit("user-notification", notification);
+    NotificationDelivery::InApp
+}
+
+#[tauri::command]
+pub async fn notify_user(
+    app: AppHandle,
+    title: String,
+    body: String,
+    kind: Option<NotificationKind>,
+) -> Result<NotificationDelivery, String> {
+    if title.trim().is_empty() {
+        return Err("Notification title is empty".to_string());
+    }
+    Ok(notify(&app, &title, &body, kind.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_the_kind_in_system_titles() {
+        assert_eq!(
+            system_title("Build finished", NotificationKind::Info),
+            "Build finished"
+        );
+        assert_eq!(
+            system_title("Build failed", NotificationKind::Error),
+            "✗ Build failed"
+        );
+        let kind: NotificationKind = serde_json::from_str("\"warning\"").unwrap();
+        assert_eq!(kind, NotificationKind::Warning);
+    }
+}
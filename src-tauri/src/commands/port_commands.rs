@@ -0,0 +1,289 @@
+// Ports
+// Which TCP ports have something listening on them, and what, so a dev server that
+// fails to start can be explained ("already running on :3000") rather than left to a
+// cryptic EADDRINUSE. Read from /proc on Linux, lsof on macOS and netstat on Windows.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListeningPort {
+    pub port: u16,
+    /// The bound address, e.g. "127.0.0.1" or "::"
+    pub address: String,
+    /// None when the process belongs to another user
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// A listener from /proc/net/tcp or tcp6: its address, port and socket inode
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_net_line(line: &str) -> Option<(IpAddr, u16, u64)> {
+    const LISTEN: &str = "0A";
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 || fields[3] != LISTEN {
+        return None;
+    }
+    let (address, port) = fields[1].split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let inode = fields[9].parse().ok()?;
+
+    // Each 32-bit word of the address is printed as a number in host byte order
+    let mut bytes = Vec::with_capacity(16);
+    for index in (0..address.len()).step_by(8) {
+        let word = u32::from_str_radix(address.get(index..index + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let address = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => return None,
+    };
+    Some((address, port, inode))
+}
+
+#[cfg(target_os = "linux")]
+fn platform_listeners() -> Result<Vec<ListeningPort>, String> {
+    let mut sockets = Vec::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        // tcp6 is missing when IPv6 is disabled
+        let Ok(content) = std::fs::read_to_string(table) else {
+            continue;
+        };
+        sockets.extend(content.lines().skip(1).filter_map(parse_proc_net_line));
+    }
+
+    // Socket inodes of the processes we may look into
+    let mut owners: HashMap<u64, u32> = HashMap::new();
+    let processes = std::fs::read_dir("/proc").map_err(|e| e.to_string())?;
+    for process in processes.flatten() {
+        let Some(pid) = process
+            .file_name()
+            .to_str()
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(descriptors) = std::fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        for descriptor in descriptors.flatten() {
+            let Ok(target) = std::fs::read_link(descriptor.path()) else {
+                continue;
+            };
+            let target = target.to_string_lossy();
+            if let Some(inode) = target
+                .strip_prefix("socket:[")
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|inode| inode.parse().ok())
+            {
+                owners.insert(inode, pid);
+            }
+        }
+    }
+
+    Ok(sockets
+        .into_iter()
+        .map(|(address, port, inode)| {
+            let pid = owners.get(&inode).copied();
+            let process = pid.and_then(|pid| {
+                std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .ok()
+                    .map(|name| name.trim().to_string())
+            });
+            ListeningPort {
+                port,
+                address: address.to_string(),
+                pid,
+                process,
+            }
+        })
+        .collect())
+}
+
+/// `host:port` as lsof and netstat print it, e.g. "*:3000", "[::1]:8080"
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn split_host_port(text: &str) -> Option<(String, u16)> {
+    let (host, port) = text.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = if host == "*" { "0.0.0.0" } else { host };
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+/// `lsof -F pcn` output: a `p` line per process, then its name and addresses
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_lsof(output: &str) -> Vec<ListeningPort> {
+    let mut ports = Vec::new();
+    let mut pid = None;
+    let mut process = None;
+    for line in output.lines() {
+        let (field, value) = line.split_at(line.len().min(1));
+        match field {
+            "p" => {
+                pid = value.parse().ok();
+                process = None;
+            }
+            "c" => process = Some(value.to_string()),
+            "n" => {
+                if let Some((address, port)) = split_host_port(value) {
+                    ports.push(ListeningPort {
+                        port,
+                        address,
+                        pid,
+                        process: process.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    ports
+}
+
+/// `netstat -ano` output, with process names from `tasklist /FO CSV /NH`
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_netstat(output: &str, tasklist: &str) -> Vec<ListeningPort> {
+    let names: HashMap<u32, String> = tasklist
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split("\",\"");
+            let name = fields.next()?.trim_start_matches('"');
+            let pid = fields.next()?.parse().ok()?;
+            Some((pid, name.to_string()))
+        })
+        .collect();
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 5 || fields[0] != "TCP" || fields[3] != "LISTENING" {
+                return None;
+            }
+            let (address, port) = split_host_port(fields[1])?;
+            let pid = fields[4].parse().ok();
+            Some(ListeningPort {
+                port,
+                address,
+                pid,
+                process: pid.and_then(|pid| names.get(&pid).cloned()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn platform_listeners() -> Result<Vec<ListeningPort>, String> {
+    let run = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+            .map_err(|e| format!("Could not run {}: {}", program, e))
+    };
+    if cfg!(windows) {
+        let netstat = run("netstat", &["-ano", "-p", "TCP"])?;
+        let netstat6 = run("netstat", &["-ano", "-p", "TCPv6"]).unwrap_or_default();
+        let tasklist = run("tasklist", &["/FO", "CSV", "/NH"]).unwrap_or_default();
+        Ok(parse_netstat(
+            &format!("{}\n{}", netstat, netstat6),
+            &tasklist,
+        ))
+    } else {
+        let lsof = run("lsof", &["-nP", "-iTCP", "-sTCP:LISTEN", "-F", "pcn"])?;
+        Ok(parse_lsof(&lsof))
+    }
+}
+
+/// Every listening TCP socket, by port; a port bound on several addresses is listed
+/// once per address
+pub(crate) fn listening_ports() -> Result<Vec<ListeningPort>, String> {
+    let mut ports = platform_listeners()?;
+    ports.sort_by(|a, b| a.port.cmp(&b.port).then_with(|| a.address.cmp(&b.address)));
+    ports.dedup();
+    Ok(ports)
+}
+
+/// Whether a server could listen on `port`: no socket in the listening table holds it
+/// and it binds on localhost. Only localhost is bound, since binding every interface can
+/// bring up the Windows firewall prompt; the table covers servers on other addresses.
+pub(crate) fn port_is_free(port: u16) -> bool {
+    let listed =
+        listening_ports().is_ok_and(|ports| ports.iter().any(|listening| listening.port == port));
+    !listed && TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+#[tauri::command]
+pub async fn get_listening_ports() -> Result<Vec<ListeningPort>, String> {
+    tokio::task::spawn_blocking(listening_ports)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn is_port_free(port: u16) -> Result<bool, String> {
+    tokio::task::spawn_blocking(move || port_is_free(port))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_listeners_in_each_platforms_output() {
+        let loopback = format!("{:08X}", u32::from_ne_bytes([127, 0, 0, 1]));
+        let line = format!(
+            "   0: {}:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000 \
+             0 4242 1 0000000000000000 100 0 0 10 0",
+            loopback
+        );
+        assert_eq!(
+            parse_proc_net_line(&line),
+            Some((IpAddr::V4(Ipv4Addr::LOCALHOST), 3000, 4242))
+        );
+        let established = line.replace(" 0A ", " 01 ");
+        assert_eq!(parse_proc_net_line(&established), None);
+
+        let lsof =
+            "p812\ncnode\nf23\nn*:5173\nf24\nn[::1]:5173\np99\ncpostgres\nf7\nn127.0.0.1:5432\n";
+        let ports = parse_lsof(lsof);
+        assert_eq!(ports.len(), 3);
+        assert_eq!(
+            (ports[0].port, ports[0].address.as_str()),
+            (5173, "0.0.0.0")
+        );
+        assert_eq!(ports[1].address, "::1");
+        assert_eq!(
+            (ports[2].pid, ports[2].process.as_deref()),
+            (Some(99), Some("postgres"))
+        );
+
+        let netstat = "\
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:3000           0.0.0.0:0              LISTENING       4312
+  TCP    127.0.0.1:50512        127.0.0.1:3000         ESTABLISHED     900
+  TCP    [::]:3000              [::]:0                 LISTENING       4312
+";
+        let tasklist = "\"node.exe\",\"4312\",\"Console\",\"1\",\"52,100 K\"\n";
+        let ports = parse_netstat(netstat, tasklist);
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].process.as_deref(), Some("node.exe"));
+        assert_eq!(ports[1].address, "::");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!port_is_free(port));
+        if cfg!(target_os = "linux") {
+            let ours = listening_ports()
+                .unwrap()
+                .into_iter()
+                .find(|listening| listening.port == port)
+                .unwrap();
+            assert_eq!(ours.pid, Some(std::process::id()));
+        }
+        drop(listener);
+        assert!(port_is_free(port));
+    }
+}
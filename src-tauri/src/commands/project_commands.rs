@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
 
+use super::ignore_engine::IgnoreRules;
 use super::workspace_index;
+use super::workspace_roots;
+use crate::error::VoidDeskError;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileEntry {
@@ -16,37 +22,102 @@ pub struct FileNode {
     pub path: String,
     pub name: String,
     pub is_dir: bool,
+    #[serde(default)]
+    pub is_symlink: bool,
+    /// Checked-out git submodule; its status comes from git_submodules
+    #[serde(default)]
+    pub is_submodule: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<FileNode>>,
 }
 
+/// One explorer row; children are fetched with expand_tree_node when it is opened
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    /// False for empty folders and folders holding only ignored entries
+    pub has_children: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryStats {
+    pub path: String,
+    pub total_size: u64,
+    pub file_count: u64,
+    pub directory_count: u64,
+    /// False while a walk is still reporting progress
+    pub done: bool,
+}
+
+/// How often get_directory_stats reports progress while walking
+const STATS_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Nodes per batch sent by stream_project_tree unless the caller picks a size
+const DEFAULT_TREE_BATCH_SIZE: usize = 500;
+
+/// A node delivered by stream_project_tree, with the folder it belongs in
+#[derive(Debug, Serialize)]
+pub struct StreamedTreeNode {
+    /// None for entries directly under the root
+    pub parent: Option<String>,
+    #[serde(flatten)]
+    pub node: FileNode,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectTreeSummary {
+    pub total_nodes: usize,
+    /// True when the webview stopped listening before the walk finished
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProjectTreeEvent {
+    Batch { nodes: Vec<StreamedTreeNode> },
+    Done(ProjectTreeSummary),
+}
+
+fn not_a_directory(path: &str) -> VoidDeskError {
+    VoidDeskError::invalid_input(format!("Path is not a directory: {}", path))
+}
+
 #[tauri::command]
-pub async fn list_directory(path: String) -> Result<Vec<FileEntry>, String> {
+pub async fn list_directory(
+    path: String,
+    root: Option<String>,
+) -> Result<Vec<FileEntry>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
     let dir_path = Path::new(&path);
+    let root = root
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dir_path.to_path_buf());
+    let ignore_rules = IgnoreRules::load(&root);
 
     if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
+        return Err(not_a_directory(&path));
     }
 
     let mut entries: Vec<FileEntry> = Vec::new();
 
-    let read_dir = fs::read_dir(dir_path).map_err(|e| e.to_string())?;
+    let read_dir = fs::read_dir(dir_path)?;
 
     for entry in read_dir {
-        let entry = entry.map_err(|e| e.to_string())?;
+        let entry = entry?;
         let file_name = entry.file_name().to_string_lossy().to_string();
 
-        // Skip hidden files and common ignore patterns
-        if file_name.starts_with('.')
-            || file_name == "node_modules"
-            || file_name == "target"
-            || file_name == "dist"
-            || file_name == "__pycache__"
-        {
+        let rel_path = entry
+            .path()
+            .strip_prefix(&root)
+            .map(|rel_path| rel_path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| file_name.clone());
+        if ignore_rules.is_hidden(&rel_path, &file_name) {
             continue;
         }
 
-        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        let file_type = entry.file_type()?;
 
         entries.push(FileEntry {
             path: entry.path().to_string_lossy().to_string(),
@@ -65,14 +136,286 @@ pub async fn list_directory(path: String) -> Result<Vec<FileEntry>, String> {
     Ok(entries)
 }
 
+/// Symlinked folders are expanded unless `follow_symlinks` is false; links that loop
+/// back to a parent folder are never descended into.
 #[tauri::command]
-pub async fn get_project_tree(path: String, max_depth: usize) -> Result<Vec<FileNode>, String> {
+pub async fn get_project_tree(
+    path: String,
+    max_depth: usize,
+    follow_symlinks: Option<bool>,
+) -> Result<Vec<FileNode>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
     let dir_path = Path::new(&path);
     if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
+        return Err(not_a_directory(&path));
+    }
+
+    let follow_symlinks = follow_symlinks.unwrap_or(true);
+    let tree = tokio::task::spawn_blocking(move || {
+        workspace_index::build_project_tree(&path, max_depth, follow_symlinks)
+    })
+    .await
+    .map_err(|e| VoidDeskError::internal(e.to_string()))??;
+    Ok(tree)
+}
+
+/// Walk the tree breadth first, so the top levels arrive before deep folders, and hand
+/// out nodes in batches. Stops early if `send` reports the receiver is gone.
+fn walk_tree_batches(
+    root: &Path,
+    max_depth: usize,
+    follow_symlinks: bool,
+    batch_size: usize,
+    mut send: impl FnMut(Vec<StreamedTreeNode>) -> bool,
+) -> ProjectTreeSummary {
+    let ignore_rules = IgnoreRules::load(root);
+    let mut visited: HashSet<PathBuf> = fs::canonicalize(root).into_iter().collect();
+    let mut pending = VecDeque::from([(root.to_path_buf(), None::<String>, 0usize)]);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut total_nodes = 0;
+
+    while let Some((dir, parent, depth)) = pending.pop_front() {
+        let Ok(mut entries) = visible_entries(root, &dir, &ignore_rules) else {
+            continue;
+        };
+        entries.sort_by(
+            |(_, a_name, a_dir), (_, b_name, b_dir)| match (a_dir, b_dir) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a_name.to_lowercase().cmp(&b_name.to_lowercase()),
+            },
+        );
+
+        for (path, name, is_dir) in entries {
+            let is_symlink = fs::symlink_metadata(&path)
+                .map(|metadata| metadata.file_type().is_symlink())
+                .unwrap_or(false);
+            let path_string = path.to_string_lossy().to_string();
+            let is_submodule = is_dir && is_submodule_root(&path);
+            // Canonical paths catch symlinks looping back to a folder already walked
+            let descend = is_dir
+                && depth + 1 < max_depth
+                && (follow_symlinks || !is_symlink)
+                && fs::canonicalize(&path).is_ok_and(|canonical| visited.insert(canonical));
+            if descend {
+                pending.push_back((path, Some(path_string.clone()), depth + 1));
+            }
+
+            batch.push(StreamedTreeNode {
+                parent: parent.clone(),
+                node: FileNode {
+                    path: path_string,
+                    name,
+                    is_dir,
+                    is_symlink,
+                    is_submodule,
+                    children: None,
+                },
+            });
+            total_nodes += 1;
+            if batch.len() >= batch_size && !send(std::mem::take(&mut batch)) {
+                return ProjectTreeSummary {
+                    total_nodes,
+                    cancelled: true,
+                };
+            }
+        }
+    }
+
+    let cancelled = !batch.is_empty() && !send(batch);
+    ProjectTreeSummary {
+        total_nodes,
+        cancelled,
+    }
+}
+
+/// Stream the project tree in batches for workspaces too large for get_project_tree's
+/// single payload. Nodes arrive as flat (parent, node) pairs, top levels first, and a
+/// final Done event marks completion.
+#[tauri::command]
+pub async fn stream_project_tree(
+    path: String,
+    max_depth: usize,
+    follow_symlinks: Option<bool>,
+    batch_size: Option<usize>,
+    on_event: Channel<ProjectTreeEvent>,
+) -> Result<ProjectTreeSummary, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(not_a_directory(&path));
     }
+    let follow_symlinks = follow_symlinks.unwrap_or(true);
+    let batch_size = batch_size.unwrap_or(DEFAULT_TREE_BATCH_SIZE).max(1);
 
-    tokio::task::spawn_blocking(move || workspace_index::build_project_tree(&path, max_depth))
-        .await
-        .map_err(|e| e.to_string())?
+    tokio::task::spawn_blocking(move || {
+        let summary = walk_tree_batches(&root, max_depth, follow_symlinks, batch_size, |nodes| {
+            on_event.send(ProjectTreeEvent::Batch { nodes }).is_ok()
+        });
+        let _ = on_event.send(ProjectTreeEvent::Done(summary.clone()));
+        summary
+    })
+    .await
+    .map_err(|e| VoidDeskError::internal(e.to_string()))
+}
+
+/// A checked-out submodule has a `.git` file pointing into the parent's git dir, where a
+/// nested repository has a `.git` folder
+pub(crate) fn is_submodule_root(dir: &Path) -> bool {
+    fs::symlink_metadata(dir.join(".git")).is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Visible entries of `dir`, using the project's ignore rules, as (path, name, is_dir)
+fn visible_entries(
+    root: &Path,
+    dir: &Path,
+    ignore_rules: &IgnoreRules,
+) -> Result<Vec<(PathBuf, String, bool)>, VoidDeskError> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let rel_path = path
+            .strip_prefix(root)
+            .map(|rel_path| rel_path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| name.clone());
+        if ignore_rules.is_hidden(&rel_path, &name) {
+            continue;
+        }
+        // Follow symlinks so linked folders can be expanded too
+        let is_dir = fs::metadata(&path)
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false);
+        entries.push((path, name, is_dir));
+    }
+    Ok(entries)
+}
+
+/// List a single level of the tree under `path` without walking the whole project, so
+/// the explorer can open large monorepos immediately and load folders as they expand
+#[tauri::command]
+pub async fn expand_tree_node(
+    path: String,
+    root: Option<String>,
+) -> Result<Vec<TreeNode>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(not_a_directory(&path));
+    }
+    let root = root.map(PathBuf::from).unwrap_or_else(|| dir.clone());
+
+    tokio::task::spawn_blocking(move || {
+        let ignore_rules = IgnoreRules::load(&root);
+        let mut nodes = visible_entries(&root, &dir, &ignore_rules)?
+            .into_iter()
+            .map(|(path, name, is_dir)| {
+                let has_children = is_dir
+                    && visible_entries(&root, &path, &ignore_rules)
+                        .map(|children| !children.is_empty())
+                        .unwrap_or(false);
+                TreeNode {
+                    path: path.to_string_lossy().to_string(),
+                    name,
+                    is_dir,
+                    has_children,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        nodes.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        });
+        Ok(nodes)
+    })
+    .await
+    .map_err(|e| VoidDeskError::internal(e.to_string()))?
+}
+
+fn walk_directory_stats(
+    root: &Path,
+    dir: &Path,
+    ignore_rules: Option<&IgnoreRules>,
+    on_progress: Option<&Channel<DirectoryStats>>,
+) -> DirectoryStats {
+    let mut stats = DirectoryStats {
+        path: dir.to_string_lossy().to_string(),
+        ..DirectoryStats::default()
+    };
+    let mut last_progress = Instant::now();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        // Unreadable folders are skipped rather than failing the whole walk
+        let Ok(read_dir) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            if let Some(ignore_rules) = ignore_rules {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let rel_path = path
+                    .strip_prefix(root)
+                    .map(|rel_path| rel_path.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_else(|_| name.clone());
+                if ignore_rules.is_hidden(&rel_path, &name) {
+                    continue;
+                }
+            }
+
+            // Symlinks are counted but not followed, so cycles can't trap the walk
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stats.directory_count += 1;
+                pending.push(path);
+            } else {
+                stats.file_count += 1;
+                stats.total_size += metadata.len();
+            }
+        }
+
+        if let Some(channel) = on_progress {
+            if last_progress.elapsed() >= STATS_PROGRESS_INTERVAL {
+                let _ = channel.send(stats.clone());
+                last_progress = Instant::now();
+            }
+        }
+    }
+
+    stats.done = true;
+    stats
+}
+
+/// Total size and entry counts under `path`. Entries matched by the project's ignore
+/// rules are skipped unless `include_ignored` is set, e.g. to size build artifacts.
+/// Large walks report intermediate totals through `on_progress`.
+#[tauri::command]
+pub async fn get_directory_stats(
+    path: String,
+    root: Option<String>,
+    include_ignored: Option<bool>,
+    on_progress: Option<Channel<DirectoryStats>>,
+) -> Result<DirectoryStats, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&path)?;
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(not_a_directory(&path));
+    }
+    let root = root.map(PathBuf::from).unwrap_or_else(|| dir.clone());
+
+    tokio::task::spawn_blocking(move || {
+        let ignore_rules = (!include_ignored.unwrap_or(false)).then(|| IgnoreRules::load(&root));
+        let stats = walk_directory_stats(&root, &dir, ignore_rules.as_ref(), on_progress.as_ref());
+        if let Some(channel) = &on_progress {
+            let _ = channel.send(stats.clone());
+        }
+        stats
+    })
+    .await
+    .map_err(|e| VoidDeskError::internal(e.to_string()))
 }
@@ -0,0 +1,256 @@
+// Recently opened files and projects
+// Kept in the app database so quick open can rank recent files and the welcome screen
+// can list recent projects; pinned items stay at the top and are never pruned
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static RECENT_ITEMS_DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Unpinned items kept per kind; older ones are pruned as new ones are opened
+const MAX_RECENT_PER_KIND: i64 = 200;
+
+const DEFAULT_RECENT_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    File,
+    Project,
+}
+
+impl RecentKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecentKind::File => "file",
+            RecentKind::Project => "project",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentItem {
+    pub path: String,
+    pub pinned: bool,
+    /// Unix timestamp in milliseconds
+    pub last_opened_at: i64,
+    pub open_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentItems {
+    pub files: Vec<RecentItem>,
+    pub projects: Vec<RecentItem>,
+}
+
+pub fn initialize_persistence(db_path: PathBuf) -> Result<(), String> {
+    let registered_path = RECENT_ITEMS_DB_PATH.get_or_init(|| db_path);
+    initialize_database(registered_path)
+}
+
+fn db_path() -> Result<&'static PathBuf, String> {
+    RECENT_ITEMS_DB_PATH
+        .get()
+        .ok_or_else(|| "Recent items have not been initialized".to_string())
+}
+
+fn open_connection(db_path: &Path) -> Result<Connection, String> {
+    let connection = Connection::open(db_path).map_err(|e| {
+        format!(
+            "failed to open recent items database at {}: {}",
+            db_path.display(),
+            e
+        )
+    })?;
+    connection
+        .busy_timeout(Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    connection
+        .execute_batch(
+            r#"
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(connection)
+}
+
+fn initialize_database(db_path: &Path) -> Result<(), String> {
+    let connection = open_connection(db_path)?;
+    connection
+        .execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS recent_items (
+                kind TEXT NOT NULL,
+                path TEXT NOT NULL,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                last_opened_at INTEGER NOT NULL,
+                open_count INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (kind, path)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_recent_items_kind_opened
+                ON recent_items(kind, last_opened_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn record_open(db_path: &Path, kind: RecentKind, path: &str) -> Result<(), String> {
+    let path = normalize(path);
+    if path.trim().is_empty() {
+        return Ok(());
+    }
+
+    let connection = open_connection(db_path)?;
+    connection
+        .execute(
+            "INSERT INTO recent_items (kind, path, last_opened_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(kind, path) DO UPDATE SET
+                 last_opened_at = excluded.last_opened_at,
+                 open_count = open_count + 1",
+            params![kind.as_str(), path, chrono::Utc::now().timestamp_millis()],
+        )
+        .map_err(|e| e.to_string())?;
+    connection
+        .execute(
+            "DELETE FROM recent_items
+             WHERE kind = ?1 AND pinned = 0 AND path NOT IN (
+                 SELECT path FROM recent_items
+                 WHERE kind = ?1 AND pinned = 0
+                 ORDER BY last_opened_at DESC
+                 LIMIT ?2
+             )",
+            params![kind.as_str(), MAX_RECENT_PER_KIND],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn set_pinned(db_path: &Path, kind: RecentKind, path: &str, pinned: bool) -> Result<(), String> {
+    let connection = open_connection(db_path)?;
+    let updated = connection
+        .execute(
+            "UPDATE recent_items SET pinned = ?3 WHERE kind = ?1 AND path = ?2",
+            params![kind.as_str(), normalize(path), pinned],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Not in the recent list: {}", path));
+    }
+    Ok(())
+}
+
+fn remove(db_path: &Path, kind: RecentKind, path: &str) -> Result<(), String> {
+    let connection = open_connection(db_path)?;
+    connection
+        .execute(
+            "DELETE FROM recent_items WHERE kind = ?1 AND path = ?2",
+            params![kind.as_str(), normalize(path)],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Pinned items first, then the most recently opened
+fn query_recent(db_path: &Path, kind: RecentKind, limit: usize) -> Result<Vec<RecentItem>, String> {
+    let connection = open_connection(db_path)?;
+    let mut statement = connection
+        .prepare(
+            "SELECT path, pinned, last_opened_at, open_count
+             FROM recent_items
+             WHERE kind = ?1
+             ORDER BY pinned DESC, last_opened_at DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = statement
+        .query_map(params![kind.as_str(), limit as i64], |row| {
+            Ok(RecentItem {
+                path: row.get(0)?,
+                pinned: row.get(1)?,
+                last_opened_at: row.get(2)?,
+                open_count: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn record_file_open(path: String) -> Result<(), String> {
+    record_open(db_path()?, RecentKind::File, &path)
+}
+
+#[tauri::command]
+pub async fn record_project_open(path: String) -> Result<(), String> {
+    record_open(db_path()?, RecentKind::Project, &path)
+}
+
+#[tauri::command]
+pub async fn get_recent(limit: Option<usize>) -> Result<RecentItems, String> {
+    let db_path = db_path()?;
+    let limit = limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+    Ok(RecentItems {
+        files: query_recent(db_path, RecentKind::File, limit)?,
+        projects: query_recent(db_path, RecentKind::Project, limit)?,
+    })
+}
+
+#[tauri::command]
+pub async fn pin_recent_item(kind: RecentKind, path: String, pinned: bool) -> Result<(), String> {
+    set_pinned(db_path()?, kind, &path, pinned)
+}
+
+#[tauri::command]
+pub async fn remove_recent_item(kind: RecentKind, path: String) -> Result<(), String> {
+    remove(db_path()?, kind, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_pinned_then_recent_and_counts_reopens() {
+        let db_path = std::env::temp_dir().join(format!(
+            "voidesk-recent-items-{}.sqlite",
+            uuid::Uuid::new_v4()
+        ));
+        initialize_database(&db_path).expect("schema should be created");
+
+        record_open(&db_path, RecentKind::File, "/repo/a.rs").unwrap();
+        record_open(&db_path, RecentKind::File, "/repo/b.rs").unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        record_open(&db_path, RecentKind::File, "/repo/a.rs").unwrap();
+        record_open(&db_path, RecentKind::Project, "C:\\work\\repo").unwrap();
+        set_pinned(&db_path, RecentKind::File, "/repo/b.rs", true).unwrap();
+
+        let files = query_recent(&db_path, RecentKind::File, 10).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "/repo/b.rs");
+        assert!(files[0].pinned);
+        assert_eq!(files[1].open_count, 2);
+
+        let projects = query_recent(&db_path, RecentKind::Project, 10).unwrap();
+        assert_eq!(projects[0].path, "C:/work/repo");
+        assert!(set_pinned(&db_path, RecentKind::Project, "/missing", true).is_err());
+
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_file(db_path.with_extension("sqlite-wal"));
+        let _ = std::fs::remove_file(db_path.with_extension("sqlite-shm"));
+    }
+}
@@ -0,0 +1,337 @@
+// Scaffolding
+// Instantiates file templates such as "New React Component". Built-in templates ship
+// with the app; users add their own as folders under the app data directory or under
+// `.voidesk/templates` in a project.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::file_commands::write_atomic;
+use super::workspace_roots;
+
+static USER_TEMPLATES_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Describes a template folder; its other files are the template's contents
+const MANIFEST_FILE_NAME: &str = "template.json";
+
+const PROJECT_TEMPLATES_DIR: &str = ".voidesk/templates";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateFile {
+    /// Relative to the target folder; may contain {{variables}}
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    #[serde(default)]
+    pub files: Vec<TemplateFile>,
+    /// "builtin", "user" or "project"
+    #[serde(default)]
+    pub source: String,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    let templates_dir = USER_TEMPLATES_DIR.get_or_init(|| app_data_dir.join("templates"));
+    fs::create_dir_all(templates_dir).map_err(|e| e.to_string())
+}
+
+fn variable(name: &str, description: &str, default: Option<&str>) -> TemplateVariable {
+    TemplateVariable {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        default: default.map(str::to_string),
+    }
+}
+
+fn file(path: &str, content: &str) -> TemplateFile {
+    TemplateFile {
+        path: path.to_string(),
+        content: content.to_string(),
+    }
+}
+
+fn builtin_templates() -> Vec<Template> {
+    vec![
+        Template {
+            id: "react-component".to_string(),
+            name: "React Component".to_string(),
+            description: "Function component with a barrel export".to_string(),
+            variables: vec![variable("name", "Component name, e.g. UserCard", None)],
+            files: vec![
+                file(
+                    "{{name}}/{{name}}.tsx",
+                    "interface {{name}}Props {}\n\n\
+                     export function {{name}}({}: {{name}}Props) {\n    \
+                     return <div>{{name}}</div>;\n}\n",
+                ),
+                file(
+                    "{{name}}/index.ts",
+                    "export { {{name}} } from \"./{{name}}\";\n",
+                ),
+            ],
+            source: "builtin".to_string(),
+        },
+        Template {
+            id: "rust-bin".to_string(),
+            name: "Rust binary crate".to_string(),
+            description: "Cargo package with a main.rs".to_string(),
+            variables: vec![variable("name", "Crate name", None)],
+            files: vec![
+                file(
+                    "{{name}}/Cargo.toml",
+                    "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\n\
+                     edition = \"2021\"\n\n[dependencies]\n",
+                ),
+                file(
+                    "{{name}}/src/main.rs",
+                    "fn main() {\n    println!(\"Hello from {{name}}!\");\n}\n",
+                ),
+            ],
+            source: "builtin".to_string(),
+        },
+    ]
+}
+
+/// Read every file below `dir` except the manifest as template files
+fn collect_template_files(
+    base: &Path,
+    dir: &Path,
+    files: &mut Vec<TemplateFile>,
+) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_dir() {
+            collect_template_files(base, &path, files)?;
+            continue;
+        }
+        let rel_path = path
+            .strip_prefix(base)
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if rel_path == MANIFEST_FILE_NAME {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Template file {} is not text: {}", path.display(), e))?;
+        files.push(file(&rel_path, &content));
+    }
+    Ok(())
+}
+
+/// Templates stored as `<dir>/<id>/template.json` plus their files
+fn load_template_dir(dir: &Path, source: &str) -> Vec<Template> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut templates = Vec::new();
+    for entry in read_dir.flatten() {
+        let template_dir = entry.path();
+        let manifest = template_dir.join(MANIFEST_FILE_NAME);
+        if !manifest.is_file() {
+            continue;
+        }
+        let loaded = fs::read_to_string(&manifest)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_str::<Template>(&raw).map_err(|e| e.to_string()))
+            .and_then(|mut template| {
+                collect_template_files(&template_dir, &template_dir, &mut template.files)?;
+                template.id = format!("{}:{}", source, entry.file_name().to_string_lossy());
+                template.source = source.to_string();
+                Ok(template)
+            });
+        match loaded {
+            Ok(template) => templates.push(template),
+            Err(e) => tracing::warn!("Skipping template {}: {}", template_dir.display(), e),
+        }
+    }
+    templates
+}
+
+fn all_templates(root: Option<&str>) -> Vec<Template> {
+    let mut templates = builtin_templates();
+    if let Some(user_dir) = USER_TEMPLATES_DIR.get() {
+        templates.extend(load_template_dir(user_dir, "user"));
+    }
+    if let Some(root) = root {
+        templates.extend(load_template_dir(
+            &Path::new(root).join(PROJECT_TEMPLATES_DIR),
+            "project",
+        ));
+    }
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+/// Replace `{{name}}` placeholders; unknown variables are an error so typos don't
+/// end up in generated files
+fn substitute(text: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        let value = variables
+            .get(name)
+            .ok_or_else(|| format!("Missing template variable: {}", name))?;
+        output.push_str(&rest[..start]);
+        output.push_str(value);
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Work out every file to write before touching the disk
+fn plan_files(
+    template: &Template,
+    target_dir: &Path,
+    provided: HashMap<String, String>,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut variables = provided;
+    for declared in &template.variables {
+        if !variables.contains_key(&declared.name) {
+            let default = declared
+                .default
+                .clone()
+                .ok_or_else(|| format!("Missing template variable: {}", declared.name))?;
+            variables.insert(declared.name.clone(), default);
+        }
+    }
+
+    let mut planned = Vec::new();
+    for template_file in &template.files {
+        let rel_path = PathBuf::from(substitute(&template_file.path, &variables)?);
+        if rel_path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(format!(
+                "Template path escapes the target folder: {}",
+                rel_path.display()
+            ));
+        }
+        let target = target_dir.join(rel_path);
+        if target.exists() {
+            return Err(format!("File already exists: {}", target.display()));
+        }
+        planned.push((target, substitute(&template_file.content, &variables)?));
+    }
+    Ok(planned)
+}
+
+/// Write all planned files, removing the ones already written if any write fails
+fn write_planned(planned: &[(PathBuf, String)]) -> Result<(), String> {
+    let mut written: Vec<&Path> = Vec::new();
+    let mut created_dirs: Vec<PathBuf> = Vec::new();
+    let result = planned.iter().try_for_each(|(path, content)| {
+        if let Some(parent) = path.parent() {
+            let mut missing = parent;
+            while !missing.exists() {
+                created_dirs.push(missing.to_path_buf());
+                match missing.parent() {
+                    Some(next) => missing = next,
+                    None => break,
+                }
+            }
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, content.as_bytes())?;
+        written.push(path);
+        Ok::<(), std::io::Error>(())
+    });
+
+    if let Err(e) = result {
+        for path in written {
+            let _ = fs::remove_file(path);
+        }
+        // Deepest first, and only if still empty
+        created_dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        for dir in created_dirs {
+            let _ = fs::remove_dir(dir);
+        }
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_templates(root: Option<String>) -> Result<Vec<Template>, String> {
+    Ok(all_templates(root.as_deref()))
+}
+
+/// Generate a template into `target_dir`; returns the created files. Nothing is written
+/// if any file already exists or a variable is missing.
+#[tauri::command]
+pub async fn scaffold_template(
+    template_id: String,
+    target_dir: String,
+    variables: HashMap<String, String>,
+    root: Option<String>,
+) -> Result<Vec<String>, String> {
+    workspace_roots::ensure_in_workspace(&target_dir)?;
+    let template = all_templates(root.as_deref())
+        .into_iter()
+        .find(|template| template.id == template_id)
+        .ok_or_else(|| format!("Template not found: {}", template_id))?;
+
+    let planned = plan_files(&template, Path::new(&target_dir), variables)?;
+    write_planned(&planned)?;
+    Ok(planned
+        .into_iter()
+        .map(|(path, _)| path.to_string_lossy().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaffolds_builtin_template_and_refuses_conflicts() {
+        let target =
+            std::env::temp_dir().join(format!("voidesk-scaffold-{}", uuid::Uuid::new_v4()));
+        let template = builtin_templates()
+            .into_iter()
+            .find(|template| template.id == "react-component")
+            .unwrap();
+        let variables = HashMap::from([("name".to_string(), "UserCard".to_string())]);
+
+        let planned = plan_files(&template, &target, variables.clone()).unwrap();
+        write_planned(&planned).unwrap();
+        let component = fs::read_to_string(target.join("UserCard/UserCard.tsx")).unwrap();
+        assert!(component.starts_with("interface UserCardProps {}\n\nexport function UserCard("));
+        assert!(component.contains("\n    return <div>UserCard</div>;"));
+
+        assert!(plan_files(&template, &target, variables).is_err());
+        assert!(plan_files(&template, &target, HashMap::new()).is_err());
+        let escape = HashMap::from([("name".to_string(), "../outside".to_string())]);
+        assert!(plan_files(&template, &target, escape).is_err());
+
+        let _ = fs::remove_dir_all(&target);
+    }
+}
@@ -1,18 +1,28 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use std::process::{Command, Stdio};
+use tauri::ipc::Channel;
 
+use super::file_commands::write_encoded;
+use super::file_encoding::{self, DecodedText};
+use super::file_watcher;
+use super::ignore_engine::IgnoreRules;
 use super::workspace_index;
+use super::workspace_roots;
+use crate::diff;
+use crate::error::VoidDeskError;
 
 #[derive(Deserialize)]
 pub struct SearchOptions {
     pub query: String,
     pub is_regex: bool,
     pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
     pub include_globs: Vec<String>,
     pub exclude_globs: Vec<String>,
     pub context_lines: usize,
@@ -78,18 +88,112 @@ struct RawSearchMatch {
     match_text: String,
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ProjectSearchOptions {
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+    pub max_results: Option<usize>,
+    pub max_file_size_bytes: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProjectSearchMatch {
+    pub line: usize,
+    /// Byte offset of the match within its line, like SearchMatch::column
+    pub column: usize,
+    pub match_text: String,
+    /// The matching line, shortened around the match when it is very long
+    pub preview: String,
+    /// Char range of the match inside `preview`
+    pub preview_start: usize,
+    pub preview_end: usize,
+}
+
+/// One file's matches, sent over the channel as soon as the file is done
+#[derive(Serialize, Clone)]
+pub struct ProjectSearchChunk {
+    pub path: String,
+    pub matches: Vec<ProjectSearchMatch>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectSearchSummary {
+    pub total_matches: usize,
+    pub files_with_matches: usize,
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ProjectReplaceOptions {
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+    pub max_file_size_bytes: Option<u64>,
+    /// Compute the replacements and diffs without touching any file
+    pub dry_run: bool,
+    /// Ids from a previous dry run; only these matches are replaced when set
+    pub selected_ids: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ReplacementPreview {
+    /// Stable for unchanged files, so a dry run's ids can be passed back as selected_ids
+    pub id: String,
+    pub line: usize,
+    pub column: usize,
+    pub match_text: String,
+    pub replacement_text: String,
+}
+
+#[derive(Serialize)]
+pub struct FileReplacePreview {
+    pub path: String,
+    pub replacements: Vec<ReplacementPreview>,
+    /// Unified diff of the lines this file's replacements touch
+    pub diff: String,
+}
+
+#[derive(Serialize)]
+pub struct ProjectReplaceResponse {
+    pub files: Vec<FileReplacePreview>,
+    pub files_changed: usize,
+    pub replacements_applied: usize,
+    pub dry_run: bool,
+    pub errors: Vec<ReplaceError>,
+}
+
+/// A replacement inside one file, with byte offsets into its original content
+struct PlannedReplacement {
+    start_byte: usize,
+    end_byte: usize,
+    replacement_text: String,
+}
+
 const DEFAULT_MAX_FILE_SIZE: u64 = 2 * 1024 * 1024; // 2MB
 const DEFAULT_MAX_RESULTS: usize = 10_000;
+/// Characters of context kept on each side of a match in long preview lines
+const PREVIEW_CONTEXT_CHARS: usize = 80;
 
 #[tauri::command]
 pub async fn search_in_files(
     root_path: String,
     options: SearchOptions,
     replace: Option<String>,
-) -> Result<SearchResponse, String> {
-    tokio::task::spawn_blocking(move || search_blocking(&root_path, &options, replace.as_deref()))
-        .await
-        .map_err(|e| e.to_string())?
+) -> Result<SearchResponse, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root_path)?;
+    build_search_regex(&options).map_err(VoidDeskError::invalid_input)?;
+    let response = tokio::task::spawn_blocking(move || {
+        search_blocking(&root_path, &options, replace.as_deref())
+    })
+    .await?;
+    Ok(response?)
 }
 
 fn search_blocking(
@@ -135,6 +239,32 @@ fn run_ripgrep(
     max_results: usize,
     max_file_size: u64,
 ) -> Result<(BTreeMap<String, Vec<RawSearchMatch>>, usize, bool), String> {
+    let mut raw_matches_by_file: BTreeMap<String, Vec<RawSearchMatch>> = BTreeMap::new();
+    let (total_matches, truncated) = stream_ripgrep(
+        root,
+        options,
+        max_results,
+        max_file_size,
+        |absolute_path, raw_match, _| {
+            raw_matches_by_file
+                .entry(absolute_path.to_string())
+                .or_default()
+                .push(raw_match);
+            true
+        },
+    )?;
+    Ok((raw_matches_by_file, total_matches, truncated))
+}
+
+/// Run ripgrep and hand every match, with its line text, to `on_match` as it is parsed.
+/// Returning false from `on_match` stops the search early.
+fn stream_ripgrep(
+    root: &Path,
+    options: &SearchOptions,
+    max_results: usize,
+    max_file_size: u64,
+    mut on_match: impl FnMut(&str, RawSearchMatch, &str) -> bool,
+) -> Result<(usize, bool), String> {
     let mut command = Command::new("rg");
     command
         .current_dir(root)
@@ -145,8 +275,11 @@ fn run_ripgrep(
         .arg("never")
         .arg("--no-messages")
         .arg("--hidden")
-        .arg("--glob")
-        .arg("!.git");
+        // The project's ignore rules replace ripgrep's own reading of .gitignore
+        .arg("--no-ignore-vcs");
+    for ignore_glob in IgnoreRules::load(root).ripgrep_globs() {
+        command.arg("--glob").arg(ignore_glob);
+    }
 
     if options.case_sensitive {
         command.arg("--case-sensitive");
@@ -158,6 +291,10 @@ fn run_ripgrep(
         command.arg("--fixed-strings");
     }
 
+    if options.whole_word {
+        command.arg("--word-regexp");
+    }
+
     command.arg("--max-filesize").arg(max_file_size.to_string());
 
     for include_glob in &options.include_globs {
@@ -194,7 +331,7 @@ fn run_ripgrep(
     let mut line = String::new();
     let mut total_matches = 0usize;
     let mut truncated = false;
-    let mut raw_matches_by_file: BTreeMap<String, Vec<RawSearchMatch>> = BTreeMap::new();
+    let mut cancelled = false;
 
     loop {
         line.clear();
@@ -225,6 +362,12 @@ fn run_ripgrep(
             .get("absolute_offset")
             .and_then(Value::as_u64)
             .ok_or_else(|| "Missing ripgrep absolute offset".to_string())? as usize;
+        let line_text = data
+            .get("lines")
+            .and_then(|value| value.get("text"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .trim_end_matches(['\r', '\n']);
 
         let submatches = data
             .get("submatches")
@@ -253,20 +396,22 @@ fn run_ripgrep(
                 .unwrap_or("")
                 .to_string();
 
-            raw_matches_by_file
-                .entry(absolute_path.clone())
-                .or_default()
-                .push(RawSearchMatch {
-                    line_number,
-                    column: start,
-                    start_byte: absolute_offset + start,
-                    end_byte: absolute_offset + end,
-                    match_text,
-                });
             total_matches += 1;
+            let raw_match = RawSearchMatch {
+                line_number,
+                column: start,
+                start_byte: absolute_offset + start,
+                end_byte: absolute_offset + end,
+                match_text,
+            };
+            if !on_match(&absolute_path, raw_match, line_text) {
+                cancelled = true;
+                let _ = child.kill();
+                break;
+            }
         }
 
-        if truncated {
+        if truncated || cancelled {
             break;
         }
     }
@@ -275,7 +420,7 @@ fn run_ripgrep(
     let mut stderr_output = String::new();
     stderr.read_to_string(&mut stderr_output).map_err(|e| e.to_string())?;
 
-    if !truncated && !status.success() && status.code() != Some(1) {
+    if !truncated && !cancelled && !status.success() && status.code() != Some(1) {
         let trimmed = stderr_output.trim();
         if trimmed.is_empty() {
             return Err(format!("ripgrep failed with status {}", status));
@@ -283,7 +428,7 @@ fn run_ripgrep(
         return Err(format!("ripgrep failed: {}", trimmed));
     }
 
-    Ok((raw_matches_by_file, total_matches, truncated))
+    Ok((total_matches, truncated))
 }
 
 fn build_response_from_raw_matches(
@@ -304,9 +449,8 @@ fn build_response_from_raw_matches(
                 .then(left.end_byte.cmp(&right.end_byte))
         });
 
-        let content = match fs::read_to_string(&absolute_path) {
-            Ok(content) => content,
-            Err(_) => continue,
+        let Some(DecodedText { content, .. }) = read_text(&absolute_path) else {
+            continue;
         };
         let lines: Vec<&str> = content.lines().collect();
 
@@ -383,20 +527,17 @@ fn collect_after_lines(lines: &[&str], line_index: usize, context_lines: usize)
 }
 
 fn build_search_regex(options: &SearchOptions) -> Result<Option<regex::Regex>, String> {
-    let pattern = if options.is_regex {
-        if options.case_sensitive {
-            options.query.clone()
-        } else {
-            format!("(?i){}", options.query)
-        }
+    let mut pattern = if options.is_regex {
+        options.query.clone()
     } else {
-        let escaped = regex::escape(&options.query);
-        if options.case_sensitive {
-            escaped
-        } else {
-            format!("(?i){}", escaped)
-        }
+        regex::escape(&options.query)
     };
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    if !options.case_sensitive {
+        pattern = format!("(?i){}", pattern);
+    }
 
     regex::Regex::new(&pattern)
         .map(Some)
@@ -439,9 +580,8 @@ fn search_with_indexed_scan(
     let mut truncated = false;
 
     for (file_idx, file_path) in file_paths.iter().enumerate() {
-        let content = match fs::read_to_string(file_path) {
-            Ok(content) => content,
-            Err(_) => continue,
+        let Some(DecodedText { content, .. }) = read_text(file_path) else {
+            continue;
         };
         let lines: Vec<&str> = content.lines().collect();
         let mut matches = Vec::new();
@@ -499,6 +639,441 @@ fn search_with_indexed_scan(
     })
 }
 
+/// Search a project and stream matches per file through `on_event`, so the Search panel can
+/// show results while ripgrep is still running
+#[tauri::command]
+pub async fn search_project(
+    root: String,
+    query: String,
+    options: Option<ProjectSearchOptions>,
+    on_event: Channel<ProjectSearchChunk>,
+) -> Result<ProjectSearchSummary, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    let options = options.unwrap_or_default();
+    let options = SearchOptions {
+        query,
+        is_regex: options.is_regex,
+        case_sensitive: options.case_sensitive,
+        whole_word: options.whole_word,
+        include_globs: options.include_globs,
+        exclude_globs: options.exclude_globs,
+        context_lines: 0,
+        max_results: options.max_results,
+        max_file_size_bytes: options.max_file_size_bytes,
+    };
+    if options.query.is_empty() {
+        return Ok(ProjectSearchSummary {
+            total_matches: 0,
+            files_with_matches: 0,
+            truncated: false,
+        });
+    }
+    build_search_regex(&options).map_err(VoidDeskError::invalid_input)?;
+
+    let summary =
+        tokio::task::spawn_blocking(move || search_project_blocking(&root, &options, &on_event))
+            .await?;
+    Ok(summary?)
+}
+
+/// Groups consecutive matches of a file and sends each file once it is complete
+struct ProjectSearchStream<'a> {
+    on_event: &'a Channel<ProjectSearchChunk>,
+    current: Option<ProjectSearchChunk>,
+    files_with_matches: usize,
+    disconnected: bool,
+}
+
+impl<'a> ProjectSearchStream<'a> {
+    fn new(on_event: &'a Channel<ProjectSearchChunk>) -> Self {
+        Self {
+            on_event,
+            current: None,
+            files_with_matches: 0,
+            disconnected: false,
+        }
+    }
+
+    /// Returns false once the frontend has stopped listening
+    fn push(&mut self, path: &str, project_match: ProjectSearchMatch) -> bool {
+        if self.current.as_ref().is_some_and(|chunk| chunk.path != path) {
+            self.flush();
+        }
+        self.current
+            .get_or_insert_with(|| ProjectSearchChunk {
+                path: path.to_string(),
+                matches: Vec::new(),
+            })
+            .matches
+            .push(project_match);
+        !self.disconnected
+    }
+
+    fn flush(&mut self) {
+        if let Some(chunk) = self.current.take() {
+            self.files_with_matches += 1;
+            if self.on_event.send(chunk).is_err() {
+                self.disconnected = true;
+            }
+        }
+    }
+}
+
+fn search_project_blocking(
+    root_path: &str,
+    options: &SearchOptions,
+    on_event: &Channel<ProjectSearchChunk>,
+) -> Result<ProjectSearchSummary, String> {
+    let root = Path::new(root_path);
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+    let max_file_size = options.max_file_size_bytes.unwrap_or(DEFAULT_MAX_FILE_SIZE);
+    let mut stream = ProjectSearchStream::new(on_event);
+
+    let ripgrep = stream_ripgrep(
+        root,
+        options,
+        max_results,
+        max_file_size,
+        |absolute_path, raw_match, line_text| {
+            let project_match = project_search_match(
+                raw_match.line_number,
+                raw_match.column,
+                raw_match.match_text,
+                line_text,
+            );
+            stream.push(absolute_path, project_match)
+        },
+    );
+    let (total_matches, truncated) = match ripgrep {
+        Ok(result) => result,
+        Err(error) if error.contains("ripgrep unavailable") => {
+            scan_project_with_index(root_path, options, max_results, max_file_size, &mut stream)?
+        }
+        Err(error) => return Err(error),
+    };
+    stream.flush();
+
+    Ok(ProjectSearchSummary {
+        total_matches,
+        files_with_matches: stream.files_with_matches,
+        truncated,
+    })
+}
+
+fn scan_project_with_index(
+    root_path: &str,
+    options: &SearchOptions,
+    max_results: usize,
+    max_file_size: u64,
+    stream: &mut ProjectSearchStream,
+) -> Result<(usize, bool), String> {
+    let regex = build_search_regex(options)?
+        .ok_or_else(|| "Failed to build search regex".to_string())?;
+    let include_patterns: Vec<glob::Pattern> = options
+        .include_globs
+        .iter()
+        .filter_map(|glob| glob::Pattern::new(glob).ok())
+        .collect();
+    let exclude_patterns: Vec<glob::Pattern> = options
+        .exclude_globs
+        .iter()
+        .filter_map(|glob| glob::Pattern::new(glob).ok())
+        .collect();
+    let file_paths = workspace_index::indexed_file_paths(
+        root_path,
+        &include_patterns,
+        &exclude_patterns,
+        max_file_size,
+    )?;
+
+    let mut total_matches = 0usize;
+    for file_path in &file_paths {
+        let Some(DecodedText { content, .. }) = read_text(file_path) else {
+            continue;
+        };
+        let path = normalize_path(file_path);
+
+        for (line_index, line_text) in content.lines().enumerate() {
+            for found in regex.find_iter(line_text) {
+                if total_matches >= max_results {
+                    return Ok((total_matches, true));
+                }
+                total_matches += 1;
+                let project_match = project_search_match(
+                    line_index + 1,
+                    found.start(),
+                    found.as_str().to_string(),
+                    line_text,
+                );
+                if !stream.push(&path, project_match) {
+                    return Ok((total_matches, false));
+                }
+            }
+        }
+    }
+
+    Ok((total_matches, false))
+}
+
+fn project_search_match(
+    line: usize,
+    column: usize,
+    match_text: String,
+    line_text: &str,
+) -> ProjectSearchMatch {
+    let (preview, preview_start, preview_end) =
+        build_preview(line_text, column, column + match_text.len());
+    ProjectSearchMatch {
+        line,
+        column,
+        match_text,
+        preview,
+        preview_start,
+        preview_end,
+    }
+}
+
+/// Trim a line to the match plus some context, returning char offsets of the match
+fn build_preview(line_text: &str, start_byte: usize, end_byte: usize) -> (String, usize, usize) {
+    let start_byte = floor_char_boundary(line_text, start_byte);
+    let end_byte = floor_char_boundary(line_text, end_byte.max(start_byte));
+    let before: Vec<char> = line_text[..start_byte].chars().collect();
+    let matched = line_text[start_byte..end_byte].chars().count();
+    let after = line_text[end_byte..].chars();
+
+    let skipped = before.len().saturating_sub(PREVIEW_CONTEXT_CHARS);
+    let mut preview = String::new();
+    if skipped > 0 {
+        preview.push('…');
+    }
+    preview.extend(before[skipped..].iter().copied());
+    let preview_start = preview.chars().count();
+    preview.push_str(&line_text[start_byte..end_byte]);
+    let preview_end = preview_start + matched;
+
+    let mut after = after.peekable();
+    preview.extend(after.by_ref().take(PREVIEW_CONTEXT_CHARS));
+    if after.peek().is_some() {
+        preview.push('…');
+    }
+
+    (preview, preview_start, preview_end)
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Replace matches across a project. `replacement` may use `$1` or `${name}` capture
+/// groups in regex mode. With `dry_run` nothing is written and the response previews
+/// every replacement and the resulting diff.
+#[tauri::command]
+pub async fn replace_in_project(
+    root: String,
+    query: String,
+    replacement: String,
+    options: Option<ProjectReplaceOptions>,
+) -> Result<ProjectReplaceResponse, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    let options = options.unwrap_or_default();
+    if query.is_empty() {
+        return Err(VoidDeskError::invalid_input("Search query is empty"));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        replace_in_project_blocking(&root, query, &replacement, options)
+    })
+    .await?
+}
+
+fn replace_in_project_blocking(
+    root_path: &str,
+    query: String,
+    replacement: &str,
+    options: ProjectReplaceOptions,
+) -> Result<ProjectReplaceResponse, VoidDeskError> {
+    let search_options = SearchOptions {
+        query,
+        is_regex: options.is_regex,
+        case_sensitive: options.case_sensitive,
+        whole_word: options.whole_word,
+        include_globs: options.include_globs,
+        exclude_globs: options.exclude_globs,
+        context_lines: 0,
+        max_results: None,
+        max_file_size_bytes: options.max_file_size_bytes,
+    };
+    let regex = build_search_regex(&search_options)
+        .map_err(VoidDeskError::invalid_input)?
+        .ok_or_else(|| VoidDeskError::internal("Failed to build search regex"))?;
+    let selected_ids: Option<HashSet<String>> =
+        options.selected_ids.map(|ids| ids.into_iter().collect());
+
+    let mut files = Vec::new();
+    let mut files_changed = 0usize;
+    let mut replacements_applied = 0usize;
+    let mut errors = Vec::new();
+
+    // The watchers report the whole replace as one batch once it is done
+    let _pause = (!options.dry_run).then(file_watcher::pause_all_watchers);
+    for path in candidate_files(root_path, &search_options)? {
+        let Some(DecodedText {
+            content, encoding, ..
+        }) = read_text(&path)
+        else {
+            continue;
+        };
+
+        let mut planned = Vec::new();
+        let mut previews = Vec::new();
+        for captures in regex.captures_iter(&content) {
+            let found = captures.get(0).expect("capture group 0 always matches");
+            if found.is_empty() {
+                continue;
+            }
+            let id = format!("{}:{}", path, found.start());
+            if selected_ids.as_ref().is_some_and(|ids| !ids.contains(&id)) {
+                continue;
+            }
+
+            let mut replacement_text = String::new();
+            if search_options.is_regex {
+                captures.expand(replacement, &mut replacement_text);
+            } else {
+                replacement_text.push_str(replacement);
+            }
+
+            let line_start = content[..found.start()]
+                .rfind('\n')
+                .map_or(0, |position| position + 1);
+            previews.push(ReplacementPreview {
+                id,
+                line: content[..found.start()].matches('\n').count() + 1,
+                column: found.start() - line_start,
+                match_text: found.as_str().to_string(),
+                replacement_text: replacement_text.clone(),
+            });
+            planned.push(PlannedReplacement {
+                start_byte: found.start(),
+                end_byte: found.end(),
+                replacement_text,
+            });
+        }
+
+        if planned.is_empty() {
+            continue;
+        }
+
+        let diff = build_replace_diff(&path, &content, &planned);
+        if !options.dry_run {
+            match write_encoded(&path, &apply_replacements(&content, &planned), encoding) {
+                Ok(_) => {
+                    files_changed += 1;
+                    replacements_applied += planned.len();
+                }
+                Err(error) => {
+                    errors.push(ReplaceError {
+                        path: path.clone(),
+                        message: format!("Failed to write file: {}", error.message),
+                    });
+                    continue;
+                }
+            }
+        }
+
+        files.push(FileReplacePreview {
+            path,
+            replacements: previews,
+            diff,
+        });
+    }
+
+    Ok(ProjectReplaceResponse {
+        files,
+        files_changed,
+        replacements_applied,
+        dry_run: options.dry_run,
+        errors,
+    })
+}
+
+/// A file's text as search and replace both see it: decoded from the encoding it is saved
+/// in, without its BOM, so the byte offsets a search returns are the ones replace uses,
+/// as they are for ripgrep. None for binary files and for text that does not decode
+/// cleanly, since writing the replacement characters back would corrupt it.
+fn read_text(path: impl AsRef<Path>) -> Option<DecodedText> {
+    let bytes = fs::read(path).ok()?;
+    let decoded = file_encoding::decode(&bytes);
+    (!decoded.had_errors && !decoded.content.contains('\0')).then_some(decoded)
+}
+
+/// Files worth opening for a replace: ripgrep's matches, or every indexed file without it
+fn candidate_files(root_path: &str, options: &SearchOptions) -> Result<Vec<String>, String> {
+    let max_file_size = options.max_file_size_bytes.unwrap_or(DEFAULT_MAX_FILE_SIZE);
+    let mut paths = BTreeSet::new();
+    let ripgrep = stream_ripgrep(
+        Path::new(root_path),
+        options,
+        usize::MAX,
+        max_file_size,
+        |absolute_path, _, _| {
+            if !paths.contains(absolute_path) {
+                paths.insert(absolute_path.to_string());
+            }
+            true
+        },
+    );
+
+    match ripgrep {
+        Ok(_) => Ok(paths.into_iter().collect()),
+        Err(error) if error.contains("ripgrep unavailable") => {
+            let include_patterns: Vec<glob::Pattern> = options
+                .include_globs
+                .iter()
+                .filter_map(|glob| glob::Pattern::new(glob).ok())
+                .collect();
+            let exclude_patterns: Vec<glob::Pattern> = options
+                .exclude_globs
+                .iter()
+                .filter_map(|glob| glob::Pattern::new(glob).ok())
+                .collect();
+            let file_paths = workspace_index::indexed_file_paths(
+                root_path,
+                &include_patterns,
+                &exclude_patterns,
+                max_file_size,
+            )?;
+            Ok(file_paths.iter().map(|path| normalize_path(path)).collect())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Apply replacements sorted by position and not overlapping, as regex matches are
+fn apply_replacements(content: &str, planned: &[PlannedReplacement]) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for replacement in planned {
+        output.push_str(&content[cursor..replacement.start_byte]);
+        output.push_str(&replacement.replacement_text);
+        cursor = replacement.end_byte;
+    }
+    output.push_str(&content[cursor..]);
+    output
+}
+
+/// Unified diff of the file before and after the replacements
+fn build_replace_diff(path: &str, content: &str, planned: &[PlannedReplacement]) -> String {
+    let updated = apply_replacements(content, planned);
+    diff::diff_texts(content, &updated)
+        .map(|diff| diff.unified(&format!("a/{}", path), &format!("b/{}", path)))
+        .unwrap_or_default()
+}
+
 fn normalize_path(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
@@ -506,7 +1081,7 @@ fn normalize_path(path: &Path) -> String {
 #[tauri::command]
 pub async fn replace_in_files(
     selections: Vec<ReplaceSelection>,
-) -> Result<ReplaceResponse, String> {
+) -> Result<ReplaceResponse, VoidDeskError> {
     let mut grouped: HashMap<String, Vec<&ReplaceSelection>> = HashMap::new();
     for selection in &selections {
         grouped.entry(selection.path.clone()).or_default().push(selection);
@@ -516,16 +1091,25 @@ pub async fn replace_in_files(
     let mut replacements_applied = 0usize;
     let mut errors = Vec::new();
 
+    let _pause = file_watcher::pause_all_watchers();
     for (path, mut file_selections) in grouped {
-        let content = match fs::read_to_string(&path) {
-            Ok(content) => content,
-            Err(error) => {
-                errors.push(ReplaceError {
-                    path: path.clone(),
-                    message: format!("Failed to read file: {}", error),
-                });
-                continue;
-            }
+        if let Err(error) = workspace_roots::ensure_in_workspace(&path) {
+            errors.push(ReplaceError {
+                path,
+                message: error.message,
+            });
+            continue;
+        }
+
+        let Some(DecodedText {
+            content, encoding, ..
+        }) = read_text(&path)
+        else {
+            errors.push(ReplaceError {
+                path: path.clone(),
+                message: "Failed to read file as text".to_string(),
+            });
+            continue;
         };
 
         let mut bytes = content.into_bytes();
@@ -570,12 +1154,13 @@ pub async fn replace_in_files(
         }
 
         if file_had_replacement {
-            match fs::write(&path, &bytes) {
+            let content = String::from_utf8_lossy(&bytes);
+            match write_encoded(&path, &content, encoding) {
                 Ok(_) => files_changed += 1,
                 Err(error) => {
                     errors.push(ReplaceError {
                         path: path.clone(),
-                        message: format!("Failed to write file: {}", error),
+                        message: format!("Failed to write file: {}", error.message),
                     });
                 }
             }
@@ -588,3 +1173,87 @@ pub async fn replace_in_files(
         errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_with_capture_groups_and_builds_hunks() {
+        let content = "let a = foo(1);\nkeep\nlet b = foo(2); foo(3)\n";
+        let regex = regex::Regex::new(r"foo\((\d)\)").unwrap();
+        let planned: Vec<PlannedReplacement> = regex
+            .captures_iter(content)
+            .map(|captures| {
+                let found = captures.get(0).unwrap();
+                let mut replacement_text = String::new();
+                captures.expand("bar($1, \"x\")", &mut replacement_text);
+                PlannedReplacement {
+                    start_byte: found.start(),
+                    end_byte: found.end(),
+                    replacement_text,
+                }
+            })
+            .collect();
+
+        assert_eq!(
+            apply_replacements(content, &planned),
+            "let a = bar(1, \"x\");\nkeep\nlet b = bar(2, \"x\"); bar(3, \"x\")\n"
+        );
+        assert_eq!(
+            build_replace_diff("src/a.rs", content, &planned),
+            concat!(
+                "--- a/src/a.rs\n+++ b/src/a.rs\n",
+                "@@ -1,3 +1,3 @@\n-let a = foo(1);\n+let a = bar(1, \"x\");\n keep\n",
+                "-let b = foo(2); foo(3)\n+let b = bar(2, \"x\"); bar(3, \"x\")\n",
+            )
+        );
+    }
+
+    #[test]
+    fn replaces_search_matches_in_a_file_with_a_bom() {
+        let dir = std::env::temp_dir().join(format!("voidesk-replace-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        workspace_roots::register(&dir.to_string_lossy()).unwrap();
+        let path = dir.join("bom.rs");
+        fs::write(&path, b"\xEF\xBB\xBFlet foo = 1;\n").unwrap();
+
+        // The offsets a search reports, which ripgrep also counts without the BOM
+        let content = read_text(&path).unwrap().content;
+        let start_byte = content.find("foo").unwrap();
+        assert_eq!(start_byte, 4);
+
+        let response = futures::executor::block_on(replace_in_files(vec![ReplaceSelection {
+            path: path.to_string_lossy().to_string(),
+            start_byte,
+            end_byte: start_byte + 3,
+            match_text: "foo".to_string(),
+            replacement_text: "bar".to_string(),
+        }]))
+        .unwrap();
+        assert!(response.errors.is_empty());
+        assert_eq!(response.replacements_applied, 1);
+        assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFlet bar = 1;\n");
+
+        fs::write(&path, b"\0\x01binary foo").unwrap();
+        assert!(read_text(&path).is_none());
+
+        workspace_roots::unregister(&dir.to_string_lossy()).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn previews_keep_context_around_the_match() {
+        let (preview, start, end) = build_preview("let needle = 1;", 4, 10);
+        assert_eq!(preview, "let needle = 1;");
+        assert_eq!((start, end), (4, 10));
+
+        let line = format!("{}needle{}", "é".repeat(100), "x".repeat(100));
+        let (preview, start, end) = build_preview(&line, 200, 206);
+        let chars: Vec<char> = preview.chars().collect();
+        assert_eq!(chars[start..end].iter().collect::<String>(), "needle");
+        assert!(preview.starts_with('…') && preview.ends_with('…'));
+        assert_eq!(chars.len(), 1 + PREVIEW_CONTEXT_CHARS + 6 + PREVIEW_CONTEXT_CHARS + 1);
+    }
+}
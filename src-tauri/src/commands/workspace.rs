@@ -0,0 +1,314 @@
+// Workspace
+// The project folders open in each window and which of them is active there. Opening a
+// folder registers it as a workspace root, records it in the recent projects and points
+// that window's language servers at it; the AI tools fall back to the active root when a
+// request names none. A folder stays a workspace root while any window has it open.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use super::ai_service::AIService;
+use super::editor_context;
+use super::file_watcher;
+use super::lsp_commands::{self, LspState};
+use super::recent_items::{self, RecentItem};
+use super::workspace_roots;
+use crate::error::VoidDeskError;
+use crate::syntax::symbol_index;
+
+/// Emitted to a window with its WorkspaceState whenever a folder is opened or closed there
+pub const WORKSPACE_CHANGED_EVENT: &str = "workspace-changed";
+
+/// The window tauri.conf.json creates at startup
+pub(crate) const MAIN_WINDOW: &str = "main";
+
+#[derive(Debug, Default)]
+struct WindowWorkspace {
+    /// In the order they were opened
+    roots: Vec<PathBuf>,
+    active: Option<PathBuf>,
+}
+
+static WINDOWS: OnceLock<RwLock<HashMap<String, WindowWorkspace>>> = OnceLock::new();
+static FOCUSED_WINDOW: OnceLock<RwLock<String>> = OnceLock::new();
+
+fn windows() -> &'static RwLock<HashMap<String, WindowWorkspace>> {
+    WINDOWS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn focused() -> &'static RwLock<String> {
+    FOCUSED_WINDOW.get_or_init(|| RwLock::new(MAIN_WINDOW.to_string()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceState {
+    /// Roots open in the window, in the order they were opened
+    pub roots: Vec<String>,
+    pub active: Option<String>,
+    /// Recently opened projects, pinned ones first; pin them with pin_recent_item
+    pub recent: Vec<RecentItem>,
+}
+
+/// The folder active in `window`, if it has one open
+pub fn active_root_of(window: &str) -> Option<PathBuf> {
+    windows().read().ok()?.get(window)?.active.clone()
+}
+
+/// The folder the user is working in: the active root of the window they used last
+pub fn active_root() -> Option<PathBuf> {
+    let window = focused().read().ok()?.clone();
+    active_root_of(&window)
+}
+
+/// Remember `window` as the one the user is working in
+pub fn window_focused(window: &str) {
+    if let Ok(mut focused) = focused().write() {
+        *focused = window.to_string();
+    }
+}
+
+fn roots_of(window: &str) -> Result<Vec<PathBuf>, String> {
+    let windows = windows().read().map_err(|e| e.to_string())?;
+    Ok(windows
+        .get(window)
+        .map(|workspace| workspace.roots.clone())
+        .unwrap_or_default())
+}
+
+fn open_anywhere(root: &Path) -> bool {
+    windows().read().is_ok_and(|windows| {
+        windows
+            .values()
+            .any(|workspace| workspace.roots.iter().any(|open| open == root))
+    })
+}
+
+/// Register `path` and make it the active root of `window`; returns its canonical path
+fn open_root(window: &str, path: &str) -> Result<PathBuf, VoidDeskError> {
+    let root = workspace_roots::register(path)?;
+    let mut windows = windows().write().map_err(|e| e.to_string())?;
+    let workspace = windows.entry(window.to_string()).or_default();
+    if !workspace.roots.contains(&root) {
+        workspace.roots.push(root.clone());
+    }
+    workspace.active = Some(root.clone());
+    Ok(root)
+}
+
+/// Close `path` in `window`, unregistering it unless another window has it open; when it
+/// was active, the most recently opened of the window's remaining roots takes over.
+/// Returns the new active root.
+fn close_root(window: &str, path: &str) -> Result<Option<PathBuf>, VoidDeskError> {
+    let root = Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path));
+    let active = {
+        let mut windows = windows().write().map_err(|e| e.to_string())?;
+        let workspace = windows.entry(window.to_string()).or_default();
+        workspace.roots.retain(|open| *open != root);
+        if workspace
+            .active
+            .as_deref()
+            .is_none_or(|active| active == root)
+        {
+            workspace.active = workspace.roots.last().cloned();
+        }
+        workspace.active.clone()
+    };
+    if !open_anywhere(&root) {
+        workspace_roots::unregister(path)?;
+    }
+    Ok(active)
+}
+
+async fn state(window: &str) -> Result<WorkspaceState, VoidDeskError> {
+    // Recents are a convenience; the open roots are still worth reporting without them
+    let recent = recent_items::get_recent(None)
+        .await
+        .map(|items| items.projects)
+        .unwrap_or_default();
+    Ok(WorkspaceState {
+        roots: roots_of(window)?
+            .iter()
+            .map(|root| root.to_string_lossy().to_string())
+            .collect(),
+        active: active_root_of(window).map(|root| root.to_string_lossy().to_string()),
+        recent,
+    })
+}
+
+async fn changed(app: &AppHandle, window: &str) -> Result<WorkspaceState, VoidDeskError> {
+    let state = state(window).await?;
+    let _ = app.emit_to(window, WORKSPACE_CHANGED_EVENT, state.clone());
+    Ok(state)
+}
+
+async fn open_in_window(
+    lsp_state: &LspState,
+    window: &str,
+    path: &str,
+) -> Result<(), VoidDeskError> {
+    let root = open_root(window, path)?;
+    let root = root.to_string_lossy().to_string();
+    if let Err(error) = recent_items::record_project_open(root.clone()).await {
+        tracing::warn!("Could not record {} as a recent project: {}", root, error);
+    }
+    symbol_index::index_root(&root);
+    Ok(lsp_commands::set_root(&lsp_state.manager(window), root).await?)
+}
+
+/// Open `path` as the active workspace of the calling window; opening an open root again
+/// makes it active
+#[tauri::command]
+pub async fn open_workspace(
+    app: AppHandle,
+    window: WebviewWindow,
+    lsp_state: State<'_, LspState>,
+    path: String,
+) -> Result<WorkspaceState, VoidDeskError> {
+    open_in_window(&lsp_state, window.label(), &path).await?;
+    window_focused(window.label());
+    changed(&app, window.label()).await
+}
+
+/// Close the workspace at `path` in the calling window and stop watching it there
+#[tauri::command]
+pub async fn close_workspace(
+    app: AppHandle,
+    window: WebviewWindow,
+    lsp_state: State<'_, LspState>,
+    path: String,
+) -> Result<WorkspaceState, VoidDeskError> {
+    let window = window.label();
+    let previous = active_root_of(window);
+    let next = close_root(window, &path)?;
+    let closed = Path::new(&path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&path));
+    file_watcher::stop_watchers_under(window, &closed)?;
+    if !open_anywhere(&closed) {
+        symbol_index::forget_root(&path);
+    }
+    if let Some(next) = next.filter(|next| previous.as_ref() != Some(next)) {
+        let manager = lsp_state.manager(window);
+        lsp_commands::set_root(&manager, next.to_string_lossy().to_string()).await?;
+    }
+    changed(&app, window).await
+}
+
+/// The calling window's open roots, its active one and the recent projects
+#[tauri::command]
+pub async fn list_workspaces(window: WebviewWindow) -> Result<WorkspaceState, VoidDeskError> {
+    state(window.label()).await
+}
+
+/// Open `path` in a new window with its own watchers, language servers and AI session;
+/// returns the window's label
+#[tauri::command]
+pub async fn open_project_window(
+    app: AppHandle,
+    lsp_state: State<'_, LspState>,
+    path: String,
+) -> Result<String, VoidDeskError> {
+    let label = format!("project-{}", uuid::Uuid::new_v4().simple());
+    // The workspace is in place before the frontend asks for it with list_workspaces
+    open_in_window(&lsp_state, &label, &path).await?;
+
+    let name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let built = WebviewWindowBuilder::new(&app, &label, WebviewUrl::default())
+        .title(format!("{} - VoiDesk", name))
+        .inner_size(1400.0, 900.0)
+        .min_inner_size(800.0, 600.0)
+        .disable_drag_drop_handler()
+        .build();
+    if let Err(error) = built {
+        window_closed(app, label).await;
+        return Err(VoidDeskError::internal(format!(
+            "Could not open a window for {}: {}",
+            path, error
+        )));
+    }
+    window_focused(&label);
+    Ok(label)
+}
+
+/// Release what a closed window held: its roots, watchers, language servers and AI session
+pub async fn window_closed(app: AppHandle, window: String) {
+    let roots = roots_of(&window).unwrap_or_default();
+    for root in &roots {
+        let root = root.to_string_lossy().to_string();
+        if let Err(error) = close_root(&window, &root) {
+            tracing::warn!("Could not close {} for window {}: {}", root, window, error);
+        }
+        if !open_anywhere(Path::new(&root)) {
+            symbol_index::forget_root(&root);
+        }
+    }
+    if let Ok(mut windows) = windows().write() {
+        windows.remove(&window);
+    }
+    editor_context::forget_window(&window);
+    if let Err(error) = file_watcher::stop_window_watchers(&window) {
+        tracing::warn!(
+            "Could not stop the watchers of window {}: {}",
+            window,
+            error
+        );
+    }
+    if let Some(lsp_state) = app.try_state::<LspState>() {
+        lsp_state.remove(&window).await;
+    }
+    if let Some(service) = app.try_state::<AIService>() {
+        service.reset_session(&window).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn closing_the_active_root_activates_the_latest_other() {
+        let base = std::env::temp_dir().join(format!("voidesk-workspace-{}", uuid::Uuid::new_v4()));
+        let (first, second) = (base.join("first"), base.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let first_path = first.to_string_lossy().to_string();
+        let second_path = second.to_string_lossy().to_string();
+        let window = format!("test-{}", uuid::Uuid::new_v4());
+        let other = format!("test-{}", uuid::Uuid::new_v4());
+
+        let first = open_root(&window, &first_path).unwrap();
+        let second = open_root(&window, &second_path).unwrap();
+        assert_eq!(active_root_of(&window), Some(second.clone()));
+        assert!(workspace_roots::ensure_in_workspace(&first_path).is_ok());
+
+        // Reopening makes an open root active without registering it twice
+        open_root(&window, &first_path).unwrap();
+        assert_eq!(active_root_of(&window), Some(first.clone()));
+        let registered = workspace_roots::list().unwrap();
+        assert_eq!(registered.iter().filter(|root| **root == first).count(), 1);
+
+        // Another window's roots are its own, and keep a shared root registered
+        open_root(&other, &first_path).unwrap();
+        assert_eq!(roots_of(&other).unwrap(), vec![first.clone()]);
+        close_root(&window, &first_path).unwrap();
+        assert!(workspace_roots::ensure_in_workspace(&first_path).is_ok());
+        assert_eq!(active_root_of(&other), Some(first.clone()));
+        close_root(&other, &first_path).unwrap();
+
+        assert!(workspace_roots::ensure_in_workspace(&first_path).is_err());
+        assert!(active_root_of(&window).is_some_and(|root| root != first));
+        close_root(&window, &second_path).unwrap();
+        assert_eq!(active_root_of(&window), None);
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -1,4 +1,6 @@
-use super::project_commands::FileNode;
+use super::ignore_engine::{is_rules_file, IgnoreRules};
+use super::project_commands::{is_submodule_root, FileNode};
+use crate::error::VoidDeskError;
 use glob::Pattern;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::Serialize;
@@ -10,16 +12,6 @@ use std::sync::{Mutex, OnceLock};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-const DEFAULT_IGNORE_RULES: &[&str] = &[
-    ".git",
-    "node_modules",
-    "target",
-    "dist",
-    "build",
-    ".next",
-    "__pycache__",
-];
-
 static WORKSPACE_INDEX: OnceLock<Mutex<Option<WorkspaceIndex>>> = OnceLock::new();
 static WORKSPACE_INDEX_DB_PATH: OnceLock<PathBuf> = OnceLock::new();
 static WORKSPACE_INDEX_PERSISTENCE_ENABLED: AtomicBool = AtomicBool::new(true);
@@ -40,7 +32,7 @@ struct IndexedEntry {
 #[derive(Debug, Clone)]
 struct WorkspaceIndex {
     root_path: String,
-    ignore_rules: Vec<String>,
+    ignore_rules: IgnoreRules,
     entries: BTreeMap<String, IndexedEntry>,
     last_indexed_at: u64,
 }
@@ -103,64 +95,26 @@ fn relative_to_root(path: &Path, root: &Path) -> Option<String> {
     path.strip_prefix(root).ok().map(normalize_rel_path)
 }
 
-fn load_ignore_rules(root: &Path) -> Vec<String> {
-    let mut rules = DEFAULT_IGNORE_RULES
-        .iter()
-        .map(|rule| (*rule).to_string())
-        .collect::<Vec<_>>();
+/// SHA-256 of file contents as hex: the one hash the index, file versions and file
+/// stats agree on
+#[derive(Default)]
+pub struct ContentHasher(Sha256);
 
-    let gitignore_path = root.join(".gitignore");
-    if let Ok(content) = fs::read_to_string(gitignore_path) {
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
-                continue;
-            }
-            rules.push(trimmed.trim_start_matches('/').to_string());
-        }
+impl ContentHasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
     }
 
-    rules
-}
-
-fn matches_ignore_rule(rule: &str, rel_path: &str, file_name: &str) -> bool {
-    let normalized_rule = rule.trim_matches('/');
-    if normalized_rule.is_empty() {
-        return false;
+    pub fn finish(self) -> String {
+        format!("{:x}", self.0.finalize())
     }
-
-    if normalized_rule.contains('*') || normalized_rule.contains('?') || normalized_rule.contains('[') {
-        if let Ok(pattern) = Pattern::new(normalized_rule) {
-            return pattern.matches(rel_path) || pattern.matches(file_name);
-        }
-    }
-
-    if rule.ends_with('/') {
-        return rel_path == normalized_rule || rel_path.starts_with(&format!("{}/", normalized_rule));
-    }
-
-    if normalized_rule.contains('/') {
-        return rel_path == normalized_rule || rel_path.starts_with(&format!("{}/", normalized_rule));
-    }
-
-    file_name == normalized_rule
 }
 
-fn should_ignore(rel_path: &str, file_name: &str, ignore_rules: &[String]) -> bool {
-    if file_name.starts_with('.') {
-        return true;
-    }
-
-    ignore_rules
-        .iter()
-        .any(|rule| matches_ignore_rule(rule, rel_path, file_name))
-}
-
-fn hash_file(path: &Path) -> Result<String, String> {
+pub fn hash_file(path: &Path) -> Result<String, String> {
     let bytes = fs::read(path).map_err(|e| e.to_string())?;
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    Ok(format!("{:x}", hasher.finalize()))
+    let mut hasher = ContentHasher::default();
+    hasher.update(&bytes);
+    Ok(hasher.finish())
 }
 
 fn entry_from_path(path: &Path, root: &Path) -> Result<Option<(String, IndexedEntry)>, String> {
@@ -250,7 +204,15 @@ fn remove_path(index: &mut WorkspaceIndex, path: &Path, root: &Path) {
     });
 }
 
-fn index_path_recursive(index: &mut WorkspaceIndex, path: &Path, root: &Path) -> Result<(), String> {
+/// Index `path` and everything below it. `ancestors` holds the canonical paths of the
+/// folders being walked, so a symlink pointing back at one of them is recorded but not
+/// descended into.
+fn index_path_recursive(
+    index: &mut WorkspaceIndex,
+    path: &Path,
+    root: &Path,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<(), String> {
     let Some(rel_path) = relative_to_root(path, root) else {
         return Ok(());
     };
@@ -263,7 +225,7 @@ fn index_path_recursive(index: &mut WorkspaceIndex, path: &Path, root: &Path) ->
         None => return Ok(()),
     };
 
-    if should_ignore(&rel_path, file_name, &index.ignore_rules) {
+    if index.ignore_rules.is_hidden(&rel_path, file_name) {
         remove_path(index, path, root);
         return Ok(());
     }
@@ -275,11 +237,18 @@ fn index_path_recursive(index: &mut WorkspaceIndex, path: &Path, root: &Path) ->
         index.entries.insert(entry_rel_path, entry);
 
         if is_dir {
+            let canonical = fs::canonicalize(path).map_err(|e| e.to_string())?;
+            if ancestors.contains(&canonical) {
+                return Ok(());
+            }
+
+            ancestors.push(canonical);
             let read_dir = fs::read_dir(path).map_err(|err| err.to_string())?;
             for child in read_dir {
                 let child = child.map_err(|e| e.to_string())?;
-                index_path_recursive(index, &child.path(), root)?;
+                index_path_recursive(index, &child.path(), root, ancestors)?;
             }
+            ancestors.pop();
         }
     }
 
@@ -294,15 +263,16 @@ fn build_index(root_path: &str) -> Result<WorkspaceIndex, String> {
 
     let mut index = WorkspaceIndex {
         root_path: normalize_path(root),
-        ignore_rules: load_ignore_rules(root),
+        ignore_rules: IgnoreRules::load(root),
         entries: BTreeMap::new(),
         last_indexed_at: current_timestamp_ms(),
     };
 
+    let mut ancestors = vec![fs::canonicalize(root).map_err(|e| e.to_string())?];
     let read_dir = fs::read_dir(root).map_err(|e| e.to_string())?;
     for child in read_dir {
         let child = child.map_err(|e| e.to_string())?;
-        index_path_recursive(&mut index, &child.path(), root)?;
+        index_path_recursive(&mut index, &child.path(), root, &mut ancestors)?;
     }
 
     index.last_indexed_at = current_timestamp_ms();
@@ -376,6 +346,18 @@ fn initialize_database(db_path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Indexes saved before overrides existed stored a plain list of rules
+fn parse_ignore_rules(json: &str) -> Result<IgnoreRules, String> {
+    serde_json::from_str::<IgnoreRules>(json)
+        .or_else(|_| {
+            serde_json::from_str::<Vec<String>>(json).map(|rules| IgnoreRules {
+                rules,
+                exceptions: Vec::new(),
+            })
+        })
+        .map_err(|e| e.to_string())
+}
+
 fn save_index_to_disk(index: &WorkspaceIndex) -> Result<(), String> {
     if !persistence_enabled() {
         return Ok(());
@@ -474,8 +456,11 @@ fn load_index_from_disk(root_path: &str) -> Result<Option<WorkspaceIndex>, Strin
         return Ok(None);
     };
 
-    let ignore_rules: Vec<String> =
-        serde_json::from_str(&ignore_rules_json).map_err(|e| e.to_string())?;
+    let ignore_rules = parse_ignore_rules(&ignore_rules_json)?;
+    // Rules edited since the index was saved make it stale
+    if ignore_rules != IgnoreRules::load(Path::new(&normalized_root)) {
+        return Ok(None);
+    }
 
     let mut entries = BTreeMap::new();
     let mut statement = connection
@@ -527,7 +512,7 @@ fn snapshot_stats(index: &WorkspaceIndex) -> WorkspaceIndexStats {
         root_path: index.root_path.clone(),
         file_count,
         directory_count,
-        ignored_rules: index.ignore_rules.clone(),
+        ignored_rules: index.ignore_rules.rules.clone(),
         last_indexed_at: index.last_indexed_at,
     }
 }
@@ -580,6 +565,17 @@ pub fn apply_file_changes(root_path: &str, changed_paths: &[String]) -> Result<(
         return Ok(());
     }
 
+    // New ignore rules can hide or reveal anything, so start over
+    if changed_paths
+        .iter()
+        .any(|changed_path| is_rules_file(&root, Path::new(changed_path)))
+    {
+        let rebuilt = build_index(&normalized_root)?;
+        save_index_to_disk(&rebuilt)?;
+        *guard = Some(rebuilt);
+        return Ok(());
+    }
+
     for changed_path in changed_paths {
         let absolute_path = PathBuf::from(changed_path);
         if !absolute_path.starts_with(&root) {
@@ -587,7 +583,15 @@ pub fn apply_file_changes(root_path: &str, changed_paths: &[String]) -> Result<(
         }
 
         if absolute_path.exists() {
-            index_path_recursive(index, &absolute_path, &root)?;
+            // Seed the cycle check with every folder between the root and the change
+            let mut ancestors = absolute_path
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| ancestor.starts_with(&root))
+                .filter_map(|ancestor| fs::canonicalize(ancestor).ok())
+                .collect::<Vec<_>>();
+            ancestors.reverse();
+            index_path_recursive(index, &absolute_path, &root, &mut ancestors)?;
         } else {
             remove_path(index, &absolute_path, &root);
         }
@@ -598,7 +602,13 @@ pub fn apply_file_changes(root_path: &str, changed_paths: &[String]) -> Result<(
     Ok(())
 }
 
-pub fn build_project_tree(root_path: &str, max_depth: usize) -> Result<Vec<FileNode>, String> {
+/// Build the explorer tree from the index. Symlinks are flagged on their nodes; with
+/// `follow_symlinks` off, symlinked folders are listed without their contents.
+pub fn build_project_tree(
+    root_path: &str,
+    max_depth: usize,
+    follow_symlinks: bool,
+) -> Result<Vec<FileNode>, String> {
     ensure_index(root_path)?;
 
     let state = get_index_state();
@@ -627,6 +637,7 @@ pub fn build_project_tree(root_path: &str, max_depth: usize) -> Result<Vec<FileN
         parent: Option<String>,
         current_depth: usize,
         max_depth: usize,
+        follow_symlinks: bool,
         children_by_parent: &HashMap<Option<String>, Vec<(String, IndexedEntry)>>,
     ) -> Vec<FileNode> {
         if current_depth >= max_depth {
@@ -640,11 +651,15 @@ pub fn build_project_tree(root_path: &str, max_depth: usize) -> Result<Vec<FileN
         children
             .iter()
             .map(|(rel_path, entry)| {
-                let child_nodes = if entry.is_dir {
+                let is_symlink = fs::symlink_metadata(&entry.path)
+                    .map(|metadata| metadata.file_type().is_symlink())
+                    .unwrap_or(false);
+                let child_nodes = if entry.is_dir && (follow_symlinks || !is_symlink) {
                     build_nodes(
                         Some(rel_path.clone()),
                         current_depth + 1,
                         max_depth,
+                        follow_symlinks,
                         children_by_parent,
                     )
                 } else {
@@ -655,6 +670,8 @@ pub fn build_project_tree(root_path: &str, max_depth: usize) -> Result<Vec<FileN
                     path: entry.path.clone(),
                     name: entry.name.clone(),
                     is_dir: entry.is_dir,
+                    is_symlink,
+                    is_submodule: entry.is_dir && is_submodule_root(Path::new(&entry.path)),
                     children: if child_nodes.is_empty() {
                         None
                     } else {
@@ -665,7 +682,7 @@ pub fn build_project_tree(root_path: &str, max_depth: usize) -> Result<Vec<FileN
             .collect()
     }
 
-    Ok(build_nodes(None, 0, max_depth, &children_by_parent))
+    Ok(build_nodes(None, 0, max_depth, follow_symlinks, &children_by_parent))
 }
 
 pub fn indexed_file_paths(
@@ -710,11 +727,30 @@ pub fn indexed_file_paths(
     Ok(file_paths)
 }
 
+/// Visit every indexed file as (relative path, absolute path) without copying the index
+pub fn for_each_indexed_file(
+    root_path: &str,
+    mut visit: impl FnMut(&str, &str),
+) -> Result<(), String> {
+    ensure_index(root_path)?;
+
+    let state = get_index_state();
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    let Some(index) = guard.as_ref() else {
+        return Ok(());
+    };
+
+    for (rel_path, entry) in &index.entries {
+        if !entry.is_dir {
+            visit(rel_path, &entry.path);
+        }
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn rebuild_workspace_index(path: String) -> Result<WorkspaceIndexStats, String> {
-    let rebuilt = tokio::task::spawn_blocking(move || build_index(&path))
-        .await
-        .map_err(|e| e.to_string())??;
+pub async fn rebuild_workspace_index(path: String) -> Result<WorkspaceIndexStats, VoidDeskError> {
+    let rebuilt = tokio::task::spawn_blocking(move || build_index(&path)).await??;
 
     save_index_to_disk(&rebuilt)?;
     let stats = snapshot_stats(&rebuilt);
@@ -723,9 +759,15 @@ pub async fn rebuild_workspace_index(path: String) -> Result<WorkspaceIndexStats
     Ok(stats)
 }
 
+/// Stats of the index currently in memory, without building one
+pub fn loaded_index_stats() -> Option<WorkspaceIndexStats> {
+    let guard = get_index_state().lock().ok()?;
+    guard.as_ref().map(snapshot_stats)
+}
+
 #[tauri::command]
-pub async fn get_workspace_index_stats(path: String) -> Result<WorkspaceIndexStats, String> {
-    tokio::task::spawn_blocking(move || {
+pub async fn get_workspace_index_stats(path: String) -> Result<WorkspaceIndexStats, VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || {
         ensure_index(&path)?;
         let guard = get_index_state().lock().map_err(|e| e.to_string())?;
         let Some(index) = guard.as_ref() else {
@@ -733,13 +775,13 @@ pub async fn get_workspace_index_stats(path: String) -> Result<WorkspaceIndexSta
         };
         Ok(snapshot_stats(index))
     })
-    .await
-    .map_err(|e| e.to_string())?
+    .await??)
 }
 
 #[tauri::command]
-pub async fn get_workspace_index_cache_summary() -> Result<PersistedWorkspaceIndexSummary, String> {
-    tokio::task::spawn_blocking(move || {
+pub async fn get_workspace_index_cache_summary(
+) -> Result<PersistedWorkspaceIndexSummary, VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || -> Result<_, String> {
         let connection = open_connection()?;
 
         let root_count = connection
@@ -790,8 +832,7 @@ pub async fn get_workspace_index_cache_summary() -> Result<PersistedWorkspaceInd
         let mut cached_roots = Vec::new();
         for row in rows {
             let (root_path, ignore_rules_json, last_indexed_at) = row.map_err(|e| e.to_string())?;
-            let ignore_rules: Vec<String> =
-                serde_json::from_str(&ignore_rules_json).map_err(|e| e.to_string())?;
+            let ignore_rules = parse_ignore_rules(&ignore_rules_json)?;
             let file_count = connection
                 .query_row(
                     "SELECT COUNT(*) FROM workspace_index_entries WHERE root_path = ?1 AND is_dir = 0",
@@ -811,7 +852,7 @@ pub async fn get_workspace_index_cache_summary() -> Result<PersistedWorkspaceInd
                 root_path,
                 file_count,
                 directory_count,
-                ignored_rules: ignore_rules,
+                ignored_rules: ignore_rules.rules,
                 last_indexed_at: last_indexed_at.max(0) as u64,
             });
         }
@@ -828,13 +869,12 @@ pub async fn get_workspace_index_cache_summary() -> Result<PersistedWorkspaceInd
             cached_roots,
         })
     })
-    .await
-    .map_err(|e| e.to_string())?
+    .await??)
 }
 
 #[tauri::command]
-pub async fn clear_workspace_index_cache() -> Result<ClearedWorkspaceIndexCache, String> {
-    tokio::task::spawn_blocking(move || {
+pub async fn clear_workspace_index_cache() -> Result<ClearedWorkspaceIndexCache, VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || -> Result<_, String> {
         let mut connection = open_connection()?;
         let transaction = connection.transaction().map_err(|e| e.to_string())?;
 
@@ -861,12 +901,34 @@ pub async fn clear_workspace_index_cache() -> Result<ClearedWorkspaceIndexCache,
             entry_count,
         })
     })
-    .await
-    .map_err(|e| e.to_string())?
+    .await??)
 }
 
 #[tauri::command]
-pub fn set_workspace_index_persistence_enabled(enabled: bool) -> Result<(), String> {
+pub fn set_workspace_index_persistence_enabled(enabled: bool) -> Result<(), VoidDeskError> {
     WORKSPACE_INDEX_PERSISTENCE_ENABLED.store(enabled, Ordering::Relaxed);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_are_indexed_without_descending() {
+        let root = std::env::temp_dir().join(format!("voidesk-index-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/nested/main.rs"), "fn main() {}").unwrap();
+        std::os::unix::fs::symlink(root.join("src"), root.join("src/nested/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("src/nested"), root.join("linked")).unwrap();
+
+        let index = build_index(&normalize_path(&root)).unwrap();
+        assert!(index.entries.contains_key("src/nested/loop"));
+        assert!(!index.entries.contains_key("src/nested/loop/nested"));
+        // A link to a folder outside the current walk is still followed
+        assert!(index.entries.contains_key("linked/main.rs"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,162 @@
+// Workspace roots
+// The folders the user has opened. File and project commands only accept paths that
+// resolve inside one of them, the same rule ai_tools applies to the agent.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use crate::error::{ErrorCode, VoidDeskError};
+
+static WORKSPACE_ROOTS: OnceLock<RwLock<Vec<PathBuf>>> = OnceLock::new();
+
+fn roots() -> &'static RwLock<Vec<PathBuf>> {
+    WORKSPACE_ROOTS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Canonicalize `path`, or for a path that doesn't exist yet its nearest existing
+/// ancestor followed by the remaining plain components
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path.display()));
+    }
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(canonical) => {
+                let mut resolved = canonical;
+                for component in missing.iter().rev() {
+                    resolved.push(component);
+                }
+                return Ok(resolved);
+            }
+            Err(_) => {
+                let Some(Component::Normal(name)) = existing.components().next_back() else {
+                    return Err(format!("Invalid path: {}", path.display()));
+                };
+                missing.push(name.to_owned());
+                existing = existing
+                    .parent()
+                    .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+            }
+        }
+    }
+}
+
+fn is_within(roots: &[PathBuf], resolved: &Path) -> bool {
+    roots.iter().any(|root| resolved.starts_with(root))
+}
+
+/// Fail unless `path` resolves inside an opened workspace root
+pub fn ensure_in_workspace(path: &str) -> Result<(), VoidDeskError> {
+    let resolved = resolve(Path::new(path)).map_err(VoidDeskError::invalid_input)?;
+    let roots = roots()
+        .read()
+        .map_err(|e| VoidDeskError::internal(e.to_string()))?;
+    if roots.is_empty() {
+        return Err(VoidDeskError::new(
+            ErrorCode::OutsideWorkspace,
+            "No workspace folder is open",
+        ));
+    }
+    if !is_within(&roots, &resolved) {
+        return Err(VoidDeskError::new(
+            ErrorCode::OutsideWorkspace,
+            format!("Access denied: '{}' is outside the open workspace", path),
+        ));
+    }
+    Ok(())
+}
+
+/// The open workspace root `path` is in; the innermost one when roots are nested
+pub fn containing_root(path: &str) -> Option<PathBuf> {
+    let resolved = resolve(Path::new(path)).ok()?;
+    let roots = roots().read().ok()?;
+    roots
+        .iter()
+        .filter(|root| resolved.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+}
+
+/// Add `path` to the roots; returns its canonical path
+pub fn register(path: &str) -> Result<PathBuf, VoidDeskError> {
+    let canonical = Path::new(path).canonicalize().map_err(|e| {
+        VoidDeskError::from(e).context(format!("Invalid workspace folder '{}'", path))
+    })?;
+    if !canonical.is_dir() {
+        return Err(VoidDeskError::invalid_input(format!(
+            "Path is not a directory: {}",
+            path
+        )));
+    }
+
+    let mut roots = roots()
+        .write()
+        .map_err(|e| VoidDeskError::internal(e.to_string()))?;
+    if !roots.contains(&canonical) {
+        roots.push(canonical.clone());
+    }
+    Ok(canonical)
+}
+
+/// Remove `path` from the roots; returns the path that was removed
+pub fn unregister(path: &str) -> Result<PathBuf, VoidDeskError> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path));
+    roots()
+        .write()
+        .map_err(|e| VoidDeskError::internal(e.to_string()))?
+        .retain(|root| *root != canonical);
+    Ok(canonical)
+}
+
+/// The open roots, in the order they were opened
+pub fn list() -> Result<Vec<PathBuf>, VoidDeskError> {
+    Ok(roots()
+        .read()
+        .map_err(|e| VoidDeskError::internal(e.to_string()))?
+        .clone())
+}
+
+/// Open a folder as a workspace root; returns its canonical path
+#[tauri::command]
+pub async fn register_workspace_root(path: String) -> Result<String, VoidDeskError> {
+    Ok(register(&path)?.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn unregister_workspace_root(path: String) -> Result<(), VoidDeskError> {
+    unregister(&path).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn list_workspace_roots() -> Result<Vec<String>, VoidDeskError> {
+    Ok(list()?
+        .iter()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_missing_paths_and_rejects_escapes() {
+        let root = std::env::temp_dir().join(format!("voidesk-roots-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let root = root.canonicalize().unwrap();
+        let roots = vec![root.clone()];
+
+        let new_file = resolve(&root.join("src/new/file.rs")).unwrap();
+        assert!(is_within(&roots, &new_file));
+        assert!(resolve(&root.join("missing/../../escape")).is_err());
+        assert!(!is_within(&roots, &resolve(&root.join("..")).unwrap()));
+        assert!(resolve(Path::new("relative/path")).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
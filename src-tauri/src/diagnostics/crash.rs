@@ -0,0 +1,141 @@
+// Crash reports
+// A panic hook that writes what panicked, where, and the backtrace to a JSON file before
+// handing over to the previous hook, so a crash leaves something to attach to a report
+
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Reports kept on disk; older ones are removed when a new one is written
+const MAX_CRASH_REPORTS: usize = 20;
+
+static CRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: Option<String>,
+    pub message: String,
+    /// file:line:column of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Panic with a non-string payload".to_string())
+}
+
+fn report_for(info: &PanicHookInfo<'_>) -> CrashReport {
+    CrashReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        thread: std::thread::current().name().map(str::to_string),
+        message: panic_message(info),
+        location: info.location().map(|location| {
+            format!(
+                "{}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        }),
+        backtrace: Backtrace::force_capture().to_string(),
+    }
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    let path = dir.join(format!(
+        "crash-{}-{}.json",
+        report.timestamp,
+        &report.id[..8]
+    ));
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    let mut reports = report_paths(dir);
+    while reports.len() > MAX_CRASH_REPORTS {
+        let _ = fs::remove_file(reports.remove(0));
+    }
+    Ok(path)
+}
+
+/// Crash report files in `dir`, oldest first
+fn report_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".json"))
+        })
+        .collect();
+    // The timestamp leads the name, so names sort by age
+    paths.sort();
+    paths
+}
+
+/// Write a report for every panic from now on, then run the hook that was installed before
+pub(super) fn install_panic_hook(dir: PathBuf) -> Result<(), String> {
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    if CRASH_DIR.set(dir).is_err() {
+        return Ok(());
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = CRASH_DIR.get() {
+            let report = report_for(info);
+            match write_report(dir, &report) {
+                Ok(path) => tracing::error!(
+                    "Panic: {} at {}; crash report written to {}",
+                    report.message,
+                    report.location.as_deref().unwrap_or("unknown location"),
+                    path.display()
+                ),
+                Err(error) => {
+                    tracing::error!("Panic: {}; no crash report: {}", report.message, error)
+                }
+            }
+        }
+        previous(info);
+    }));
+    Ok(())
+}
+
+/// The newest `limit` crash reports as (file name, contents), oldest first
+pub(super) fn recent_reports(dir: &Path, limit: usize) -> Vec<(String, String)> {
+    let paths = report_paths(dir);
+    let skip = paths.len().saturating_sub(limit);
+    paths
+        .into_iter()
+        .skip(skip)
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let content = fs::read_to_string(&path).ok()?;
+            Some((name, content))
+        })
+        .collect()
+}
+
+pub(super) fn crash_dir() -> Option<&'static PathBuf> {
+    CRASH_DIR.get()
+}
@@ -0,0 +1,260 @@
+// Backend health
+// One call gathering the state of every long-running part of the backend for the
+// diagnostics page: the AI provider, language servers, file watchers, terminals and the
+// workspace index. Each part is read as it is; nothing is restarted or rebuilt here.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{State, WebviewWindow};
+
+use super::{display_url, LAST_AI_REQUEST};
+use crate::commands::file_watcher::{self, WatcherStatus};
+use crate::commands::lsp_commands::LspState;
+use crate::commands::{workspace, workspace_index};
+use crate::lsp::manager::ServerHealth;
+use crate::terminal::TerminalState;
+use crate::{secrets, settings};
+
+/// The provider probe gives up after this long
+const AI_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An index older than this is reported as stale
+const INDEX_STALE_AFTER_MS: u64 = 30 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AiHealth {
+    pub provider_type: Option<String>,
+    pub model_id: Option<String>,
+    /// Without credentials or query
+    pub base_url: Option<String>,
+    /// Whether a request was sent to the provider
+    pub probed: bool,
+    /// Any HTTP response counts, even an error status
+    pub reachable: Option<bool>,
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexHealth {
+    pub root_path: String,
+    pub file_count: usize,
+    /// Milliseconds since the Unix epoch
+    pub last_indexed_at: u64,
+    pub age_ms: u64,
+    pub stale: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BackendHealth {
+    /// Milliseconds since the Unix epoch
+    pub checked_at: i64,
+    pub ai: AiHealth,
+    pub lsp_servers: Vec<ServerHealth>,
+    pub watchers: Vec<WatcherStatus>,
+    pub terminal_count: usize,
+    /// None until a workspace has been indexed
+    pub index: Option<IndexHealth>,
+    /// One line per part that needs attention; empty when everything is fine
+    pub problems: Vec<String>,
+}
+
+/// The provider of the last AI request, or the configured one before any request
+fn ai_target() -> (Option<String>, Option<String>, Option<String>) {
+    if let Some(last) = LAST_AI_REQUEST.lock().ok().and_then(|last| last.clone()) {
+        return (Some(last.provider_type), Some(last.model_id), last.base_url);
+    }
+    let active_root = workspace::active_root();
+    let ai = settings::effective(active_root.as_deref()).ai;
+    (ai.provider_type, ai.model_id, ai.base_url)
+}
+
+/// Ask the provider for its model list; the status tells whether it is up and the key
+/// accepted, and listing models costs nothing
+async fn probe_ai(base_url: &str) -> (Option<bool>, Option<u16>, Option<u64>, Option<String>) {
+    let client = match reqwest::Client::builder().timeout(AI_PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(error) => return (None, None, None, Some(error.to_string())),
+    };
+    let mut request = client.get(format!("{}/models", base_url.trim_end_matches('/')));
+    let api_key = secrets::get(secrets::AI_API_KEY);
+    if let Some(api_key) = api_key.as_deref() {
+        request = request.bearer_auth(api_key);
+    }
+
+    let started = Instant::now();
+    match request.send().await {
+        Ok(response) => {
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let status = response.status();
+            let error = if status.is_server_error() {
+                Some(format!("The provider answered with {}", status))
+            } else if api_key.is_some() && matches!(status.as_u16(), 401 | 403) {
+                Some("The provider rejected the stored API key".to_string())
+            } else {
+                None
+            };
+            (Some(true), Some(status.as_u16()), Some(latency_ms), error)
+        }
+        Err(error) => (Some(false), None, None, Some(error.to_string())),
+    }
+}
+
+async fn ai_health(probe: bool) -> AiHealth {
+    let (provider_type, model_id, base_url) = ai_target();
+    let base_url = base_url.filter(|url| !url.trim().is_empty());
+    let mut health = AiHealth {
+        base_url: base_url.as_deref().and_then(display_url),
+        provider_type,
+        model_id,
+        probed: false,
+        reachable: None,
+        status_code: None,
+        latency_ms: None,
+        error: None,
+    };
+    // The Codex subscription goes through its own endpoint and sign-in
+    let probe_url =
+        base_url.filter(|_| probe && health.provider_type.as_deref() != Some("codex_subscription"));
+    if let Some(base_url) = probe_url {
+        let (reachable, status_code, latency_ms, error) = probe_ai(&base_url).await;
+        health.probed = true;
+        health.reachable = reachable;
+        health.status_code = status_code;
+        health.latency_ms = latency_ms;
+        health.error = error;
+    }
+    health
+}
+
+fn index_health(now_ms: u64) -> Option<IndexHealth> {
+    let stats = workspace_index::loaded_index_stats()?;
+    let age_ms = now_ms.saturating_sub(stats.last_indexed_at);
+    Some(IndexHealth {
+        root_path: stats.root_path,
+        file_count: stats.file_count,
+        last_indexed_at: stats.last_indexed_at,
+        age_ms,
+        stale: age_ms > INDEX_STALE_AFTER_MS,
+    })
+}
+
+fn problems(health: &BackendHealth) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(error) = health.ai.error.as_deref() {
+        problems.push(format!("AI provider: {}", error));
+    }
+    for server in &health.lsp_servers {
+        if server.restarting {
+            problems.push(format!(
+                "{} language server is restarting (attempt {})",
+                server.language, server.restart_attempts
+            ));
+        } else if !server.alive {
+            problems.push(format!(
+                "{} language server is not running",
+                server.language
+            ));
+        }
+    }
+    for watcher in &health.watchers {
+        if !watcher.running {
+            problems.push(format!("File watcher for {} has stopped", watcher.path));
+        } else if let Some(error) = watcher.last_error.as_deref() {
+            problems.push(format!("File watcher for {}: {}", watcher.path, error));
+        }
+    }
+    if let Some(index) = health.index.as_ref().filter(|index| index.stale) {
+        problems.push(format!(
+            "Workspace index for {} is {} minutes old",
+            index.root_path,
+            index.age_ms / 60_000
+        ));
+    }
+    problems
+}
+
+/// Status of every backend subsystem in one call, with the language servers of the
+/// calling window. `probe_ai` (default true) sends one request to the AI provider to
+/// check that it answers.
+#[tauri::command]
+pub async fn get_backend_health(
+    window: WebviewWindow,
+    lsp_state: State<'_, LspState>,
+    terminal_state: State<'_, TerminalState>,
+    probe_ai: Option<bool>,
+) -> Result<BackendHealth, String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut health = BackendHealth {
+        checked_at: now,
+        ai: ai_health(probe_ai.unwrap_or(true)).await,
+        lsp_servers: lsp_state.manager(window.label()).server_health().await,
+        watchers: file_watcher::watcher_statuses()?,
+        terminal_count: terminal_state.count(),
+        index: index_health(now.max(0) as u64),
+        problems: Vec::new(),
+    };
+    health.problems = problems(&health);
+    Ok(health)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_name_the_parts_that_need_attention() {
+        let mut health = BackendHealth {
+            checked_at: 0,
+            ai: AiHealth {
+                provider_type: Some("openai_compatible".to_string()),
+                model_id: Some("gpt-4.1".to_string()),
+                base_url: Some("https://api.example.com/v1".to_string()),
+                probed: true,
+                reachable: Some(true),
+                status_code: Some(200),
+                latency_ms: Some(120),
+                error: None,
+            },
+            lsp_servers: vec![ServerHealth {
+                language: "rust".to_string(),
+                alive: true,
+                restarting: false,
+                restart_attempts: 0,
+                progress: vec!["Indexing".to_string()],
+            }],
+            watchers: Vec::new(),
+            terminal_count: 2,
+            index: Some(IndexHealth {
+                root_path: "/work/app".to_string(),
+                file_count: 10,
+                last_indexed_at: 0,
+                age_ms: 1_000,
+                stale: false,
+            }),
+            problems: Vec::new(),
+        };
+        assert!(problems(&health).is_empty());
+
+        health.ai.error = Some("The provider rejected the stored API key".to_string());
+        health.lsp_servers[0].alive = false;
+        health.lsp_servers.push(ServerHealth {
+            language: "python".to_string(),
+            alive: false,
+            restarting: true,
+            restart_attempts: 2,
+            progress: Vec::new(),
+        });
+        if let Some(index) = health.index.as_mut() {
+            index.age_ms = 45 * 60_000;
+            index.stale = true;
+        }
+        let problems = problems(&health);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].starts_with("AI provider"));
+        assert_eq!(problems[1], "rust language server is not running");
+        assert!(problems[2].contains("restarting (attempt 2)"));
+        assert!(problems[3].contains("45 minutes old"));
+    }
+}
@@ -0,0 +1,324 @@
+// Diagnostics
+// Crash reports, and the bundle a user attaches to a bug report: recent logs, crash
+// reports, metrics, settings with anything secret redacted, system info and the AI
+// provider and model last used. Also the backend health check behind the diagnostics page.
+
+mod crash;
+mod health;
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::commands::workspace;
+use crate::{log_buffer, metrics, secrets, settings, tracing_setup};
+
+pub use health::get_backend_health;
+
+const DIAGNOSTICS_DIR: &str = "diagnostics";
+const CRASH_REPORTS_DIR: &str = "crash-reports";
+
+/// Crash reports included in a bundle
+const BUNDLED_CRASH_REPORTS: usize = 5;
+
+/// How much of the end of the log file goes into a bundle
+const LOG_FILE_TAIL_BYTES: u64 = 2 * 1024 * 1024;
+
+const REDACTED: &str = "[redacted]";
+
+/// Object keys whose values are never put in a bundle
+const SECRET_KEY_PARTS: &[&str] = &["key", "token", "secret", "password", "passwd", "auth"];
+
+static DIAGNOSTICS_PATH: OnceLock<PathBuf> = OnceLock::new();
+static LAST_AI_REQUEST: Mutex<Option<AiRequestInfo>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AiRequestInfo {
+    /// Matches the request id on the streamed chunks and in the logs
+    pub request_id: String,
+    pub provider_type: String,
+    pub model_id: String,
+    /// Without credentials or query
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SystemInfo {
+    app_version: &'static str,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    cpus: Option<usize>,
+    /// Milliseconds since the Unix epoch
+    generated_at: i64,
+    last_ai_request: Option<AiRequestInfo>,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    let dir = DIAGNOSTICS_PATH.get_or_init(|| app_data_dir.join(DIAGNOSTICS_DIR));
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    crash::install_panic_hook(app_data_dir.join(CRASH_REPORTS_DIR))
+}
+
+/// `url` without credentials, query or fragment
+fn display_url(url: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse(url).ok()?;
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.set_query(None);
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+/// Remember the provider and model of an AI request for the next bundle and health check
+pub fn record_ai_request(request_id: &str, provider_type: &str, model_id: &str, base_url: &str) {
+    let base_url = display_url(base_url);
+    if let Ok(mut last) = LAST_AI_REQUEST.lock() {
+        *last = Some(AiRequestInfo {
+            request_id: request_id.to_string(),
+            provider_type: provider_type.to_string(),
+            model_id: model_id.to_string(),
+            base_url,
+        });
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+/// Replace values under secret-looking keys, and any stored secret appearing anywhere
+fn redact(value: &mut Value, known_secrets: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value, known_secrets);
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact(item, known_secrets)),
+        Value::String(text) => {
+            for secret in known_secrets.iter().filter(|secret| secret.len() >= 4) {
+                if text.contains(secret.as_str()) {
+                    *text = text.replace(secret.as_str(), REDACTED);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redacted_json(value: impl Serialize, known_secrets: &[String]) -> Result<String, String> {
+    let mut value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    redact(&mut value, known_secrets);
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+fn redacted_text(text: &str, known_secrets: &[String]) -> String {
+    let mut value = Value::String(text.to_string());
+    redact(&mut value, known_secrets);
+    match value {
+        Value::String(text) => text,
+        _ => String::new(),
+    }
+}
+
+/// The last `max_bytes` of the file at `path`, starting at a line break
+fn file_tail(path: &Path, max_bytes: u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    let start = size.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    let text = String::from_utf8_lossy(&bytes).to_string();
+    Some(match (start, text.find('\n')) {
+        (0, _) | (_, None) => text,
+        (_, Some(newline)) => text[newline + 1..].to_string(),
+    })
+}
+
+/// Everything a bundle holds, as (file name, contents)
+fn bundle_files(crash_dir: Option<&Path>) -> Result<Vec<(String, String)>, String> {
+    let known_secrets = secrets::unlocked_values();
+    let last_ai_request = LAST_AI_REQUEST.lock().ok().and_then(|last| last.clone());
+    let system = SystemInfo {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        os_family: std::env::consts::FAMILY,
+        arch: std::env::consts::ARCH,
+        cpus: std::thread::available_parallelism().ok().map(usize::from),
+        generated_at: chrono::Utc::now().timestamp_millis(),
+        last_ai_request,
+    };
+
+    let active_root = workspace::active_root();
+    let settings = serde_json::json!({
+        "app": settings::effective(None),
+        "active_workspace": active_root.as_ref().map(|root| serde_json::json!({
+            "root": root,
+            "effective": settings::effective(Some(root)),
+        })),
+    });
+
+    let mut files = vec![
+        (
+            "system.json".to_string(),
+            redacted_json(&system, &known_secrets)?,
+        ),
+        (
+            "settings.json".to_string(),
+            redacted_json(&settings, &known_secrets)?,
+        ),
+        (
+            "logs.json".to_string(),
+            redacted_json(log_buffer::recent_entries(), &known_secrets)?,
+        ),
+        (
+            "metrics.json".to_string(),
+            redacted_json(metrics::snapshot()?, &known_secrets)?,
+        ),
+    ];
+    if let Some(log) =
+        tracing_setup::log_file_path().and_then(|path| file_tail(&path, LOG_FILE_TAIL_BYTES))
+    {
+        files.push((
+            "ai-debug.log".to_string(),
+            redacted_text(&log, &known_secrets),
+        ));
+    }
+    if let Some(crash_dir) = crash_dir {
+        for (name, content) in crash::recent_reports(crash_dir, BUNDLED_CRASH_REPORTS) {
+            files.push((
+                format!("crash-reports/{}", name),
+                redacted_text(&content, &known_secrets),
+            ));
+        }
+    }
+    Ok(files)
+}
+
+fn write_bundle(path: &Path, files: &[(String, String)]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in files {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Zip up what a bug report needs and return where it was written: `destination`, or a
+/// new file in the app's diagnostics folder
+#[tauri::command]
+pub async fn export_diagnostics_bundle(destination: Option<String>) -> Result<String, String> {
+    let path = match destination {
+        Some(destination) => PathBuf::from(destination),
+        None => DIAGNOSTICS_PATH
+            .get()
+            .ok_or_else(|| "Diagnostics have not been initialized".to_string())?
+            .join(format!(
+                "voidesk-diagnostics-{}.zip",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            )),
+    };
+    tokio::task::spawn_blocking(move || {
+        let files = bundle_files(crash::crash_dir().map(PathBuf::as_path))?;
+        write_bundle(&path, &files)?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bundles_redacted_settings_logs_and_crash_reports() {
+        let dir =
+            std::env::temp_dir().join(format!("voidesk-diagnostics-{}", uuid::Uuid::new_v4()));
+        let crash_dir = dir.join(CRASH_REPORTS_DIR);
+        fs::create_dir_all(&crash_dir).unwrap();
+        fs::write(
+            crash_dir.join("crash-1-aaaaaaaa.json"),
+            r#"{"message":"old"}"#,
+        )
+        .unwrap();
+        fs::write(
+            crash_dir.join("crash-2-bbbbbbbb.json"),
+            r#"{"message":"boom"}"#,
+        )
+        .unwrap();
+
+        let mut value = json!({
+            "ai": { "api_key": "sk-live", "model_id": "gpt" },
+            "terminal": { "profiles": [{ "env": { "GITHUB_TOKEN": "ghp_x" } }] },
+            "note": "uses hunter22 to log in",
+        });
+        redact(&mut value, &["hunter22".to_string()]);
+        assert_eq!(value["ai"]["api_key"], REDACTED);
+        assert_eq!(value["ai"]["model_id"], "gpt");
+        assert_eq!(
+            value["terminal"]["profiles"][0]["env"]["GITHUB_TOKEN"],
+            REDACTED
+        );
+        assert_eq!(value["note"], "uses [redacted] to log in");
+
+        record_ai_request(
+            "request-1",
+            "openai_compatible",
+            "gpt-4.1",
+            "https://user:pw@api.example.com/v1?key=x",
+        );
+        let files = bundle_files(Some(&crash_dir)).unwrap();
+        let system = &files
+            .iter()
+            .find(|(name, _)| name == "system.json")
+            .unwrap()
+            .1;
+        assert!(system.contains("https://api.example.com/v1"), "{}", system);
+        assert!(
+            !system.contains("pw@") && !system.contains("key=x"),
+            "{}",
+            system
+        );
+
+        let bundle = dir.join("bundle.zip");
+        write_bundle(&bundle, &files).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&bundle).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&"settings.json") && names.contains(&"logs.json"));
+        assert!(names.contains(&"crash-reports/crash-2-bbbbbbbb.json"));
+        let mut crash = String::new();
+        archive
+            .by_name("crash-reports/crash-2-bbbbbbbb.json")
+            .unwrap()
+            .read_to_string(&mut crash)
+            .unwrap();
+        assert!(crash.contains("boom"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,240 @@
+// Text diffs
+// One diff implementation for everything that shows changes: libgit2's Myers diff with
+// real hunks and context, plus word-level highlights within changed lines. Git views,
+// the AI edit tools and previews of edits not yet written all go through it.
+
+mod words;
+
+use git2::{DiffOptions, Patch};
+use serde::Serialize;
+
+use crate::error::VoidDeskError;
+
+pub use words::DiffSegment;
+
+/// Unchanged lines kept around each change, as `git diff` does
+const CONTEXT_LINES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 1-based line number on the old side; None for added lines
+    pub old_line: Option<u32>,
+    /// 1-based line number on the new side; None for removed lines
+    pub new_line: Option<u32>,
+    /// Without the trailing newline
+    pub content: String,
+    /// For a line that replaces another, or is replaced, its text split into runs that
+    /// changed and runs that did not; empty otherwise
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<DiffSegment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffHunk {
+    /// Identifies the hunk to git_stage_hunk; changes when the file does
+    pub id: String,
+    /// The "@@ -a,b +c,d @@ context" line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The changes between two versions of a text
+#[derive(Debug, Clone, Serialize)]
+pub struct TextDiff {
+    pub hunks: Vec<DiffHunk>,
+    pub additions: usize,
+    pub deletions: usize,
+    /// The hunks as unified diff text, with the "no newline" markers
+    #[serde(skip)]
+    patch: String,
+}
+
+impl TextDiff {
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// As a unified diff whose file headers name the two sides `old_label` and `new_label`
+    pub fn unified(&self, old_label: &str, new_label: &str) -> String {
+        format!("--- {}\n+++ {}\n{}", old_label, new_label, self.patch)
+    }
+}
+
+pub fn hunk_id(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> String {
+    format!("{},{},{},{}", old_start, old_lines, new_start, new_lines)
+}
+
+fn trim_newline(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    text.strip_suffix('\r').unwrap_or(text).to_string()
+}
+
+/// Pair each run of removed lines with the added lines right after it, line by line, and
+/// mark which words of each pair changed
+fn add_word_segments(lines: &mut [DiffLine]) {
+    let mut index = 0;
+    while index < lines.len() {
+        if lines[index].kind != DiffLineKind::Removed {
+            index += 1;
+            continue;
+        }
+        let removed_start = index;
+        while index < lines.len() && lines[index].kind == DiffLineKind::Removed {
+            index += 1;
+        }
+        let added_start = index;
+        while index < lines.len() && lines[index].kind == DiffLineKind::Added {
+            index += 1;
+        }
+        let pairs = (added_start - removed_start).min(index - added_start);
+        for offset in 0..pairs {
+            let (old, new) = (removed_start + offset, added_start + offset);
+            if let Some((old_segments, new_segments)) =
+                words::segments(&lines[old].content, &lines[new].content)
+            {
+                lines[old].segments = old_segments;
+                lines[new].segments = new_segments;
+            }
+        }
+    }
+}
+
+pub(crate) fn patch_hunks(patch: &Patch) -> Result<Vec<DiffHunk>, String> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index).map_err(|e| e.to_string())?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|e| e.to_string())?;
+            // Other origins are the "no newline at end of file" markers
+            let kind = match line.origin() {
+                ' ' => DiffLineKind::Context,
+                '+' => DiffLineKind::Added,
+                '-' => DiffLineKind::Removed,
+                _ => continue,
+            };
+            lines.push(DiffLine {
+                kind,
+                old_line: line.old_lineno(),
+                new_line: line.new_lineno(),
+                content: trim_newline(line.content()),
+                segments: Vec::new(),
+            });
+        }
+        add_word_segments(&mut lines);
+        hunks.push(DiffHunk {
+            id: hunk_id(
+                hunk.old_start(),
+                hunk.old_lines(),
+                hunk.new_start(),
+                hunk.new_lines(),
+            ),
+            header: trim_newline(hunk.header()),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Diff `old` against `new` line by line
+pub fn diff_texts(old: &str, new: &str) -> Result<TextDiff, String> {
+    let mut options = DiffOptions::new();
+    options.context_lines(CONTEXT_LINES).force_text(true);
+    let mut patch = Patch::from_buffers(
+        old.as_bytes(),
+        None,
+        new.as_bytes(),
+        None,
+        Some(&mut options),
+    )
+    .map_err(|e| e.to_string())?;
+    let (_, additions, deletions) = patch.line_stats().map_err(|e| e.to_string())?;
+    let hunks = patch_hunks(&patch)?;
+
+    let mut text = Vec::new();
+    patch
+        .print(&mut |_, _, line| {
+            match line.origin() {
+                // The file header names no files; unified() writes its own
+                'F' => return true,
+                origin @ (' ' | '+' | '-') => text.push(origin as u8),
+                _ => {}
+            }
+            text.extend_from_slice(line.content());
+            true
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(TextDiff {
+        hunks,
+        additions,
+        deletions,
+        patch: String::from_utf8_lossy(&text).to_string(),
+    })
+}
+
+/// Diff two versions of a text, for previews of edits that have not been written
+#[tauri::command]
+pub async fn compute_diff(old_text: String, new_text: String) -> Result<TextDiff, VoidDeskError> {
+    Ok(diff_texts(&old_text, &new_text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_context_and_marks_changed_words() {
+        let old = "fn main() {\n    let a = 1;\n    let b = 2;\n    print(a);\n}";
+        let new = "fn main() {\n    let a = 10;\n    let b = 2;\n    print(a);\n}\n";
+        let diff = diff_texts(old, new).unwrap();
+        assert_eq!((diff.additions, diff.deletions), (2, 2));
+        assert_eq!(
+            diff.unified("original", "updated"),
+            concat!(
+                "--- original\n+++ updated\n",
+                "@@ -1,5 +1,5 @@\n",
+                " fn main() {\n",
+                "-    let a = 1;\n",
+                "+    let a = 10;\n",
+                "     let b = 2;\n",
+                "     print(a);\n",
+                "-}\n\\ No newline at end of file\n",
+                "+}\n",
+            )
+        );
+
+        let changed: Vec<(&str, bool)> = diff.hunks[0].lines[2]
+            .segments
+            .iter()
+            .map(|segment| (segment.text.as_str(), segment.changed))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![("    let a = ", false), ("10", true), (";", false)]
+        );
+        assert!(diff.hunks[0].lines[0].segments.is_empty());
+
+        assert!(diff_texts("same\n", "same\n").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+/// Token pairs beyond this are not compared; the whole line shows as changed
+const MAX_COMPARED_CELLS: usize = 40_000;
+
+/// A run of a changed line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffSegment {
+    pub text: String,
+    /// Whether the run differs from the line it is paired with
+    pub changed: bool,
+}
+
+/// Words, runs of whitespace, and single other characters
+fn tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let same_run: fn(char) -> bool = if c.is_alphanumeric() || c == '_' {
+            |next| next.is_alphanumeric() || next == '_'
+        } else if c.is_whitespace() {
+            char::is_whitespace
+        } else {
+            |_| false
+        };
+        let mut end = index + c.len_utf8();
+        while let Some(&(next_index, next)) = chars.peek() {
+            if !same_run(next) {
+                break;
+            }
+            end = next_index + next.len_utf8();
+            chars.next();
+        }
+        tokens.push(&line[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+/// Join tokens into runs, merging neighbours that are both changed or both kept
+fn runs(tokens: &[&str], kept: &[bool]) -> Vec<DiffSegment> {
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    for (token, kept) in tokens.iter().zip(kept) {
+        match segments.last_mut() {
+            Some(last) if last.changed != *kept => last.text.push_str(token),
+            _ => segments.push(DiffSegment {
+                text: token.to_string(),
+                changed: !kept,
+            }),
+        }
+    }
+    segments
+}
+
+/// The words of `old` and `new` split by whether they are part of their longest common
+/// subsequence. None when the lines are equal, share no words, or are too long to compare.
+pub(super) fn segments(old: &str, new: &str) -> Option<(Vec<DiffSegment>, Vec<DiffSegment>)> {
+    if old == new {
+        return None;
+    }
+    let old_tokens = tokens(old);
+    let new_tokens = tokens(new);
+    let width = new_tokens.len() + 1;
+    if (old_tokens.len() + 1) * width > MAX_COMPARED_CELLS {
+        return None;
+    }
+
+    // lengths[i * width + j]: common subsequence length of old[i..] and new[j..]
+    let mut lengths = vec![0_u32; (old_tokens.len() + 1) * width];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lengths[i * width + j] = if old_tokens[i] == new_tokens[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut old_kept = vec![false; old_tokens.len()];
+    let mut new_kept = vec![false; new_tokens.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old_tokens.len() && j < new_tokens.len() {
+        if old_tokens[i] == new_tokens[j] {
+            old_kept[i] = true;
+            new_kept[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    // Only whitespace in common says nothing about what changed
+    let shares_words = old_tokens
+        .iter()
+        .zip(&old_kept)
+        .any(|(token, kept)| *kept && !token.trim().is_empty());
+    if !shares_words {
+        return None;
+    }
+    Some((runs(&old_tokens, &old_kept), runs(&new_tokens, &new_kept)))
+}
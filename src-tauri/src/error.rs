@@ -0,0 +1,214 @@
+// Command errors
+// What a failed command sends the frontend: `{ code, message, details }`. The code lets
+// it tell a missing file from a refused one or a rejected API key and react to each; the
+// message is for people; details carry whatever else the code needs (sizes, statuses).
+// File, project, git, AI, LSP and terminal commands return it. The image, markdown,
+// format, lint, log, ignore rules, recent items, scaffold, settings, snippets, syntax,
+// metrics, remote file and dev server commands still reject with a plain message string
+// and are left to convert; the frontend's errorMessage() reads both.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::sdk::{ErrorCategory, SdkError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    AlreadyExists,
+    InvalidInput,
+    /// The path is outside every open workspace root, or none is open
+    OutsideWorkspace,
+    /// The file changed on disk since the caller read it
+    Conflict,
+    /// The SSH host is not in known_hosts yet; details carry its key's fingerprint
+    UnknownHostKey,
+    TooLarge,
+    Timeout,
+    Cancelled,
+    /// The provider rejected the API key or subscription
+    Unauthorized,
+    RateLimited,
+    /// The AI provider failed for another reason
+    Provider,
+    Io,
+    Internal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoidDeskError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl VoidDeskError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    /// Say what was being done in the message, keeping the code and details
+    pub fn context(mut self, context: impl Display) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+}
+
+impl Display for VoidDeskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for VoidDeskError {}
+
+/// Lets code that still reports errors as strings use `?` on these
+impl From<VoidDeskError> for String {
+    fn from(error: VoidDeskError) -> Self {
+        error.message
+    }
+}
+
+/// Errors that are only a message have no more specific code
+impl From<String> for VoidDeskError {
+    fn from(message: String) -> Self {
+        Self::internal(message)
+    }
+}
+
+impl From<&str> for VoidDeskError {
+    fn from(message: &str) -> Self {
+        Self::internal(message)
+    }
+}
+
+fn io_code(kind: io::ErrorKind) -> ErrorCode {
+    match kind {
+        io::ErrorKind::NotFound => ErrorCode::NotFound,
+        io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+        io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => ErrorCode::InvalidInput,
+        io::ErrorKind::TimedOut => ErrorCode::Timeout,
+        _ => ErrorCode::Io,
+    }
+}
+
+impl From<io::Error> for VoidDeskError {
+    fn from(error: io::Error) -> Self {
+        let converted = Self::new(io_code(error.kind()), error.to_string());
+        match error.raw_os_error() {
+            Some(os_error) => converted.with_details(json!({ "os_error": os_error })),
+            None => converted,
+        }
+    }
+}
+
+impl From<SdkError> for VoidDeskError {
+    fn from(error: SdkError) -> Self {
+        let code = match (error.status, error.category) {
+            (Some(401) | Some(403), _) => ErrorCode::Unauthorized,
+            (Some(429), _) => ErrorCode::RateLimited,
+            (Some(404), _) => ErrorCode::NotFound,
+            (_, ErrorCategory::Timeout) => ErrorCode::Timeout,
+            (_, ErrorCategory::Validation) => ErrorCode::InvalidInput,
+            (_, ErrorCategory::Permission) => ErrorCode::PermissionDenied,
+            (_, ErrorCategory::Internal) => ErrorCode::Internal,
+            _ => ErrorCode::Provider,
+        };
+        Self::new(code, error.message.clone()).with_details(json!({
+            "category": format!("{:?}", error.category).to_lowercase(),
+            "status": error.status,
+            "provider_code": error.code,
+            "retryable": error.retryable,
+        }))
+    }
+}
+
+/// Keeps the code of an SDK or IO error wrapped in the anyhow chain
+impl From<anyhow::Error> for VoidDeskError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(sdk_error) = error.downcast_ref::<SdkError>() {
+            return sdk_error.clone().into();
+        }
+        if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            return Self::new(io_code(io_error.kind()), error.to_string());
+        }
+        if let Some(error) = error.downcast_ref::<VoidDeskError>() {
+            return error.clone();
+        }
+        Self::internal(error.to_string())
+    }
+}
+
+/// A blocking task that panicked or was cancelled before it returned
+impl From<tokio::task::JoinError> for VoidDeskError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        let code = match error.is_cancelled() {
+            true => ErrorCode::Cancelled,
+            false => ErrorCode::Internal,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for VoidDeskError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::invalid_input(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_cause_in_the_code() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(VoidDeskError::from(missing).code, ErrorCode::NotFound);
+        let denied = io::Error::from_raw_os_error(13);
+        let denied = VoidDeskError::from(denied);
+        if cfg!(unix) {
+            assert_eq!(denied.code, ErrorCode::PermissionDenied);
+            assert_eq!(denied.details, Some(json!({ "os_error": 13 })));
+        }
+
+        let unauthorized = SdkError::provider("Invalid API key").with_status(401);
+        let wrapped = anyhow::Error::new(unauthorized);
+        let error = VoidDeskError::from(wrapped);
+        assert_eq!(error.code, ErrorCode::Unauthorized);
+        assert_eq!(error.message, "Invalid API key");
+
+        let json = serde_json::to_value(VoidDeskError::not_found("No such session")).unwrap();
+        assert_eq!(
+            json,
+            json!({ "code": "not_found", "message": "No such session" })
+        );
+        assert_eq!(String::from(VoidDeskError::internal("boom")), "boom");
+    }
+}
@@ -0,0 +1,135 @@
+// Blame
+// Who last changed each line of a file, for inline annotations in the editor and as
+// authorship context for the AI. The file is blamed as it is on disk, so lines not
+// committed yet come back without a commit.
+
+use git2::{Oid, Repository};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{open_repository, relative_path, workdir};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitBlameLine {
+    /// 1-based line number in the file on disk
+    pub line: usize,
+    /// None for lines that are not committed yet
+    pub commit_id: Option<String>,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// Author date as a Unix timestamp in milliseconds
+    pub timestamp: Option<i64>,
+    pub summary: Option<String>,
+}
+
+struct CommitDetails {
+    author_name: String,
+    author_email: String,
+    timestamp: i64,
+    summary: String,
+}
+
+fn commit_details(repo: &Repository, oid: Oid) -> Result<CommitDetails, String> {
+    let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+    let author = commit.author();
+    Ok(CommitDetails {
+        author_name: author.name().unwrap_or_default().to_string(),
+        author_email: author.email().unwrap_or_default().to_string(),
+        timestamp: author.when().seconds() * 1000,
+        summary: commit.summary().unwrap_or_default().to_string(),
+    })
+}
+
+pub(super) fn blame_path(repo: &Repository, rel_path: &str) -> Result<Vec<GitBlameLine>, String> {
+    // A file that was never committed has nothing to blame
+    let committed = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .is_ok_and(|tree| tree.get_path(Path::new(rel_path)).is_ok());
+    if !committed {
+        return Ok(Vec::new());
+    }
+
+    let committed_blame = repo
+        .blame_file(Path::new(rel_path), None)
+        .map_err(|e| e.to_string())?;
+    let contents = fs::read(workdir(repo)?.join(rel_path)).map_err(|e| e.to_string())?;
+    let blame = committed_blame
+        .blame_buffer(&contents)
+        .map_err(|e| e.to_string())?;
+
+    let mut commits: HashMap<Oid, CommitDetails> = HashMap::new();
+    let mut lines = Vec::new();
+    for hunk in blame.iter() {
+        let oid = hunk.final_commit_id();
+        let details = if oid.is_zero() {
+            None
+        } else {
+            if let Entry::Vacant(entry) = commits.entry(oid) {
+                entry.insert(commit_details(repo, oid)?);
+            }
+            commits.get(&oid)
+        };
+        let start = hunk.final_start_line();
+        for line in start..start + hunk.lines_in_hunk() {
+            lines.push(GitBlameLine {
+                line,
+                commit_id: details.map(|_| oid.to_string()),
+                author_name: details.map(|details| details.author_name.clone()),
+                author_email: details.map(|details| details.author_email.clone()),
+                timestamp: details.map(|details| details.timestamp),
+                summary: details.map(|details| details.summary.clone()),
+            });
+        }
+    }
+    Ok(lines)
+}
+
+/// Per-line blame of `path` as it is on disk, uncommitted edits included; empty for
+/// files that were never committed
+#[tauri::command]
+pub async fn git_blame(path: String) -> Result<Vec<GitBlameLine>, String> {
+    let repo = open_repository(&path)?;
+    let rel_path = relative_path(workdir(&repo)?, &path)?;
+    blame_path(&repo, &rel_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn blames_committed_and_uncommitted_lines() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        let first = test_repo::commit(&repo, &["a.txt"], "First");
+        fs::write(dir.join("a.txt"), "one\nTWO\n").unwrap();
+        let second = test_repo::commit(&repo, &["a.txt"], "Second");
+        fs::write(dir.join("a.txt"), "one\nTWO\nthree\n").unwrap();
+
+        let lines = blame_path(&repo, "a.txt").unwrap();
+        let commits: Vec<(usize, Option<String>)> = lines
+            .iter()
+            .map(|line| (line.line, line.commit_id.clone()))
+            .collect();
+        assert_eq!(
+            commits,
+            vec![
+                (1, Some(first.to_string())),
+                (2, Some(second.to_string())),
+                (3, None),
+            ]
+        );
+        assert_eq!(lines[1].summary.as_deref(), Some("Second"));
+        assert_eq!(lines[0].author_name.as_deref(), Some("Test"));
+
+        fs::write(dir.join("new.txt"), "new\n").unwrap();
+        assert!(blame_path(&repo, "new.txt").unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,256 @@
+// Branches
+// Listing, creating and switching branches. Switching refuses to run over uncommitted
+// changes to tracked files, so nothing in the working tree is lost or carried along.
+
+use git2::build::CheckoutBuilder;
+use git2::{Branch, BranchType, Repository, StatusOptions};
+use serde::Serialize;
+use serde_json::json;
+
+use super::{absolute_path, open_repository, workdir};
+use crate::commands::file_watcher;
+use crate::error::{ErrorCode, VoidDeskError};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitBranch {
+    /// "main", or "origin/main" for remote branches
+    pub name: String,
+    pub is_remote: bool,
+    pub is_current: bool,
+    /// Remote branch a local branch tracks
+    pub upstream: Option<String>,
+    /// Commit id the branch points at
+    pub target: Option<String>,
+}
+
+/// git_switch_branch sends a dirty tree as a `conflict` VoidDeskError with the paths in
+/// its details
+#[derive(Debug)]
+pub enum SwitchBranchError {
+    /// Tracked files have uncommitted changes; commit, stash or discard them first
+    DirtyWorkingTree {
+        message: String,
+        paths: Vec<String>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for SwitchBranchError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+impl From<SwitchBranchError> for VoidDeskError {
+    fn from(error: SwitchBranchError) -> Self {
+        match error {
+            SwitchBranchError::DirtyWorkingTree { message, paths } => {
+                Self::new(ErrorCode::Conflict, message).with_details(json!({ "paths": paths }))
+            }
+            SwitchBranchError::Failed { message } => Self::internal(message),
+        }
+    }
+}
+
+fn describe_branch(branch: &Branch, branch_type: BranchType) -> Result<GitBranch, String> {
+    let name = branch
+        .name()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let upstream = match branch_type {
+        BranchType::Local => branch
+            .upstream()
+            .ok()
+            .and_then(|upstream| upstream.name().ok().flatten().map(str::to_string)),
+        BranchType::Remote => None,
+    };
+    Ok(GitBranch {
+        name,
+        is_remote: branch_type == BranchType::Remote,
+        is_current: branch.is_head(),
+        upstream,
+        target: branch.get().target().map(|oid| oid.to_string()),
+    })
+}
+
+pub(super) fn list_branches(repo: &Repository) -> Result<Vec<GitBranch>, String> {
+    let mut branches = Vec::new();
+    for entry in repo.branches(None).map_err(|e| e.to_string())? {
+        let (branch, branch_type) = entry.map_err(|e| e.to_string())?;
+        // origin/HEAD only points at another remote branch
+        if branch.get().symbolic_target().is_some() {
+            continue;
+        }
+        branches.push(describe_branch(&branch, branch_type)?);
+    }
+    // Local before remote, then by name
+    branches.sort_by(|a, b| (a.is_remote, &a.name).cmp(&(b.is_remote, &b.name)));
+    Ok(branches)
+}
+
+pub(super) fn create_branch(
+    repo: &Repository,
+    name: &str,
+    from: Option<&str>,
+) -> Result<GitBranch, String> {
+    if !Branch::name_is_valid(name).unwrap_or(false) {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    let start = repo
+        .revparse_single(from.unwrap_or("HEAD"))
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Cannot branch from {}: {}", from.unwrap_or("HEAD"), e))?;
+    let branch = repo
+        .branch(name, &start, false)
+        .map_err(|e| e.message().to_string())?;
+    describe_branch(&branch, BranchType::Local)
+}
+
+/// Tracked files with uncommitted changes, staged or not
+pub(super) fn dirty_paths(repo: &Repository) -> Result<Vec<String>, String> {
+    let workdir = workdir(repo)?;
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| e.to_string())?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(|path| absolute_path(workdir, path)))
+        .collect())
+}
+
+/// Local branch `name`, or a new one tracking the remote branch of that name
+fn find_or_track_branch<'repo>(
+    repo: &'repo Repository,
+    name: &str,
+) -> Result<Branch<'repo>, String> {
+    if let Ok(branch) = repo.find_branch(name, BranchType::Local) {
+        return Ok(branch);
+    }
+    let remote_branch = repo
+        .branches(Some(BranchType::Remote))
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .map(|(branch, _)| branch)
+        .find(|branch| {
+            branch
+                .name()
+                .ok()
+                .flatten()
+                .and_then(|remote_name| remote_name.split_once('/'))
+                .is_some_and(|(_, branch_name)| branch_name == name)
+        })
+        .ok_or_else(|| format!("Branch not found: {}", name))?;
+    let commit = remote_branch
+        .get()
+        .peel_to_commit()
+        .map_err(|e| e.to_string())?;
+    let mut branch = repo
+        .branch(name, &commit, false)
+        .map_err(|e| e.to_string())?;
+    let upstream = remote_branch
+        .name()
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default()
+        .to_string();
+    branch
+        .set_upstream(Some(&upstream))
+        .map_err(|e| e.to_string())?;
+    Ok(branch)
+}
+
+pub(super) fn switch_branch(repo: &Repository, name: &str) -> Result<(), SwitchBranchError> {
+    let paths = dirty_paths(repo)?;
+    if !paths.is_empty() {
+        return Err(SwitchBranchError::DirtyWorkingTree {
+            message: format!(
+                "{} file(s) have uncommitted changes; commit, stash or discard them first",
+                paths.len()
+            ),
+            paths,
+        });
+    }
+
+    let branch = find_or_track_branch(repo, name)?;
+    let reference = branch
+        .get()
+        .name()
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let commit = branch.get().peel_to_commit().map_err(|e| e.to_string())?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| e.to_string())?;
+    repo.set_head(&reference).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Local and remote branches of the repository containing `root`
+#[tauri::command]
+pub async fn git_branches(root: String) -> Result<Vec<GitBranch>, String> {
+    list_branches(&open_repository(&root)?)
+}
+
+/// Create `name` at `from` (any revision, HEAD by default) without switching to it
+#[tauri::command]
+pub async fn git_create_branch(
+    root: String,
+    name: String,
+    from: Option<String>,
+) -> Result<GitBranch, String> {
+    create_branch(&open_repository(&root)?, &name, from.as_deref())
+}
+
+/// Check out branch `name`; a remote-only branch gets a local tracking branch
+#[tauri::command]
+pub async fn git_switch_branch(root: String, name: String) -> Result<(), VoidDeskError> {
+    let repo = open_repository(&root)?;
+    let _pause = file_watcher::pause_all_watchers();
+    Ok(switch_branch(&repo, &name)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    #[test]
+    fn creates_and_switches_branches_but_not_over_changes() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("a.txt"), "main\n").unwrap();
+        test_repo::commit(&repo, &["a.txt"], "init");
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let feature = create_branch(&repo, "feature", None).unwrap();
+        assert!(!feature.is_current);
+        assert!(create_branch(&repo, "bad..name", None).is_err());
+        assert!(create_branch(&repo, "feature", None).is_err());
+
+        switch_branch(&repo, "feature").unwrap();
+        fs::write(dir.join("a.txt"), "feature\n").unwrap();
+        test_repo::commit(&repo, &["a.txt"], "on feature");
+
+        fs::write(dir.join("a.txt"), "uncommitted\n").unwrap();
+        match switch_branch(&repo, &main) {
+            Err(SwitchBranchError::DirtyWorkingTree { paths, .. }) => {
+                assert!(paths[0].ends_with("a.txt"))
+            }
+            _ => panic!("expected the dirty tree to block switching"),
+        }
+
+        fs::write(dir.join("a.txt"), "feature\n").unwrap();
+        switch_branch(&repo, &main).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "main\n");
+        let branches = list_branches(&repo).unwrap();
+        assert_eq!(branches.len(), 2);
+        assert!(branches
+            .iter()
+            .any(|branch| branch.name == main && branch.is_current));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
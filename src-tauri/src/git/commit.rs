@@ -0,0 +1,244 @@
+// Committing
+// Commits the index with the identity from the user's git config, optionally amending
+// HEAD, adding a Signed-off-by trailer or signing it
+
+use git2::{Commit, Oid, Repository, RepositoryState, Signature, Tree};
+
+use super::open_repository;
+use super::signing::{commit_signer, sign_buffer, Signer};
+
+fn signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    repo.signature().map_err(|_| {
+        "No git identity configured; set user.name and user.email in your git config".to_string()
+    })
+}
+
+fn with_signoff(message: &str, signature: &Signature) -> String {
+    let trailer = format!(
+        "Signed-off-by: {} <{}>",
+        signature.name().unwrap_or_default(),
+        signature.email().unwrap_or_default()
+    );
+    if message.lines().any(|line| line.trim() == trailer) {
+        return message.to_string();
+    }
+    format!("{}\n\n{}", message.trim_end(), trailer)
+}
+
+/// Write a signed commit without moving any reference
+fn signed_commit(
+    repo: &Repository,
+    signer: &Signer,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+) -> Result<Oid, String> {
+    let buffer = repo
+        .commit_create_buffer(author, committer, message, tree, parents)
+        .map_err(|e| e.to_string())?;
+    let buffer = buffer
+        .as_str()
+        .ok_or_else(|| "The commit is not valid UTF-8 and cannot be signed".to_string())?;
+    let signature = sign_buffer(signer, buffer)?;
+    repo.commit_signed(buffer, &signature, None)
+        .map_err(|e| e.to_string())
+}
+
+/// Point HEAD, or the branch it is on, at `oid`
+fn move_head(repo: &Repository, oid: Oid, log_message: &str) -> Result<(), String> {
+    let head = repo.find_reference("HEAD").map_err(|e| e.to_string())?;
+    match head.symbolic_target() {
+        Some(branch) => repo
+            .reference(branch, oid, true, log_message)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        None => repo.set_head_detached(oid).map_err(|e| e.to_string()),
+    }
+}
+
+/// `sign` overrides commit.gpgsign; None follows the repository's config
+pub(super) fn commit_index(
+    repo: &mut Repository,
+    message: &str,
+    amend: bool,
+    signoff: bool,
+    sign: Option<bool>,
+) -> Result<Oid, String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    // Concluding a merge: its other parents are listed in MERGE_HEAD
+    let merging = repo.state() == RepositoryState::Merge;
+    let mut merge_heads = Vec::new();
+    if merging {
+        repo.mergehead_foreach(|oid| {
+            merge_heads.push(*oid);
+            true
+        })
+        .map_err(|e| e.to_string())?;
+    }
+
+    let committer = signature(repo)?;
+    let identity = format!(
+        "{} <{}>",
+        committer.name().unwrap_or_default(),
+        committer.email().unwrap_or_default()
+    );
+    let signer = commit_signer(repo, sign, &identity)?;
+    let message = if signoff {
+        with_signoff(message, &committer)
+    } else {
+        message.to_string()
+    };
+
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    if index.has_conflicts() {
+        return Err("Resolve the merge conflicts before committing".to_string());
+    }
+    let tree = repo
+        .find_tree(index.write_tree().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+    if amend {
+        let head = head.ok_or_else(|| "There is no commit to amend".to_string())?;
+        // The original author stays; the committer becomes the current user
+        if let Some(signer) = &signer {
+            let parents: Vec<_> = head.parents().collect();
+            let parent_refs: Vec<_> = parents.iter().collect();
+            let oid = signed_commit(
+                repo,
+                signer,
+                &head.author(),
+                &committer,
+                &message,
+                &tree,
+                &parent_refs,
+            )?;
+            let summary = message.lines().next().unwrap_or_default();
+            move_head(repo, oid, &format!("commit (amend): {}", summary))?;
+            return Ok(oid);
+        }
+        return head
+            .amend(
+                Some("HEAD"),
+                None,
+                Some(&committer),
+                None,
+                Some(&message),
+                Some(&tree),
+            )
+            .map_err(|e| e.to_string());
+    }
+
+    let mut parents: Vec<_> = head.into_iter().collect();
+    for oid in merge_heads {
+        parents.push(repo.find_commit(oid).map_err(|e| e.to_string())?);
+    }
+    // A merge commit may leave the tree as it is; anything else needs staged changes
+    let unchanged = match parents.first() {
+        Some(head) => head.tree_id() == tree.id(),
+        None => index.is_empty(),
+    };
+    if unchanged && !merging {
+        return Err("Nothing staged to commit".to_string());
+    }
+
+    let parent_refs: Vec<_> = parents.iter().collect();
+    let oid = match &signer {
+        Some(signer) => {
+            let oid = signed_commit(
+                repo,
+                signer,
+                &committer,
+                &committer,
+                &message,
+                &tree,
+                &parent_refs,
+            )?;
+            let summary = message.lines().next().unwrap_or_default();
+            move_head(repo, oid, &format!("commit: {}", summary))?;
+            oid
+        }
+        None => repo
+            .commit(
+                Some("HEAD"),
+                &committer,
+                &committer,
+                &message,
+                &tree,
+                &parent_refs,
+            )
+            .map_err(|e| e.to_string())?,
+    };
+    if merging {
+        repo.cleanup_state().map_err(|e| e.to_string())?;
+    }
+    Ok(oid)
+}
+
+/// Commit what is staged in the repository containing `root`; returns the new
+/// commit id. `amend` replaces HEAD instead, keeping its author. Commits are signed when
+/// commit.gpgsign is set, unless `sign` says otherwise.
+#[tauri::command]
+pub async fn git_commit(
+    root: String,
+    message: String,
+    amend: Option<bool>,
+    signoff: Option<bool>,
+    sign: Option<bool>,
+) -> Result<String, String> {
+    // Signing may wait on a passphrase prompt, so keep it off the async runtime
+    tokio::task::spawn_blocking(move || {
+        let mut repo = open_repository(&root)?;
+        let oid = commit_index(
+            &mut repo,
+            &message,
+            amend.unwrap_or(false),
+            signoff.unwrap_or(false),
+            sign,
+        )?;
+        Ok(oid.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn commits_staged_changes_and_amends() {
+        let (dir, mut repo) = test_repo::init();
+        assert!(commit_index(&mut repo, "empty", false, false, None).is_err());
+
+        fs::write(dir.join("a.txt"), "a\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        let first = commit_index(&mut repo, "  Add a  \n", false, true, None).unwrap();
+        assert_eq!(
+            repo.find_commit(first).unwrap().message().unwrap(),
+            "Add a\n\nSigned-off-by: Test <test@example.com>"
+        );
+        assert_eq!(
+            commit_index(&mut repo, "again", false, false, None).unwrap_err(),
+            "Nothing staged to commit"
+        );
+
+        let amended = commit_index(&mut repo, "Add a.txt", true, false, None).unwrap();
+        let amended = repo.find_commit(amended).unwrap();
+        assert_eq!(amended.message().unwrap(), "Add a.txt");
+        assert_eq!(amended.parent_count(), 0);
+        assert_eq!(repo.head().unwrap().target().unwrap(), amended.id());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
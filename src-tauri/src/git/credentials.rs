@@ -0,0 +1,195 @@
+// Remote credentials and progress
+// Callbacks shared by everything that talks to a remote. SSH remotes authenticate
+// through the running SSH agent; HTTPS remotes use a token passed in by the frontend or
+// else the one stored in the secrets for the remote's host (`git.token.<host>`), then
+// whatever the user's git credential helper (usually the OS keychain) has stored.
+
+use git2::{Config, Cred, CredentialType, ErrorClass, ErrorCode, RemoteCallbacks};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::error::VoidDeskError;
+use crate::secrets;
+
+pub const PROGRESS_EVENT: &str = "git-progress";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitProgressEvent {
+    /// "fetch", "pull", "push" or "clone"
+    pub operation: String,
+    pub remote: String,
+    /// Objects received (or sent, for a push) so far
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+    /// Progress text from the server, e.g. "Counting objects: 42% (21/50)"
+    pub message: Option<String>,
+}
+
+/// Commands send these as a VoidDeskError: `unauthorized`, `conflict` or `internal`
+#[derive(Debug)]
+pub enum GitRemoteError {
+    /// No credential was accepted; the UI can ask for a token and retry with it
+    AuthFailed {
+        message: String,
+    },
+    /// The remote refused the update, usually because it is not a fast-forward
+    Rejected {
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for GitRemoteError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+impl From<GitRemoteError> for VoidDeskError {
+    fn from(error: GitRemoteError) -> Self {
+        use crate::error::ErrorCode as Code;
+        match error {
+            GitRemoteError::AuthFailed { message } => Self::new(Code::Unauthorized, message),
+            GitRemoteError::Rejected { message } => Self::new(Code::Conflict, message),
+            GitRemoteError::Failed { message } => Self::internal(message),
+        }
+    }
+}
+
+impl From<git2::Error> for GitRemoteError {
+    fn from(error: git2::Error) -> Self {
+        let message = error.message().to_string();
+        match error.code() {
+            ErrorCode::Auth => Self::AuthFailed { message },
+            _ => Self::Failed { message },
+        }
+    }
+}
+
+/// Name of the secret holding the token for the host of `url`
+fn token_secret_name(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    Some(format!("{}{}", secrets::GIT_TOKEN_PREFIX, url.host_str()?))
+}
+
+/// Credential, progress and sideband callbacks for one remote operation. Each kind of
+/// credential is offered once, so a rejected one ends in an auth error instead of a loop.
+pub(super) fn remote_callbacks<'a>(
+    config: &'a Config,
+    token: Option<String>,
+    operation: &'static str,
+    remote: &'a str,
+    on_progress: &'a dyn Fn(GitProgressEvent),
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let mut token = token.filter(|token| !token.trim().is_empty());
+    let mut tried_token = false;
+    let mut tried = CredentialType::empty();
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        let username = username_from_url.unwrap_or("git");
+        if allowed.contains(CredentialType::SSH_KEY) && !tried.contains(CredentialType::SSH_KEY) {
+            tried |= CredentialType::SSH_KEY;
+            return Cred::ssh_key_from_agent(username);
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            // Hosts take a token as the password; the user name is not checked
+            if !tried_token {
+                tried_token = true;
+                let stored = || token_secret_name(url).and_then(|name| secrets::get(&name));
+                if let Some(token) = token.take().or_else(stored) {
+                    return Cred::userpass_plaintext(username, token.trim());
+                }
+            }
+            if !tried.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                tried |= CredentialType::USER_PASS_PLAINTEXT;
+                if let Ok(cred) = Cred::credential_helper(config, url, username_from_url) {
+                    return Ok(cred);
+                }
+            }
+        }
+        if allowed.contains(CredentialType::USERNAME) && !tried.contains(CredentialType::USERNAME) {
+            tried |= CredentialType::USERNAME;
+            return Cred::username(username);
+        }
+        Err(git2::Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Net,
+            format!("No accepted credentials for {}", url),
+        ))
+    });
+
+    let event = move |current: usize, total: usize, bytes: usize, message: Option<String>| {
+        GitProgressEvent {
+            operation: operation.to_string(),
+            remote: remote.to_string(),
+            current,
+            total,
+            bytes,
+            message,
+        }
+    };
+    // Transfers call back per object; only the first, the last and one per interval
+    // are worth an event
+    let throttle = |last: &mut Option<Instant>, done: bool| {
+        let due = done || last.is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL);
+        if due {
+            *last = Some(Instant::now());
+        }
+        due
+    };
+
+    let mut last_transfer = None;
+    callbacks.transfer_progress(move |stats| {
+        let done = stats.received_objects() == stats.total_objects();
+        if throttle(&mut last_transfer, done) {
+            on_progress(event(
+                stats.received_objects(),
+                stats.total_objects(),
+                stats.received_bytes(),
+                None,
+            ));
+        }
+        true
+    });
+
+    let mut last_push = None;
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        if throttle(&mut last_push, current == total) {
+            on_progress(event(current, total, bytes, None));
+        }
+    });
+
+    callbacks.sideband_progress(move |text| {
+        let text = String::from_utf8_lossy(text);
+        let text = text.trim();
+        if !text.is_empty() {
+            on_progress(event(0, 0, 0, Some(text.to_string())));
+        }
+        true
+    });
+
+    callbacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_token_secret_after_the_remote_host() {
+        assert_eq!(
+            token_secret_name("https://github.com/owner/repo.git").as_deref(),
+            Some("git.token.github.com")
+        );
+        assert_eq!(
+            token_secret_name("https://me@git.example.com:8443/team/repo").as_deref(),
+            Some("git.token.git.example.com")
+        );
+        assert_eq!(token_secret_name("git@github.com:owner/repo.git"), None);
+    }
+}
@@ -0,0 +1,253 @@
+// File diffs
+// Structured hunks for one file, either staged (HEAD against the index) or unstaged
+// (the index against the working tree), for side-by-side views and gutter markers
+
+use git2::{Delta, Diff, DiffDelta, DiffFormat, DiffOptions, Patch, Repository};
+use serde::Serialize;
+
+use super::{absolute_path, open_repository, relative_path, workdir};
+use crate::diff::{patch_hunks, DiffHunk};
+
+pub(super) use crate::diff::hunk_id;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Previous path when the file was renamed
+    pub old_path: Option<String>,
+    pub is_binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Repository-relative path a delta is about; the new path for renames
+fn delta_path(delta: &DiffDelta) -> Option<String> {
+    delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Diff containing `rel_path`. A staged diff covers the whole index so a rename's old
+/// path is there to pair with; the working tree side is limited to the one file.
+pub(super) fn diff_path<'repo>(
+    repo: &'repo Repository,
+    rel_path: &str,
+    staged: bool,
+    context_lines: u32,
+) -> Result<Diff<'repo>, String> {
+    let mut options = DiffOptions::new();
+    options
+        .context_lines(context_lines)
+        .include_untracked(true)
+        .show_untracked_content(true);
+    if !staged {
+        options.pathspec(rel_path).disable_pathspec_match(true);
+    }
+
+    let mut diff = if staged {
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+            // Nothing committed yet: everything staged is an addition
+            Err(_) => None,
+        };
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut options))
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    }
+    .map_err(|e| e.to_string())?;
+    diff.find_similar(None).map_err(|e| e.to_string())?;
+    Ok(diff)
+}
+
+pub(super) fn file_diff(
+    repo: &Repository,
+    rel_path: &str,
+    staged: bool,
+) -> Result<FileDiff, String> {
+    let workdir = workdir(repo)?;
+    let diff = diff_path(repo, rel_path, staged, 3)?;
+    let mut file_diff = FileDiff {
+        path: absolute_path(workdir, rel_path),
+        old_path: None,
+        is_binary: false,
+        hunks: Vec::new(),
+    };
+    for delta_index in 0..diff.deltas().len() {
+        let is_target = diff
+            .get_delta(delta_index)
+            .and_then(|delta| delta_path(&delta))
+            .is_some_and(|path| path == rel_path);
+        if !is_target {
+            continue;
+        }
+        let Some(patch) = Patch::from_diff(&diff, delta_index).map_err(|e| e.to_string())? else {
+            // libgit2 gives no patch for binary files
+            file_diff.is_binary = true;
+            continue;
+        };
+        let delta = patch.delta();
+        if delta.flags().is_binary() {
+            file_diff.is_binary = true;
+            continue;
+        }
+        if delta.status() == Delta::Renamed {
+            file_diff.old_path = delta
+                .old_file()
+                .path()
+                .map(|path| absolute_path(workdir, &path.to_string_lossy()));
+        }
+        file_diff.hunks.extend(patch_hunks(&patch)?);
+    }
+    Ok(file_diff)
+}
+
+/// Staged changes as a unified diff, for prompts. A per-file summary comes first so it
+/// survives when the patch itself is cut at `max_bytes`.
+pub(super) fn staged_patch_text(repo: &Repository, max_bytes: usize) -> Result<String, String> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    let mut diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, None)
+        .map_err(|e| e.to_string())?;
+    diff.find_similar(None).map_err(|e| e.to_string())?;
+    if diff.deltas().len() == 0 {
+        return Err("Nothing staged to commit".to_string());
+    }
+
+    let mut summary = String::new();
+    for delta_index in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, delta_index).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let (_, additions, deletions) = patch.line_stats().map_err(|e| e.to_string())?;
+        summary.push_str(&format!(
+            "{:?} {} (+{} -{})\n",
+            delta.status(),
+            delta_path(&delta).unwrap_or_default(),
+            additions,
+            deletions
+        ));
+    }
+
+    let mut patch = Vec::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), ' ' | '+' | '-') {
+            patch.push(line.origin() as u8);
+        }
+        patch.extend_from_slice(line.content());
+        true
+    })
+    .map_err(|e| e.to_string())?;
+    let mut patch = String::from_utf8_lossy(&patch).to_string();
+    if patch.len() > max_bytes {
+        let mut cut = max_bytes;
+        while !patch.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let cut = patch[..cut].rfind('\n').map_or(cut, |newline| newline + 1);
+        let omitted = patch.len() - cut;
+        patch.truncate(cut);
+        patch.push_str(&format!("[diff truncated, {} more bytes]\n", omitted));
+    }
+    Ok(format!("{}\n{}", summary, patch))
+}
+
+/// Staged changes of the repository containing `root` as text for the AI, capped at
+/// about `max_bytes`
+pub fn staged_patch(root: &str, max_bytes: usize) -> Result<String, String> {
+    staged_patch_text(&open_repository(root)?, max_bytes)
+}
+
+/// Hunks of the changes to `path`: staged ones when `staged`, otherwise the ones still
+/// in the working tree. Empty when the file has no such changes.
+#[tauri::command]
+pub async fn git_diff_file(path: String, staged: bool) -> Result<FileDiff, String> {
+    let repo = open_repository(&path)?;
+    let rel_path = relative_path(workdir(&repo)?, &path)?;
+    file_diff(&repo, &rel_path, staged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DiffLineKind;
+    use crate::git::test_repo;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn reports_unstaged_and_staged_hunks_with_line_numbers() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("lib.rs"), "a\nb\nc\nd\n").unwrap();
+        test_repo::commit(&repo, &["lib.rs"], "init");
+
+        fs::write(dir.join("lib.rs"), "a\nB\nc\nd\ne\n").unwrap();
+        let unstaged = file_diff(&repo, "lib.rs", false).unwrap();
+        assert!(!unstaged.is_binary);
+        assert_eq!(unstaged.hunks.len(), 1);
+        let changed: Vec<(DiffLineKind, Option<u32>, Option<u32>, &str)> = unstaged.hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.kind != DiffLineKind::Context)
+            .map(|line| {
+                (
+                    line.kind,
+                    line.old_line,
+                    line.new_line,
+                    line.content.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (DiffLineKind::Removed, Some(2), None, "b"),
+                (DiffLineKind::Added, None, Some(2), "B"),
+                (DiffLineKind::Added, None, Some(5), "e"),
+            ]
+        );
+        assert!(file_diff(&repo, "lib.rs", true).unwrap().hunks.is_empty());
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        index.write().unwrap();
+        assert_eq!(file_diff(&repo, "lib.rs", true).unwrap().hunks.len(), 1);
+        assert!(file_diff(&repo, "lib.rs", false).unwrap().hunks.is_empty());
+
+        fs::rename(dir.join("lib.rs"), dir.join("main.rs")).unwrap();
+        index.remove_path(Path::new("lib.rs")).unwrap();
+        index.add_path(Path::new("main.rs")).unwrap();
+        index.write().unwrap();
+        let renamed = file_diff(&repo, "main.rs", true).unwrap();
+        assert!(renamed.old_path.unwrap().ends_with("lib.rs"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn staged_patch_summarizes_and_caps_the_diff() {
+        let (dir, repo) = test_repo::init();
+        assert!(staged_patch_text(&repo, 1_000).is_err());
+
+        let long: String = (0..200).map(|n| format!("line {}\n", n)).collect();
+        fs::write(dir.join("long.txt"), &long).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("long.txt")).unwrap();
+        index.write().unwrap();
+
+        let full = staged_patch_text(&repo, 100_000).unwrap();
+        assert!(full.starts_with("Added long.txt (+200 -0)\n"));
+        assert!(full.contains("+line 199\n"));
+
+        let capped = staged_patch_text(&repo, 300).unwrap();
+        assert!(capped.len() < 400);
+        assert!(capped.starts_with("Added long.txt (+200 -0)\n"));
+        assert!(capped.trim_end().ends_with("more bytes]"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
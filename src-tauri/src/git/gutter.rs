@@ -0,0 +1,181 @@
+// Gutter markers
+// Line ranges the editor buffer changes relative to the index or HEAD. The buffer is
+// diffed in memory, so markers follow typing without saving the file.
+
+use git2::{DiffOptions, Patch, Repository};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{open_repository, relative_path, workdir};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GutterBase {
+    /// Like the explorer's unstaged changes; staged lines show no marker
+    #[default]
+    Index,
+    Head,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GutterKind {
+    Added,
+    Modified,
+    /// Lines were removed between `start_line - 1` and `start_line`; the range is empty
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GutterChange {
+    pub kind: GutterKind,
+    /// 1-based, inclusive, in the buffer
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Contents of `rel_path` in the index or HEAD; None when it is not there
+fn base_contents(
+    repo: &Repository,
+    rel_path: &str,
+    base: GutterBase,
+) -> Result<Option<Vec<u8>>, String> {
+    let oid = match base {
+        GutterBase::Index => {
+            let index = repo.index().map_err(|e| e.to_string())?;
+            index.get_path(Path::new(rel_path), 0).map(|entry| entry.id)
+        }
+        GutterBase::Head => repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .ok()
+            .and_then(|tree| tree.get_path(Path::new(rel_path)).ok())
+            .map(|entry| entry.id()),
+    };
+    match oid {
+        Some(oid) => Ok(Some(
+            repo.find_blob(oid)
+                .map_err(|e| e.to_string())?
+                .content()
+                .to_vec(),
+        )),
+        None => Ok(None),
+    }
+}
+
+pub(super) fn gutter_changes(
+    repo: &Repository,
+    rel_path: &str,
+    content: &str,
+    base: GutterBase,
+) -> Result<Vec<GutterChange>, String> {
+    let old = match base_contents(repo, rel_path, base)? {
+        Some(old) => old,
+        // Untracked files get no markers; a file added since HEAD is all new
+        None if base == GutterBase::Head
+            && base_contents(repo, rel_path, GutterBase::Index)?.is_some() =>
+        {
+            Vec::new()
+        }
+        None => return Ok(Vec::new()),
+    };
+
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    let patch = Patch::from_buffers(
+        &old,
+        Some(Path::new(rel_path)),
+        content.as_bytes(),
+        Some(Path::new(rel_path)),
+        Some(&mut options),
+    )
+    .map_err(|e| e.to_string())?;
+    if patch.delta().flags().is_binary() {
+        return Ok(Vec::new());
+    }
+
+    let mut changes = Vec::with_capacity(patch.num_hunks());
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(hunk_index).map_err(|e| e.to_string())?;
+        let change = if hunk.new_lines() == 0 {
+            // With no new lines, new_start is the line before the removed ones
+            GutterChange {
+                kind: GutterKind::Deleted,
+                start_line: hunk.new_start() + 1,
+                end_line: hunk.new_start(),
+            }
+        } else {
+            GutterChange {
+                kind: if hunk.old_lines() == 0 {
+                    GutterKind::Added
+                } else {
+                    GutterKind::Modified
+                },
+                start_line: hunk.new_start(),
+                end_line: hunk.new_start() + hunk.new_lines() - 1,
+            }
+        };
+        changes.push(change);
+    }
+    Ok(changes)
+}
+
+/// Changed line ranges of the editor buffer `current_content` for `path`, against the
+/// index by default or HEAD
+#[tauri::command]
+pub async fn git_file_gutter(
+    path: String,
+    current_content: String,
+    base: Option<GutterBase>,
+) -> Result<Vec<GutterChange>, String> {
+    let repo = open_repository(&path)?;
+    let rel_path = relative_path(workdir(&repo)?, &path)?;
+    gutter_changes(&repo, &rel_path, &current_content, base.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    fn change(kind: GutterKind, start_line: u32, end_line: u32) -> GutterChange {
+        GutterChange {
+            kind,
+            start_line,
+            end_line,
+        }
+    }
+
+    #[test]
+    fn reports_added_modified_and_deleted_ranges() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("a.txt"), "1\n2\n3\n4\n5\n6\n7\n8\n").unwrap();
+        test_repo::commit(&repo, &["a.txt"], "init");
+
+        let buffer = "1\nTWO\n3\n4\nnew\n5\n7\n8\n";
+        let changes = gutter_changes(&repo, "a.txt", buffer, GutterBase::Index).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                change(GutterKind::Modified, 2, 2),
+                change(GutterKind::Added, 5, 5),
+                change(GutterKind::Deleted, 7, 6),
+            ]
+        );
+
+        fs::write(dir.join("b.txt"), "b\n").unwrap();
+        assert!(gutter_changes(&repo, "b.txt", "b\nc\n", GutterBase::Index)
+            .unwrap()
+            .is_empty());
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("b.txt")).unwrap();
+        index.write().unwrap();
+        assert_eq!(
+            gutter_changes(&repo, "b.txt", "b\nc\n", GutterBase::Head).unwrap(),
+            vec![change(GutterKind::Added, 1, 2)]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
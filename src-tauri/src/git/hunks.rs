@@ -0,0 +1,279 @@
+// Partial staging
+// Staging or unstaging single hunks, or chosen lines of one, from the structured diff.
+// The file's index entry is rewritten with the selected changes applied (or reverted);
+// the working tree is never touched.
+
+use git2::{IndexEntry, IndexTime, Oid, Patch, Repository};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::diff::{diff_path, hunk_id};
+use super::{open_repository, relative_path, workdir};
+
+/// Inclusive range of positions in a hunk's `lines`, as git_diff_file returns them
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+struct HunkLine {
+    origin: char,
+    /// Raw content, line ending included
+    content: Vec<u8>,
+}
+
+struct SelectedHunk {
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+    lines: Vec<HunkLine>,
+}
+
+/// The hunk `id` of the staged or unstaged diff of `rel_path`, with the "no newline at
+/// end of file" markers left out so positions match DiffHunk::lines
+fn find_hunk(
+    repo: &Repository,
+    rel_path: &str,
+    staged: bool,
+    id: &str,
+) -> Result<SelectedHunk, String> {
+    let diff = diff_path(repo, rel_path, staged, 3)?;
+    for delta_index in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, delta_index).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        let is_target = patch
+            .delta()
+            .new_file()
+            .path()
+            .is_some_and(|path| path.to_string_lossy().replace('\\', "/") == rel_path);
+        if !is_target {
+            continue;
+        }
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_index).map_err(|e| e.to_string())?;
+            let this_id = hunk_id(
+                hunk.old_start(),
+                hunk.old_lines(),
+                hunk.new_start(),
+                hunk.new_lines(),
+            );
+            if this_id != id {
+                continue;
+            }
+            let mut lines = Vec::with_capacity(line_count);
+            for line_index in 0..line_count {
+                let line = patch
+                    .line_in_hunk(hunk_index, line_index)
+                    .map_err(|e| e.to_string())?;
+                if matches!(line.origin(), ' ' | '+' | '-') {
+                    lines.push(HunkLine {
+                        origin: line.origin(),
+                        content: line.content().to_vec(),
+                    });
+                }
+            }
+            return Ok(SelectedHunk {
+                old_start: hunk.old_start() as usize,
+                old_lines: hunk.old_lines() as usize,
+                new_start: hunk.new_start() as usize,
+                new_lines: hunk.new_lines() as usize,
+                lines,
+            });
+        }
+    }
+    Err("The change was not found; the file changed since the diff was shown".to_string())
+}
+
+/// `base` with the selected lines of `hunk` applied. Staging applies the unstaged hunk
+/// to the index contents (its old side); unstaging reverts the staged hunk from the
+/// index contents (its new side), so added and removed swap roles.
+fn apply_hunk(
+    base: &[u8],
+    hunk: &SelectedHunk,
+    selected: impl Fn(usize) -> bool,
+    reverse: bool,
+) -> Vec<u8> {
+    let base_lines: Vec<&[u8]> = base.split_inclusive(|&byte| byte == b'\n').collect();
+    let (start, count, base_origin, other_origin) = if reverse {
+        (hunk.new_start, hunk.new_lines, '+', '-')
+    } else {
+        (hunk.old_start, hunk.old_lines, '-', '+')
+    };
+    // An empty side's start is the line before the change rather than its first line
+    let start = if count == 0 { start } else { start - 1 };
+
+    let mut result = Vec::with_capacity(base.len());
+    let mut position = start.min(base_lines.len());
+    for line in &base_lines[..position] {
+        result.extend_from_slice(line);
+    }
+    for (index, line) in hunk.lines.iter().enumerate() {
+        if line.origin == ' ' {
+            if let Some(base_line) = base_lines.get(position) {
+                result.extend_from_slice(base_line);
+            }
+            position += 1;
+        } else if line.origin == base_origin {
+            if !selected(index) {
+                if let Some(base_line) = base_lines.get(position) {
+                    result.extend_from_slice(base_line);
+                }
+            }
+            position += 1;
+        } else if line.origin == other_origin && selected(index) {
+            result.extend_from_slice(&line.content);
+        }
+    }
+    for line in base_lines.iter().skip(position) {
+        result.extend_from_slice(line);
+    }
+    result
+}
+
+pub(super) fn apply_to_index(
+    repo: &Repository,
+    rel_path: &str,
+    id: &str,
+    lines: Option<&[LineRange]>,
+    unstage: bool,
+) -> Result<(), String> {
+    let hunk = find_hunk(repo, rel_path, unstage, id)?;
+    let selected = |index: usize| {
+        lines.is_none_or(|ranges| {
+            ranges
+                .iter()
+                .any(|range| range.start <= index && index <= range.end)
+        })
+    };
+
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    let existing = index.get_path(Path::new(rel_path), 0);
+    let base = match &existing {
+        Some(entry) => repo
+            .find_blob(entry.id)
+            .map_err(|e| e.to_string())?
+            .content()
+            .to_vec(),
+        None => Vec::new(),
+    };
+    let content = apply_hunk(&base, &hunk, selected, unstage);
+
+    // Unstaging all of a newly added file takes it out of the index again
+    let in_head = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .is_ok_and(|tree| tree.get_path(Path::new(rel_path)).is_ok());
+    if unstage && content.is_empty() && !in_head {
+        index
+            .remove_path(Path::new(rel_path))
+            .map_err(|e| e.to_string())?;
+        return index.write().map_err(|e| e.to_string());
+    }
+
+    let entry = existing.unwrap_or_else(|| IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: rel_path.as_bytes().to_vec(),
+    });
+    index
+        .add_frombuffer(&entry, &content)
+        .map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())
+}
+
+/// Stage hunk `hunk_id` of the unstaged changes to `path`, or only the given lines of it
+#[tauri::command]
+pub async fn git_stage_hunk(
+    path: String,
+    hunk_id: String,
+    lines: Option<Vec<LineRange>>,
+) -> Result<(), String> {
+    let repo = open_repository(&path)?;
+    let rel_path = relative_path(workdir(&repo)?, &path)?;
+    apply_to_index(&repo, &rel_path, &hunk_id, lines.as_deref(), false)
+}
+
+/// Unstage hunk `hunk_id` of the staged changes to `path`, or only the given lines of it
+#[tauri::command]
+pub async fn git_unstage_hunk(
+    path: String,
+    hunk_id: String,
+    lines: Option<Vec<LineRange>>,
+) -> Result<(), String> {
+    let repo = open_repository(&path)?;
+    let rel_path = relative_path(workdir(&repo)?, &path)?;
+    apply_to_index(&repo, &rel_path, &hunk_id, lines.as_deref(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::file_diff;
+    use crate::git::test_repo;
+    use std::fs;
+
+    fn staged_content(repo: &Repository, rel_path: &str) -> String {
+        let index = repo.index().unwrap();
+        let entry = index.get_path(Path::new(rel_path), 0).unwrap();
+        String::from_utf8(repo.find_blob(entry.id).unwrap().content().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn stages_and_unstages_hunks_and_lines() {
+        let (dir, repo) = test_repo::init();
+        let original: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        fs::write(dir.join("a.txt"), &original).unwrap();
+        test_repo::commit(&repo, &["a.txt"], "init");
+
+        let edited = original
+            .replace("\n2\n", "\ntwo\n")
+            .replace("\n18\n", "\neighteen\n");
+        fs::write(dir.join("a.txt"), &edited).unwrap();
+        let hunks = file_diff(&repo, "a.txt", false).unwrap().hunks;
+        assert_eq!(hunks.len(), 2);
+
+        // Only the second hunk
+        apply_to_index(&repo, "a.txt", &hunks[1].id, None, false).unwrap();
+        assert_eq!(
+            staged_content(&repo, "a.txt"),
+            original.replace("\n18\n", "\neighteen\n")
+        );
+        assert_eq!(file_diff(&repo, "a.txt", false).unwrap().hunks.len(), 1);
+
+        // Only the added line of the first hunk: "two" goes in, "2" stays
+        let first = &file_diff(&repo, "a.txt", false).unwrap().hunks[0];
+        let added = first
+            .lines
+            .iter()
+            .position(|line| line.content == "two")
+            .unwrap();
+        let range = [LineRange {
+            start: added,
+            end: added,
+        }];
+        apply_to_index(&repo, "a.txt", &first.id, Some(&range), false).unwrap();
+        assert!(staged_content(&repo, "a.txt").starts_with("1\n2\ntwo\n3\n"));
+
+        // Unstaging the staged second hunk restores line 18 in the index
+        let staged = file_diff(&repo, "a.txt", true).unwrap().hunks;
+        let last = staged.last().unwrap();
+        apply_to_index(&repo, "a.txt", &last.id, None, true).unwrap();
+        assert!(staged_content(&repo, "a.txt").contains("\n18\n"));
+        assert!(apply_to_index(&repo, "a.txt", "1,1,1,1", None, false).is_err());
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), edited);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
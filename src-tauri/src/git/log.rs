@@ -0,0 +1,190 @@
+// Commit history
+// Recent commits with the files each one touched, for the History panel, or only the
+// commits touching one file for its timeline
+
+use git2::{Commit, DiffOptions, Patch, Repository, Sort};
+use serde::Serialize;
+
+use super::status::{delta_kind, GitChangeKind};
+use super::{absolute_path, open_repository, relative_path, workdir};
+
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitCommitFile {
+    pub path: String,
+    pub kind: GitChangeKind,
+    /// Previous path of a renamed file
+    pub old_path: Option<String>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitCommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date as a Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub subject: String,
+    pub parents: Vec<String>,
+    /// Compared with the first parent
+    pub files: Vec<GitCommitFile>,
+}
+
+/// Files changed by `commit` relative to its first parent, limited to `pathspec`
+fn changed_files(
+    repo: &Repository,
+    commit: &Commit,
+    pathspec: Option<&str>,
+) -> Result<Vec<GitCommitFile>, String> {
+    let workdir = workdir(repo)?;
+    let tree = commit.tree().map_err(|e| e.to_string())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    let mut options = DiffOptions::new();
+    if let Some(pathspec) = pathspec {
+        options.pathspec(pathspec).disable_pathspec_match(true);
+    }
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))
+        .map_err(|e| e.to_string())?;
+    if pathspec.is_none() {
+        diff.find_similar(None).map_err(|e| e.to_string())?;
+    }
+
+    let mut files = Vec::with_capacity(diff.deltas().len());
+    for (delta_index, delta) in diff.deltas().enumerate() {
+        let Some(kind) = delta_kind(delta.status()) else {
+            continue;
+        };
+        let Some(rel_path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+            continue;
+        };
+        let (additions, deletions) = match Patch::from_diff(&diff, delta_index) {
+            Ok(Some(patch)) => patch
+                .line_stats()
+                .map(|(_, additions, deletions)| (additions, deletions))
+                .unwrap_or_default(),
+            _ => (0, 0),
+        };
+        files.push(GitCommitFile {
+            path: absolute_path(workdir, &rel_path.to_string_lossy()),
+            kind,
+            old_path: (kind == GitChangeKind::Renamed)
+                .then(|| delta.old_file().path())
+                .flatten()
+                .map(|path| absolute_path(workdir, &path.to_string_lossy())),
+            additions,
+            deletions,
+        });
+    }
+    Ok(files)
+}
+
+/// Newest first from HEAD; with `rel_path`, only commits that changed that path
+pub(super) fn read_log(
+    repo: &Repository,
+    limit: usize,
+    rel_path: Option<&str>,
+) -> Result<Vec<GitCommitInfo>, String> {
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    if revwalk.push_head().is_err() {
+        // No commits yet
+        return Ok(Vec::new());
+    }
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .map_err(|e| e.to_string())?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+        let commit = repo
+            .find_commit(oid.map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+        let files = changed_files(repo, &commit, rel_path)?;
+        if rel_path.is_some() && files.is_empty() {
+            continue;
+        }
+        let author = commit.author();
+        let id = commit.id().to_string();
+        commits.push(GitCommitInfo {
+            short_id: id[..7].to_string(),
+            id,
+            author_name: author.name().unwrap_or_default().to_string(),
+            author_email: author.email().unwrap_or_default().to_string(),
+            timestamp: author.when().seconds() * 1000,
+            subject: commit.summary().unwrap_or_default().to_string(),
+            parents: commit.parent_ids().map(|oid| oid.to_string()).collect(),
+            files,
+        });
+    }
+    Ok(commits)
+}
+
+/// Recent commits of the repository containing `root`, or only those that changed
+/// `path` when given. Renames of `path` are not followed.
+#[tauri::command]
+pub async fn git_log(
+    root: String,
+    limit: Option<usize>,
+    path: Option<String>,
+) -> Result<Vec<GitCommitInfo>, String> {
+    let repo = open_repository(&root)?;
+    let rel_path = match &path {
+        Some(path) => Some(relative_path(workdir(&repo)?, path)?),
+        None => None,
+    };
+    read_log(
+        &repo,
+        limit.unwrap_or(DEFAULT_LOG_LIMIT),
+        rel_path.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    #[test]
+    fn lists_commits_with_file_stats_and_filters_by_path() {
+        let (dir, repo) = test_repo::init();
+        assert!(read_log(&repo, 10, None).unwrap().is_empty());
+
+        fs::write(dir.join("a.txt"), "1\n2\n").unwrap();
+        fs::write(dir.join("b.txt"), "b\n").unwrap();
+        test_repo::commit(&repo, &["a.txt", "b.txt"], "Add files\n\nWith a body");
+        fs::write(dir.join("a.txt"), "1\ntwo\n3\n").unwrap();
+        test_repo::commit(&repo, &["a.txt"], "Edit a");
+        fs::write(dir.join("b.txt"), "bee\n").unwrap();
+        test_repo::commit(&repo, &["b.txt"], "Edit b");
+
+        let log = read_log(&repo, 10, None).unwrap();
+        let subjects: Vec<&str> = log.iter().map(|commit| commit.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["Edit b", "Edit a", "Add files"]);
+        assert_eq!(log[1].files.len(), 1);
+        assert_eq!(log[1].files[0].kind, GitChangeKind::Modified);
+        assert_eq!(
+            (log[1].files[0].additions, log[1].files[0].deletions),
+            (2, 1)
+        );
+        assert_eq!(log[2].files.len(), 2);
+        assert_eq!(log[2].parents.len(), 0);
+
+        let a_log = read_log(&repo, 10, Some("a.txt")).unwrap();
+        let subjects: Vec<&str> = a_log.iter().map(|commit| commit.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["Edit a", "Add files"]);
+        assert_eq!(read_log(&repo, 1, None).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
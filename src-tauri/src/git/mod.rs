@@ -0,0 +1,137 @@
+// Git integration
+// Source control through libgit2, so the explorer, editor and AI get repository state
+// without shelling out to a git binary
+
+mod blame;
+mod branches;
+mod commit;
+mod credentials;
+mod diff;
+mod gutter;
+mod hunks;
+mod log;
+mod remotes;
+mod setup;
+mod signing;
+mod staging;
+mod status;
+mod submodules;
+mod sync;
+mod worktrees;
+
+use git2::Repository;
+use std::path::Path;
+
+use crate::commands::workspace_roots;
+
+pub use blame::git_blame;
+pub use branches::{git_branches, git_create_branch, git_switch_branch};
+pub use commit::git_commit;
+pub use diff::git_diff_file;
+pub(crate) use diff::staged_patch;
+pub use gutter::git_file_gutter;
+pub use hunks::{git_stage_hunk, git_unstage_hunk};
+pub use log::git_log;
+pub use remotes::git_remotes;
+pub use setup::{git_clone, git_init};
+pub use staging::{git_discard, git_stage, git_unstage};
+pub use status::git_status;
+pub use submodules::git_submodules;
+pub use sync::{git_fetch, git_pull, git_push};
+pub use worktrees::{git_add_worktree, git_remove_worktree, git_worktrees};
+
+/// Open the repository containing `path`, which may be any folder or file inside it
+fn open_repository(path: &str) -> Result<Repository, String> {
+    workspace_roots::ensure_in_workspace(path)?;
+    Repository::discover(path).map_err(|e| format!("Not a git repository: {}", e.message()))
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, String> {
+    repo.workdir()
+        .ok_or_else(|| "Bare repositories are not supported".to_string())
+}
+
+/// Open the repository the paths belong to and make them repository-relative
+fn open_repository_for_paths(paths: &[String]) -> Result<(Repository, Vec<String>), String> {
+    let first = paths.first().ok_or_else(|| "No paths given".to_string())?;
+    for path in paths {
+        workspace_roots::ensure_in_workspace(path)?;
+    }
+    let repo = open_repository(first)?;
+    let workdir = workdir(&repo)?;
+    let rel_paths = paths
+        .iter()
+        .map(|path| relative_path(workdir, path))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((repo, rel_paths))
+}
+
+/// `path` relative to the repository root, with forward slashes as git stores it
+fn relative_path(workdir: &Path, path: &str) -> Result<String, String> {
+    let path = Path::new(path);
+    let rel_path = match path.strip_prefix(workdir) {
+        Ok(rel_path) => rel_path.to_path_buf(),
+        // The workdir is canonical, the given path may not be (symlinks, /tmp on macOS)
+        Err(_) => {
+            let canonical_workdir = workdir.canonicalize().map_err(|e| e.to_string())?;
+            let canonical_path = path
+                .parent()
+                .and_then(|parent| parent.canonicalize().ok())
+                .zip(path.file_name())
+                .map(|(parent, name)| parent.join(name))
+                .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+            canonical_path
+                .strip_prefix(&canonical_workdir)
+                .map_err(|_| format!("Not inside the repository: {}", path.display()))?
+                .to_path_buf()
+        }
+    };
+    Ok(rel_path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Absolute path of a repository-relative path, as the frontend uses everywhere else
+fn absolute_path(workdir: &Path, rel_path: &str) -> String {
+    workdir.join(rel_path).to_string_lossy().to_string()
+}
+
+/// Throwaway repositories for the git tests
+#[cfg(test)]
+mod test_repo {
+    use git2::{Oid, Repository};
+    use std::path::{Path, PathBuf};
+
+    pub fn init() -> (PathBuf, Repository) {
+        let dir = std::env::temp_dir().join(format!("voidesk-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        (dir, repo)
+    }
+
+    /// Stage the given files and commit them on top of HEAD
+    pub fn commit(repo: &Repository, paths: &[&str], message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        for path in paths {
+            if repo.workdir().unwrap().join(path).exists() {
+                index.add_path(Path::new(path)).unwrap();
+            } else {
+                index.remove_path(Path::new(path)).unwrap();
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+}
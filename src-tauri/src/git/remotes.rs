@@ -0,0 +1,155 @@
+// Remotes
+// Configured remotes and how far the current branch is ahead of or behind each of them,
+// for the status bar's "↑2 ↓1" and the prompt to sync
+
+use git2::{BranchType, Oid, Repository};
+use serde::Serialize;
+
+use super::open_repository;
+use super::status::current_branch;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitRemote {
+    pub name: String,
+    pub url: Option<String>,
+    /// Only set when it differs from `url`
+    pub push_url: Option<String>,
+    /// Commits on the current branch missing from the remote's branch of the same name;
+    /// None when the remote has no such branch (as of the last fetch)
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitRemotes {
+    pub branch: Option<String>,
+    /// e.g. "origin/main"
+    pub upstream: Option<String>,
+    /// Counts against the upstream
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub remotes: Vec<GitRemote>,
+}
+
+fn ahead_behind(repo: &Repository, local: Oid, reference: &str) -> Option<(usize, usize)> {
+    let remote = repo.refname_to_id(reference).ok()?;
+    repo.graph_ahead_behind(local, remote).ok()
+}
+
+pub(super) fn read_remotes(repo: &Repository) -> Result<GitRemotes, String> {
+    let branch = current_branch(repo);
+    let head = repo.head().ok().and_then(|head| head.target());
+
+    let upstream = branch.as_deref().and_then(|branch| {
+        repo.find_branch(branch, BranchType::Local)
+            .ok()?
+            .upstream()
+            .ok()?
+            .name()
+            .ok()
+            .flatten()
+            .map(str::to_string)
+    });
+    let upstream_counts = match (head, &upstream) {
+        (Some(head), Some(upstream)) => {
+            ahead_behind(repo, head, &format!("refs/remotes/{}", upstream))
+        }
+        _ => None,
+    };
+
+    let mut remotes = Vec::new();
+    for name in repo.remotes().map_err(|e| e.to_string())?.iter().flatten() {
+        let remote = repo.find_remote(name).map_err(|e| e.to_string())?;
+        let url = remote.url().map(str::to_string);
+        let push_url = remote
+            .pushurl()
+            .map(str::to_string)
+            .filter(|push_url| Some(push_url) != url.as_ref());
+        let counts = match (head, &branch) {
+            (Some(head), Some(branch)) => {
+                ahead_behind(repo, head, &format!("refs/remotes/{}/{}", name, branch))
+            }
+            _ => None,
+        };
+        remotes.push(GitRemote {
+            name: name.to_string(),
+            url,
+            push_url,
+            ahead: counts.map(|(ahead, _)| ahead),
+            behind: counts.map(|(_, behind)| behind),
+        });
+    }
+
+    Ok(GitRemotes {
+        branch,
+        upstream,
+        ahead: upstream_counts.map(|(ahead, _)| ahead),
+        behind: upstream_counts.map(|(_, behind)| behind),
+        remotes,
+    })
+}
+
+/// Remotes of the repository containing `root` with ahead/behind counts for the current
+/// branch. Counts use the remote-tracking refs, so they are as fresh as the last fetch.
+#[tauri::command]
+pub async fn git_remotes(root: String) -> Result<GitRemotes, String> {
+    read_remotes(&open_repository(&root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    #[test]
+    fn counts_commits_ahead_and_behind_the_upstream() {
+        let (dir, repo) = test_repo::init();
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        fs::write(dir.join("a.txt"), "1\n").unwrap();
+        let base = test_repo::commit(&repo, &["a.txt"], "one");
+        let branch = current_branch(&repo).unwrap();
+
+        let unpublished = read_remotes(&repo).unwrap();
+        assert_eq!(unpublished.remotes.len(), 1);
+        assert_eq!(unpublished.remotes[0].ahead, None);
+        assert_eq!(unpublished.upstream, None);
+
+        // Pretend the remote has a commit we lack, and we have two it lacks
+        fs::write(dir.join("a.txt"), "remote\n").unwrap();
+        let remote_commit = test_repo::commit(&repo, &["a.txt"], "remote");
+        repo.reference(
+            &format!("refs/remotes/origin/{}", branch),
+            remote_commit,
+            true,
+            "test",
+        )
+        .unwrap();
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.reset(base_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        for n in 2..4 {
+            fs::write(dir.join("a.txt"), format!("{}\n", n)).unwrap();
+            test_repo::commit(&repo, &["a.txt"], "local");
+        }
+        repo.find_branch(&branch, BranchType::Local)
+            .unwrap()
+            .set_upstream(Some(&format!("origin/{}", branch)))
+            .unwrap();
+
+        let info = read_remotes(&repo).unwrap();
+        assert_eq!(info.upstream, Some(format!("origin/{}", branch)));
+        assert_eq!((info.ahead, info.behind), (Some(2), Some(1)));
+        assert_eq!(
+            (info.remotes[0].ahead, info.remotes[0].behind),
+            (Some(2), Some(1))
+        );
+        assert_eq!(
+            info.remotes[0].url.as_deref(),
+            Some("https://example.com/repo.git")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,146 @@
+// Starting repositories
+// `git init` and `git clone` for the welcome screen. The target is a folder the user
+// just picked, usually before any workspace is open, so unlike the other git commands
+// these do not require it to be inside one.
+
+use git2::build::RepoBuilder;
+use git2::{Config, FetchOptions, Repository, RepositoryInitOptions};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use super::credentials::{remote_callbacks, GitProgressEvent, GitRemoteError, PROGRESS_EVENT};
+use crate::error::VoidDeskError;
+
+fn canonical_root(repo: &Repository) -> Result<String, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Bare repositories are not supported".to_string())?;
+    let workdir = workdir.canonicalize().map_err(|e| e.to_string())?;
+    Ok(workdir.to_string_lossy().to_string())
+}
+
+pub(super) fn init_repository(path: &Path, initial_branch: Option<&str>) -> Result<String, String> {
+    if Repository::open(path).is_ok() {
+        return Err(format!("Already a git repository: {}", path.display()));
+    }
+    let mut options = RepositoryInitOptions::new();
+    options.mkpath(true);
+    if let Some(branch) = initial_branch {
+        options.initial_head(branch);
+    }
+    let repo = Repository::init_opts(path, &options).map_err(|e| e.message().to_string())?;
+    canonical_root(&repo)
+}
+
+pub(super) fn clone_repository(
+    url: &str,
+    dest: &Path,
+    branch: Option<&str>,
+    token: Option<String>,
+    on_progress: &dyn Fn(GitProgressEvent),
+) -> Result<String, GitRemoteError> {
+    let existed = dest.exists();
+    if existed {
+        let empty = fs::read_dir(dest)
+            .map_err(|e| e.to_string())?
+            .next()
+            .is_none();
+        if !empty {
+            return Err(format!("{} already exists and is not empty", dest.display()).into());
+        }
+    }
+
+    // No repository yet, so credential helpers come from the global git config
+    let config = Config::open_default()?;
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(&config, token, "clone", url, on_progress));
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(fetch_options);
+    if let Some(branch) = branch {
+        builder.branch(branch);
+    }
+
+    match builder.clone(url, dest) {
+        Ok(repo) => Ok(canonical_root(&repo)?),
+        Err(error) => {
+            // Leave nothing half-cloned behind
+            if existed {
+                if let Ok(entries) = fs::read_dir(dest) {
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        let _ = if path.is_dir() {
+                            fs::remove_dir_all(&path)
+                        } else {
+                            fs::remove_file(&path)
+                        };
+                    }
+                }
+            } else {
+                let _ = fs::remove_dir_all(dest);
+            }
+            Err(error.into())
+        }
+    }
+}
+
+/// Create a repository in `path` (created if missing); returns its canonical path
+#[tauri::command]
+pub async fn git_init(path: String, initial_branch: Option<String>) -> Result<String, String> {
+    init_repository(Path::new(&path), initial_branch.as_deref())
+}
+
+/// Clone `url` into `dest`, which must not exist or be empty; returns the canonical
+/// path to open as a workspace. Progress arrives as "git-progress" events.
+#[tauri::command]
+pub async fn git_clone(
+    app: AppHandle,
+    url: String,
+    dest: String,
+    branch: Option<String>,
+    token: Option<String>,
+) -> Result<String, VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let emit = |event: GitProgressEvent| {
+            let _ = app.emit(PROGRESS_EVENT, event);
+        };
+        clone_repository(&url, Path::new(&dest), branch.as_deref(), token, &emit)
+    })
+    .await??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn initializes_and_clones() {
+        let dir = std::env::temp_dir().join(format!("voidesk-git-{}", uuid::Uuid::new_v4()));
+        let root = init_repository(&dir.join("fresh"), Some("trunk")).unwrap();
+        let fresh = Repository::open(&root).unwrap();
+        assert_eq!(
+            fresh.find_reference("HEAD").unwrap().symbolic_target(),
+            Some("refs/heads/trunk")
+        );
+        assert!(init_repository(Path::new(&root), None).is_err());
+
+        let (source_dir, source) = test_repo::init();
+        fs::write(source_dir.join("a.txt"), "a\n").unwrap();
+        test_repo::commit(&source, &["a.txt"], "init");
+
+        let dest = dir.join("clone");
+        let on_progress = |_: GitProgressEvent| {};
+        let url = source_dir.to_string_lossy().to_string();
+        let cloned = clone_repository(&url, &dest, None, None, &on_progress).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "a\n");
+        let cloned = Repository::open(cloned).unwrap();
+        assert!(cloned.find_remote("origin").is_ok());
+
+        assert!(clone_repository(&url, &dest, None, None, &on_progress).is_err());
+        assert!(dest.join("a.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&source_dir);
+    }
+}
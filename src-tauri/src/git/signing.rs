@@ -0,0 +1,258 @@
+// Commit signing
+// Signs commits the way git does, from commit.gpgsign, gpg.format and user.signingkey:
+// the commit buffer goes to gpg, gpgsm or ssh-keygen on stdin and the detached signature
+// is stored in the commit's gpgsig header
+
+use git2::{Config, Repository};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl SigningFormat {
+    fn config_name(self) -> &'static str {
+        match self {
+            Self::OpenPgp => "openpgp",
+            Self::X509 => "x509",
+            Self::Ssh => "ssh",
+        }
+    }
+
+    fn default_program(self) -> &'static str {
+        match self {
+            Self::OpenPgp => "gpg",
+            Self::X509 => "gpgsm",
+            Self::Ssh => "ssh-keygen",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Signer {
+    format: SigningFormat,
+    program: String,
+    /// user.signingkey; for OpenPGP and X.509 it falls back to the committer identity
+    key: String,
+}
+
+/// The signer to use for a new commit, or None when it should not be signed. `sign`
+/// overrides commit.gpgsign.
+pub(super) fn commit_signer(
+    repo: &Repository,
+    sign: Option<bool>,
+    committer: &str,
+) -> Result<Option<Signer>, String> {
+    let config = repo.config().map_err(|e| e.to_string())?;
+    let enabled = sign.unwrap_or_else(|| config.get_bool("commit.gpgsign").unwrap_or(false));
+    if !enabled {
+        return Ok(None);
+    }
+
+    let format = match config.get_string("gpg.format").ok().as_deref() {
+        None | Some("openpgp") => SigningFormat::OpenPgp,
+        Some("x509") => SigningFormat::X509,
+        Some("ssh") => SigningFormat::Ssh,
+        Some(other) => return Err(format!("Unsupported gpg.format \"{}\"", other)),
+    };
+    let program = program_for(&config, format);
+    let key = match config.get_string("user.signingkey") {
+        Ok(key) if !key.trim().is_empty() => key.trim().to_string(),
+        _ if format == SigningFormat::Ssh => {
+            return Err(
+                "SSH signing needs user.signingkey set to a key file or public key".to_string(),
+            )
+        }
+        _ => committer.to_string(),
+    };
+    Ok(Some(Signer {
+        format,
+        program,
+        key,
+    }))
+}
+
+fn program_for(config: &Config, format: SigningFormat) -> String {
+    let specific = format!("gpg.{}.program", format.config_name());
+    config
+        .get_string(&specific)
+        .ok()
+        .or_else(|| {
+            // The older gpg.program still applies to OpenPGP
+            (format == SigningFormat::OpenPgp)
+                .then(|| config.get_string("gpg.program").ok())
+                .flatten()
+        })
+        .unwrap_or_else(|| format.default_program().to_string())
+}
+
+/// A literal "ssh-ed25519 AAAA..." key rather than a path to one
+fn is_literal_ssh_key(key: &str) -> bool {
+    key.starts_with("key::") || key.starts_with("ssh-") || key.starts_with("ecdsa-")
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Detached, armored signature of `buffer`
+pub(super) fn sign_buffer(signer: &Signer, buffer: &str) -> Result<String, String> {
+    let mut literal_key_file = None;
+    let mut command = Command::new(&signer.program);
+    match signer.format {
+        SigningFormat::OpenPgp | SigningFormat::X509 => {
+            command.args(["--status-fd=2", "-bsau", &signer.key]);
+        }
+        SigningFormat::Ssh => {
+            command.args(["-Y", "sign", "-n", "git", "-f"]);
+            if is_literal_ssh_key(&signer.key) {
+                // ssh-keygen takes the public key from a file and the private one from the agent
+                let path = std::env::temp_dir()
+                    .join(format!("voidesk-signingkey-{}.pub", uuid::Uuid::new_v4()));
+                let key = signer.key.strip_prefix("key::").unwrap_or(&signer.key);
+                fs::write(&path, format!("{}\n", key)).map_err(|e| e.to_string())?;
+                command.arg(&path).arg("-U");
+                literal_key_file = Some(path);
+            } else {
+                command.arg(expand_home(&signer.key));
+            }
+        }
+    }
+
+    let result = run_signer(command, buffer, signer);
+    if let Some(path) = literal_key_file {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+fn run_signer(mut command: Command, buffer: &str, signer: &Signer) -> Result<String, String> {
+    let setting = match signer.format {
+        SigningFormat::Ssh => "gpg.ssh.program",
+        SigningFormat::X509 => "gpg.x509.program",
+        SigningFormat::OpenPgp => "gpg.program",
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => format!(
+                "Commit signing is enabled but {} was not found; install it or set {}",
+                signer.program, setting
+            ),
+            _ => format!("Could not start {}: {}", signer.program, e),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(buffer.as_bytes())
+            .map_err(|e| format!("Could not send the commit to {}: {}", signer.program, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // gpg can exit cleanly without signing, e.g. when the passphrase prompt is dismissed
+    let created = match signer.format {
+        SigningFormat::Ssh => true,
+        _ => stderr.contains("[GNUPG:] SIG_CREATED "),
+    };
+    let signature = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || !created || signature.trim().is_empty() {
+        let detail = stderr
+            .lines()
+            .filter(|line| !line.starts_with("[GNUPG:]") && !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(if detail.is_empty() {
+            format!("Signing the commit with {} failed", signer.program)
+        } else {
+            format!(
+                "Signing the commit with {} failed: {}",
+                signer.program, detail
+            )
+        });
+    }
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::commit::commit_index;
+    use crate::git::test_repo;
+    use std::path::Path;
+
+    fn stage(repo: &Repository, dir: &Path, name: &str) {
+        fs::write(dir.join(name), format!("{}\n", name)).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+    }
+
+    #[test]
+    fn signs_commits_from_the_repository_config() {
+        let (dir, mut repo) = test_repo::init();
+        let mut config = repo.config().unwrap();
+        config.set_bool("commit.gpgsign", true).unwrap();
+        config
+            .set_str("gpg.program", "voidesk-missing-gpg")
+            .unwrap();
+
+        stage(&repo, &dir, "a.txt");
+        let error = commit_index(&mut repo, "Add a", false, false, None).unwrap_err();
+        assert!(
+            error.contains("voidesk-missing-gpg was not found"),
+            "{}",
+            error
+        );
+        // Explicitly unsigned commits ignore commit.gpgsign
+        commit_index(&mut repo, "Add a", false, false, Some(false)).unwrap();
+
+        config.set_str("gpg.format", "ssh").unwrap();
+        stage(&repo, &dir, "b.txt");
+        let error = commit_index(&mut repo, "Add b", false, false, None).unwrap_err();
+        assert!(error.contains("user.signingkey"), "{}", error);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let script = dir.join("fake-gpg.sh");
+            fs::write(
+                &script,
+                "#!/bin/sh\ncat >/dev/null\necho '[GNUPG:] SIG_CREATED D' >&2\necho FAKE-SIGNATURE\n",
+            )
+            .unwrap();
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+            config.set_str("gpg.format", "openpgp").unwrap();
+            config
+                .set_str("gpg.program", &script.to_string_lossy())
+                .unwrap();
+
+            let signed = commit_index(&mut repo, "Add b", false, false, None).unwrap();
+            assert_eq!(repo.head().unwrap().target(), Some(signed));
+            let (signature, _) = repo.extract_signature(&signed, None).unwrap();
+            assert_eq!(signature.as_str(), Some("FAKE-SIGNATURE\n"));
+
+            let amended = commit_index(&mut repo, "Add b.txt", true, false, None).unwrap();
+            let commit = repo.find_commit(amended).unwrap();
+            assert_eq!(commit.message(), Some("Add b.txt"));
+            assert_eq!(commit.parent_count(), 1);
+            assert!(repo.extract_signature(&amended, None).is_ok());
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
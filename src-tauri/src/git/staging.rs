@@ -0,0 +1,210 @@
+// Staging
+// Moving changes between the working tree and the index, and throwing working tree
+// changes away. Discarding is destructive, so it previews by default and snapshots
+// every file into local history before touching it.
+
+use git2::build::CheckoutBuilder;
+use git2::{IndexAddOption, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::fs;
+
+use super::{absolute_path, open_repository_for_paths, workdir};
+use crate::commands::local_history;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscardPlan {
+    /// Files restored to their staged (or committed) contents
+    pub restored: Vec<String>,
+    /// Untracked files that are deleted
+    pub deleted: Vec<String>,
+    /// False for a preview; nothing was changed
+    pub applied: bool,
+}
+
+pub(super) fn stage_paths(repo: &Repository, rel_paths: &[String]) -> Result<(), String> {
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    // add_all picks up new and modified files, update_all the deleted ones
+    index
+        .add_all(
+            rel_paths,
+            IndexAddOption::DEFAULT | IndexAddOption::DISABLE_PATHSPEC_MATCH,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+    index
+        .update_all(rel_paths, None)
+        .map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())
+}
+
+pub(super) fn unstage_paths(repo: &Repository, rel_paths: &[String]) -> Result<(), String> {
+    match repo.head().and_then(|head| head.peel_to_commit()) {
+        Ok(head) => repo
+            .reset_default(Some(head.as_object()), rel_paths)
+            .map_err(|e| e.to_string()),
+        // Nothing committed yet, so unstaging means dropping the paths from the index
+        Err(_) => {
+            let mut index = repo.index().map_err(|e| e.to_string())?;
+            index
+                .remove_all(rel_paths, None)
+                .map_err(|e| e.to_string())?;
+            index.write().map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Working tree changes under `rel_paths` (folders included) and what discarding
+/// them would do, as repository-relative paths
+fn plan_discard(
+    repo: &Repository,
+    rel_paths: &[String],
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .disable_pathspec_match(true);
+    for rel_path in rel_paths {
+        options.pathspec(rel_path);
+    }
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| e.to_string())?;
+
+    let mut restored = Vec::new();
+    let mut deleted = Vec::new();
+    for entry in statuses.iter() {
+        let Some(rel_path) = entry.path() else {
+            continue;
+        };
+        let flags = entry.status();
+        if flags.contains(Status::WT_NEW) {
+            deleted.push(rel_path.to_string());
+        } else if flags.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+        ) {
+            restored.push(rel_path.to_string());
+        }
+    }
+    Ok((restored, deleted))
+}
+
+pub(super) fn discard_paths(
+    repo: &Repository,
+    rel_paths: &[String],
+    confirm: bool,
+) -> Result<DiscardPlan, String> {
+    let workdir = workdir(repo)?;
+    let (restored, deleted) = plan_discard(repo, rel_paths)?;
+
+    if confirm {
+        for rel_path in restored.iter().chain(&deleted) {
+            let path = workdir.join(rel_path);
+            if path.is_file() {
+                local_history::record_version(&path);
+            }
+        }
+
+        if !restored.is_empty() {
+            let mut checkout = CheckoutBuilder::new();
+            checkout.force();
+            for rel_path in &restored {
+                checkout.path(rel_path);
+            }
+            repo.checkout_index(None, Some(&mut checkout))
+                .map_err(|e| e.to_string())?;
+        }
+        for rel_path in &deleted {
+            let path = workdir.join(rel_path);
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        }
+    }
+
+    let to_absolute = |paths: Vec<String>| {
+        paths
+            .iter()
+            .map(|rel_path| absolute_path(workdir, rel_path))
+            .collect()
+    };
+    Ok(DiscardPlan {
+        restored: to_absolute(restored),
+        deleted: to_absolute(deleted),
+        applied: confirm,
+    })
+}
+
+#[tauri::command]
+pub async fn git_stage(paths: Vec<String>) -> Result<(), String> {
+    let (repo, rel_paths) = open_repository_for_paths(&paths)?;
+    stage_paths(&repo, &rel_paths)
+}
+
+#[tauri::command]
+pub async fn git_unstage(paths: Vec<String>) -> Result<(), String> {
+    let (repo, rel_paths) = open_repository_for_paths(&paths)?;
+    unstage_paths(&repo, &rel_paths)
+}
+
+/// Throw away working tree changes under `paths`: modified and deleted files are
+/// restored from the index, untracked files are deleted. Without `confirm` nothing is
+/// changed and the returned plan lists what would be, for the confirmation prompt.
+#[tauri::command]
+pub async fn git_discard(paths: Vec<String>, confirm: bool) -> Result<DiscardPlan, String> {
+    let (repo, rel_paths) = open_repository_for_paths(&paths)?;
+    discard_paths(&repo, &rel_paths, confirm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::path::Path;
+
+    fn status(repo: &Repository, rel_path: &str) -> Status {
+        repo.status_file(Path::new(rel_path)).unwrap()
+    }
+
+    #[test]
+    fn stages_unstages_and_discards() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("kept.rs"), "original\n").unwrap();
+        fs::write(dir.join("gone.rs"), "original\n").unwrap();
+        test_repo::commit(&repo, &["kept.rs", "gone.rs"], "init");
+
+        fs::write(dir.join("kept.rs"), "changed\n").unwrap();
+        fs::remove_file(dir.join("gone.rs")).unwrap();
+        fs::write(dir.join("new.rs"), "new\n").unwrap();
+        let all = vec![
+            "kept.rs".to_string(),
+            "gone.rs".to_string(),
+            "new.rs".to_string(),
+        ];
+
+        stage_paths(&repo, &all).unwrap();
+        assert_eq!(status(&repo, "kept.rs"), Status::INDEX_MODIFIED);
+        assert_eq!(status(&repo, "gone.rs"), Status::INDEX_DELETED);
+        assert_eq!(status(&repo, "new.rs"), Status::INDEX_NEW);
+
+        unstage_paths(&repo, &all).unwrap();
+        assert_eq!(status(&repo, "kept.rs"), Status::WT_MODIFIED);
+        assert_eq!(status(&repo, "new.rs"), Status::WT_NEW);
+
+        let preview = discard_paths(&repo, &all, false).unwrap();
+        assert!(!preview.applied);
+        assert_eq!(preview.restored.len(), 2);
+        assert!(preview.deleted[0].ends_with("new.rs"));
+        assert!(dir.join("new.rs").exists());
+
+        discard_paths(&repo, &all, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("kept.rs")).unwrap(),
+            "original\n"
+        );
+        assert!(dir.join("gone.rs").exists());
+        assert!(!dir.join("new.rs").exists());
+        assert!(repo.statuses(None).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
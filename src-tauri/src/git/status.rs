@@ -0,0 +1,190 @@
+// Working tree status
+// Splits `git status` into the groups a source control panel shows, with renames
+// paired up in both the index and the working tree
+
+use git2::{Delta, DiffDelta, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::path::Path;
+
+use super::{absolute_path, open_repository, workdir};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+    Untracked,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitStatusEntry {
+    pub path: String,
+    /// Relative to the repository root, with forward slashes
+    pub rel_path: String,
+    pub kind: GitChangeKind,
+    /// Previous path of a renamed file
+    pub old_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitStatus {
+    pub repo_root: String,
+    /// None when HEAD is detached
+    pub branch: Option<String>,
+    pub staged: Vec<GitStatusEntry>,
+    pub unstaged: Vec<GitStatusEntry>,
+    pub untracked: Vec<GitStatusEntry>,
+    pub conflicted: Vec<GitStatusEntry>,
+}
+
+pub(super) fn delta_kind(delta: Delta) -> Option<GitChangeKind> {
+    match delta {
+        Delta::Added | Delta::Copied => Some(GitChangeKind::Added),
+        Delta::Modified => Some(GitChangeKind::Modified),
+        Delta::Deleted => Some(GitChangeKind::Deleted),
+        Delta::Renamed => Some(GitChangeKind::Renamed),
+        Delta::Typechange => Some(GitChangeKind::TypeChange),
+        Delta::Untracked => Some(GitChangeKind::Untracked),
+        Delta::Conflicted => Some(GitChangeKind::Conflicted),
+        Delta::Unmodified | Delta::Ignored | Delta::Unreadable => None,
+    }
+}
+
+fn delta_entry(workdir: &Path, delta: &DiffDelta) -> Option<GitStatusEntry> {
+    let kind = delta_kind(delta.status())?;
+    let rel_path = delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let old_path = (kind == GitChangeKind::Renamed)
+        .then(|| delta.old_file().path())
+        .flatten()
+        .map(|path| absolute_path(workdir, &path.to_string_lossy()));
+    Some(GitStatusEntry {
+        path: absolute_path(workdir, &rel_path),
+        rel_path,
+        kind,
+        old_path,
+    })
+}
+
+/// Current branch name, or None when HEAD is detached. An unborn branch (a fresh
+/// repository without commits) still has a name.
+pub(super) fn current_branch(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|head| head.symbolic_target().map(str::to_string))
+            .map(|target| target.trim_start_matches("refs/heads/").to_string()),
+    }
+}
+
+pub(super) fn read_status(repo: &Repository) -> Result<GitStatus, String> {
+    let workdir = workdir(repo)?;
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| e.to_string())?;
+
+    let mut status = GitStatus {
+        repo_root: workdir.to_string_lossy().to_string(),
+        branch: current_branch(repo),
+        staged: Vec::new(),
+        unstaged: Vec::new(),
+        untracked: Vec::new(),
+        conflicted: Vec::new(),
+    };
+    for entry in statuses.iter() {
+        let flags = entry.status();
+        if flags.is_conflicted() {
+            if let Some(rel_path) = entry.path() {
+                status.conflicted.push(GitStatusEntry {
+                    path: absolute_path(workdir, rel_path),
+                    rel_path: rel_path.to_string(),
+                    kind: GitChangeKind::Conflicted,
+                    old_path: None,
+                });
+            }
+            continue;
+        }
+        if let Some(entry) = entry
+            .head_to_index()
+            .and_then(|delta| delta_entry(workdir, &delta))
+        {
+            status.staged.push(entry);
+        }
+        if let Some(entry) = entry
+            .index_to_workdir()
+            .and_then(|delta| delta_entry(workdir, &delta))
+        {
+            if flags.contains(Status::WT_NEW) {
+                status.untracked.push(entry);
+            } else {
+                status.unstaged.push(entry);
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Staged, unstaged, untracked and conflicted files of the repository containing `root`
+#[tauri::command]
+pub async fn git_status(root: String) -> Result<GitStatus, String> {
+    read_status(&open_repository(&root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    #[test]
+    fn groups_changes_and_detects_staged_renames() {
+        let (dir, repo) = test_repo::init();
+        let original = "fn main() {\n    println!(\"hello\");\n}\n".repeat(4);
+        fs::write(dir.join("old.rs"), &original).unwrap();
+        fs::write(dir.join("edited.rs"), "one\n").unwrap();
+        test_repo::commit(&repo, &["old.rs", "edited.rs"], "init");
+
+        fs::rename(dir.join("old.rs"), dir.join("new.rs")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.rs")).unwrap();
+        index.add_path(Path::new("new.rs")).unwrap();
+        index.write().unwrap();
+        fs::write(dir.join("edited.rs"), "two\n").unwrap();
+        fs::write(dir.join("notes.txt"), "untracked\n").unwrap();
+
+        let status = read_status(&repo).unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].kind, GitChangeKind::Renamed);
+        assert_eq!(status.staged[0].rel_path, "new.rs");
+        assert!(status.staged[0]
+            .old_path
+            .as_ref()
+            .unwrap()
+            .ends_with("old.rs"));
+        assert_eq!(status.unstaged[0].rel_path, "edited.rs");
+        assert_eq!(status.unstaged[0].kind, GitChangeKind::Modified);
+        assert_eq!(status.untracked[0].rel_path, "notes.txt");
+        assert!(status.conflicted.is_empty());
+        assert!(status.branch.is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
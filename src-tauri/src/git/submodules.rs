@@ -0,0 +1,97 @@
+// Submodules
+// Submodules registered in .gitmodules and the state of their checkouts, so a monorepo's
+// explorer can tell a missing or dirty submodule from an ordinary folder
+
+use git2::{Repository, SubmoduleIgnore, SubmoduleStatus};
+use serde::Serialize;
+
+use super::{open_repository, workdir};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitSubmodule {
+    pub name: String,
+    /// Absolute path of the submodule's folder
+    pub path: String,
+    pub url: Option<String>,
+    /// Commit the parent repository records for it
+    pub head_id: Option<String>,
+    /// Commit checked out in the submodule; None when it is not initialized
+    pub workdir_id: Option<String>,
+    /// Cloned and checked out, not just listed in .gitmodules
+    pub is_initialized: bool,
+    /// The checked-out commit differs from the recorded one
+    pub is_moved: bool,
+    /// Uncommitted or untracked changes inside the submodule
+    pub is_dirty: bool,
+}
+
+pub(super) fn list_submodules(repo: &Repository) -> Result<Vec<GitSubmodule>, String> {
+    let root = workdir(repo)?;
+    let mut submodules = Vec::new();
+    for submodule in repo.submodules().map_err(|e| e.to_string())? {
+        let name = submodule.name().unwrap_or_default().to_string();
+        let status = repo
+            .submodule_status(&name, SubmoduleIgnore::None)
+            .map_err(|e| e.to_string())?;
+        let is_initialized = status.contains(SubmoduleStatus::IN_WD)
+            && !status.contains(SubmoduleStatus::WD_UNINITIALIZED);
+        let is_dirty = status.intersects(
+            SubmoduleStatus::WD_INDEX_MODIFIED
+                | SubmoduleStatus::WD_WD_MODIFIED
+                | SubmoduleStatus::WD_UNTRACKED,
+        );
+        submodules.push(GitSubmodule {
+            name,
+            path: root.join(submodule.path()).to_string_lossy().to_string(),
+            url: submodule.url().map(str::to_string),
+            head_id: submodule.head_id().map(|id| id.to_string()),
+            workdir_id: submodule.workdir_id().map(|id| id.to_string()),
+            is_initialized,
+            is_moved: status.contains(SubmoduleStatus::WD_MODIFIED),
+            is_dirty,
+        });
+    }
+    submodules.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(submodules)
+}
+
+/// Submodules of the repository containing `root` with their checkout state
+#[tauri::command]
+pub async fn git_submodules(root: String) -> Result<Vec<GitSubmodule>, String> {
+    list_submodules(&open_repository(&root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::project_commands::is_submodule_root;
+    use crate::git::test_repo;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn reports_initialized_and_dirty_submodules() {
+        let (library_dir, library) = test_repo::init();
+        fs::write(library_dir.join("lib.txt"), "lib\n").unwrap();
+        test_repo::commit(&library, &["lib.txt"], "library");
+
+        let (dir, repo) = test_repo::init();
+        let url = library_dir.to_string_lossy().to_string();
+        let mut submodule = repo.submodule(&url, Path::new("vendor/lib"), true).unwrap();
+        submodule.clone(None).unwrap();
+        submodule.add_finalize().unwrap();
+
+        let listed = list_submodules(&repo).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].url.as_deref(), Some(url.as_str()));
+        assert!(listed[0].is_initialized && !listed[0].is_dirty && !listed[0].is_moved);
+        assert!(is_submodule_root(&dir.join("vendor/lib")));
+        assert!(!is_submodule_root(&dir));
+
+        fs::write(dir.join("vendor/lib/lib.txt"), "changed\n").unwrap();
+        assert!(list_submodules(&repo).unwrap()[0].is_dirty);
+
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&library_dir);
+    }
+}
@@ -0,0 +1,316 @@
+// Syncing with remotes
+// Fetch, pull and push, reporting transfer progress as "git-progress" events. They
+// wait on the network, so the commands run them on a blocking thread.
+
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, FetchOptions, PushOptions, Repository};
+use serde::Serialize;
+use std::cell::RefCell;
+use tauri::{AppHandle, Emitter};
+
+use super::commit::commit_index;
+use super::credentials::{remote_callbacks, GitProgressEvent, GitRemoteError, PROGRESS_EVENT};
+use super::status::current_branch;
+use super::{absolute_path, open_repository, workdir};
+use crate::commands::file_watcher;
+use crate::error::VoidDeskError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    /// The merge stopped on conflicts; resolve them and commit to finish it
+    Conflicts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PullResult {
+    pub outcome: PullOutcome,
+    pub conflicts: Vec<String>,
+}
+
+/// `remote` and `branch`, defaulting to the current branch and the remote it tracks
+/// (or "origin")
+fn resolve_target(
+    repo: &Repository,
+    remote: Option<String>,
+    branch: Option<String>,
+) -> Result<(String, String), String> {
+    let branch = match branch {
+        Some(branch) => branch,
+        None => current_branch(repo).ok_or_else(|| "HEAD is not on a branch".to_string())?,
+    };
+    let remote = match remote {
+        Some(remote) => remote,
+        None => repo
+            .branch_upstream_remote(&format!("refs/heads/{}", branch))
+            .ok()
+            .and_then(|remote| remote.as_str().map(str::to_string))
+            .unwrap_or_else(|| "origin".to_string()),
+    };
+    Ok((remote, branch))
+}
+
+pub(super) fn fetch_branch(
+    repo: &Repository,
+    remote_name: &str,
+    branch: &str,
+    operation: &'static str,
+    token: Option<String>,
+    on_progress: &dyn Fn(GitProgressEvent),
+) -> Result<(), GitRemoteError> {
+    let config = repo.config()?;
+    let mut remote = repo.find_remote(remote_name)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks(
+        &config,
+        token,
+        operation,
+        remote_name,
+        on_progress,
+    ));
+    let refspec = format!("+refs/heads/{0}:refs/remotes/{1}/{0}", branch, remote_name);
+    remote.fetch(&[refspec], Some(&mut options), None)?;
+    Ok(())
+}
+
+/// Paths with merge conflicts in the index
+fn conflicted_paths(repo: &Repository) -> Result<Vec<String>, GitRemoteError> {
+    let workdir = workdir(repo)?;
+    let mut paths = Vec::new();
+    for conflict in repo.index()?.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(absolute_path(
+                workdir,
+                &String::from_utf8_lossy(&entry.path),
+            ));
+        }
+    }
+    Ok(paths)
+}
+
+/// Bring `remote_name/branch` (already fetched) into HEAD: fast-forward when possible,
+/// otherwise a merge commit, or a stopped merge when it conflicts
+pub(super) fn merge_fetched(
+    repo: &mut Repository,
+    remote_name: &str,
+    branch: &str,
+) -> Result<PullResult, GitRemoteError> {
+    let result = |outcome| PullResult {
+        outcome,
+        conflicts: Vec::new(),
+    };
+    {
+        let tracking = repo.find_reference(&format!("refs/remotes/{}/{}", remote_name, branch))?;
+        let fetched = repo.reference_to_annotated_commit(&tracking)?;
+        let (analysis, _) = repo.merge_analysis(&[&fetched])?;
+
+        if analysis.is_up_to_date() {
+            return Ok(result(PullOutcome::UpToDate));
+        }
+        if analysis.is_fast_forward() || analysis.is_unborn() {
+            // HEAD names the branch even before its first commit
+            let head_ref = repo
+                .find_reference("HEAD")?
+                .symbolic_target()
+                .ok_or_else(|| "HEAD is not on a branch".to_string())?
+                .to_string();
+            let target = repo.find_object(fetched.id(), None)?;
+            repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+            repo.reference(&head_ref, fetched.id(), true, "pull: fast-forward")?;
+            return Ok(result(PullOutcome::FastForward));
+        }
+        repo.merge(&[&fetched], None, Some(CheckoutBuilder::new().safe()))?;
+    }
+
+    let conflicts = conflicted_paths(repo)?;
+    if !conflicts.is_empty() {
+        return Ok(PullResult {
+            outcome: PullOutcome::Conflicts,
+            conflicts,
+        });
+    }
+    let message = format!("Merge branch '{}' of {}", branch, remote_name);
+    commit_index(repo, &message, false, false, None)?;
+    Ok(result(PullOutcome::Merged))
+}
+
+pub(super) fn push_branch(
+    repo: &Repository,
+    remote_name: &str,
+    branch: &str,
+    token: Option<String>,
+    on_progress: &dyn Fn(GitProgressEvent),
+) -> Result<(), GitRemoteError> {
+    let config = repo.config()?;
+    let mut remote = repo.find_remote(remote_name)?;
+    let rejected = RefCell::new(None);
+    let mut callbacks = remote_callbacks(&config, token, "push", remote_name, on_progress);
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() = Some(format!("{} was rejected: {}", refname, status));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    remote.push(&[refspec], Some(&mut options))?;
+    drop(options);
+    if let Some(message) = rejected.into_inner() {
+        return Err(GitRemoteError::Rejected { message });
+    }
+
+    // Like `git push -u`: a branch pushed for the first time starts tracking the remote
+    let mut local = repo.find_branch(branch, BranchType::Local)?;
+    if local.upstream().is_err() {
+        local.set_upstream(Some(&format!("{}/{}", remote_name, branch)))?;
+    }
+    Ok(())
+}
+
+fn emit_progress(app: &AppHandle) -> impl Fn(GitProgressEvent) + '_ {
+    move |event| {
+        let _ = app.emit(PROGRESS_EVENT, event);
+    }
+}
+
+/// Fetch `branch` (the current one by default) from `remote` (its upstream, or
+/// "origin"). `token` is tried as the HTTPS password before the credential helper.
+#[tauri::command]
+pub async fn git_fetch(
+    app: AppHandle,
+    root: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    token: Option<String>,
+) -> Result<(), VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let repo = open_repository(&root)?;
+        let (remote, branch) = resolve_target(&repo, remote, branch)?;
+        fetch_branch(
+            &repo,
+            &remote,
+            &branch,
+            "fetch",
+            token,
+            &emit_progress(&app),
+        )
+    })
+    .await??)
+}
+
+/// Fetch, then fast-forward or merge into the current branch. Conflicts leave the
+/// merge in progress and are listed in the result.
+#[tauri::command]
+pub async fn git_pull(
+    app: AppHandle,
+    root: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    token: Option<String>,
+) -> Result<PullResult, VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let mut repo = open_repository(&root)?;
+        let (remote, branch) = resolve_target(&repo, remote, branch)?;
+        fetch_branch(&repo, &remote, &branch, "pull", token, &emit_progress(&app))?;
+        let _pause = file_watcher::pause_all_watchers();
+        merge_fetched(&mut repo, &remote, &branch)
+    })
+    .await??)
+}
+
+/// Push `branch` to the branch of the same name on `remote`, setting it as the
+/// upstream if there is none yet
+#[tauri::command]
+pub async fn git_push(
+    app: AppHandle,
+    root: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    token: Option<String>,
+) -> Result<(), VoidDeskError> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let repo = open_repository(&root)?;
+        let (remote, branch) = resolve_target(&repo, remote, branch)?;
+        push_branch(&repo, &remote, &branch, token, &emit_progress(&app))
+    })
+    .await??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+    use std::fs;
+
+    fn no_progress(_: GitProgressEvent) {}
+
+    fn with_origin(bare: &std::path::Path) -> (std::path::PathBuf, Repository) {
+        let (dir, repo) = test_repo::init();
+        repo.remote("origin", &bare.to_string_lossy()).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn pushes_pulls_and_merges_through_a_local_remote() {
+        let bare = std::env::temp_dir().join(format!("voidesk-git-{}", uuid::Uuid::new_v4()));
+        Repository::init_bare(&bare).unwrap();
+
+        let (a_dir, a) = with_origin(&bare);
+        fs::write(a_dir.join("a.txt"), "a\n").unwrap();
+        test_repo::commit(&a, &["a.txt"], "init");
+        let branch = current_branch(&a).unwrap();
+        push_branch(&a, "origin", &branch, None, &no_progress).unwrap();
+        assert!(a
+            .find_branch(&branch, BranchType::Local)
+            .unwrap()
+            .upstream()
+            .is_ok());
+
+        // A fresh clone-like repository pulls into its unborn branch
+        let (b_dir, mut b) = with_origin(&bare);
+        b.set_head(&format!("refs/heads/{}", branch)).unwrap();
+        fetch_branch(&b, "origin", &branch, "pull", None, &no_progress).unwrap();
+        let pulled = merge_fetched(&mut b, "origin", &branch).unwrap();
+        assert_eq!(pulled.outcome, PullOutcome::FastForward);
+        assert_eq!(fs::read_to_string(b_dir.join("a.txt")).unwrap(), "a\n");
+
+        fs::write(b_dir.join("b.txt"), "b\n").unwrap();
+        test_repo::commit(&b, &["b.txt"], "from b");
+        push_branch(&b, "origin", &branch, None, &no_progress).unwrap();
+
+        // A has diverged, so its push is refused until it pulls and merges
+        fs::write(a_dir.join("c.txt"), "c\n").unwrap();
+        test_repo::commit(&a, &["c.txt"], "from a");
+        assert!(matches!(
+            push_branch(&a, "origin", &branch, None, &no_progress),
+            Err(GitRemoteError::Rejected { .. }) | Err(GitRemoteError::Failed { .. })
+        ));
+        let mut a = a;
+        fetch_branch(&a, "origin", &branch, "pull", None, &no_progress).unwrap();
+        let merged = merge_fetched(&mut a, "origin", &branch).unwrap();
+        assert_eq!(merged.outcome, PullOutcome::Merged);
+        assert!(a_dir.join("b.txt").exists());
+        assert_eq!(
+            a.head().unwrap().peel_to_commit().unwrap().parent_count(),
+            2
+        );
+        push_branch(&a, "origin", &branch, None, &no_progress).unwrap();
+
+        fetch_branch(&b, "origin", &branch, "pull", None, &no_progress).unwrap();
+        let caught_up = merge_fetched(&mut b, "origin", &branch).unwrap();
+        assert_eq!(caught_up.outcome, PullOutcome::FastForward);
+        assert_eq!(
+            merge_fetched(&mut b, "origin", &branch).unwrap().outcome,
+            PullOutcome::UpToDate
+        );
+
+        for dir in [&bare, &a_dir, &b_dir] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
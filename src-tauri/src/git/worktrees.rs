@@ -0,0 +1,214 @@
+// Worktrees
+// Extra working directories of the same repository, each with its own branch checked
+// out, so work (or an agent) can run on one branch without disturbing the main tree
+
+use git2::{BranchType, Repository, WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::branches::dirty_paths;
+use super::open_repository;
+use super::status::current_branch;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitWorktree {
+    /// None for the main working tree
+    pub name: Option<String>,
+    pub path: String,
+    pub branch: Option<String>,
+    pub is_main: bool,
+    /// The worktree `root` belongs to
+    pub is_current: bool,
+    pub is_locked: bool,
+    /// False when its directory is gone; it can be removed
+    pub is_valid: bool,
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// The main working tree's git dir. A linked worktree's own git dir lives inside it and
+/// records the way back in its `commondir` file.
+fn common_dir(repo: &Repository) -> PathBuf {
+    if !repo.is_worktree() {
+        return repo.path().to_path_buf();
+    }
+    match fs::read_to_string(repo.path().join("commondir")) {
+        Ok(relative) => repo.path().join(relative.trim()),
+        Err(_) => repo.path().join("../.."),
+    }
+}
+
+pub(super) fn list_worktrees(repo: &Repository) -> Result<Vec<GitWorktree>, String> {
+    let current = repo.workdir();
+    let is_current = |path: &Path| current.is_some_and(|current| same_path(current, path));
+
+    let mut worktrees = Vec::new();
+    let main_repo = Repository::open(common_dir(repo)).map_err(|e| e.to_string())?;
+    if let Some(main_path) = main_repo.workdir() {
+        worktrees.push(GitWorktree {
+            name: None,
+            path: main_path.to_string_lossy().to_string(),
+            branch: current_branch(&main_repo),
+            is_main: true,
+            is_current: is_current(main_path),
+            is_locked: false,
+            is_valid: true,
+        });
+    }
+
+    let names = repo.worktrees().map_err(|e| e.to_string())?;
+    for name in names.iter().flatten() {
+        let worktree = repo.find_worktree(name).map_err(|e| e.to_string())?;
+        let is_valid = worktree.validate().is_ok();
+        let branch = if is_valid {
+            Repository::open_from_worktree(&worktree)
+                .ok()
+                .and_then(|worktree_repo| current_branch(&worktree_repo))
+        } else {
+            None
+        };
+        worktrees.push(GitWorktree {
+            name: Some(name.to_string()),
+            path: worktree.path().to_string_lossy().to_string(),
+            branch,
+            is_main: false,
+            is_current: is_current(worktree.path()),
+            is_locked: matches!(worktree.is_locked(), Ok(WorktreeLockStatus::Locked(_))),
+            is_valid,
+        });
+    }
+    Ok(worktrees)
+}
+
+/// Check out `branch` into the new directory `path`, creating the branch from HEAD when
+/// it does not exist. The worktree is named after the directory.
+pub(super) fn add_worktree(
+    repo: &Repository,
+    branch: &str,
+    path: &Path,
+) -> Result<GitWorktree, String> {
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid worktree path: {}", path.display()))?;
+
+    let local = match repo.find_branch(branch, BranchType::Local) {
+        Ok(local) => local,
+        Err(_) => {
+            let head = repo
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .map_err(|_| "There is no commit to branch from yet".to_string())?;
+            repo.branch(branch, &head, false)
+                .map_err(|e| e.message().to_string())?
+        }
+    };
+    let mut options = WorktreeAddOptions::new();
+    options.reference(Some(local.get()));
+    let worktree = repo
+        .worktree(name, path, Some(&options))
+        .map_err(|e| e.message().to_string())?;
+
+    list_worktrees(repo)?
+        .into_iter()
+        .find(|listed| same_path(Path::new(&listed.path), worktree.path()))
+        .ok_or_else(|| "The worktree was created but could not be read back".to_string())
+}
+
+/// Delete the worktree `name` and its directory. Refuses locked worktrees and ones with
+/// uncommitted changes to tracked files.
+pub(super) fn remove_worktree(repo: &Repository, name: &str) -> Result<(), String> {
+    let worktree = repo.find_worktree(name).map_err(|e| e.to_string())?;
+    if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+        return Err(match reason {
+            Some(reason) => format!("Worktree {} is locked: {}", name, reason),
+            None => format!("Worktree {} is locked", name),
+        });
+    }
+    if worktree.validate().is_ok() {
+        let worktree_repo = Repository::open_from_worktree(&worktree).map_err(|e| e.to_string())?;
+        let dirty = dirty_paths(&worktree_repo)?;
+        if !dirty.is_empty() {
+            return Err(format!(
+                "Worktree {} has {} file(s) with uncommitted changes",
+                name,
+                dirty.len()
+            ));
+        }
+    }
+    worktree
+        .prune(Some(
+            WorktreePruneOptions::new().valid(true).working_tree(true),
+        ))
+        .map_err(|e| e.to_string())
+}
+
+/// The main working tree and every linked worktree of the repository containing `root`
+#[tauri::command]
+pub async fn git_worktrees(root: String) -> Result<Vec<GitWorktree>, String> {
+    list_worktrees(&open_repository(&root)?)
+}
+
+/// Check out `branch` into a new worktree at `path`, which may be outside the workspace
+#[tauri::command]
+pub async fn git_add_worktree(
+    root: String,
+    branch: String,
+    path: String,
+) -> Result<GitWorktree, String> {
+    add_worktree(&open_repository(&root)?, &branch, Path::new(&path))
+}
+
+#[tauri::command]
+pub async fn git_remove_worktree(root: String, name: String) -> Result<(), String> {
+    remove_worktree(&open_repository(&root)?, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo;
+
+    #[test]
+    fn adds_lists_and_removes_worktrees() {
+        let (dir, repo) = test_repo::init();
+        fs::write(dir.join("a.txt"), "a\n").unwrap();
+        test_repo::commit(&repo, &["a.txt"], "init");
+
+        let path = std::env::temp_dir().join(format!("voidesk-wt-{}", uuid::Uuid::new_v4()));
+        let added = add_worktree(&repo, "feature", &path).unwrap();
+        assert_eq!(added.branch.as_deref(), Some("feature"));
+        assert!(!added.is_main && !added.is_current);
+        assert!(path.join("a.txt").exists());
+        assert!(add_worktree(&repo, "feature", &path).is_err());
+
+        let listed = list_worktrees(&repo).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].is_main && listed[0].is_current);
+
+        // Seen from inside the linked worktree, the same two are listed
+        let linked = Repository::open(&path).unwrap();
+        let from_linked = list_worktrees(&linked).unwrap();
+        assert_eq!(from_linked.len(), 2);
+        assert!(from_linked[1].is_current);
+
+        let name = added.name.unwrap();
+        fs::write(path.join("a.txt"), "changed\n").unwrap();
+        assert!(remove_worktree(&repo, &name).is_err());
+        fs::write(path.join("a.txt"), "a\n").unwrap();
+        remove_worktree(&repo, &name).unwrap();
+        assert!(!path.exists());
+        assert_eq!(list_worktrees(&repo).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
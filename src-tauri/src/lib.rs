@@ -1,6 +1,18 @@
 mod commands;
+mod diagnostics;
+mod diff;
+mod error;
+mod git;
+mod log_buffer;
 mod lsp;
+mod metrics;
+mod remote;
 mod sdk;
+mod secrets;
+mod settings;
+mod snippets;
+mod syntax;
+mod tasks;
 mod terminal;
 mod tracing_setup;
 
@@ -8,32 +20,62 @@ use tauri::Manager;
 
 use commands::ai_commands;
 use commands::ai_debug;
+use commands::ai_diagnostic;
+use commands::ai_edit;
 use commands::ai_service;
+use commands::ai_terminal;
 use commands::attachment_commands;
 use commands::chat_storage;
 use commands::codex_auth;
+use commands::conversation_export;
+use commands::editor_context;
 use commands::file_commands;
+use commands::file_finder;
 use commands::file_watcher;
+use commands::format_commands;
+use commands::ignore_engine;
+use commands::image_commands;
+use commands::lint_commands;
+use commands::local_history;
+use commands::log_tail;
 use commands::lsp_commands;
 use commands::lsp_runtime;
+use commands::markdown_commands;
+use commands::notification_commands;
+use commands::port_commands;
 use commands::project_commands;
+use commands::recent_items;
+use commands::scaffold;
 use commands::search_commands;
+use commands::workspace;
 use commands::workspace_index;
+use commands::workspace_roots;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tracing_setup::init_logging();
     tauri::Builder::default()
         .manage(terminal::TerminalState::new())
+        .manage(tasks::DevServerState::new())
         .setup(|app| {
             let chat_storage_state = chat_storage::ChatStorageState::new(app.handle())?;
             let ai_service_state =
                 ai_service::AIService::from_db_path(chat_storage_state.db_path().to_path_buf())?;
             let codex_auth_state = codex_auth::CodexAuthState::new(app.handle())?;
-            let lsp_state = lsp_commands::LspState::new();
+            let lsp_state = lsp_commands::LspState::new(app.handle().clone());
             workspace_index::initialize_persistence(chat_storage_state.db_path().to_path_buf())
                 .map_err(anyhow::Error::msg)?;
-            tauri::async_runtime::block_on(lsp_state.manager.set_app_handle(app.handle().clone()));
+            terminal::initialize_history(chat_storage_state.db_path().to_path_buf())
+                .map_err(anyhow::Error::msg)?;
+            recent_items::initialize_persistence(chat_storage_state.db_path().to_path_buf())
+                .map_err(anyhow::Error::msg)?;
+            local_history::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            scaffold::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            settings::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            secrets::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            snippets::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            diagnostics::initialize(app.path().app_data_dir()?).map_err(anyhow::Error::msg)?;
+            metrics::set_enabled(settings::effective(None).diagnostics.metrics_enabled);
             app.manage(chat_storage_state);
             app.manage(ai_service_state);
             app.manage(codex_auth_state);
@@ -42,10 +84,22 @@ pub fn run() {
         })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             // File operations
             file_commands::read_file,
+            file_commands::probe_file,
+            file_commands::get_file_stats,
+            file_commands::get_large_file_threshold,
+            file_commands::set_large_file_threshold,
+            file_commands::read_file_range,
+            file_commands::read_file_lines,
             file_commands::write_file,
+            file_commands::get_file_version,
+            file_commands::read_file_with_encoding,
+            file_commands::write_file_with_encoding,
+            file_commands::read_file_binary,
+            file_commands::write_file_binary,
             file_commands::delete_file,
             file_commands::create_directory,
             file_commands::move_file,
@@ -53,14 +107,65 @@ pub fn run() {
             file_commands::rename_file,
             file_commands::batch_delete_files,
             file_commands::batch_move_files,
+            file_commands::copy_file,
+            file_commands::copy_directory,
+            file_commands::duplicate_path,
+            file_commands::get_permissions,
+            file_commands::set_permissions,
+            format_commands::format_file,
+            image_commands::get_image_info,
+            image_commands::get_image_thumbnail,
+            lint_commands::run_linter,
+            markdown_commands::render_markdown,
+            notification_commands::notify_user,
+            port_commands::get_listening_ports,
+            port_commands::is_port_free,
+            local_history::list_file_history,
+            local_history::read_file_version,
+            local_history::restore_file_version,
+            scaffold::list_templates,
+            scaffold::scaffold_template,
             // Project operations
             project_commands::list_directory,
+            workspace_roots::register_workspace_root,
+            workspace_roots::unregister_workspace_root,
+            workspace_roots::list_workspace_roots,
+            workspace::open_workspace,
+            workspace::close_workspace,
+            workspace::list_workspaces,
+            workspace::open_project_window,
             project_commands::get_project_tree,
+            project_commands::stream_project_tree,
+            project_commands::expand_tree_node,
+            project_commands::get_directory_stats,
             workspace_index::rebuild_workspace_index,
             workspace_index::get_workspace_index_stats,
             workspace_index::get_workspace_index_cache_summary,
             workspace_index::clear_workspace_index_cache,
             workspace_index::set_workspace_index_persistence_enabled,
+            ignore_engine::get_ignore_rules,
+            settings::get_settings,
+            settings::update_settings,
+            settings::get_project_config,
+            secrets::secrets_status,
+            secrets::unlock_secrets,
+            secrets::lock_secrets,
+            secrets::list_secrets,
+            secrets::get_secret,
+            secrets::set_secret,
+            secrets::delete_secret,
+            tracing_setup::get_log_level,
+            tracing_setup::set_log_level,
+            log_buffer::get_logs,
+            metrics::get_metrics,
+            metrics::reset_metrics,
+            diagnostics::export_diagnostics_bundle,
+            diagnostics::get_backend_health,
+            recent_items::record_file_open,
+            recent_items::record_project_open,
+            recent_items::get_recent,
+            recent_items::pin_recent_item,
+            recent_items::remove_recent_item,
             // AI operations
             ai_commands::ask_ai_stream,
             ai_commands::ask_ai_stream_with_session,
@@ -68,10 +173,16 @@ pub fn run() {
             ai_commands::test_ai_connection,
             ai_commands::reset_ai_conversation,
             ai_commands::get_inline_completion,
+            ai_commands::generate_commit_message,
+            ai_edit::ai_edit_selection,
+            ai_terminal::generate_terminal_command,
+            ai_diagnostic::explain_diagnostic,
             ai_commands::create_chat_session,
             ai_commands::list_chat_sessions,
             ai_commands::delete_chat_session,
             ai_commands::rename_chat_session,
+            conversation_export::export_conversation_markdown,
+            editor_context::update_editor_context,
             codex_auth::codex_auth_status,
             codex_auth::codex_start_login,
             codex_auth::codex_logout,
@@ -85,19 +196,82 @@ pub fn run() {
             // Search
             search_commands::search_in_files,
             search_commands::replace_in_files,
+            search_commands::search_project,
+            search_commands::replace_in_project,
+            file_finder::find_files,
             // File watcher
             file_watcher::start_file_watcher,
             file_watcher::stop_file_watcher,
             file_watcher::is_watching,
+            file_watcher::list_file_watchers,
+            file_watcher::pause_file_watcher,
+            file_watcher::resume_file_watcher,
+            file_watcher::watcher_status,
+            log_tail::tail_file,
+            log_tail::stop_tail,
             // Terminal
             terminal::create_pty,
             terminal::write_to_pty,
             terminal::resize_pty,
             terminal::close_pty,
+            terminal::signal_pty,
+            terminal::set_pty_restart_on_exit,
+            terminal::set_pty_title,
+            terminal::pause_pty,
+            terminal::resume_pty,
+            terminal::get_pty_buffer,
+            terminal::search_pty_buffer,
+            terminal::list_ptys,
+            terminal::rerun_last_command,
+            terminal::get_command_history,
+            // Remote projects
+            remote::remote_connect,
+            remote::remote_disconnect,
+            remote::remote_list_connections,
+            remote::remote_list_directory,
+            remote::remote_get_project_tree,
+            remote::remote_read_file,
+            remote::remote_write_file,
+            remote::remote_create_directory,
+            remote::remote_delete_file,
+            remote::remote_rename_file,
+            remote::remote_create_pty,
+            // Tasks
+            tasks::list_tasks,
+            tasks::run_task,
+            tasks::start_dev_server,
+            tasks::stop_dev_server,
+            tasks::list_dev_servers,
+            // Git
+            git::git_status,
+            git::git_diff_file,
+            git::git_file_gutter,
+            git::git_stage,
+            git::git_unstage,
+            git::git_stage_hunk,
+            git::git_unstage_hunk,
+            git::git_discard,
+            git::git_commit,
+            git::git_branches,
+            git::git_create_branch,
+            git::git_switch_branch,
+            git::git_worktrees,
+            git::git_add_worktree,
+            git::git_remove_worktree,
+            git::git_log,
+            git::git_blame,
+            git::git_fetch,
+            git::git_pull,
+            git::git_push,
+            git::git_remotes,
+            git::git_submodules,
+            git::git_init,
+            git::git_clone,
+            // Diffs
+            diff::compute_diff,
             // Attachments
             attachment_commands::prepare_chat_attachments,
             // LSP
-            lsp_commands::lsp_set_root,
             lsp_commands::lsp_did_open,
             lsp_commands::lsp_did_change,
             lsp_commands::lsp_completion,
@@ -106,12 +280,36 @@ pub fn run() {
             lsp_commands::lsp_definition,
             lsp_commands::lsp_references,
             lsp_commands::lsp_rename,
+            lsp_commands::lsp_get_request_timeouts,
+            lsp_commands::lsp_set_request_timeouts,
+            syntax::syntax_outline,
+            syntax::syntax_folding_ranges,
+            syntax::syntax_matching_bracket,
+            syntax::symbol_index::search_symbols,
+            // Snippets
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::import_vscode_snippets,
             lsp_runtime::lsp_list_extensions,
             lsp_runtime::lsp_ensure_default_extensions,
             lsp_runtime::lsp_install_extension,
             lsp_runtime::lsp_update_extension,
             lsp_runtime::lsp_uninstall_extension,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            tauri::RunEvent::Exit => {
+                app.state::<terminal::TerminalState>().kill_all();
+            }
+            tauri::RunEvent::WindowEvent { label, event, .. } => match event {
+                tauri::WindowEvent::Focused(true) => workspace::window_focused(&label),
+                tauri::WindowEvent::Destroyed => {
+                    tauri::async_runtime::spawn(workspace::window_closed(app.clone(), label));
+                }
+                _ => {}
+            },
+            _ => {}
+        });
 }
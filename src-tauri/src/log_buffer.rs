@@ -0,0 +1,225 @@
+// Log buffer
+// The most recent tracing events, kept in memory for the in-app log viewer so nobody has
+// to go looking for ai-debug.log. Only what passes the log filter is kept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Oldest entries are dropped past this many
+const CAPACITY: usize = 2_000;
+
+/// Returned when a query sets no limit
+const DEFAULT_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Increases with every event; pass the last one seen as `since` to get newer ones
+    pub id: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// The event's other fields, formatted
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogQuery {
+    /// Least severe level to include, e.g. "warn" for warnings and errors
+    pub level: Option<String>,
+    /// Target prefix, e.g. "voidesk_lib::lsp"
+    pub target: Option<String>,
+    /// Case-insensitive text to find in the message or fields
+    pub contains: Option<String>,
+    /// Newest entries returned at most
+    pub limit: Option<usize>,
+}
+
+struct Buffer {
+    entries: VecDeque<LogEntry>,
+    next_id: u64,
+}
+
+static BUFFER: OnceLock<Mutex<Buffer>> = OnceLock::new();
+
+fn buffer() -> &'static Mutex<Buffer> {
+    BUFFER.get_or_init(|| {
+        Mutex::new(Buffer {
+            entries: VecDeque::with_capacity(CAPACITY),
+            next_id: 1,
+        })
+    })
+}
+
+/// Tracing layer feeding the buffer
+pub struct LogBufferLayer;
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+
+        let Ok(mut buffer) = buffer().lock() else {
+            return;
+        };
+        let id = buffer.next_id;
+        buffer.next_id += 1;
+        if buffer.entries.len() == CAPACITY {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(LogEntry {
+            id,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+impl LogQuery {
+    fn matches(&self, entry: &LogEntry, level: Option<Level>) -> bool {
+        // Levels compare by verbosity: ERROR is the smallest
+        if let Some(level) = level {
+            if Level::from_str(&entry.level).is_ok_and(|entry_level| entry_level > level) {
+                return false;
+            }
+        }
+        if let Some(target) = self.target.as_deref() {
+            if !entry.target.starts_with(target) {
+                return false;
+            }
+        }
+        if let Some(needle) = self.contains.as_deref() {
+            let needle = needle.to_lowercase();
+            return entry.message.to_lowercase().contains(&needle)
+                || entry
+                    .fields
+                    .values()
+                    .any(|value| value.to_lowercase().contains(&needle));
+        }
+        true
+    }
+}
+
+fn query(filter: &LogQuery, since: Option<u64>) -> Result<Vec<LogEntry>, String> {
+    let level = filter
+        .level
+        .as_deref()
+        .map(|name| Level::from_str(name).map_err(|_| format!("Unknown log level \"{}\"", name)))
+        .transpose()?;
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT);
+
+    let buffer = buffer().lock().map_err(|e| e.to_string())?;
+    let mut entries: Vec<LogEntry> = buffer
+        .entries
+        .iter()
+        .rev()
+        .take_while(|entry| since.is_none_or(|since| entry.id > since))
+        .filter(|entry| filter.matches(entry, level))
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Everything in the buffer, oldest first
+pub(crate) fn recent_entries() -> Vec<LogEntry> {
+    buffer()
+        .lock()
+        .map(|buffer| buffer.entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Buffered log entries matching `filter`, oldest first; with `since`, only those after
+/// that entry id
+#[tauri::command]
+pub async fn get_logs(
+    filter: Option<LogQuery>,
+    since: Option<u64>,
+) -> Result<Vec<LogEntry>, String> {
+    query(&filter.unwrap_or_default(), since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn keeps_events_and_filters_them() {
+        let subscriber = tracing_subscriber::registry().with(LogBufferLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "voidesk_test::buffer", "starting");
+            tracing::warn!(target: "voidesk_test::buffer", server = "rust-analyzer", "Exited");
+            tracing::debug!(target: "voidesk_test::other", "noise");
+        });
+
+        let all = LogQuery {
+            target: Some("voidesk_test".to_string()),
+            ..LogQuery::default()
+        };
+        let entries = query(&all, None).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].message, "starting");
+
+        let warnings = LogQuery {
+            level: Some("warn".to_string()),
+            contains: Some("RUST-ANALYZER".to_string()),
+            ..all.clone()
+        };
+        let entries = query(&warnings, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].level, "WARN");
+        assert_eq!(entries[0].fields["server"], "rust-analyzer");
+
+        let newer = query(&all, Some(entries[0].id)).unwrap();
+        assert_eq!(newer.len(), 1);
+        assert_eq!(newer[0].target, "voidesk_test::other");
+
+        assert!(query(
+            &LogQuery {
+                level: Some("loud".to_string()),
+                ..LogQuery::default()
+            },
+            None
+        )
+        .is_err());
+    }
+}
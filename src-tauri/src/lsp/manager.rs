@@ -1,27 +1,100 @@
 // LSP Manager
 // Manages the lifecycle of language server processes
 
-use crate::lsp::protocol;
-use crate::lsp::transport::LspTransport;
 use crate::commands::lsp_runtime;
+use crate::lsp::protocol;
+use crate::lsp::transport::{LspTransport, RequestTimeouts, REQUEST_TIMED_OUT};
 use lsp_types::{
-    GotoDefinitionResponse, OneOf, PublishDiagnosticsParams, ReferenceContext, ReferenceParams,
-    RenameParams, TextDocumentPositionParams, Url, WorkspaceEdit,
+    CompletionResponse, DidChangeWatchedFilesParams, FileChangeType, FileEvent,
+    GotoDefinitionResponse, Hover, HoverContents, MarkedString, OneOf, PublishDiagnosticsParams,
+    ReferenceContext, ReferenceParams, RenameParams, TextDocumentPositionParams, Url,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Mutex, RwLock};
 
 const DIAGNOSTICS_EVENT: &str = "lsp://diagnostics";
+const MESSAGE_EVENT: &str = "lsp://message";
+const PROGRESS_EVENT: &str = "lsp://progress";
+const SERVER_STATUS_EVENT: &str = "lsp://server-status";
+
+/// Give up restarting a crashing server after this many consecutive attempts
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+/// A server that stayed up this long is considered healthy again
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+/// Restart attempts that count against MAX_RESTART_ATTEMPTS when a server that ran for
+/// `uptime` crashes after `previous` attempts; a stable run starts the count over
+fn attempts_so_far(previous: u32, uptime: Duration) -> u32 {
+    if uptime >= STABLE_UPTIME {
+        0
+    } else {
+        previous
+    }
+}
+
+/// How long to wait before restart `attempt` (1-based): 1s, 2s, 4s, ... capped at 30s.
+/// None once MAX_RESTART_ATTEMPTS have been made.
+fn restart_delay(attempt: u32) -> Option<Duration> {
+    if attempt > MAX_RESTART_ATTEMPTS {
+        return None;
+    }
+    Some(restart_backoff(attempt))
+}
+
+fn restart_backoff(attempt: u32) -> Duration {
+    let doublings = attempt.saturating_sub(1).min(5);
+    Duration::from_secs((1u64 << doublings).min(30))
+}
 
 /// Per-language server state
 pub struct LanguageServer {
     pub transport: Arc<LspTransport>,
+    /// Work-done progress tokens the server created, with the title once it has begun
+    active_progress: Arc<RwLock<HashMap<String, Option<String>>>>,
+}
+
+impl LanguageServer {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.transport.next_request_id().await;
+        self.request_with_id(id, method, params).await
+    }
+
+    async fn request_with_id(&self, id: u64, method: &str, params: Value) -> Result<Value, String> {
+        match self
+            .transport
+            .send_request_with_id(id, method, params)
+            .await
+        {
+            Err(error) if error.starts_with(REQUEST_TIMED_OUT) => {
+                Err(self.describe_timeout(error).await)
+            }
+            result => result,
+        }
+    }
+
+    /// A timeout while the server reports ongoing work almost always means it is still indexing
+    async fn describe_timeout(&self, error: String) -> String {
+        let active = self.active_progress.read().await;
+        let mut titles = active.values().flatten().cloned().collect::<Vec<_>>();
+        if titles.is_empty() {
+            return error;
+        }
+        titles.sort();
+        titles.dedup();
+        format!(
+            "Language server is still indexing ({}); try again in a moment",
+            titles.join(", ")
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,12 +125,80 @@ pub struct DiagnosticEvent {
     pub diagnostics: Vec<LspDiagnostic>,
 }
 
+/// A `window/showMessage` or `window/logMessage` notification forwarded to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerMessageEvent {
+    pub language: String,
+    /// "show" for messages the server wants surfaced, "log" for log output
+    pub kind: String,
+    /// One of "error", "warning", "info" or "log"
+    pub severity: String,
+    pub message: String,
+}
+
+/// Work-done progress reported by a server through `$/progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub language: String,
+    pub token: String,
+    /// One of "begin", "report" or "end"
+    pub kind: String,
+    pub title: Option<String>,
+    pub message: Option<String>,
+    pub percentage: Option<u32>,
+    pub done: bool,
+}
+
+/// Lifecycle change of a language server process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerStatusEvent {
+    pub language: String,
+    /// One of "running", "crashed", "restarting" or "failed"
+    pub status: String,
+    pub attempt: Option<u32>,
+    pub message: Option<String>,
+}
+
+/// State of one language server, for the health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerHealth {
+    pub language: String,
+    /// The process is still running
+    pub alive: bool,
+    pub restarting: bool,
+    /// Restarts since the server was last stable
+    pub restart_attempts: u32,
+    /// Titles of work the server reports in progress, e.g. indexing
+    pub progress: Vec<String>,
+}
+
+/// Last known content of a document the editor has open, used to re-open it after a restart
+#[derive(Debug, Clone)]
+struct TrackedDocument {
+    language: String,
+    content: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LspLocation {
     pub path: String,
     pub range: LspRange,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: Option<String>,
+    pub detail: Option<String>,
+    pub insert_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoverResult {
+    pub contents: String,
+    pub range: Option<LspRange>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenameFileEdit {
     pub path: String,
@@ -71,16 +212,36 @@ pub struct RenameResult {
     pub edits: Vec<RenameFileEdit>,
 }
 
-/// Central manager for all language servers
+/// Central manager for all language servers of one window's project
 pub struct LspManager {
     servers: RwLock<HashMap<String, Arc<LanguageServer>>>,
     root_path: RwLock<Option<String>>,
     doc_versions: RwLock<HashMap<String, i32>>,
     diagnostics: Arc<RwLock<HashMap<String, Vec<LspDiagnostic>>>>,
     app_handle: Arc<RwLock<Option<AppHandle>>>,
+    /// Label of the window the events go to; every window when None
+    window: Option<String>,
+    /// Latest in-flight request id per `language:method:path`, used to cancel superseded requests
+    in_flight: Mutex<HashMap<String, u64>>,
+    timeouts: Arc<std::sync::RwLock<RequestTimeouts>>,
+    open_documents: RwLock<HashMap<String, TrackedDocument>>,
+    /// Languages whose server is currently being restarted by its supervisor
+    restarting: Mutex<HashSet<String>>,
+    restart_attempts: Mutex<HashMap<String, u32>>,
+    weak_self: Weak<LspManager>,
 }
 
 impl LspManager {
+    /// Create the manager of a window, which can supervise and restart its servers
+    pub fn for_window(app_handle: AppHandle, window: &str) -> Arc<Self> {
+        Arc::new_cyclic(|weak| Self {
+            app_handle: Arc::new(RwLock::new(Some(app_handle))),
+            window: Some(window.to_string()),
+            weak_self: weak.clone(),
+            ..Self::new()
+        })
+    }
+
     pub fn new() -> Self {
         Self {
             servers: RwLock::new(HashMap::new()),
@@ -88,12 +249,32 @@ impl LspManager {
             doc_versions: RwLock::new(HashMap::new()),
             diagnostics: Arc::new(RwLock::new(HashMap::new())),
             app_handle: Arc::new(RwLock::new(None)),
+            window: None,
+            in_flight: Mutex::new(HashMap::new()),
+            timeouts: Arc::new(std::sync::RwLock::new(RequestTimeouts::default())),
+            open_documents: RwLock::new(HashMap::new()),
+            restarting: Mutex::new(HashSet::new()),
+            restart_attempts: Mutex::new(HashMap::new()),
+            weak_self: Weak::new(),
         }
     }
 
-    pub async fn set_app_handle(&self, app_handle: AppHandle) {
-        let mut handle = self.app_handle.write().await;
-        *handle = Some(app_handle);
+    pub fn request_timeouts(&self) -> RequestTimeouts {
+        self.timeouts
+            .read()
+            .map(|timeouts| timeouts.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace request timeouts; applies to running servers as well as new ones
+    pub fn set_request_timeouts(&self, timeouts: RequestTimeouts) -> Result<(), String> {
+        let mut current = self.timeouts.write().map_err(|e| e.to_string())?;
+        *current = timeouts;
+        Ok(())
+    }
+
+    pub async fn root_path(&self) -> Option<String> {
+        self.root_path.read().await.clone()
     }
 
     /// Set the workspace root path
@@ -102,6 +283,14 @@ impl LspManager {
         *root = Some(path);
         self.diagnostics.write().await.clear();
         self.doc_versions.write().await.clear();
+        self.open_documents.write().await.clear();
+    }
+
+    /// Stop every server, for a window that has closed; dropping a server kills it
+    pub async fn shutdown(&self) {
+        self.servers.write().await.clear();
+        self.open_documents.write().await.clear();
+        self.diagnostics.write().await.clear();
     }
 
     /// Start a language server if not already running
@@ -112,6 +301,14 @@ impl LspManager {
                 return Ok(Arc::clone(server));
             }
         }
+        if self.restarting.lock().await.contains(language) {
+            return Err(format!("Language server for {} is restarting", language));
+        }
+
+        self.start_server(language).await
+    }
+
+    async fn start_server(&self, language: &str) -> Result<Arc<LanguageServer>, String> {
         let app_handle = self
             .app_handle
             .read()
@@ -126,91 +323,317 @@ impl LspManager {
 
         let args_refs: Vec<&str> = resolved.args.iter().map(|arg| arg.as_str()).collect();
         let (notification_tx, notification_rx) = mpsc::unbounded_channel();
-        let (transport, _handle) =
-            match LspTransport::spawn(&resolved.command, &args_refs, Some(notification_tx)).await {
-                Ok(result) => result,
-                Err(error) => {
-                    let message = format!(
-                        "Failed to start LSP server '{}' for {}: {}",
-                        resolved.command, language, error
-                    );
-                    return Err(message);
-                }
-            };
+        let (transport, exit_handle) = match LspTransport::spawn(
+            &resolved.command,
+            &args_refs,
+            Some(notification_tx),
+            Arc::clone(&self.timeouts),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(error) => {
+                let message = format!(
+                    "Failed to start LSP server '{}' for {}: {}",
+                    resolved.command, language, error
+                );
+                return Err(message);
+            }
+        };
 
         let server = Arc::new(LanguageServer {
             transport: Arc::new(transport),
+            active_progress: Arc::new(RwLock::new(HashMap::new())),
         });
 
         if let Err(error) = self.initialize_server(&server).await {
             return Err(error);
         }
-        self.spawn_notification_handler(notification_rx);
+        self.spawn_notification_handler(
+            language,
+            Arc::clone(&server.active_progress),
+            notification_rx,
+        );
 
         {
             let mut servers = self.servers.write().await;
             servers.insert(language.to_string(), Arc::clone(&server));
         }
+        self.spawn_supervisor(language, &server, exit_handle);
 
         Ok(server)
     }
 
-    fn spawn_notification_handler(&self, mut notification_rx: mpsc::UnboundedReceiver<Value>) {
+    /// Wait for the server process to exit and restart it unless it was replaced on purpose
+    fn spawn_supervisor(
+        &self,
+        language: &str,
+        server: &Arc<LanguageServer>,
+        exit_handle: tokio::task::JoinHandle<Option<i32>>,
+    ) {
+        let manager = self.weak_self.clone();
+        let server = Arc::downgrade(server);
+        let language = language.to_string();
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let exit_code = exit_handle.await.ok().flatten();
+            let Some(manager) = manager.upgrade() else {
+                return;
+            };
+            manager
+                .handle_server_exit(language, server, exit_code, started.elapsed())
+                .await;
+        });
+    }
+
+    async fn handle_server_exit(
+        &self,
+        language: String,
+        server: Weak<LanguageServer>,
+        exit_code: Option<i32>,
+        uptime: Duration,
+    ) {
+        {
+            let mut servers = self.servers.write().await;
+            match servers.get(&language) {
+                Some(current) if Weak::ptr_eq(&Arc::downgrade(current), &server) => {
+                    servers.remove(&language);
+                }
+                // Replaced or shut down deliberately
+                _ => return,
+            }
+        }
+
+        let reason = match exit_code {
+            Some(code) => format!("Language server exited with code {}", code),
+            None => "Language server was terminated".to_string(),
+        };
+        tracing::warn!("{} server crashed: {}", language, reason);
+        self.emit_server_status(&language, "crashed", None, Some(reason.clone()))
+            .await;
+
+        self.restarting.lock().await.insert(language.clone());
+        let previous_attempts = self
+            .restart_attempts
+            .lock()
+            .await
+            .get(&language)
+            .copied()
+            .unwrap_or(0);
+        let mut attempt = attempts_so_far(previous_attempts, uptime);
+
+        loop {
+            attempt += 1;
+            let Some(delay) = restart_delay(attempt) else {
+                self.restarting.lock().await.remove(&language);
+                self.restart_attempts.lock().await.remove(&language);
+                self.emit_server_status(
+                    &language,
+                    "failed",
+                    Some(attempt - 1),
+                    Some(format!(
+                        "Gave up after {} restart attempts: {}",
+                        MAX_RESTART_ATTEMPTS, reason
+                    )),
+                )
+                .await;
+                return;
+            };
+
+            self.emit_server_status(&language, "restarting", Some(attempt), None)
+                .await;
+            tokio::time::sleep(delay).await;
+
+            match self.start_server(&language).await {
+                Ok(server) => {
+                    self.restart_attempts
+                        .lock()
+                        .await
+                        .insert(language.clone(), attempt);
+                    self.restarting.lock().await.remove(&language);
+                    self.reopen_documents(&language, &server).await;
+                    self.emit_server_status(&language, "running", Some(attempt), None)
+                        .await;
+                    return;
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        "Restart attempt {} for {} failed: {}",
+                        attempt,
+                        language,
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Send didOpen again for every tracked document of a freshly started server
+    async fn reopen_documents(&self, language: &str, server: &Arc<LanguageServer>) {
+        let documents = self
+            .open_documents
+            .read()
+            .await
+            .iter()
+            .filter(|(_, document)| document.language == language)
+            .map(|(path, document)| (path.clone(), document.content.clone()))
+            .collect::<Vec<_>>();
+
+        for (path, content) in documents {
+            self.doc_versions.write().await.insert(path.clone(), 1);
+            let result = protocol::create_did_open_params(&path, &content, 1).and_then(|params| {
+                server
+                    .transport
+                    .send_notification("textDocument/didOpen", params)
+            });
+            if let Err(error) = result {
+                tracing::warn!("Failed to re-open {}: {}", path, error);
+            }
+        }
+    }
+
+    async fn emit_server_status(
+        &self,
+        language: &str,
+        status: &str,
+        attempt: Option<u32>,
+        message: Option<String>,
+    ) {
+        if let Some(app) = self.app_handle.read().await.clone() {
+            emit_to_window(
+                &app,
+                self.window.as_deref(),
+                SERVER_STATUS_EVENT,
+                ServerStatusEvent {
+                    language: language.to_string(),
+                    status: status.to_string(),
+                    attempt,
+                    message,
+                },
+            );
+        }
+    }
+
+    fn spawn_notification_handler(
+        &self,
+        language: &str,
+        active_progress: Arc<RwLock<HashMap<String, Option<String>>>>,
+        mut notification_rx: mpsc::UnboundedReceiver<Value>,
+    ) {
         let diagnostics = Arc::clone(&self.diagnostics);
         let app_handle = Arc::clone(&self.app_handle);
+        let window = self.window.clone();
+        let language = language.to_string();
 
         tokio::spawn(async move {
             while let Some(message) = notification_rx.recv().await {
                 let method = message.get("method").and_then(|v| v.as_str()).unwrap_or("");
-                if method != "textDocument/publishDiagnostics" {
-                    continue;
-                }
-
                 let Some(params) = message.get("params").cloned() else {
                     continue;
                 };
 
-                let Ok(params) = serde_json::from_value::<PublishDiagnosticsParams>(params) else {
-                    continue;
-                };
-
-                let Ok(path) = uri_to_path(&params.uri) else {
-                    continue;
-                };
-
-                let converted = params
-                    .diagnostics
-                    .into_iter()
-                    .map(|diagnostic| LspDiagnostic {
-                        path: path.clone(),
-                        message: diagnostic.message,
-                        severity: diagnostic.severity.map(diagnostic_severity_to_u32),
-                        source: diagnostic.source,
-                        code: diagnostic.code.map(|code| match code {
-                            lsp_types::NumberOrString::String(value) => value,
-                            lsp_types::NumberOrString::Number(value) => value.to_string(),
-                        }),
-                        range: to_range(diagnostic.range),
-                    })
-                    .collect::<Vec<_>>();
-
-                {
-                    let mut map = diagnostics.write().await;
-                    if converted.is_empty() {
-                        map.remove(&path);
-                    } else {
-                        map.insert(path.clone(), converted.clone());
+                match method {
+                    "textDocument/publishDiagnostics" => {
+                        let window = window.as_deref();
+                        handle_publish_diagnostics(&diagnostics, &app_handle, window, params).await;
                     }
-                }
+                    "window/showMessage" | "window/logMessage" => {
+                        let Ok(params) =
+                            serde_json::from_value::<lsp_types::ShowMessageParams>(params)
+                        else {
+                            continue;
+                        };
+
+                        let kind = if method == "window/showMessage" {
+                            "show"
+                        } else {
+                            "log"
+                        };
+                        tracing::info!("{} ({}): {}", language, kind, params.message);
+
+                        if let Some(app) = app_handle.read().await.clone() {
+                            emit_to_window(
+                                &app,
+                                window.as_deref(),
+                                MESSAGE_EVENT,
+                                ServerMessageEvent {
+                                    language: language.clone(),
+                                    kind: kind.to_string(),
+                                    severity: message_type_to_string(params.typ).to_string(),
+                                    message: params.message,
+                                },
+                            );
+                        }
+                    }
+                    "window/workDoneProgress/create" => {
+                        if let Some(token) = params.get("token").and_then(progress_token_to_string)
+                        {
+                            active_progress.write().await.insert(token, None);
+                        }
+                    }
+                    "$/progress" => {
+                        let Some(token) = params.get("token").and_then(progress_token_to_string)
+                        else {
+                            continue;
+                        };
+                        let Some(value) = params.get("value").cloned() else {
+                            continue;
+                        };
+                        let Ok(progress) =
+                            serde_json::from_value::<lsp_types::WorkDoneProgress>(value)
+                        else {
+                            continue;
+                        };
+
+                        let mut progress_titles = active_progress.write().await;
+                        let Some(title_slot) = progress_titles.get_mut(&token) else {
+                            // Not a work-done token we handed out (e.g. partial results)
+                            continue;
+                        };
+
+                        let event = match progress {
+                            lsp_types::WorkDoneProgress::Begin(begin) => {
+                                *title_slot = Some(begin.title.clone());
+                                ProgressEvent {
+                                    language: language.clone(),
+                                    token: token.clone(),
+                                    kind: "begin".to_string(),
+                                    title: Some(begin.title),
+                                    message: begin.message,
+                                    percentage: begin.percentage,
+                                    done: false,
+                                }
+                            }
+                            lsp_types::WorkDoneProgress::Report(report) => ProgressEvent {
+                                language: language.clone(),
+                                token: token.clone(),
+                                kind: "report".to_string(),
+                                title: title_slot.clone(),
+                                message: report.message,
+                                percentage: report.percentage,
+                                done: false,
+                            },
+                            lsp_types::WorkDoneProgress::End(end) => {
+                                let title = progress_titles.remove(&token).flatten();
+                                ProgressEvent {
+                                    language: language.clone(),
+                                    token: token.clone(),
+                                    kind: "end".to_string(),
+                                    title,
+                                    message: end.message,
+                                    percentage: None,
+                                    done: true,
+                                }
+                            }
+                        };
+                        drop(progress_titles);
 
-                if let Some(app) = app_handle.read().await.clone() {
-                    let _ = app.emit(
-                        DIAGNOSTICS_EVENT,
-                        DiagnosticEvent {
-                            path,
-                            diagnostics: converted,
-                        },
-                    );
+                        if let Some(app) = app_handle.read().await.clone() {
+                            emit_to_window(&app, window.as_deref(), PROGRESS_EVENT, event);
+                        }
+                    }
+                    _ => {}
                 }
             }
         });
@@ -239,9 +662,15 @@ impl LspManager {
                 "name": workspace_name
             }],
             "capabilities": {
+                "window": {
+                    "workDoneProgress": true
+                },
                 "workspace": {
                     "workspaceFolders": true,
-                    "configuration": true
+                    "configuration": true,
+                    "didChangeWatchedFiles": {
+                        "dynamicRegistration": false
+                    }
                 },
                 "textDocument": {
                     "synchronization": {
@@ -279,10 +708,7 @@ impl LspManager {
             }
         });
 
-        let _result = server
-            .transport
-            .send_request("initialize", init_params)
-            .await?;
+        let _result = server.request("initialize", init_params).await?;
 
         server
             .transport
@@ -299,14 +725,27 @@ impl LspManager {
         path: &str,
         line: u32,
         character: u32,
-    ) -> Result<Value, String> {
+    ) -> Result<Vec<CompletionItem>, String> {
         let server = self.ensure_server(language).await?;
         let params = protocol::create_completion_params(path, line, character)?;
 
-        server
-            .transport
-            .send_request("textDocument/completion", params)
-            .await
+        let result = self
+            .send_latest_request(&server, language, path, "textDocument/completion", params)
+            .await?;
+
+        if result.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let response = serde_json::from_value::<CompletionResponse>(result)
+            .map_err(|e| format!("Failed to parse completion response: {}", e))?;
+
+        let items = match response {
+            CompletionResponse::Array(items) => items,
+            CompletionResponse::List(list) => list.items,
+        };
+
+        Ok(items.into_iter().map(to_completion_item).collect())
     }
 
     /// Request hover info at a position
@@ -316,14 +755,72 @@ impl LspManager {
         path: &str,
         line: u32,
         character: u32,
-    ) -> Result<Value, String> {
+    ) -> Result<Option<HoverResult>, String> {
         let server = self.ensure_server(language).await?;
         let params = protocol::create_hover_params(path, line, character)?;
 
-        server
-            .transport
-            .send_request("textDocument/hover", params)
-            .await
+        let result = self
+            .send_latest_request(&server, language, path, "textDocument/hover", params)
+            .await?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        let hover = serde_json::from_value::<Hover>(result)
+            .map_err(|e| format!("Failed to parse hover response: {}", e))?;
+
+        let contents = match hover.contents {
+            HoverContents::Scalar(value) => marked_string_to_text(value),
+            HoverContents::Array(values) => values
+                .into_iter()
+                .map(marked_string_to_text)
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            HoverContents::Markup(markup) => markup.value,
+        };
+
+        if contents.trim().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(HoverResult {
+            contents,
+            range: hover.range.map(to_range),
+        }))
+    }
+
+    /// Send a request where only the newest one per document matters ("latest request wins").
+    /// Any earlier request for the same method and document that is still waiting gets cancelled.
+    async fn send_latest_request(
+        &self,
+        server: &Arc<LanguageServer>,
+        language: &str,
+        path: &str,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        let key = format!("{}:{}:{}", language, method, path);
+        let id = server.transport.next_request_id().await;
+
+        let superseded = self.in_flight.lock().await.insert(key.clone(), id);
+        if let Some(previous_id) = superseded {
+            if let Err(error) = server.transport.cancel_request(previous_id).await {
+                tracing::warn!("Failed to cancel request {}: {}", previous_id, error);
+            }
+        }
+
+        let result = server.request_with_id(id, method, params).await;
+
+        {
+            let mut in_flight = self.in_flight.lock().await;
+            if in_flight.get(&key) == Some(&id) {
+                in_flight.remove(&key);
+            }
+        }
+
+        result
     }
 
     pub async fn definition(
@@ -335,10 +832,7 @@ impl LspManager {
     ) -> Result<Vec<LspLocation>, String> {
         let server = self.ensure_server(language).await?;
         let params = protocol::create_definition_params(path, line, character)?;
-        let result = server
-            .transport
-            .send_request("textDocument/definition", params)
-            .await?;
+        let result = server.request("textDocument/definition", params).await?;
 
         if result.is_null() {
             return Ok(Vec::new());
@@ -389,8 +883,7 @@ impl LspManager {
         };
 
         let result = server
-            .transport
-            .send_request(
+            .request(
                 "textDocument/references",
                 serde_json::to_value(params).map_err(|e| e.to_string())?,
             )
@@ -430,8 +923,7 @@ impl LspManager {
         };
 
         let result = server
-            .transport
-            .send_request(
+            .request(
                 "textDocument/rename",
                 serde_json::to_value(params).map_err(|e| e.to_string())?,
             )
@@ -458,6 +950,13 @@ impl LspManager {
             let mut versions = self.doc_versions.write().await;
             versions.insert(path.to_string(), 1);
         }
+        self.open_documents.write().await.insert(
+            path.to_string(),
+            TrackedDocument {
+                language: language.to_string(),
+                content: content.to_string(),
+            },
+        );
 
         let params = protocol::create_did_open_params(path, content, 1)?;
 
@@ -482,6 +981,14 @@ impl LspManager {
             *v
         };
 
+        self.open_documents.write().await.insert(
+            path.to_string(),
+            TrackedDocument {
+                language: language.to_string(),
+                content: content.to_string(),
+            },
+        );
+
         let params = protocol::create_did_change_params(path, content, version)?;
 
         server
@@ -489,6 +996,84 @@ impl LspManager {
             .send_notification("textDocument/didChange", params)
     }
 
+    /// Notify every running server about files changed outside the editor
+    /// (file watcher events, AI tool writes). Relative paths resolve against the workspace root.
+    pub async fn did_change_watched_files(
+        &self,
+        changes: Vec<(String, FileChangeType)>,
+    ) -> Result<(), String> {
+        let servers = self
+            .servers
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        if servers.is_empty() || changes.is_empty() {
+            return Ok(());
+        }
+
+        let root_path = self.root_path.read().await.clone();
+        let mut events = Vec::with_capacity(changes.len());
+        for (path, typ) in changes {
+            let resolved = match &root_path {
+                Some(root) if Path::new(&path).is_relative() => {
+                    pathbuf_to_string(Path::new(root).join(&path))
+                }
+                _ => path,
+            };
+            let Ok(uri) = protocol::path_to_uri(&resolved) else {
+                continue;
+            };
+            events.push(FileEvent { uri, typ });
+        }
+
+        let params = serde_json::to_value(DidChangeWatchedFilesParams { changes: events })
+            .map_err(|e| e.to_string())?;
+
+        for server in servers {
+            server
+                .transport
+                .send_notification("workspace/didChangeWatchedFiles", params.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Every server started so far, including ones being restarted, sorted by language
+    pub async fn server_health(&self) -> Vec<ServerHealth> {
+        let servers = self.servers.read().await;
+        let restarting = self.restarting.lock().await;
+        let attempts = self.restart_attempts.lock().await;
+        let mut languages = servers
+            .keys()
+            .chain(restarting.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        languages.sort();
+        languages.dedup();
+
+        let mut health = Vec::with_capacity(languages.len());
+        for language in languages {
+            let server = servers.get(&language);
+            let progress = match server {
+                Some(server) => {
+                    let active = server.active_progress.read().await;
+                    active.values().flatten().cloned().collect()
+                }
+                None => Vec::new(),
+            };
+            health.push(ServerHealth {
+                alive: server.is_some_and(|server| server.transport.is_alive()),
+                restarting: restarting.contains(&language),
+                restart_attempts: attempts.get(&language).copied().unwrap_or(0),
+                progress,
+                language,
+            });
+        }
+        health
+    }
+
     pub async fn list_diagnostics(&self) -> Vec<LspDiagnostic> {
         let diagnostics = self.diagnostics.read().await;
         diagnostics
@@ -504,6 +1089,71 @@ impl Default for LspManager {
     }
 }
 
+/// Send an event to a manager's window, or to every window when it has none
+fn emit_to_window<S: Serialize + Clone>(
+    app: &AppHandle,
+    window: Option<&str>,
+    event: &str,
+    payload: S,
+) {
+    let _ = match window {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit(event, payload),
+    };
+}
+
+async fn handle_publish_diagnostics(
+    diagnostics: &RwLock<HashMap<String, Vec<LspDiagnostic>>>,
+    app_handle: &RwLock<Option<AppHandle>>,
+    window: Option<&str>,
+    params: Value,
+) {
+    let Ok(params) = serde_json::from_value::<PublishDiagnosticsParams>(params) else {
+        return;
+    };
+
+    let Ok(path) = uri_to_path(&params.uri) else {
+        return;
+    };
+
+    let converted = params
+        .diagnostics
+        .into_iter()
+        .map(|diagnostic| LspDiagnostic {
+            path: path.clone(),
+            message: diagnostic.message,
+            severity: diagnostic.severity.map(diagnostic_severity_to_u32),
+            source: diagnostic.source,
+            code: diagnostic.code.map(|code| match code {
+                lsp_types::NumberOrString::String(value) => value,
+                lsp_types::NumberOrString::Number(value) => value.to_string(),
+            }),
+            range: to_range(diagnostic.range),
+        })
+        .collect::<Vec<_>>();
+
+    {
+        let mut map = diagnostics.write().await;
+        if converted.is_empty() {
+            map.remove(&path);
+        } else {
+            map.insert(path.clone(), converted.clone());
+        }
+    }
+
+    if let Some(app) = app_handle.read().await.clone() {
+        emit_to_window(
+            &app,
+            window,
+            DIAGNOSTICS_EVENT,
+            DiagnosticEvent {
+                path,
+                diagnostics: converted,
+            },
+        );
+    }
+}
+
 fn to_range(range: lsp_types::Range) -> LspRange {
     LspRange {
         start: LspPosition {
@@ -517,6 +1167,58 @@ fn to_range(range: lsp_types::Range) -> LspRange {
     }
 }
 
+fn to_completion_item(item: lsp_types::CompletionItem) -> CompletionItem {
+    let insert_text = match item.text_edit {
+        Some(lsp_types::CompletionTextEdit::Edit(edit)) => Some(edit.new_text),
+        Some(lsp_types::CompletionTextEdit::InsertAndReplace(edit)) => Some(edit.new_text),
+        None => item.insert_text,
+    };
+
+    CompletionItem {
+        kind: item
+            .kind
+            .map(|kind| completion_kind_to_string(kind).to_string()),
+        detail: item
+            .detail
+            .or_else(|| item.label_details.and_then(|details| details.description)),
+        insert_text: insert_text.or_else(|| Some(item.label.clone())),
+        label: item.label,
+    }
+}
+
+/// Maps LSP completion kinds onto the completion types the editor knows how to render
+fn completion_kind_to_string(kind: lsp_types::CompletionItemKind) -> &'static str {
+    match kind {
+        lsp_types::CompletionItemKind::METHOD => "method",
+        lsp_types::CompletionItemKind::FUNCTION | lsp_types::CompletionItemKind::CONSTRUCTOR => {
+            "function"
+        }
+        lsp_types::CompletionItemKind::FIELD | lsp_types::CompletionItemKind::PROPERTY => {
+            "property"
+        }
+        lsp_types::CompletionItemKind::VARIABLE | lsp_types::CompletionItemKind::REFERENCE => {
+            "variable"
+        }
+        lsp_types::CompletionItemKind::CLASS | lsp_types::CompletionItemKind::STRUCT => "class",
+        lsp_types::CompletionItemKind::INTERFACE => "interface",
+        lsp_types::CompletionItemKind::MODULE => "namespace",
+        lsp_types::CompletionItemKind::ENUM => "enum",
+        lsp_types::CompletionItemKind::ENUM_MEMBER
+        | lsp_types::CompletionItemKind::CONSTANT
+        | lsp_types::CompletionItemKind::VALUE => "constant",
+        lsp_types::CompletionItemKind::KEYWORD => "keyword",
+        lsp_types::CompletionItemKind::TYPE_PARAMETER => "type",
+        _ => "text",
+    }
+}
+
+fn marked_string_to_text(value: MarkedString) -> String {
+    match value {
+        MarkedString::String(text) => text,
+        MarkedString::LanguageString(code) => code.value,
+    }
+}
+
 fn diagnostic_severity_to_u32(severity: lsp_types::DiagnosticSeverity) -> u32 {
     match severity {
         lsp_types::DiagnosticSeverity::ERROR => 1,
@@ -527,6 +1229,23 @@ fn diagnostic_severity_to_u32(severity: lsp_types::DiagnosticSeverity) -> u32 {
     }
 }
 
+fn progress_token_to_string(token: &Value) -> Option<String> {
+    match token {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn message_type_to_string(message_type: lsp_types::MessageType) -> &'static str {
+    match message_type {
+        lsp_types::MessageType::ERROR => "error",
+        lsp_types::MessageType::WARNING => "warning",
+        lsp_types::MessageType::INFO => "info",
+        _ => "log",
+    }
+}
+
 fn to_location(location: lsp_types::Location) -> Result<LspLocation, String> {
     Ok(LspLocation {
         path: uri_to_path(&location.uri)?,
@@ -676,3 +1395,22 @@ fn position_to_offset(
 
     Ok(offset.min(line_end))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restarts_back_off_give_up_and_start_over_after_a_stable_run() {
+        let delays: Vec<u64> = (1..=MAX_RESTART_ATTEMPTS)
+            .map(|attempt| restart_delay(attempt).unwrap().as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16]);
+        assert_eq!(restart_delay(MAX_RESTART_ATTEMPTS + 1), None);
+        assert_eq!(restart_backoff(6), Duration::from_secs(30));
+        assert_eq!(restart_backoff(40), Duration::from_secs(30));
+
+        assert_eq!(attempts_so_far(3, Duration::from_secs(5)), 3);
+        assert_eq!(attempts_so_far(3, STABLE_UPTIME), 0);
+    }
+}
@@ -41,6 +41,16 @@ pub fn language_id_from_extension(ext: &str) -> &'static str {
         "css" => "css",
         "json" => "json",
         "md" => "markdown",
+        "go" => "go",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "scss" => "scss",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "sql" => "sql",
+        "sh" | "bash" | "zsh" => "shellscript",
         _ => "plaintext",
     }
 }
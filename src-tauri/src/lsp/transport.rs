@@ -1,50 +1,108 @@
 // LSP Transport Layer
 // Handles JSON-RPC message framing over stdin/stdout with proper request/response routing
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Read, Write};
-use std::process::{ChildStdin, ChildStdout, Stdio};
-use std::sync::Arc;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, ChildStdout};
 use tokio::sync::{mpsc, oneshot, Mutex};
 
-/// Sender for stdin writes (thread-safe)
-pub struct StdinWriter {
-    stdin: std::sync::Mutex<ChildStdin>,
+use crate::metrics;
+
+/// JSON-RPC error code servers use to answer a cancelled request
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// Error code used locally to fail requests that were pending when the server exited
+const SERVER_EXITED: i64 = -32099;
+
+/// Prefix of the error returned when a request runs past its timeout
+pub const REQUEST_TIMED_OUT: &str = "Request timed out";
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// Request timeouts in milliseconds, with per-method overrides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTimeouts {
+    pub default_ms: u64,
+    pub per_method_ms: HashMap<String, u64>,
 }
 
-impl StdinWriter {
-    fn new(stdin: ChildStdin) -> Self {
-        Self {
-            stdin: std::sync::Mutex::new(stdin),
-        }
+impl RequestTimeouts {
+    pub fn for_method(&self, method: &str) -> Duration {
+        let ms = self
+            .per_method_ms
+            .get(method)
+            .copied()
+            .unwrap_or(self.default_ms);
+        Duration::from_millis(ms.max(1))
     }
+}
 
-    pub fn write_message(&self, message: &Value) -> Result<(), String> {
-        let content = serde_json::to_string(message).map_err(|e| e.to_string())?;
-        let header = format!("Content-Length: {}\r\n\r\n", content.len());
-
-        let mut stdin = self.stdin.lock().map_err(|e| e.to_string())?;
-        stdin
-            .write_all(header.as_bytes())
-            .map_err(|e| e.to_string())?;
-        stdin
-            .write_all(content.as_bytes())
-            .map_err(|e| e.to_string())?;
-        stdin.flush().map_err(|e| e.to_string())?;
-
-        Ok(())
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        let per_method_ms = [
+            // rust-analyzer and tsserver can take a long time to answer on a cold project
+            ("initialize", 60_000),
+            ("textDocument/completion", 30_000),
+            // A hover that shows up late is worse than none at all
+            ("textDocument/hover", 3_000),
+            ("textDocument/definition", 15_000),
+            ("textDocument/references", 20_000),
+            ("textDocument/rename", 20_000),
+        ]
+        .into_iter()
+        .map(|(method, ms)| (method.to_string(), ms))
+        .collect();
+
+        Self {
+            default_ms: 10_000,
+            per_method_ms,
+        }
     }
 }
 
-/// LSP Transport with proper request/response routing
-pub struct LspTransport {
-    writer: Arc<StdinWriter>,
-    pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
-    next_id: Mutex<u64>,
+/// Sender for stdin writes. Messages are framed here and handed to a dedicated
+/// writer task, so callers never block on the server's stdin pipe.
+pub struct StdinWriter {
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
 }
 
 impl StdinWriter {
+    fn spawn(mut stdin: ChildStdin) -> Self {
+        let (outgoing, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
+
+        tokio::spawn(async move {
+            while let Some(frame) = rx.recv().await {
+                if let Err(e) = stdin.write_all(&frame).await {
+                    tracing::warn!("Failed to write to server: {}", e);
+                    break;
+                }
+                if let Err(e) = stdin.flush().await {
+                    tracing::warn!("Failed to flush server stdin: {}", e);
+                    break;
+                }
+            }
+            tracing::debug!("Writer task ended");
+        });
+
+        Self { outgoing }
+    }
+
+    pub fn write_message(&self, message: &Value) -> Result<(), String> {
+        let content = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        let mut frame = format!("Content-Length: {}\r\n\r\n", content.len()).into_bytes();
+        frame.extend_from_slice(content.as_bytes());
+
+        self.outgoing
+            .send(frame)
+            .map_err(|_| "Language server stdin is closed".to_string())
+    }
+
     /// Send a response to a server request
     pub fn send_response(&self, id: Value, result: Value) -> Result<(), String> {
         let response = serde_json::json!({
@@ -56,19 +114,31 @@ impl StdinWriter {
     }
 }
 
+/// LSP Transport with proper request/response routing
+pub struct LspTransport {
+    writer: Arc<StdinWriter>,
+    pending_requests: PendingRequests,
+    next_id: Mutex<u64>,
+    timeouts: Arc<RwLock<RequestTimeouts>>,
+    alive: Arc<AtomicBool>,
+    /// Dropping this (together with the transport) kills the server process
+    _kill_tx: oneshot::Sender<()>,
+}
+
 impl LspTransport {
     /// Spawns a new language server process and sets up communication
     pub async fn spawn(
         command: &str,
         args: &[&str],
         notification_tx: Option<mpsc::UnboundedSender<Value>>,
-    ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
+        timeouts: Arc<RwLock<RequestTimeouts>>,
+    ) -> Result<(Self, tokio::task::JoinHandle<Option<i32>>), String> {
         let mut cmd = if cfg!(windows) && !command.ends_with(".exe") {
-            let mut c = std::process::Command::new("cmd");
+            let mut c = tokio::process::Command::new("cmd");
             c.arg("/C").arg(command);
             c
         } else {
-            std::process::Command::new(command)
+            tokio::process::Command::new(command)
         };
 
         let mut child = cmd
@@ -76,170 +146,221 @@ impl LspTransport {
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
+            .kill_on_drop(true)
             .spawn()
             .map_err(|e| format!("Failed to spawn LSP ({}): {}", command, e))?;
 
         let stdin = child.stdin.take().ok_or("Failed to capture stdin")?;
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
 
-        let writer = Arc::new(StdinWriter::new(stdin));
-        let pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>> =
-            Arc::new(Mutex::new(HashMap::new()));
-
-        // Clone for the background reader
-        let pending_clone = Arc::clone(&pending_requests);
-        let writer_clone = Arc::clone(&writer);
+        let writer = Arc::new(StdinWriter::spawn(stdin));
+        let pending_requests: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
 
         // Spawn a background task to read all responses and route them
-        let handle = tokio::task::spawn_blocking(move || {
-            let reader = BufReader::new(stdout);
-            Self::read_loop(reader, pending_clone, writer_clone, notification_tx);
-        });
+        tokio::spawn(Self::read_loop(
+            BufReader::new(stdout),
+            Arc::clone(&pending_requests),
+            Arc::clone(&writer),
+            notification_tx,
+        ));
+
+        // Watch the process: resolves with the exit code once the server is gone,
+        // after failing every request that was still waiting on it
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        let alive = Arc::new(AtomicBool::new(true));
+        let monitor = {
+            let pending = Arc::clone(&pending_requests);
+            let alive = Arc::clone(&alive);
+            tokio::spawn(async move {
+                let exit_code = tokio::select! {
+                    status = child.wait() => status.ok().and_then(|status| status.code()),
+                    _ = kill_rx => {
+                        let _ = child.kill().await;
+                        None
+                    }
+                };
+
+                alive.store(false, Ordering::SeqCst);
+                tracing::info!("Server process exited (code: {:?})", exit_code);
+                Self::fail_pending(&pending).await;
+                exit_code
+            })
+        };
 
         Ok((
             Self {
                 writer,
                 pending_requests,
                 next_id: Mutex::new(1),
+                timeouts,
+                alive,
+                _kill_tx: kill_tx,
             },
-            handle,
+            monitor,
         ))
     }
 
-    /// Background reader that routes responses to waiting requests
-    fn read_loop(
-        mut reader: BufReader<ChildStdout>,
-        pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
-        writer: Arc<StdinWriter>,
-        notification_tx: Option<mpsc::UnboundedSender<Value>>,
-    ) {
+    /// Whether the server process is still running
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Resolve every pending request with an error instead of letting them time out
+    async fn fail_pending(pending: &PendingRequests) {
+        let drained = pending.lock().await.drain().collect::<Vec<_>>();
+        for (id, tx) in drained {
+            let _ = tx.send(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": SERVER_EXITED,
+                    "message": "Language server exited"
+                }
+            }));
+        }
+    }
+
+    /// Reads one framed message body; `None` once the stream is closed or broken
+    async fn read_message(reader: &mut BufReader<ChildStdout>) -> Option<Vec<u8>> {
+        let mut content_length: Option<usize> = None;
+
+        // Read headers until the blank separator line
         loop {
-            // Read Content-Length header
-            let mut header_line = String::new();
-            if reader.read_line(&mut header_line).unwrap_or(0) == 0 {
-                eprintln!("[LSP Transport] Reader loop ended - no more data");
-                break;
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return None;
             }
 
-            let content_length: usize = if header_line.starts_with("Content-Length:") {
-                header_line
-                    .trim_start_matches("Content-Length:")
-                    .trim()
-                    .parse()
-                    .unwrap_or(0)
-            } else {
-                continue;
-            };
-
-            // Skip empty line (and any other headers like Content-Type)
-            loop {
-                let mut line = String::new();
-                if reader.read_line(&mut line).unwrap_or(0) == 0 {
-                    break;
-                }
-                if line.trim().is_empty() {
+            let line = line.trim();
+            if line.is_empty() {
+                if content_length.is_some() {
                     break;
                 }
+                continue;
             }
 
-            // Read the JSON body
-            let mut body = vec![0u8; content_length];
-            if reader.read_exact(&mut body).is_err() {
-                eprintln!("[LSP Transport] Failed to read body");
-                break;
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                content_length = value.trim().parse().ok();
             }
+        }
 
-            if let Ok(json) = serde_json::from_slice::<Value>(&body) {
-                let has_id = json.get("id").is_some();
-                let has_method = json.get("method").is_some();
-
-                if has_id && !has_method {
-                    // This is a response to our request
-                    if let Some(id) = json.get("id").and_then(|v| v.as_u64()) {
-                        let sender = {
-                            let rt = tokio::runtime::Handle::try_current();
-                            if let Ok(handle) = rt {
-                                handle.block_on(async { pending.lock().await.remove(&id) })
-                            } else {
-                                eprintln!(
-                                    "[LSP Transport] No tokio runtime for routing response id: {}",
-                                    id
-                                );
-                                None
-                            }
-                        };
-
-                        if let Some(tx) = sender {
-                            eprintln!("[LSP Transport] Routing response for id: {}", id);
-                            let _ = tx.send(json);
+        let mut body = vec![0u8; content_length?];
+        if let Err(e) = reader.read_exact(&mut body).await {
+            tracing::warn!("Failed to read body: {}", e);
+            return None;
+        }
+        Some(body)
+    }
+
+    /// Background reader that routes responses to waiting requests
+    async fn read_loop(
+        mut reader: BufReader<ChildStdout>,
+        pending: PendingRequests,
+        writer: Arc<StdinWriter>,
+        notification_tx: Option<mpsc::UnboundedSender<Value>>,
+    ) {
+        while let Some(body) = Self::read_message(&mut reader).await {
+            let Ok(json) = serde_json::from_slice::<Value>(&body) else {
+                tracing::warn!("Dropping malformed message");
+                continue;
+            };
+
+            let has_id = json.get("id").is_some();
+            let has_method = json.get("method").is_some();
+
+            if has_id && !has_method {
+                // This is a response to our request
+                if let Some(id) = json.get("id").and_then(|v| v.as_u64()) {
+                    let sender = pending.lock().await.remove(&id);
+                    if let Some(tx) = sender {
+                        tracing::debug!("Routing response for id: {}", id);
+                        let _ = tx.send(json);
+                    } else {
+                        tracing::debug!("No pending request for id: {}", id);
+                    }
+                }
+            } else if has_id && has_method {
+                // Request from server - we need to respond!
+                let method = json.get("method").and_then(|v| v.as_str()).unwrap_or("");
+                let id = json.get("id").cloned().unwrap_or(Value::Null);
+
+                tracing::debug!("Server request: {} (id: {})", method, id);
+
+                // Handle common server requests
+                let response_result = match method {
+                    "workspace/configuration" => {
+                        // Return empty configuration for each requested item
+                        // The server sends an array of items it wants config for
+                        if let Some(items) = json
+                            .get("params")
+                            .and_then(|p| p.get("items"))
+                            .and_then(|i| i.as_array())
+                        {
+                            // Return an empty object for each config item requested
+                            let configs: Vec<Value> =
+                                items.iter().map(|_| serde_json::json!({})).collect();
+                            serde_json::json!(configs)
                         } else {
-                            eprintln!("[LSP Transport] No pending request for id: {}", id);
+                            serde_json::json!([{}])
                         }
                     }
-                } else if has_id && has_method {
-                    // Request from server - we need to respond!
-                    let method = json.get("method").and_then(|v| v.as_str()).unwrap_or("");
-                    let id = json.get("id").cloned().unwrap_or(Value::Null);
-
-                    eprintln!("[LSP Transport] Server request: {} (id: {})", method, id);
-
-                    // Handle common server requests
-                    let response_result = match method {
-                        "workspace/configuration" => {
-                            // Return empty configuration for each requested item
-                            // The server sends an array of items it wants config for
-                            if let Some(items) = json
-                                .get("params")
-                                .and_then(|p| p.get("items"))
-                                .and_then(|i| i.as_array())
-                            {
-                                // Return an empty object for each config item requested
-                                let configs: Vec<Value> =
-                                    items.iter().map(|_| serde_json::json!({})).collect();
-                                serde_json::json!(configs)
-                            } else {
-                                serde_json::json!([{}])
-                            }
-                        }
-                        "client/registerCapability" => {
-                            // Accept capability registration
-                            serde_json::json!(null)
-                        }
-                        "window/workDoneProgress/create" => {
-                            // Accept progress token creation
-                            serde_json::json!(null)
-                        }
-                        _ => {
-                            eprintln!("[LSP Transport] Unhandled server request: {}", method);
-                            serde_json::json!(null)
-                        }
-                    };
-
-                    // Send response
-                    if let Err(e) = writer.send_response(id, response_result) {
-                        eprintln!("[LSP Transport] Failed to send response: {}", e);
+                    "client/registerCapability" => {
+                        // Accept capability registration
+                        serde_json::json!(null)
                     }
-                } else {
-                    // Notification from server (no id, has method)
-                    if let Some(method) = json.get("method").and_then(|v| v.as_str()) {
-                        eprintln!("[LSP Transport] Notification: {}", method);
+                    "window/workDoneProgress/create" => {
+                        // Accept progress token creation and let the manager track the token
                         if let Some(tx) = &notification_tx {
                             let _ = tx.send(json.clone());
                         }
+                        serde_json::json!(null)
+                    }
+                    _ => {
+                        tracing::warn!("Unhandled server request: {}", method);
+                        serde_json::json!(null)
                     }
+                };
+
+                // Send response
+                if let Err(e) = writer.send_response(id, response_result) {
+                    tracing::warn!("Failed to send response: {}", e);
+                }
+            } else if let Some(method) = json.get("method").and_then(|v| v.as_str()) {
+                // Notification from server (no id, has method)
+                tracing::debug!("Notification: {}", method);
+                if let Some(tx) = &notification_tx {
+                    let _ = tx.send(json.clone());
                 }
             }
         }
+
+        tracing::debug!("Reader loop ended - no more data");
+    }
+
+    /// Reserves the next request id so callers can track (and later cancel) a request
+    pub async fn next_request_id(&self) -> u64 {
+        let mut next = self.next_id.lock().await;
+        let id = *next;
+        *next += 1;
+        id
     }
 
     /// Sends a JSON-RPC request and waits for the response
     pub async fn send_request(&self, method: &str, params: Value) -> Result<Value, String> {
-        let id = {
-            let mut next = self.next_id.lock().await;
-            let id = *next;
-            *next += 1;
-            id
-        };
+        let id = self.next_request_id().await;
+        self.send_request_with_id(id, method, params).await
+    }
+
+    /// Sends a JSON-RPC request using an id reserved with `next_request_id`
+    pub async fn send_request_with_id(
+        &self,
+        id: u64,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        if !self.is_alive() {
+            return Err("Language server is not running".to_string());
+        }
 
         // Create a oneshot channel for the response
         let (tx, rx) = oneshot::channel();
@@ -248,6 +369,11 @@ impl LspTransport {
         {
             let mut pending = self.pending_requests.lock().await;
             pending.insert(id, tx);
+            // The monitor may have drained the map between the check above and the insert
+            if !self.is_alive() {
+                pending.remove(&id);
+                return Err("Language server is not running".to_string());
+            }
         }
 
         // Build and send the request
@@ -258,21 +384,32 @@ impl LspTransport {
             "params": params
         });
 
-        eprintln!(
-            "[LSP Transport] Sending request id: {}, method: {}",
-            id, method
-        );
-        self.writer.write_message(&request)?;
+        tracing::debug!("Sending request id: {}, method: {}", id, method);
+        if let Err(e) = self.writer.write_message(&request) {
+            self.pending_requests.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        let timeout = self
+            .timeouts
+            .read()
+            .map(|timeouts| timeouts.for_method(method))
+            .unwrap_or_else(|_| RequestTimeouts::default().for_method(method));
 
         // Wait for response with timeout
-        match tokio::time::timeout(std::time::Duration::from_secs(10), rx).await {
+        let started = Instant::now();
+        let result = match tokio::time::timeout(timeout, rx).await {
             Ok(Ok(response)) => {
-                eprintln!("[LSP Transport] Got response for id: {}", id);
+                tracing::debug!("Got response for id: {}", id);
                 // Extract result or error
                 if let Some(result) = response.get("result") {
                     Ok(result.clone())
                 } else if let Some(error) = response.get("error") {
-                    Err(format!("LSP error: {:?}", error))
+                    match error.get("code").and_then(|c| c.as_i64()) {
+                        Some(REQUEST_CANCELLED) => Err("Request cancelled".to_string()),
+                        Some(SERVER_EXITED) => Err("Language server exited".to_string()),
+                        _ => Err(format!("LSP error: {:?}", error)),
+                    }
                 } else {
                     Ok(Value::Null)
                 }
@@ -285,10 +422,39 @@ impl LspTransport {
             Err(_) => {
                 // Timeout
                 self.pending_requests.lock().await.remove(&id);
-                eprintln!("[LSP Transport] Request timed out for id: {}", id);
-                Err("Request timed out".to_string())
+                tracing::warn!("Request timed out for id: {}", id);
+                metrics::increment("lsp.timeouts", Some(method), 1);
+                Err(format!(
+                    "{} after {}ms ({})",
+                    REQUEST_TIMED_OUT,
+                    timeout.as_millis(),
+                    method
+                ))
             }
-        }
+        };
+        metrics::record_duration("lsp.request_ms", Some(method), started.elapsed());
+        result
+    }
+
+    /// Cancels an in-flight request: notifies the server via `$/cancelRequest` and
+    /// resolves the local waiter immediately instead of letting it run into the timeout.
+    /// Does nothing if the request already completed.
+    pub async fn cancel_request(&self, id: u64) -> Result<(), String> {
+        let Some(tx) = self.pending_requests.lock().await.remove(&id) else {
+            return Ok(());
+        };
+
+        tracing::debug!("Cancelling request id: {}", id);
+        let _ = tx.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": REQUEST_CANCELLED,
+                "message": "Request cancelled"
+            }
+        }));
+
+        self.send_notification("$/cancelRequest", serde_json::json!({ "id": id }))
     }
 
     /// Sends a JSON-RPC notification (no response expected)
@@ -299,7 +465,31 @@ impl LspTransport {
             "params": params
         });
 
-        eprintln!("[LSP Transport] Sending notification: {}", method);
+        tracing::debug!("Sending notification: {}", method);
         self.writer.write_message(&notification)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_method_timeouts_override_the_default() {
+        let mut timeouts = RequestTimeouts::default();
+        assert_eq!(
+            timeouts.for_method("textDocument/hover"),
+            Duration::from_millis(3_000)
+        );
+        assert_eq!(
+            timeouts.for_method("workspace/symbol"),
+            Duration::from_millis(timeouts.default_ms)
+        );
+
+        timeouts.default_ms = 0;
+        assert_eq!(
+            timeouts.for_method("workspace/symbol"),
+            Duration::from_millis(1)
+        );
+    }
+}
@@ -0,0 +1,172 @@
+// Metrics
+// Counters and duration histograms for the paths that tend to regress: AI requests and
+// tools, LSP requests and file watcher traffic. Off unless the user turns on
+// `diagnostics.metrics_enabled`; nothing leaves the machine, get_metrics only hands the
+// numbers to the frontend.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets in milliseconds; slower samples go in a last one
+const BUCKET_BOUNDS_MS: &[u64] = &[
+    1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static METRICS: OnceLock<Mutex<Metrics>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    pub count: u64,
+    pub sum_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Samples per bucket, matching `bucket_bounds_ms` plus one for anything slower
+    pub buckets: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub enabled: bool,
+    /// When recording started or was last reset, in milliseconds since the Unix epoch
+    pub since: i64,
+    pub bucket_bounds_ms: &'static [u64],
+    pub counters: BTreeMap<String, u64>,
+    pub histograms: BTreeMap<String, Histogram>,
+}
+
+struct Metrics {
+    since: i64,
+    counters: BTreeMap<String, u64>,
+    histograms: BTreeMap<String, Histogram>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            since: chrono::Utc::now().timestamp_millis(),
+            counters: BTreeMap::new(),
+            histograms: BTreeMap::new(),
+        }
+    }
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            sum_ms: 0.0,
+            min_ms: f64::MAX,
+            max_ms: 0.0,
+            buckets: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+        }
+    }
+
+    fn record(&mut self, ms: f64) {
+        self.count += 1;
+        self.sum_ms += ms;
+        self.min_ms = self.min_ms.min(ms);
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound as f64)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+}
+
+fn metrics() -> &'static Mutex<Metrics> {
+    METRICS.get_or_init(|| Mutex::new(Metrics::new()))
+}
+
+/// `name`, or `name[label]` for a per-method or per-tool series
+fn key(name: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{}[{}]", name, label),
+        None => name.to_string(),
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Start or stop recording; what was recorded so far is kept
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn increment(name: &str, label: Option<&str>, by: u64) {
+    if !is_enabled() {
+        return;
+    }
+    if let Ok(mut metrics) = metrics().lock() {
+        *metrics.counters.entry(key(name, label)).or_insert(0) += by;
+    }
+}
+
+pub fn record_duration(name: &str, label: Option<&str>, duration: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    if let Ok(mut metrics) = metrics().lock() {
+        metrics
+            .histograms
+            .entry(key(name, label))
+            .or_insert_with(Histogram::new)
+            .record(duration.as_secs_f64() * 1_000.0);
+    }
+}
+
+pub(crate) fn snapshot() -> Result<MetricsSnapshot, String> {
+    let metrics = metrics().lock().map_err(|e| e.to_string())?;
+    Ok(MetricsSnapshot {
+        enabled: is_enabled(),
+        since: metrics.since,
+        bucket_bounds_ms: BUCKET_BOUNDS_MS,
+        counters: metrics.counters.clone(),
+        histograms: metrics.histograms.clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_metrics() -> Result<MetricsSnapshot, String> {
+    snapshot()
+}
+
+#[tauri::command]
+pub async fn reset_metrics() -> Result<(), String> {
+    *metrics().lock().map_err(|e| e.to_string())? = Metrics::new();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_enabled() {
+        increment("test.ignored", None, 1);
+        set_enabled(true);
+        increment("test.events", Some("watcher"), 3);
+        increment("test.events", Some("watcher"), 2);
+        record_duration("test.request_ms", None, Duration::from_millis(3));
+        record_duration("test.request_ms", None, Duration::from_millis(700));
+        record_duration("test.request_ms", None, Duration::from_secs(60));
+        set_enabled(false);
+        record_duration("test.request_ms", None, Duration::from_millis(1));
+
+        let snapshot = snapshot().unwrap();
+        assert!(!snapshot.counters.contains_key("test.ignored"));
+        assert_eq!(snapshot.counters["test.events[watcher]"], 5);
+        let histogram = &snapshot.histograms["test.request_ms"];
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.buckets[1], 1);
+        assert_eq!(histogram.buckets[8], 1);
+        assert_eq!(histogram.buckets[BUCKET_BOUNDS_MS.len()], 1);
+        assert!(histogram.min_ms >= 3.0 && histogram.max_ms >= 60_000.0);
+    }
+}
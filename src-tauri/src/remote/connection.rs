@@ -0,0 +1,313 @@
+// SSH connections
+// One SSH session per remote machine, with an SFTP channel for file access opened when
+// it connects. Host keys are checked against ~/.ssh/known_hosts. An unknown host is
+// refused with its key's fingerprint, and added once the user connects again accepting
+// that fingerprint; a changed key refuses the connection.
+
+use russh::client::{self, Handle, Handler};
+use russh::keys::{self, HashAlg, PrivateKeyWithHashAlg, PublicKey};
+use russh::Disconnect;
+use russh_sftp::client::SftpSession;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::{ErrorCode, VoidDeskError};
+
+pub(super) const DEFAULT_PORT: u16 = 22;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tried in order when no key file is given
+const DEFAULT_KEY_FILES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteAuth {
+    Password {
+        password: String,
+    },
+    /// A private key file, by default the usual ones in ~/.ssh
+    Key {
+        path: Option<String>,
+        passphrase: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteConnectOptions {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub auth: RemoteAuth,
+    /// The project folder; the user's home folder when not given
+    pub root: Option<String>,
+    /// SHA-256 fingerprint of an unknown host's key the user has checked and accepts,
+    /// as reported by a refused connection
+    #[serde(default)]
+    pub accept_host_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteInfo {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// The project folder as a tagged remote path
+    pub root: String,
+}
+
+pub struct RemoteConnection {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Absolute path of the project folder on the remote machine
+    pub root: String,
+    pub(super) handle: Handle<HostKeyCheck>,
+    pub(super) sftp: SftpSession,
+}
+
+impl RemoteConnection {
+    pub fn info(&self) -> RemoteInfo {
+        RemoteInfo {
+            id: self.id.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            root: super::RemotePath::new(&self.id, &self.root).to_string(),
+        }
+    }
+
+    pub async fn close(&self) {
+        let _ = self.sftp.close().await;
+        let _ = self
+            .handle
+            .disconnect(Disconnect::ByApplication, "", "en")
+            .await;
+    }
+}
+
+/// Connections are named by user, host and port, so paths stay the same across
+/// reconnects
+pub fn connection_id(username: &str, host: &str, port: u16) -> String {
+    format!("{}@{}:{}", username, host, port)
+}
+
+pub(super) struct HostKeyCheck {
+    host: String,
+    port: u16,
+    accept_host_key: Option<String>,
+    /// Why the key was refused, reported instead of russh's generic error
+    rejection: Arc<Mutex<Option<VoidDeskError>>>,
+}
+
+impl Handler for HostKeyCheck {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let fingerprint = server_public_key.fingerprint(HashAlg::Sha256).to_string();
+        let rejection = match keys::check_known_hosts(&self.host, self.port, server_public_key) {
+            Ok(true) => return Ok(true),
+            Ok(false) if self.accept_host_key.as_deref() == Some(fingerprint.as_str()) => {
+                match keys::learn_known_hosts(&self.host, self.port, server_public_key) {
+                    Ok(()) => {
+                        tracing::info!("Added {} ({}) to known_hosts", self.host, fingerprint);
+                        return Ok(true);
+                    }
+                    Err(e) => VoidDeskError::new(
+                        ErrorCode::Io,
+                        format!("Could not record the host key of {}: {}", self.host, e),
+                    ),
+                }
+            }
+            Ok(false) => VoidDeskError::new(
+                ErrorCode::UnknownHostKey,
+                format!(
+                    "{} is not a known host; its key has the fingerprint {}",
+                    self.host, fingerprint
+                ),
+            )
+            .with_details(serde_json::json!({ "host": self.host, "fingerprint": fingerprint })),
+            Err(keys::Error::KeyChanged { line }) => VoidDeskError::new(
+                ErrorCode::PermissionDenied,
+                format!(
+                    "The host key of {} does not match the one in known_hosts (line {}); \
+                     it now has the key {}",
+                    self.host, line, fingerprint
+                ),
+            )
+            .with_details(serde_json::json!({ "host": self.host, "fingerprint": fingerprint })),
+            Err(e) => VoidDeskError::internal(format!(
+                "Could not check the host key of {}: {}",
+                self.host, e
+            )),
+        };
+        if let Ok(mut slot) = self.rejection.lock() {
+            *slot = Some(rejection);
+        }
+        Ok(false)
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn key_files(path: Option<&str>) -> Vec<PathBuf> {
+    match path {
+        Some(path) => vec![PathBuf::from(path)],
+        None => home_dir()
+            .map(|home| {
+                DEFAULT_KEY_FILES
+                    .iter()
+                    .map(|name| home.join(".ssh").join(name))
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+async fn authenticate(
+    handle: &mut Handle<HostKeyCheck>,
+    username: &str,
+    auth: &RemoteAuth,
+) -> Result<(), String> {
+    match auth {
+        RemoteAuth::Password { password } => {
+            let result = handle
+                .authenticate_password(username, password)
+                .await
+                .map_err(|e| e.to_string())?;
+            if result.success() {
+                return Ok(());
+            }
+            Err(format!("The password for {} was not accepted", username))
+        }
+        RemoteAuth::Key { path, passphrase } => {
+            let files = key_files(path.as_deref());
+            if files.is_empty() {
+                return Err("No SSH key found in ~/.ssh".to_string());
+            }
+            // A key that cannot be loaded, say for a wrong passphrase, leaves the others
+            let mut load_errors = Vec::new();
+            for file in files {
+                let key = match keys::load_secret_key(&file, passphrase.as_deref()) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        load_errors.push(format!("could not load {}: {}", file.display(), e));
+                        continue;
+                    }
+                };
+                // RSA keys are signed with the best hash the server supports
+                let hash = handle
+                    .best_supported_rsa_hash()
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .flatten();
+                let result = handle
+                    .authenticate_publickey(
+                        username,
+                        PrivateKeyWithHashAlg::new(Arc::new(key), hash),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if result.success() {
+                    return Ok(());
+                }
+            }
+            if load_errors.is_empty() {
+                return Err(format!("No SSH key was accepted for {}", username));
+            }
+            Err(format!(
+                "No SSH key was accepted for {} ({})",
+                username,
+                load_errors.join("; ")
+            ))
+        }
+    }
+}
+
+/// Connect, log in and start SFTP
+pub async fn connect(options: RemoteConnectOptions) -> Result<RemoteConnection, VoidDeskError> {
+    let port = options.port.unwrap_or(DEFAULT_PORT);
+    let config = Arc::new(client::Config {
+        inactivity_timeout: None,
+        keepalive_interval: Some(KEEPALIVE_INTERVAL),
+        ..Default::default()
+    });
+    let rejection = Arc::new(Mutex::new(None));
+    let handler = HostKeyCheck {
+        host: options.host.clone(),
+        port,
+        accept_host_key: options.accept_host_key.clone(),
+        rejection: rejection.clone(),
+    };
+
+    let connecting = client::connect(config, (options.host.as_str(), port), handler);
+    let mut handle = match tokio::time::timeout(CONNECT_TIMEOUT, connecting).await {
+        Err(_) => {
+            return Err(VoidDeskError::new(
+                ErrorCode::Timeout,
+                format!("Timed out connecting to {}", options.host),
+            ))
+        }
+        Ok(Err(e)) => {
+            let rejection = rejection.lock().ok().and_then(|mut slot| slot.take());
+            return Err(rejection.unwrap_or_else(|| {
+                VoidDeskError::new(
+                    ErrorCode::Io,
+                    format!("Could not connect to {}: {}", options.host, e),
+                )
+            }));
+        }
+        Ok(Ok(handle)) => handle,
+    };
+    authenticate(&mut handle, &options.username, &options.auth).await?;
+
+    let channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| e.to_string())?;
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| e.to_string())?;
+    let sftp = SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| format!("Could not start SFTP on {}: {}", options.host, e))?;
+
+    let root = sftp
+        .canonicalize(options.root.as_deref().unwrap_or("."))
+        .await
+        .map_err(|e| e.to_string())?;
+    let is_dir = sftp
+        .metadata(root.as_str())
+        .await
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or(false);
+    if !is_dir {
+        return Err(VoidDeskError::invalid_input(format!(
+            "{} is not a folder on {}",
+            root, options.host
+        )));
+    }
+
+    Ok(RemoteConnection {
+        id: connection_id(&options.username, &options.host, port),
+        host: options.host,
+        port,
+        username: options.username,
+        root,
+        handle,
+        sftp,
+    })
+}
@@ -0,0 +1,242 @@
+// Remote files
+// The explorer and editor file commands over SFTP. They take and return tagged remote
+// paths and the same entry types as the local commands, so the frontend can treat a
+// remote project like any other.
+
+use russh_sftp::protocol::OpenFlags;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::io::AsyncWriteExt;
+
+use super::connection::RemoteConnection;
+use super::{resolve, RemotePath};
+use crate::commands::ignore_engine::IgnoreRules;
+use crate::commands::project_commands::{FileEntry, FileNode};
+
+/// Largest file opened in the editor
+const MAX_READ_BYTES: u64 = 20 * 1024 * 1024;
+
+/// The built-in rules plus the project's .gitignore on the remote machine
+async fn ignore_rules(connection: &RemoteConnection) -> IgnoreRules {
+    let gitignore = format!("{}/.gitignore", connection.root.trim_end_matches('/'));
+    let content = connection.sftp.read(gitignore).await.unwrap_or_default();
+    IgnoreRules::with_defaults(&String::from_utf8_lossy(&content))
+}
+
+struct Listed {
+    path: RemotePath,
+    is_dir: bool,
+    is_symlink: bool,
+}
+
+/// Visible entries of a folder, folders first, then by name
+async fn list(
+    connection: &RemoteConnection,
+    rules: &IgnoreRules,
+    dir: &RemotePath,
+) -> Result<Vec<Listed>, String> {
+    let entries = connection
+        .sftp
+        .read_dir(dir.path.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut listed = Vec::new();
+    for entry in entries {
+        let name = entry.file_name();
+        let path = dir.join(&name);
+        if rules.is_hidden(&path.relative_to(&connection.root), &name) {
+            continue;
+        }
+        let file_type = entry.file_type();
+        // A link's own entry doesn't say what it points at
+        let is_dir = if file_type.is_symlink() {
+            connection
+                .sftp
+                .metadata(path.path.as_str())
+                .await
+                .map(|metadata| metadata.is_dir())
+                .unwrap_or(false)
+        } else {
+            file_type.is_dir()
+        };
+        listed.push(Listed {
+            path,
+            is_dir,
+            is_symlink: file_type.is_symlink(),
+        });
+    }
+    listed.sort_by(|a, b| {
+        b.is_dir.cmp(&a.is_dir).then_with(|| {
+            a.path
+                .file_name()
+                .to_lowercase()
+                .cmp(&b.path.file_name().to_lowercase())
+        })
+    });
+    Ok(listed)
+}
+
+fn tree<'a>(
+    connection: &'a RemoteConnection,
+    rules: &'a IgnoreRules,
+    dir: RemotePath,
+    depth: usize,
+) -> Pin<Box<dyn Future<Output = Result<Vec<FileNode>, String>> + Send + 'a>> {
+    Box::pin(async move {
+        let mut nodes = Vec::new();
+        for entry in list(connection, rules, &dir).await? {
+            // Linked folders are listed but not descended into; they may loop
+            let children = if entry.is_dir && !entry.is_symlink && depth > 1 {
+                Some(tree(connection, rules, entry.path.clone(), depth - 1).await?)
+            } else {
+                None
+            };
+            nodes.push(FileNode {
+                name: entry.path.file_name().to_string(),
+                path: entry.path.to_string(),
+                is_dir: entry.is_dir,
+                is_symlink: entry.is_symlink,
+                is_submodule: false,
+                children,
+            });
+        }
+        Ok(nodes)
+    })
+}
+
+#[tauri::command]
+pub async fn remote_list_directory(path: String) -> Result<Vec<FileEntry>, String> {
+    let (connection, dir) = resolve(&path)?;
+    let rules = ignore_rules(&connection).await;
+    Ok(list(&connection, &rules, &dir)
+        .await?
+        .into_iter()
+        .map(|entry| FileEntry {
+            name: entry.path.file_name().to_string(),
+            path: entry.path.to_string(),
+            is_dir: entry.is_dir,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn remote_get_project_tree(
+    path: String,
+    max_depth: usize,
+) -> Result<Vec<FileNode>, String> {
+    let (connection, dir) = resolve(&path)?;
+    let rules = ignore_rules(&connection).await;
+    tree(&connection, &rules, dir, max_depth.max(1)).await
+}
+
+#[tauri::command]
+pub async fn remote_read_file(path: String) -> Result<String, String> {
+    let (connection, file) = resolve(&path)?;
+    let metadata = connection
+        .sftp
+        .metadata(file.path.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+    if metadata.is_dir() {
+        return Err(format!("{} is a folder", path));
+    }
+    if metadata.len() > MAX_READ_BYTES {
+        return Err(format!(
+            "File is too large to open ({} bytes, limit {} bytes)",
+            metadata.len(),
+            MAX_READ_BYTES
+        ));
+    }
+    let bytes = connection
+        .sftp
+        .read(file.path.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8 text", path))
+}
+
+#[tauri::command]
+pub async fn remote_write_file(path: String, content: String) -> Result<(), String> {
+    let (connection, file) = resolve(&path)?;
+    // SftpSession::write neither creates nor truncates
+    let mut handle = connection
+        .sftp
+        .open_with_flags(
+            file.path.as_str(),
+            OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    handle
+        .write_all(content.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    handle.shutdown().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remote_create_directory(path: String) -> Result<(), String> {
+    let (connection, dir) = resolve(&path)?;
+    connection
+        .sftp
+        .create_dir(dir.path.as_str())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn remove<'a>(
+    connection: &'a RemoteConnection,
+    path: String,
+) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>> {
+    Box::pin(async move {
+        let metadata = connection
+            .sftp
+            .symlink_metadata(path.as_str())
+            .await
+            .map_err(|e| e.to_string())?;
+        if !metadata.is_dir() {
+            return connection
+                .sftp
+                .remove_file(path)
+                .await
+                .map_err(|e| e.to_string());
+        }
+        let entries = connection
+            .sftp
+            .read_dir(path.as_str())
+            .await
+            .map_err(|e| e.to_string())?;
+        for entry in entries {
+            remove(connection, format!("{}/{}", path, entry.file_name())).await?;
+        }
+        connection
+            .sftp
+            .remove_dir(path)
+            .await
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Delete a file, or a folder with everything in it
+#[tauri::command]
+pub async fn remote_delete_file(path: String) -> Result<(), String> {
+    let (connection, target) = resolve(&path)?;
+    if target.path == connection.root {
+        return Err("The project folder itself can't be deleted".to_string());
+    }
+    remove(&connection, target.path).await
+}
+
+#[tauri::command]
+pub async fn remote_rename_file(old_path: String, new_path: String) -> Result<(), String> {
+    let (connection, from) = resolve(&old_path)?;
+    let (_, to) = resolve(&new_path)?;
+    if from.remote_id != to.remote_id {
+        return Err("Files can only be renamed within one remote machine".to_string());
+    }
+    connection
+        .sftp
+        .rename(from.path, to.path)
+        .await
+        .map_err(|e| e.to_string())
+}
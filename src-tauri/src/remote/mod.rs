@@ -0,0 +1,104 @@
+// Remote projects
+// Folders on other machines opened over SSH, edited through SFTP and with terminals
+// running there. Every remote file is named by a tagged path carrying its connection's
+// id, and only paths inside a connection's project folder are served.
+
+mod connection;
+mod files;
+mod paths;
+pub mod pty;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::error::VoidDeskError;
+
+use connection::RemoteConnection;
+pub use connection::{RemoteConnectOptions, RemoteInfo};
+pub use files::{
+    remote_create_directory, remote_delete_file, remote_get_project_tree, remote_list_directory,
+    remote_read_file, remote_rename_file, remote_write_file,
+};
+pub use paths::RemotePath;
+pub use pty::remote_create_pty;
+
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, Arc<RemoteConnection>>>> = OnceLock::new();
+
+fn connections() -> &'static Mutex<HashMap<String, Arc<RemoteConnection>>> {
+    CONNECTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn connection_for(remote_id: &str) -> Result<Arc<RemoteConnection>, String> {
+    connections()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(remote_id)
+        .cloned()
+        .ok_or_else(|| format!("Not connected to {}", remote_id))
+}
+
+/// The connection for a tagged path, failing unless the path is in its project folder
+fn resolve(tagged: &str) -> Result<(Arc<RemoteConnection>, RemotePath), String> {
+    let path = RemotePath::parse(tagged)?;
+    let connection = connection_for(&path.remote_id)?;
+    if !path.is_within(&connection.root) {
+        return Err(format!("Path is outside the remote project: {}", tagged));
+    }
+    Ok((connection, path))
+}
+
+/// Connect to a machine and open its project folder. Connecting again to the same
+/// user, host and port reuses the open connection. An unknown host fails with an
+/// unknown_host_key error carrying its fingerprint; connecting again with that
+/// fingerprint in `accept_host_key` trusts it.
+#[tauri::command]
+pub async fn remote_connect(options: RemoteConnectOptions) -> Result<RemoteInfo, VoidDeskError> {
+    let id = connection::connection_id(
+        &options.username,
+        &options.host,
+        options.port.unwrap_or(connection::DEFAULT_PORT),
+    );
+    if let Ok(existing) = connection_for(&id) {
+        if !existing.handle.is_closed() {
+            return Ok(existing.info());
+        }
+    }
+
+    let connection = Arc::new(connection::connect(options).await?);
+    let info = connection.info();
+    tracing::info!("Connected to {} at {}", info.id, connection.root);
+    let replaced = connections()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(info.id.clone(), connection);
+    if let Some(replaced) = replaced {
+        replaced.close().await;
+    }
+    Ok(info)
+}
+
+/// Close a connection and the terminals running over it
+#[tauri::command]
+pub async fn remote_disconnect(remote_id: String) -> Result<(), String> {
+    let connection = connections()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&remote_id);
+    pty::close_all(&remote_id);
+    if let Some(connection) = connection {
+        connection.close().await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remote_list_connections() -> Result<Vec<RemoteInfo>, String> {
+    let connections = connections().lock().map_err(|e| e.to_string())?;
+    let mut infos: Vec<RemoteInfo> = connections
+        .values()
+        .filter(|connection| !connection.handle.is_closed())
+        .map(|connection| connection.info())
+        .collect();
+    infos.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(infos)
+}
@@ -0,0 +1,110 @@
+// Remote paths
+// A file on a remote machine is named `remote://<connection id>/<absolute path>`, so
+// tabs, the explorer and recent items can hold it next to local paths. Remote paths are
+// POSIX, whatever this machine uses.
+
+const SCHEME: &str = "remote://";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemotePath {
+    pub remote_id: String,
+    /// Absolute and normalized, e.g. "/home/me/project/src"
+    pub path: String,
+}
+
+impl RemotePath {
+    /// Parse a tagged path, resolving `.` and `..`
+    pub fn parse(tagged: &str) -> Result<Self, String> {
+        let rest = tagged
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| format!("Not a remote path: {}", tagged))?;
+        let (remote_id, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        if remote_id.is_empty() {
+            return Err(format!("Remote path has no connection: {}", tagged));
+        }
+        Ok(Self {
+            remote_id: remote_id.to_string(),
+            path: normalize(path),
+        })
+    }
+
+    pub fn new(remote_id: &str, path: &str) -> Self {
+        Self {
+            remote_id: remote_id.to_string(),
+            path: normalize(path),
+        }
+    }
+
+    pub fn join(&self, name: &str) -> Self {
+        Self::new(&self.remote_id, &format!("{}/{}", self.path, name))
+    }
+
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Whether this is `root` or inside it
+    pub fn is_within(&self, root: &str) -> bool {
+        let root = normalize(root);
+        root == "/" || self.path == root || self.path.starts_with(&format!("{}/", root))
+    }
+
+    /// The path below `root` with `/` separators, as ignore rules expect
+    pub fn relative_to(&self, root: &str) -> String {
+        let root = normalize(root);
+        self.path
+            .strip_prefix(&root)
+            .unwrap_or(&self.path)
+            .trim_start_matches('/')
+            .to_string()
+    }
+}
+
+impl std::fmt::Display for RemotePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", SCHEME, self.remote_id, self.path)
+    }
+}
+
+/// An absolute POSIX path without `.`, `..`, repeated or trailing slashes
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_tagged_paths() {
+        let path = RemotePath::parse("remote://me@build:22/home/me/app/./src/../lib.rs").unwrap();
+        assert_eq!(path.remote_id, "me@build:22");
+        assert_eq!(path.path, "/home/me/app/lib.rs");
+        assert_eq!(path.file_name(), "lib.rs");
+        assert_eq!(path.to_string(), "remote://me@build:22/home/me/app/lib.rs");
+        assert!(path.is_within("/home/me/app/"));
+        assert!(!path.is_within("/home/me/ap"));
+        assert_eq!(path.relative_to("/home/me"), "app/lib.rs");
+
+        let escaped = RemotePath::parse("remote://me@build:22/home/me/app/../../../etc").unwrap();
+        assert_eq!(escaped.path, "/etc");
+        assert!(!escaped.is_within("/home/me/app"));
+
+        assert_eq!(RemotePath::parse("remote://host").unwrap().path, "/");
+        assert!(RemotePath::parse("remote:///etc").is_err());
+        assert!(RemotePath::parse("/home/me").is_err());
+    }
+}
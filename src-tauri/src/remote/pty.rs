@@ -0,0 +1,193 @@
+// Remote terminals
+// Shells on the remote machine in an SSH channel with a PTY. They take ids from the
+// local terminals' sequence and report through the same pty-output and pty-exit events,
+// and write_to_pty, resize_pty and close_pty pass on to them, so a terminal tab works
+// the same either way.
+
+use russh::{ChannelMsg, Pty};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::mpsc;
+
+use super::{connection_for, resolve};
+use crate::error::VoidDeskError;
+use crate::terminal::{PtyExitEvent, PtyInfo, PtyOutputEvent, TerminalState, Utf8Decoder};
+
+enum PtyInput {
+    Data(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    Close,
+}
+
+struct RemotePty {
+    remote_id: String,
+    input: mpsc::UnboundedSender<PtyInput>,
+}
+
+static PTYS: OnceLock<Mutex<HashMap<u32, RemotePty>>> = OnceLock::new();
+
+fn ptys() -> &'static Mutex<HashMap<u32, RemotePty>> {
+    PTYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn send(pid: u32, input: PtyInput) -> Option<Result<(), String>> {
+    let ptys = ptys().lock().ok()?;
+    let pty = ptys.get(&pid)?;
+    Some(
+        pty.input
+            .send(input)
+            .map_err(|_| "Remote terminal has exited".to_string()),
+    )
+}
+
+/// Pass input to a remote terminal; None when `pid` isn't one
+pub fn write(pid: u32, data: &str) -> Option<Result<(), String>> {
+    send(pid, PtyInput::Data(data.as_bytes().to_vec()))
+}
+
+pub fn resize(pid: u32, cols: u16, rows: u16) -> Option<Result<(), String>> {
+    send(pid, PtyInput::Resize { cols, rows })
+}
+
+/// Close a remote terminal; false when `pid` isn't one
+pub fn close(pid: u32) -> bool {
+    send(pid, PtyInput::Close).is_some()
+}
+
+/// Close every terminal on a machine that is disconnecting
+pub fn close_all(remote_id: &str) {
+    if let Ok(ptys) = ptys().lock() {
+        for pty in ptys.values().filter(|pty| pty.remote_id == remote_id) {
+            let _ = pty.input.send(PtyInput::Close);
+        }
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Open a shell on a connected machine, in `cwd` (a tagged path in its project folder)
+/// or the project folder
+#[tauri::command]
+pub async fn remote_create_pty(
+    state: State<'_, TerminalState>,
+    app: AppHandle,
+    remote_id: String,
+    cols: u16,
+    rows: u16,
+    cwd: Option<String>,
+) -> Result<PtyInfo, VoidDeskError> {
+    let (connection, cwd) = match cwd {
+        Some(cwd) => {
+            let (connection, path) = resolve(&cwd)?;
+            if path.remote_id != remote_id {
+                return Err(VoidDeskError::invalid_input(format!(
+                    "{} is not on {}",
+                    cwd, remote_id
+                )));
+            }
+            (connection, path.path)
+        }
+        None => {
+            let connection = connection_for(&remote_id)?;
+            let root = connection.root.clone();
+            (connection, root)
+        }
+    };
+
+    let mut channel = connection
+        .handle
+        .channel_open_session()
+        .await
+        .map_err(|e| e.to_string())?;
+    channel
+        .request_pty(
+            true,
+            "xterm-256color",
+            cols as u32,
+            rows as u32,
+            0,
+            0,
+            &[(Pty::TTY_OP_END, 0)],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    // The login shell replaces the `cd`, so exiting it ends the channel
+    let command = format!("cd {} && exec \"$SHELL\" -l", shell_quote(&cwd));
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let pid = state.next_pid();
+    let (input, mut inputs) = mpsc::unbounded_channel();
+    ptys()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, RemotePty { remote_id, input });
+
+    tauri::async_runtime::spawn(async move {
+        let mut decoder = Utf8Decoder::new();
+        let mut exit_code = None;
+        loop {
+            tokio::select! {
+                message = channel.wait() => match message {
+                    Some(ChannelMsg::Data { data })
+                    | Some(ChannelMsg::ExtendedData { data, .. }) => {
+                        let data = decoder.decode(&data);
+                        if !data.is_empty() {
+                            let _ = app.emit(
+                                "pty-output",
+                                PtyOutputEvent { pid, data, dropped_bytes: 0 },
+                            );
+                        }
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status }) => exit_code = Some(exit_status),
+                    Some(ChannelMsg::Close) | None => break,
+                    Some(_) => {}
+                },
+                input = inputs.recv() => match input {
+                    Some(PtyInput::Data(data)) => {
+                        if channel.data(&data[..]).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(PtyInput::Resize { cols, rows }) => {
+                        let _ = channel.window_change(cols as u32, rows as u32, 0, 0).await;
+                    }
+                    Some(PtyInput::Close) | None => {
+                        let _ = channel.close().await;
+                        break;
+                    }
+                },
+            }
+        }
+
+        if let Ok(mut ptys) = ptys().lock() {
+            ptys.remove(&pid);
+        }
+        let tail = decoder.finish();
+        if !tail.is_empty() {
+            let _ = app.emit(
+                "pty-output",
+                PtyOutputEvent {
+                    pid,
+                    data: tail,
+                    dropped_bytes: 0,
+                },
+            );
+        }
+        let _ = app.emit(
+            "pty-exit",
+            PtyExitEvent {
+                pid,
+                exit_code,
+                success: exit_code.map(|code| code == 0),
+            },
+        );
+    });
+
+    Ok(PtyInfo { pid })
+}
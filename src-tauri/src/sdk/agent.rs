@@ -9,10 +9,11 @@ use std::sync::{
     Arc,
 };
 use tokio::sync::mpsc;
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout, Duration, Instant};
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{error, info};
 
+use crate::metrics;
 use crate::sdk::core::{
     AgentEvent, CancelledEvent, ChatRequest, DebugEvent, ErrorCategory, InlineImageAttachment,
     Message, MessageContent, MessagePart, SdkError,
@@ -38,7 +39,7 @@ pub struct AgentResult {
     pub messages: Vec<Message>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct AgentRunHandle {
     cancelled: Arc<AtomicBool>,
 }
@@ -489,6 +490,16 @@ impl Agent {
     }
 
     async fn execute_tool_with_policy(&self, name: &str, input: Value) -> Result<AgentToolOutput> {
+        let started = Instant::now();
+        let result = self.run_tool_with_policy(name, input).await;
+        metrics::record_duration("ai.tool_ms", Some(name), started.elapsed());
+        if result.is_err() {
+            metrics::increment("ai.tool_errors", Some(name), 1);
+        }
+        result
+    }
+
+    async fn run_tool_with_policy(&self, name: &str, input: Value) -> Result<AgentToolOutput> {
         if name == "run_command" {
             let policy = self.tools.policy();
             if !policy.allow_command_tool {
@@ -38,9 +38,11 @@ pub use core::types::{
 };
 
 // Provider re-exports
+#[cfg(feature = "local_inference")]
+pub use provider::LocalModel;
 pub use provider::{
-    CodexSubscriptionProvider, ModelCapabilities, ModelInfo, OpenAICompatibleConfig,
-    OpenAICompatibleProvider, Provider,
+    CodexSubscriptionProvider, FimFormat, FimProvider, ModelCapabilities, ModelInfo,
+    OpenAICompatibleConfig, OpenAICompatibleProvider, Provider,
 };
 
 // Tools re-exports
@@ -0,0 +1,251 @@
+use anyhow::{Error, Result};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::sdk::core::SdkError;
+use crate::sdk::transport::HttpTransport;
+
+/// How a fill-in-the-middle prompt is put to the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FimFormat {
+    /// The server takes the suffix as a request field (OpenAI, Codestral, llama.cpp)
+    Suffix,
+    StarCoder,
+    DeepSeek,
+    CodeLlama,
+    Qwen,
+}
+
+impl FimFormat {
+    /// The format a model was trained with, judged by its id
+    pub fn detect(model_id: &str) -> Option<Self> {
+        let id = model_id.to_lowercase();
+        if id.contains("starcoder") || id.contains("santacoder") {
+            Some(Self::StarCoder)
+        } else if id.contains("deepseek-coder") || id.contains("deepseek_coder") {
+            Some(Self::DeepSeek)
+        } else if id.contains("codellama") || id.contains("code-llama") {
+            Some(Self::CodeLlama)
+        } else if id.contains("qwen") && id.contains("coder") {
+            Some(Self::Qwen)
+        } else if id.contains("codestral") || id.starts_with("gpt-3.5-turbo-instruct") {
+            Some(Self::Suffix)
+        } else {
+            None
+        }
+    }
+
+    /// Tokens that end the middle section when the server does not stop on them itself
+    pub fn stop(self) -> &'static [&'static str] {
+        match self {
+            Self::Suffix => &[],
+            Self::StarCoder => &["<|endoftext|>", "<file_sep>"],
+            Self::DeepSeek => &["<｜end▁of▁sentence｜>", "<|EOT|>"],
+            Self::CodeLlama => &["<EOT>"],
+            Self::Qwen => &["<|endoftext|>", "<|fim_pad|>", "<|file_sep|>"],
+        }
+    }
+
+    /// The prompt in the model's special tokens; None for `Suffix`, which leaves that
+    /// to the server
+    pub fn prompt(self, prefix: &str, suffix: &str) -> Option<String> {
+        match self {
+            Self::Suffix => None,
+            Self::StarCoder => Some(format!(
+                "<fim_prefix>{}<fim_suffix>{}<fim_middle>",
+                prefix, suffix
+            )),
+            Self::DeepSeek => Some(format!(
+                "<｜fim▁begin｜>{}<｜fim▁hole｜>{}<｜fim▁end｜>",
+                prefix, suffix
+            )),
+            Self::CodeLlama => Some(format!("<PRE> {} <SUF>{} <MID>", prefix, suffix)),
+            Self::Qwen => Some(format!(
+                "<|fim_prefix|>{}<|fim_suffix|>{}<|fim_middle|>",
+                prefix, suffix
+            )),
+        }
+    }
+
+    /// The request body for the text completions endpoint
+    pub fn request(self, model: &str, prefix: &str, suffix: &str, max_tokens: u32) -> FimRequest {
+        let (prompt, suffix) = match self.prompt(prefix, suffix) {
+            Some(prompt) => (prompt, None),
+            None => (prefix.to_string(), Some(suffix.to_string())),
+        };
+        FimRequest {
+            model: model.to_string(),
+            prompt,
+            suffix,
+            max_tokens,
+            temperature: 0.2,
+            stop: self.stop().iter().map(|stop| stop.to_string()).collect(),
+            stream: true,
+        }
+    }
+}
+
+/// A `/v1/completions` request
+#[derive(Debug, Clone, Serialize)]
+pub struct FimRequest {
+    pub model: String,
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    pub stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChunk {
+    #[serde(default)]
+    choices: Vec<CompletionChoice>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChoice {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// The text of each event in a `/v1/completions` SSE stream
+pub fn parse_completion_stream(
+    byte_stream: impl Stream<Item = reqwest::Result<Bytes>> + Unpin + Send + 'static,
+) -> impl Stream<Item = Result<String>> {
+    let mut buffer = String::new();
+    let mut done = false;
+
+    byte_stream.flat_map(move |chunk| {
+        let mut texts: Vec<Result<String>> = Vec::new();
+        match chunk {
+            Ok(chunk) => {
+                buffer.push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n"));
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim_end().to_string();
+                    buffer = buffer[pos + 1..].to_string();
+                    let Some(data) = line.strip_prefix("data:").map(str::trim_start) else {
+                        continue;
+                    };
+                    if done || data.is_empty() {
+                        continue;
+                    }
+                    if data == "[DONE]" {
+                        done = true;
+                        continue;
+                    }
+                    match serde_json::from_str::<CompletionChunk>(data) {
+                        Ok(CompletionChunk {
+                            error: Some(error), ..
+                        }) => texts.push(Err(Error::new(SdkError::provider(format!(
+                            "Completion error: {}",
+                            error
+                        ))))),
+                        Ok(chunk) => texts.extend(
+                            chunk
+                                .choices
+                                .into_iter()
+                                .filter_map(|choice| choice.text)
+                                .filter(|text| !text.is_empty())
+                                .map(Ok),
+                        ),
+                        Err(e) => texts.push(Err(Error::new(SdkError::stream(format!(
+                            "Failed to parse SSE json: {}",
+                            e
+                        ))))),
+                    }
+                }
+            }
+            Err(e) => texts.push(Err(Error::new(SdkError::stream(format!(
+                "Stream read failed: {}",
+                e
+            ))))),
+        }
+        futures::stream::iter(texts)
+    })
+}
+
+/// Fill-in-the-middle completions from a model served behind `/v1/completions`
+#[derive(Clone)]
+pub struct FimProvider {
+    transport: HttpTransport,
+    model: String,
+    format: FimFormat,
+}
+
+impl FimProvider {
+    pub fn new(api_key: &str, base_url: &str, model: &str, format: FimFormat) -> Result<Self> {
+        Ok(Self {
+            transport: HttpTransport::new(api_key, base_url)?,
+            model: model.to_string(),
+            format,
+        })
+    }
+
+    /// Stream the text that belongs between `prefix` and `suffix`
+    pub async fn stream(
+        &self,
+        prefix: &str,
+        suffix: &str,
+        max_tokens: u32,
+    ) -> Result<impl Stream<Item = Result<String>>> {
+        let request = self.format.request(&self.model, prefix, suffix, max_tokens);
+        let body = serde_json::to_string(&request)?;
+        let byte_stream = self.transport.post_stream("completions", &body).await?;
+        Ok(parse_completion_stream(Box::pin(byte_stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_prompts_and_reads_completion_events() {
+        assert_eq!(
+            FimFormat::detect("bigcode/starcoder2-3b"),
+            Some(FimFormat::StarCoder)
+        );
+        assert_eq!(
+            FimFormat::detect("deepseek-coder-6.7b-base"),
+            Some(FimFormat::DeepSeek)
+        );
+        assert_eq!(
+            FimFormat::detect("Qwen2.5-Coder-1.5B"),
+            Some(FimFormat::Qwen)
+        );
+        assert_eq!(FimFormat::detect("gpt-4o"), None);
+
+        let request = FimFormat::StarCoder.request("starcoder", "fn a() {", "}", 64);
+        assert_eq!(
+            request.prompt,
+            "<fim_prefix>fn a() {<fim_suffix>}<fim_middle>"
+        );
+        assert_eq!(request.suffix, None);
+
+        let request = FimFormat::Suffix.request("codestral-latest", "fn a() {", "}", 64);
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["prompt"], "fn a() {");
+        assert_eq!(body["suffix"], "}");
+        assert!(body.get("stop").is_none());
+
+        let events = "data: {\"choices\":[{\"text\":\"let x\"}]}\n\n\
+                      data: {\"choices\":[{\"text\":\" = 1;\"}]}\n\ndata: [DONE]\n\n";
+        let (first, second) = events.split_at(30);
+        let chunks = vec![
+            Ok(Bytes::from(first.to_string())),
+            Ok(Bytes::from(second.to_string())),
+        ];
+        let texts = parse_completion_stream(futures::stream::iter(chunks))
+            .map(|text| text.unwrap())
+            .collect::<Vec<_>>();
+        let texts = futures::executor::block_on(texts);
+        assert_eq!(texts, vec!["let x", " = 1;"]);
+    }
+}
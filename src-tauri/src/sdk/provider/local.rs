@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::llama_batch::LlamaBatch;
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::{AddBos, LlamaModel, Special};
+use llama_cpp_2::sampling::LlamaSampler;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Enough for the code around the cursor plus the completion
+const CONTEXT_TOKENS: u32 = 4_096;
+
+/// The model last used, kept loaded between keystrokes
+static LOADED: Mutex<Option<Arc<LocalModel>>> = Mutex::new(None);
+
+/// llama.cpp may only be initialized once per process
+fn backend() -> Result<&'static LlamaBackend> {
+    static BACKEND: OnceLock<Result<LlamaBackend, String>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| LlamaBackend::init().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| anyhow!("Could not initialize llama.cpp: {}", e))
+}
+
+/// A GGUF code model run in-process with llama.cpp, for inline completions
+pub struct LocalModel {
+    path: PathBuf,
+    model: LlamaModel,
+}
+
+impl LocalModel {
+    pub fn load(path: &Path) -> Result<Self> {
+        let model = LlamaModel::load_from_file(backend()?, path, &LlamaModelParams::default())
+            .map_err(|e| anyhow!("Could not load {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            model,
+        })
+    }
+
+    /// The model at `path`, loading it unless it is the one already loaded
+    pub fn shared(path: &Path) -> Result<Arc<Self>> {
+        let mut loaded = LOADED.lock().map_err(|e| anyhow!(e.to_string()))?;
+        if let Some(model) = loaded.as_ref().filter(|model| model.path == path) {
+            return Ok(model.clone());
+        }
+        // Drop the old model first so two are never in memory at once
+        *loaded = None;
+        let model = Arc::new(Self::load(path)?);
+        *loaded = Some(model.clone());
+        Ok(model)
+    }
+
+    /// Continue `prompt` greedily for up to `max_tokens`, handing each piece of text to
+    /// `on_text` until it returns false or the model produces one of `stop`
+    pub fn complete(
+        &self,
+        prompt: &str,
+        stop: &[&str],
+        max_tokens: usize,
+        mut on_text: impl FnMut(&str) -> bool,
+    ) -> Result<()> {
+        let tokens = self.model.str_to_token(prompt, AddBos::Always)?;
+        if tokens.len() + max_tokens > CONTEXT_TOKENS as usize {
+            return Err(anyhow!(
+                "The prompt is {} tokens, more than the local model's context allows",
+                tokens.len()
+            ));
+        }
+        let params = LlamaContextParams::default().with_n_ctx(NonZeroU32::new(CONTEXT_TOKENS));
+        let mut context = self.model.new_context(backend()?, params)?;
+
+        let mut batch = LlamaBatch::new(tokens.len(), 1);
+        let last = tokens.len() as i32 - 1;
+        for (position, token) in (0_i32..).zip(tokens) {
+            batch.add(token, position, &[0], position == last)?;
+        }
+        context.decode(&mut batch)?;
+
+        let mut sampler = LlamaSampler::greedy();
+        let mut position = batch.n_tokens();
+        // A character can span tokens; hold its bytes until it is complete
+        let mut pending: Vec<u8> = Vec::new();
+        for _ in 0..max_tokens {
+            let token = sampler.sample(&context, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if self.model.is_eog_token(token) {
+                break;
+            }
+            pending.extend(self.model.token_to_bytes(token, Special::Tokenize)?);
+            let text = match std::str::from_utf8(&pending) {
+                Ok(text) => Some(text.to_string()),
+                Err(error) if error.error_len().is_none() => None,
+                Err(_) => Some(String::from_utf8_lossy(&pending).to_string()),
+            };
+            if let Some(text) = text {
+                pending.clear();
+                if stop.contains(&text.as_str()) || !on_text(&text) {
+                    break;
+                }
+            }
+
+            batch.clear();
+            batch.add(token, position, &[0], true)?;
+            position += 1;
+            context.decode(&mut batch)?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,15 @@
 pub mod codex_subscription;
 pub mod config;
+pub mod fim;
+#[cfg(feature = "local_inference")]
+pub mod local;
 pub mod openai_compatible;
 
 pub use codex_subscription::CodexSubscriptionProvider;
 pub use config::OpenAICompatibleConfig;
+pub use fim::{FimFormat, FimProvider};
+#[cfg(feature = "local_inference")]
+pub use local::LocalModel;
 pub use openai_compatible::OpenAICompatibleProvider;
 
 use anyhow::Result;
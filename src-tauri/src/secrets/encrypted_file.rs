@@ -0,0 +1,156 @@
+// Encrypted secrets file
+// All secrets in one JSON document, sealed with XChaCha20-Poly1305 under a key derived
+// from the app passphrase with Argon2id. The salt and KDF parameters are stored next to
+// the ciphertext so they can be raised later without breaking existing files.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::commands::file_commands::write_atomic_private;
+
+const FORMAT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+/// Upper bounds on the stored KDF parameters, well above the defaults, so a tampered
+/// file cannot make unlocking allocate gigabytes or spin for minutes
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// What is written to disk; nothing in it is secret
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kdf: KdfParams,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// An unlocked secrets file: the derived key and the decrypted secrets
+pub(super) struct SecretsFile {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    kdf: KdfParams,
+    pub(super) secrets: BTreeMap<String, String>,
+}
+
+impl Drop for SecretsFile {
+    fn drop(&mut self) {
+        for value in self.secrets.values_mut() {
+            zeroize::Zeroize::zeroize(value);
+        }
+    }
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Could not generate random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+fn derive_key(passphrase: &str, kdf: &KdfParams) -> Result<Zeroizing<[u8; KEY_LEN]>, String> {
+    let salt = BASE64
+        .decode(&kdf.salt)
+        .map_err(|_| "Damaged secrets file: invalid salt".to_string())?;
+    let params = Params::new(
+        kdf.memory_kib.min(MAX_MEMORY_KIB),
+        kdf.iterations.min(MAX_ITERATIONS),
+        kdf.parallelism.min(MAX_PARALLELISM),
+        Some(KEY_LEN),
+    )
+    .map_err(|e| format!("Damaged secrets file: {}", e))?;
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+        .map_err(|e| format!("Could not derive the secrets key: {}", e))?;
+    Ok(key)
+}
+
+impl SecretsFile {
+    /// An empty store sealed with `passphrase`, not yet written
+    pub(super) fn create(passphrase: &str) -> Result<Self, String> {
+        let defaults = Params::default();
+        let kdf = KdfParams {
+            salt: BASE64.encode(random_bytes::<SALT_LEN>()?),
+            memory_kib: defaults.m_cost(),
+            iterations: defaults.t_cost(),
+            parallelism: defaults.p_cost(),
+        };
+        Ok(Self {
+            key: derive_key(passphrase, &kdf)?,
+            kdf,
+            secrets: BTreeMap::new(),
+        })
+    }
+
+    /// Decrypt the file at `path`
+    pub(super) fn open(path: &Path, passphrase: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let envelope: Envelope = serde_json::from_str(&content)
+            .map_err(|e| format!("Damaged secrets file {}: {}", path.display(), e))?;
+        if envelope.version != FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported secrets file version {}",
+                envelope.version
+            ));
+        }
+
+        let key = derive_key(passphrase, &envelope.kdf)?;
+        let nonce = BASE64
+            .decode(&envelope.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or_else(|| "Damaged secrets file: invalid nonce".to_string())?;
+        let ciphertext = BASE64
+            .decode(&envelope.ciphertext)
+            .map_err(|_| "Damaged secrets file: invalid ciphertext".to_string())?;
+        let plaintext = Zeroizing::new(
+            XChaCha20Poly1305::new(key.as_ref().into())
+                .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| "Wrong passphrase, or the secrets file is damaged".to_string())?,
+        );
+        let secrets = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Damaged secrets file {}: {}", path.display(), e))?;
+        Ok(Self {
+            key,
+            kdf: envelope.kdf,
+            secrets,
+        })
+    }
+
+    /// Encrypt the secrets under a fresh nonce and replace the file at `path`
+    pub(super) fn save(&self, path: &Path) -> Result<(), String> {
+        let nonce = random_bytes::<NONCE_LEN>()?;
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(&self.secrets).map_err(|e| e.to_string())?);
+        let ciphertext = XChaCha20Poly1305::new(self.key.as_ref().into())
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| "Could not encrypt the secrets".to_string())?;
+        let envelope = Envelope {
+            version: FORMAT_VERSION,
+            kdf: self.kdf.clone(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let json = serde_json::to_string_pretty(&envelope).map_err(|e| e.to_string())?;
+        // Only the owner may read the file, even though its contents are encrypted
+        write_atomic_private(path, json.as_bytes()).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,189 @@
+// Secrets
+// API keys and tokens, kept out of the settings files and the frontend's storage. They
+// are stored in an encrypted file in the app data directory, for setups without a
+// keyring daemon, and unlocked once per session with the app passphrase.
+
+mod encrypted_file;
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use zeroize::Zeroizing;
+
+use encrypted_file::SecretsFile;
+
+/// Secret the AI requests fall back to when they carry no API key
+pub const AI_API_KEY: &str = "ai.api_key";
+
+/// Prefix of the per-host tokens HTTPS git remotes authenticate with, as in
+/// "git.token.github.com"
+pub const GIT_TOKEN_PREFIX: &str = "git.token.";
+
+const SECRETS_FILE: &str = "secrets.enc.json";
+
+static SECRETS_PATH: OnceLock<PathBuf> = OnceLock::new();
+static UNLOCKED: OnceLock<Mutex<Option<SecretsFile>>> = OnceLock::new();
+
+fn unlocked() -> &'static Mutex<Option<SecretsFile>> {
+    UNLOCKED.get_or_init(|| Mutex::new(None))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretsStatus {
+    pub backend: &'static str,
+    /// A secrets file was created before; unlocking checks the passphrase against it
+    pub exists: bool,
+    pub unlocked: bool,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
+    SECRETS_PATH.get_or_init(|| app_data_dir.join(SECRETS_FILE));
+    Ok(())
+}
+
+fn secrets_path() -> Result<&'static PathBuf, String> {
+    SECRETS_PATH
+        .get()
+        .ok_or_else(|| "Secrets have not been initialized".to_string())
+}
+
+/// Run `f` on the unlocked store
+fn with_unlocked<T>(f: impl FnOnce(&mut SecretsFile) -> Result<T, String>) -> Result<T, String> {
+    let mut unlocked = unlocked().lock().map_err(|e| e.to_string())?;
+    let store = unlocked
+        .as_mut()
+        .ok_or_else(|| "Secrets are locked; unlock them with the app passphrase".to_string())?;
+    f(store)
+}
+
+/// The secret `name`, or None when it is unset or the secrets are locked
+pub fn get(name: &str) -> Option<String> {
+    let unlocked = unlocked().lock().ok()?;
+    unlocked.as_ref()?.secrets.get(name).cloned()
+}
+
+/// Every stored secret value while unlocked, for scrubbing them out of diagnostics
+pub fn unlocked_values() -> Vec<String> {
+    let Ok(unlocked) = unlocked().lock() else {
+        return Vec::new();
+    };
+    unlocked
+        .as_ref()
+        .map(|store| store.secrets.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn secrets_status() -> Result<SecretsStatus, String> {
+    Ok(SecretsStatus {
+        backend: "encrypted_file",
+        exists: secrets_path()?.is_file(),
+        unlocked: unlocked().lock().map_err(|e| e.to_string())?.is_some(),
+    })
+}
+
+/// Unlock the secrets file with `passphrase`, creating it when there is none yet.
+/// Deriving the key is deliberately slow, so it runs off the async runtime.
+#[tauri::command]
+pub async fn unlock_secrets(passphrase: String) -> Result<SecretsStatus, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.is_empty() {
+        return Err("The passphrase cannot be empty".to_string());
+    }
+    let path = secrets_path()?;
+    let store = tokio::task::spawn_blocking(move || {
+        if path.is_file() {
+            SecretsFile::open(path, &passphrase)
+        } else {
+            let store = SecretsFile::create(&passphrase)?;
+            store.save(path)?;
+            Ok(store)
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    *unlocked().lock().map_err(|e| e.to_string())? = Some(store);
+    secrets_status().await
+}
+
+/// Forget the key and the decrypted secrets until the next unlock
+#[tauri::command]
+pub async fn lock_secrets() -> Result<(), String> {
+    *unlocked().lock().map_err(|e| e.to_string())? = None;
+    Ok(())
+}
+
+/// Names of the stored secrets; their values only come back one at a time
+#[tauri::command]
+pub async fn list_secrets() -> Result<Vec<String>, String> {
+    with_unlocked(|store| Ok(store.secrets.keys().cloned().collect()))
+}
+
+#[tauri::command]
+pub async fn get_secret(name: String) -> Result<Option<String>, String> {
+    with_unlocked(|store| Ok(store.secrets.get(&name).cloned()))
+}
+
+#[tauri::command]
+pub async fn set_secret(name: String, value: String) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Secret names cannot be empty".to_string());
+    }
+    let path = secrets_path()?;
+    with_unlocked(|store| {
+        let previous = store.secrets.insert(name.clone(), value);
+        store.save(path).inspect_err(|_| match previous {
+            Some(previous) => {
+                store.secrets.insert(name, previous);
+            }
+            None => {
+                store.secrets.remove(&name);
+            }
+        })
+    })
+}
+
+#[tauri::command]
+pub async fn delete_secret(name: String) -> Result<(), String> {
+    let path = secrets_path()?;
+    with_unlocked(|store| match store.secrets.remove(&name) {
+        Some(previous) => store.save(path).inspect_err(|_| {
+            store.secrets.insert(name, previous);
+        }),
+        None => Ok(()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn secrets_round_trip_only_with_the_passphrase() {
+        let dir = std::env::temp_dir().join(format!("voidesk-secrets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SECRETS_FILE);
+
+        let mut store = SecretsFile::create("correct horse").unwrap();
+        store
+            .secrets
+            .insert(AI_API_KEY.to_string(), "sk-test-123".to_string());
+        store.save(&path).unwrap();
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("sk-test-123"));
+        assert!(!on_disk.contains(AI_API_KEY));
+
+        let reopened = SecretsFile::open(&path, "correct horse").unwrap();
+        assert_eq!(
+            reopened.secrets.get(AI_API_KEY).map(String::as_str),
+            Some("sk-test-123")
+        );
+        let error = SecretsFile::open(&path, "wrong horse").err().unwrap();
+        assert!(error.contains("Wrong passphrase"), "{}", error);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
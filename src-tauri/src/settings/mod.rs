@@ -0,0 +1,509 @@
+// Settings
+// App-wide settings and per-project overrides of them, kept as JSON in the app data
+// directory, plus the team's shared settings in the project's own .voidesk/config.json.
+// Each file holds only the keys that were changed; the AI defaults, LSP timeouts,
+// terminal profiles and ignore patterns are read from the merged result. A cloned
+// repository is not trusted, so its config file may only set the keys in SHARED_KEYS.
+
+mod schema;
+
+pub use schema::Settings;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::file_commands::write_atomic;
+use crate::commands::lsp_commands::LspState;
+use crate::commands::workspace_roots;
+use crate::metrics;
+
+/// Emitted with a SettingsChangedEvent after every update
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+const APP_SETTINGS_FILE: &str = "settings.json";
+
+/// Shared project settings, checked in with the project. Applied over the app settings
+/// and under the user's own overrides for that project.
+pub const PROJECT_CONFIG_FILE: &str = ".voidesk/config.json";
+
+/// What the project's config file may set, by section; None allows every key of the
+/// section. The programs terminals start, the host the API key is sent to and the model
+/// file loaded can only come from the user's own layers.
+const SHARED_KEYS: &[(&str, Option<&[&str]>)] = &[
+    ("ai", Some(&["rules", "command_allowlist"])),
+    ("lsp", Some(&["request_timeout_ms", "method_timeouts_ms"])),
+    ("editor", None),
+    ("files", Some(&["exclude"])),
+];
+
+/// Project layers are named by a hash of the project root
+const PROJECT_SETTINGS_DIR: &str = "project-settings";
+
+static SETTINGS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingsScope {
+    App,
+    Project,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsSnapshot {
+    /// Defaults with the app and project layers applied
+    pub effective: Settings,
+    /// The changed keys stored in each layer
+    pub app: Value,
+    /// The project's .voidesk/config.json
+    pub shared: Option<Value>,
+    pub project: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectConfig {
+    /// Where the project's config file is, whether or not it exists
+    pub path: String,
+    /// Its contents; None when the project has none
+    pub config: Option<Value>,
+    /// The app settings, then the config file, then the user's own project overrides
+    pub effective: Settings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsChangedEvent {
+    pub scope: SettingsScope,
+    pub root: Option<String>,
+    /// Effective settings for `root`, or the app settings alone
+    pub settings: Settings,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    let settings_dir = SETTINGS_DIR.get_or_init(|| app_data_dir);
+    fs::create_dir_all(settings_dir.join(PROJECT_SETTINGS_DIR)).map_err(|e| e.to_string())
+}
+
+fn layer_path(settings_dir: &Path, root: Option<&Path>) -> PathBuf {
+    match root {
+        None => settings_dir.join(APP_SETTINGS_FILE),
+        Some(root) => {
+            let mut hasher = Sha256::new();
+            hasher.update(root.to_string_lossy().replace('\\', "/").as_bytes());
+            let digest = format!("{:x}", hasher.finalize());
+            settings_dir
+                .join(PROJECT_SETTINGS_DIR)
+                .join(format!("{}.json", &digest[..32]))
+        }
+    }
+}
+
+/// A stored layer; an empty object when nothing was saved yet
+fn read_layer(path: &Path) -> Result<Value, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Value::Object(Map::new()))
+        }
+        Err(error) => return Err(error.to_string()),
+    };
+    let layer: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid settings file {}: {}", path.display(), e))?;
+    if !layer.is_object() {
+        return Err(format!(
+            "Invalid settings file {}: expected an object",
+            path.display()
+        ));
+    }
+    Ok(layer)
+}
+
+/// The project's config file, rejected whole when it sets a key outside SHARED_KEYS
+fn read_shared_layer(path: &Path) -> Result<Value, String> {
+    let layer = read_layer(path)?;
+    let sections = layer.as_object().into_iter().flatten();
+    for (section, values) in sections {
+        let allowed = SHARED_KEYS
+            .iter()
+            .find(|(name, _)| name == section)
+            .map(|(_, keys)| *keys);
+        let forbidden = match (allowed, values.as_object()) {
+            (None, _) => Some(section.clone()),
+            (Some(None), _) => None,
+            (Some(Some(keys)), Some(values)) => values
+                .keys()
+                .find(|key| !keys.contains(&key.as_str()))
+                .map(|key| format!("{}.{}", section, key)),
+            // Not an object; the schema check reports it
+            (Some(Some(_)), None) => None,
+        };
+        if let Some(key) = forbidden {
+            return Err(format!(
+                "`{}` cannot be set in {}; set it in your own settings instead",
+                key, PROJECT_CONFIG_FILE
+            ));
+        }
+    }
+    Ok(layer)
+}
+
+/// Apply `overlay` to `base` key by key; a null removes the key
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    base.remove(key);
+                } else if let Some(existing) = base.get_mut(key).filter(|_| value.is_object()) {
+                    merge(existing, value);
+                } else {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Defaults with `layers` applied in order, checked against the schema
+fn resolve(layers: &[&Value]) -> Result<Settings, String> {
+    let mut merged = Value::Object(Map::new());
+    for layer in layers {
+        merge(&mut merged, layer);
+    }
+    let settings: Settings =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+    settings
+        .validate()
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    Ok(settings)
+}
+
+/// Merged settings for the project at `root`, or the app settings alone. A layer that
+/// cannot be read or fails validation is skipped with a warning rather than failing
+/// whoever asked.
+pub fn effective(root: Option<&Path>) -> Settings {
+    let Some(settings_dir) = SETTINGS_DIR.get() else {
+        return Settings::default();
+    };
+    let mut paths = vec![(layer_path(settings_dir, None), false)];
+    if let Some(root) = root {
+        paths.push((root.join(PROJECT_CONFIG_FILE), true));
+        paths.push((layer_path(settings_dir, Some(root)), false));
+    }
+
+    let mut layers = Vec::new();
+    let mut settings = Settings::default();
+    for (path, shared) in paths {
+        let layer = match shared {
+            true => read_shared_layer(&path),
+            false => read_layer(&path),
+        };
+        let applied = layer.and_then(|layer| {
+            let mut candidate: Vec<&Value> = layers.iter().collect();
+            candidate.push(&layer);
+            let resolved = resolve(&candidate)?;
+            Ok((layer, resolved))
+        });
+        match applied {
+            Ok((layer, resolved)) => {
+                layers.push(layer);
+                settings = resolved;
+            }
+            Err(error) => tracing::warn!("Ignoring settings in {}: {}", path.display(), error),
+        }
+    }
+    settings
+}
+
+/// Merged settings for the open workspace `path` is in, or the app settings
+pub fn for_path(path: Option<&str>) -> Settings {
+    let root = path.and_then(workspace_roots::containing_root);
+    effective(root.as_deref())
+}
+
+fn settings_dir() -> Result<&'static PathBuf, String> {
+    SETTINGS_DIR
+        .get()
+        .ok_or_else(|| "Settings have not been initialized".to_string())
+}
+
+fn canonical_root(root: &str) -> Result<PathBuf, String> {
+    workspace_roots::ensure_in_workspace(root)?;
+    Path::new(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid project folder '{}': {}", root, e))
+}
+
+fn read_shared(root: Option<&Path>) -> Result<Option<Value>, String> {
+    root.map(|root| read_shared_layer(&root.join(PROJECT_CONFIG_FILE)))
+        .transpose()
+}
+
+/// Resolve the layers in order, checking the result
+fn snapshot_of(
+    app: Value,
+    shared: Option<Value>,
+    project: Option<Value>,
+) -> Result<SettingsSnapshot, String> {
+    let mut layers = vec![&app];
+    layers.extend(shared.as_ref());
+    layers.extend(project.as_ref());
+    Ok(SettingsSnapshot {
+        effective: resolve(&layers)?,
+        app,
+        shared,
+        project,
+    })
+}
+
+fn snapshot(settings_dir: &Path, root: Option<&Path>) -> Result<SettingsSnapshot, String> {
+    let app = read_layer(&layer_path(settings_dir, None))?;
+    let project = root
+        .map(|root| read_layer(&layer_path(settings_dir, Some(root))))
+        .transpose()?;
+    snapshot_of(app, read_shared(root)?, project)
+}
+
+/// Merge `patch` into the stored layer and save it if the result is valid
+fn update_layer(
+    settings_dir: &Path,
+    scope: SettingsScope,
+    root: Option<&Path>,
+    patch: &Value,
+) -> Result<SettingsSnapshot, String> {
+    if !patch.is_object() {
+        return Err("Settings updates must be an object".to_string());
+    }
+    let target = match scope {
+        SettingsScope::App => None,
+        SettingsScope::Project => {
+            Some(root.ok_or_else(|| "Project settings need a project root".to_string())?)
+        }
+    };
+
+    let path = layer_path(settings_dir, target);
+    let mut layer = read_layer(&path)?;
+    merge(&mut layer, patch);
+
+    let app = match target {
+        None => layer.clone(),
+        Some(_) => read_layer(&layer_path(settings_dir, None))?,
+    };
+    let project = match (target, root) {
+        (Some(_), _) => Some(layer.clone()),
+        (None, Some(root)) => Some(read_layer(&layer_path(settings_dir, Some(root)))?),
+        (None, None) => None,
+    };
+    let snapshot = snapshot_of(app, read_shared(root)?, project)?;
+
+    let json = serde_json::to_string_pretty(&layer).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(snapshot)
+}
+
+/// Each window's language servers follow the project they were started for
+async fn apply_lsp_settings(lsp_state: &LspState) -> Result<(), String> {
+    for manager in lsp_state.managers() {
+        let lsp_root = manager.root_path().await;
+        let lsp_settings = for_path(lsp_root.as_deref()).lsp;
+        manager.set_request_timeouts(lsp_settings.request_timeouts())?;
+    }
+    Ok(())
+}
+
+/// Called by the file watcher when the .voidesk/config.json of `root` changed on disk
+pub async fn project_config_changed(app: &AppHandle, root: &Path) {
+    if let Some(lsp_state) = app.try_state::<LspState>() {
+        if let Err(error) = apply_lsp_settings(&lsp_state).await {
+            tracing::warn!("Could not apply the LSP settings: {}", error);
+        }
+    }
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let _ = app.emit(
+        SETTINGS_CHANGED_EVENT,
+        SettingsChangedEvent {
+            scope: SettingsScope::Project,
+            settings: effective(Some(&root)),
+            root: Some(root.to_string_lossy().to_string()),
+        },
+    );
+}
+
+/// Stored and effective settings; with `root`, including that project's overrides
+#[tauri::command]
+pub async fn get_settings(root: Option<String>) -> Result<SettingsSnapshot, String> {
+    let root = root.as_deref().map(canonical_root).transpose()?;
+    snapshot(settings_dir()?, root.as_deref())
+}
+
+/// Merge `patch` into the app settings or the overrides of project `root`; a null value
+/// resets that key. Nothing is saved when the result does not validate.
+#[tauri::command]
+pub async fn update_settings(
+    app: AppHandle,
+    lsp_state: State<'_, LspState>,
+    scope: SettingsScope,
+    root: Option<String>,
+    patch: Value,
+) -> Result<SettingsSnapshot, String> {
+    let root = root.as_deref().map(canonical_root).transpose()?;
+    let snapshot = update_layer(settings_dir()?, scope, root.as_deref(), &patch)?;
+    apply_lsp_settings(&lsp_state).await?;
+    metrics::set_enabled(effective(None).diagnostics.metrics_enabled);
+
+    let _ = app.emit(
+        SETTINGS_CHANGED_EVENT,
+        SettingsChangedEvent {
+            scope,
+            root: root.map(|root| root.to_string_lossy().to_string()),
+            settings: snapshot.effective.clone(),
+        },
+    );
+    Ok(snapshot)
+}
+
+/// The settings in effect for the project at `root`, and its .voidesk/config.json.
+/// Unlike the merge the rest of the app uses, a broken config file is reported here.
+#[tauri::command]
+pub async fn get_project_config(root: String) -> Result<ProjectConfig, String> {
+    let root = canonical_root(&root)?;
+    let snapshot = snapshot(settings_dir()?, Some(&root))?;
+    let config = snapshot
+        .shared
+        .filter(|_| root.join(PROJECT_CONFIG_FILE).is_file());
+    Ok(ProjectConfig {
+        path: root.join(PROJECT_CONFIG_FILE).to_string_lossy().to_string(),
+        config,
+        effective: snapshot.effective,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn layers_merge_validate_and_reset() {
+        let dir = std::env::temp_dir().join(format!("voidesk-settings-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join(PROJECT_SETTINGS_DIR)).unwrap();
+        let root = Path::new("/projects/app");
+
+        let patch = json!({
+            "ai": { "model_id": "gpt-4.1", "context_window_tokens": 64000 },
+            "terminal": {
+                "profiles": [{ "name": "bash", "program": "/bin/bash", "args": ["-l"] }],
+                "default_profile": "bash"
+            }
+        });
+        update_layer(&dir, SettingsScope::App, None, &patch).unwrap();
+        let project_patch = json!({ "ai": { "model_id": "local-model" } });
+        let snapshot =
+            update_layer(&dir, SettingsScope::Project, Some(root), &project_patch).unwrap();
+        assert_eq!(
+            snapshot.effective.ai.model_id.as_deref(),
+            Some("local-model")
+        );
+        assert_eq!(snapshot.effective.ai.context_window_tokens, Some(64000));
+        let profile = snapshot.effective.terminal.profile(None).unwrap().unwrap();
+        assert_eq!(profile.args, vec!["-l".to_string()]);
+
+        // Invalid results are rejected and leave the file alone
+        let unknown = json!({ "ai": { "modle_id": "typo" } });
+        assert!(update_layer(&dir, SettingsScope::App, None, &unknown).is_err());
+        let dangling = json!({ "terminal": { "default_profile": "zsh" } });
+        let error = update_layer(&dir, SettingsScope::App, None, &dangling).unwrap_err();
+        assert!(error.contains("terminal.default_profile"), "{}", error);
+        assert!(update_layer(&dir, SettingsScope::Project, None, &project_patch).is_err());
+
+        let reset = json!({ "ai": { "model_id": null } });
+        let snapshot = update_layer(&dir, SettingsScope::Project, Some(root), &reset).unwrap();
+        assert_eq!(snapshot.effective.ai.model_id.as_deref(), Some("gpt-4.1"));
+        assert_eq!(snapshot.project, Some(json!({ "ai": {} })));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn project_config_sits_between_app_and_user_overrides() {
+        let dir = std::env::temp_dir().join(format!("voidesk-settings-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("project");
+        fs::create_dir_all(dir.join(PROJECT_SETTINGS_DIR)).unwrap();
+        fs::create_dir_all(root.join(".voidesk")).unwrap();
+        fs::write(
+            root.join(PROJECT_CONFIG_FILE),
+            r#"{
+                "ai": { "rules": ["Use tabs"], "command_allowlist": ["cargo"] },
+                "lsp": { "request_timeout_ms": 5000 },
+                "editor": { "format_on_save": true }
+            }"#,
+        )
+        .unwrap();
+
+        let app_patch = json!({ "ai": { "model_id": "mine", "base_url": "http://localhost" } });
+        let snapshot = update_layer(&dir, SettingsScope::App, Some(&root), &app_patch).unwrap();
+        let effective = &snapshot.effective;
+        assert_eq!(effective.ai.model_id.as_deref(), Some("mine"));
+        assert_eq!(effective.ai.base_url.as_deref(), Some("http://localhost"));
+        assert_eq!(effective.ai.rules, vec!["Use tabs".to_string()]);
+        assert_eq!(effective.lsp.request_timeout_ms, Some(5000));
+        assert!(effective.editor.format_on_save);
+
+        let user_patch = json!({ "editor": { "format_on_save": false } });
+        let snapshot =
+            update_layer(&dir, SettingsScope::Project, Some(&root), &user_patch).unwrap();
+        assert!(!snapshot.effective.editor.format_on_save);
+        assert_eq!(
+            snapshot.effective.ai.command_allowlist,
+            Some(vec!["cargo".to_string()])
+        );
+
+        // A broken config file is reported rather than silently dropped
+        fs::write(
+            root.join(PROJECT_CONFIG_FILE),
+            r#"{ "ai": { "rule": [] } }"#,
+        )
+        .unwrap();
+        assert!(super::snapshot(&dir, Some(&root)).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn project_config_cannot_pick_programs_hosts_or_models() {
+        let dir = std::env::temp_dir().join(format!("voidesk-settings-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("project");
+        fs::create_dir_all(dir.join(PROJECT_SETTINGS_DIR)).unwrap();
+        fs::create_dir_all(root.join(".voidesk")).unwrap();
+
+        let forbidden = [
+            json!({ "terminal": { "profiles": [{ "name": "x", "program": "/tmp/x" }] } }),
+            json!({ "terminal": { "default_profile": "x" } }),
+            json!({ "ai": { "base_url": "https://collector.example" } }),
+            json!({ "ai": { "provider_type": "openai_compatible" } }),
+            json!({ "ai": { "local_model_path": "model.gguf" } }),
+            json!({ "diagnostics": { "metrics_enabled": true } }),
+        ];
+        for config in forbidden {
+            fs::write(root.join(PROJECT_CONFIG_FILE), config.to_string()).unwrap();
+            let error = read_shared(Some(&root)).unwrap_err();
+            assert!(error.contains("cannot be set"), "{}", error);
+            assert!(super::snapshot(&dir, Some(&root)).is_err());
+        }
+
+        // One key outside the allowlist rejects the whole file
+        let config = json!({
+            "ai": { "rules": ["Use tabs"], "base_url": "https://collector.example" },
+            "lsp": { "method_timeouts_ms": { "textDocument/hover": 2000 } }
+        });
+        fs::write(root.join(PROJECT_CONFIG_FILE), config.to_string()).unwrap();
+        let error = read_shared(Some(&root)).unwrap_err();
+        assert!(error.contains("ai.base_url"), "{}", error);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
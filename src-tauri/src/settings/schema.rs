@@ -0,0 +1,217 @@
+// Settings schema
+// Every setting the backend understands, with its default. Stored files only hold the
+// keys a user changed; unknown keys and out-of-range values are rejected.
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::lsp::transport::RequestTimeouts;
+
+/// Smallest context window the AI settings accept, matching the chat's own floor
+const MIN_CONTEXT_WINDOW_TOKENS: usize = 1_024;
+
+const PROVIDER_TYPES: &[&str] = &["openai_compatible", "codex_subscription"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub ai: AiSettings,
+    pub lsp: LspSettings,
+    pub terminal: TerminalSettings,
+    pub files: FileSettings,
+    pub editor: EditorSettings,
+    pub diagnostics: DiagnosticsSettings,
+}
+
+/// Used when an AI request leaves the value out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiSettings {
+    pub provider_type: Option<String>,
+    pub base_url: Option<String>,
+    pub model_id: Option<String>,
+    pub context_window_tokens: Option<usize>,
+    /// Project instructions appended to the agent's system prompt
+    pub rules: Vec<String>,
+    /// Command prefixes the agent's run_command tool may start; any command when unset
+    pub command_allowlist: Option<Vec<String>>,
+    /// GGUF model for the "local" inline-completion provider; needs a build with the
+    /// local_inference feature
+    pub local_model_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LspSettings {
+    /// Replaces the built-in default for methods without their own timeout
+    pub request_timeout_ms: Option<u64>,
+    /// Per-method timeouts, e.g. "textDocument/hover", on top of the built-in ones
+    pub method_timeouts_ms: HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerminalProfile {
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalSettings {
+    pub profiles: Vec<TerminalProfile>,
+    /// Profile for new terminals that don't name one; the login shell when unset
+    pub default_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileSettings {
+    /// Extra ignore patterns in .gitignore syntax, applied everywhere ignore rules are
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorSettings {
+    pub format_on_save: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagnosticsSettings {
+    /// Record local performance metrics for get_metrics; app-wide, projects can't set it
+    pub metrics_enabled: bool,
+}
+
+impl LspSettings {
+    pub fn request_timeouts(&self) -> RequestTimeouts {
+        let mut timeouts = RequestTimeouts::default();
+        if let Some(default_ms) = self.request_timeout_ms {
+            timeouts.default_ms = default_ms;
+        }
+        timeouts.per_method_ms.extend(
+            self.method_timeouts_ms
+                .iter()
+                .map(|(method, ms)| (method.clone(), *ms)),
+        );
+        timeouts
+    }
+}
+
+impl TerminalSettings {
+    /// The named profile, or the default one when `name` is None
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&TerminalProfile>, String> {
+        match name.or(self.default_profile.as_deref()) {
+            Some(name) => self
+                .profiles
+                .iter()
+                .find(|profile| profile.name == name)
+                .map(Some)
+                .ok_or_else(|| format!("No terminal profile named \"{}\"", name)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Settings {
+    /// Check values serde cannot; the error names the offending key
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(provider_type) = &self.ai.provider_type {
+            if !PROVIDER_TYPES.contains(&provider_type.as_str()) {
+                return Err(format!(
+                    "ai.provider_type must be one of {}",
+                    PROVIDER_TYPES.join(", ")
+                ));
+            }
+        }
+        if self
+            .ai
+            .context_window_tokens
+            .is_some_and(|tokens| tokens < MIN_CONTEXT_WINDOW_TOKENS)
+        {
+            return Err(format!(
+                "ai.context_window_tokens must be at least {}",
+                MIN_CONTEXT_WINDOW_TOKENS
+            ));
+        }
+        if self.ai.rules.iter().any(|rule| rule.trim().is_empty()) {
+            return Err("ai.rules: rules cannot be empty".to_string());
+        }
+        if self
+            .ai
+            .command_allowlist
+            .iter()
+            .flatten()
+            .any(|command| command.trim().is_empty())
+        {
+            return Err("ai.command_allowlist: commands cannot be empty".to_string());
+        }
+        if self
+            .ai
+            .local_model_path
+            .as_ref()
+            .is_some_and(|path| !path.to_lowercase().ends_with(".gguf"))
+        {
+            return Err("ai.local_model_path must point to a .gguf model".to_string());
+        }
+
+        if self.lsp.request_timeout_ms == Some(0) {
+            return Err("lsp.request_timeout_ms must be greater than 0".to_string());
+        }
+        if let Some(method) = self
+            .lsp
+            .method_timeouts_ms
+            .iter()
+            .find_map(|(method, ms)| (*ms == 0).then_some(method))
+        {
+            return Err(format!(
+                "lsp.method_timeouts_ms.{} must be greater than 0",
+                method
+            ));
+        }
+
+        let mut names = HashSet::new();
+        for profile in &self.terminal.profiles {
+            if profile.name.trim().is_empty() {
+                return Err("terminal.profiles: every profile needs a name".to_string());
+            }
+            if profile.program.trim().is_empty() {
+                return Err(format!(
+                    "terminal.profiles: \"{}\" needs a program",
+                    profile.name
+                ));
+            }
+            if !names.insert(profile.name.as_str()) {
+                return Err(format!(
+                    "terminal.profiles: \"{}\" is defined twice",
+                    profile.name
+                ));
+            }
+        }
+        if let Some(default_profile) = &self.terminal.default_profile {
+            if !names.contains(default_profile.as_str()) {
+                return Err(format!(
+                    "terminal.default_profile: no profile named \"{}\"",
+                    default_profile
+                ));
+            }
+        }
+
+        for pattern in &self.files.exclude {
+            let pattern = pattern.trim_start_matches('!');
+            if pattern.trim().is_empty() {
+                return Err("files.exclude: patterns cannot be empty".to_string());
+            }
+            if let Err(error) = Pattern::new(pattern.trim_matches('/')) {
+                return Err(format!("files.exclude: \"{}\" {}", pattern, error.msg));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+// Snippets
+// The user's snippets for the editor's snippet completion: a prefix that triggers them,
+// a body in TextMate syntax ($1, ${2:default}, ${3|a,b|}, $0) and the languages they
+// apply to. Each profile keeps its snippets in one JSON file in the app data directory.
+
+mod vscode;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::commands::file_commands::write_atomic;
+
+const SNIPPETS_DIR: &str = "snippets";
+
+const DEFAULT_PROFILE: &str = "default";
+
+static SNIPPETS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Held across a read and the write that follows it
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub body: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Language ids the snippet is offered in; every language when empty
+    #[serde(default)]
+    pub scope: Vec<String>,
+    /// Unix timestamp in milliseconds
+    pub updated_at: i64,
+}
+
+/// A snippet to save; one with an id replaces the stored snippet with that id
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnippetInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub prefix: String,
+    pub body: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub scope: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetImport {
+    pub imported: usize,
+    /// Imported snippets that replaced one with the same name and prefix
+    pub replaced: usize,
+}
+
+pub fn initialize(app_data_dir: PathBuf) -> Result<(), String> {
+    let snippets_dir = SNIPPETS_PATH.get_or_init(|| app_data_dir.join(SNIPPETS_DIR));
+    fs::create_dir_all(snippets_dir).map_err(|e| e.to_string())
+}
+
+fn snippets_dir() -> Result<&'static PathBuf, String> {
+    SNIPPETS_PATH
+        .get()
+        .ok_or_else(|| "Snippets have not been initialized".to_string())
+}
+
+/// The profile's file; profile names become file names, so they are kept plain
+fn profile_path(dir: &Path, profile: Option<&str>) -> Result<PathBuf, String> {
+    let profile = profile.unwrap_or(DEFAULT_PROFILE);
+    let valid = !profile.is_empty()
+        && profile.len() <= 64
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid snippet profile name: {}", profile));
+    }
+    Ok(dir.join(format!("{}.json", profile)))
+}
+
+fn read_snippets(path: &Path) -> Result<Vec<Snippet>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.to_string()),
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid snippets file {}: {}", path.display(), e))
+}
+
+fn write_snippets(path: &Path, snippets: &[Snippet]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(snippets).map_err(|e| e.to_string())?;
+    write_atomic(path, json.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Fail on a body whose `${` placeholders are not closed
+fn validate_body(body: &str) -> Result<(), String> {
+    let mut open = 0usize;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                open += 1;
+            }
+            '}' if open > 0 => open -= 1,
+            _ => {}
+        }
+    }
+    if open > 0 {
+        return Err("Snippet body has an unclosed ${ placeholder".to_string());
+    }
+    Ok(())
+}
+
+fn to_snippet(input: SnippetInput, now: i64) -> Result<Snippet, String> {
+    let prefix = input.prefix.trim().to_string();
+    if prefix.is_empty() || prefix.chars().any(char::is_whitespace) {
+        return Err(format!("Invalid snippet prefix: '{}'", input.prefix));
+    }
+    validate_body(&input.body)?;
+    let name = match input.name.trim() {
+        "" => prefix.clone(),
+        name => name.to_string(),
+    };
+    Ok(Snippet {
+        id: input.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name,
+        prefix,
+        body: input.body,
+        description: input.description.filter(|text| !text.trim().is_empty()),
+        scope: input.scope,
+        updated_at: now,
+    })
+}
+
+fn applies_to(snippet: &Snippet, language: &str) -> bool {
+    snippet.scope.is_empty()
+        || snippet
+            .scope
+            .iter()
+            .any(|scope| scope.eq_ignore_ascii_case(language))
+}
+
+fn list(dir: &Path, profile: Option<&str>, language: Option<&str>) -> Result<Vec<Snippet>, String> {
+    let mut snippets = read_snippets(&profile_path(dir, profile)?)?;
+    if let Some(language) = language {
+        snippets.retain(|snippet| applies_to(snippet, language));
+    }
+    snippets.sort_by(|a, b| a.prefix.cmp(&b.prefix).then_with(|| a.name.cmp(&b.name)));
+    Ok(snippets)
+}
+
+fn save(dir: &Path, profile: Option<&str>, input: SnippetInput) -> Result<Snippet, String> {
+    let path = profile_path(dir, profile)?;
+    let _guard = WRITE_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut snippets = read_snippets(&path)?;
+    let snippet = to_snippet(input, chrono::Utc::now().timestamp_millis())?;
+    match snippets
+        .iter_mut()
+        .find(|existing| existing.id == snippet.id)
+    {
+        Some(existing) => *existing = snippet.clone(),
+        None => snippets.push(snippet.clone()),
+    }
+    write_snippets(&path, &snippets)?;
+    Ok(snippet)
+}
+
+fn delete(dir: &Path, profile: Option<&str>, id: &str) -> Result<(), String> {
+    let path = profile_path(dir, profile)?;
+    let _guard = WRITE_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut snippets = read_snippets(&path)?;
+    let count = snippets.len();
+    snippets.retain(|snippet| snippet.id != id);
+    if snippets.len() == count {
+        return Err(format!("Snippet not found: {}", id));
+    }
+    write_snippets(&path, &snippets)
+}
+
+fn import(
+    dir: &Path,
+    profile: Option<&str>,
+    inputs: Vec<SnippetInput>,
+) -> Result<SnippetImport, String> {
+    let path = profile_path(dir, profile)?;
+    let _guard = WRITE_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut snippets = read_snippets(&path)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut result = SnippetImport {
+        imported: 0,
+        replaced: 0,
+    };
+    for input in inputs {
+        let mut snippet = to_snippet(input, now)?;
+        // Importing the same file again updates its snippets instead of doubling them
+        let existing = snippets
+            .iter_mut()
+            .find(|existing| existing.name == snippet.name && existing.prefix == snippet.prefix);
+        match existing {
+            Some(existing) => {
+                snippet.id = existing.id.clone();
+                *existing = snippet;
+                result.replaced += 1;
+            }
+            None => snippets.push(snippet),
+        }
+        result.imported += 1;
+    }
+    write_snippets(&path, &snippets)?;
+    Ok(result)
+}
+
+/// The profile's snippets, or those offered in `language`, by prefix
+#[tauri::command]
+pub async fn list_snippets(
+    profile: Option<String>,
+    language: Option<String>,
+) -> Result<Vec<Snippet>, String> {
+    list(snippets_dir()?, profile.as_deref(), language.as_deref())
+}
+
+/// Create a snippet, or update the one with the input's id
+#[tauri::command]
+pub async fn save_snippet(
+    profile: Option<String>,
+    snippet: SnippetInput,
+) -> Result<Snippet, String> {
+    save(snippets_dir()?, profile.as_deref(), snippet)
+}
+
+#[tauri::command]
+pub async fn delete_snippet(profile: Option<String>, id: String) -> Result<(), String> {
+    delete(snippets_dir()?, profile.as_deref(), &id)
+}
+
+/// Import the contents of a VS Code snippet file; `language` is the language of a
+/// per-language file such as `javascript.json`
+#[tauri::command]
+pub async fn import_vscode_snippets(
+    profile: Option<String>,
+    content: String,
+    language: Option<String>,
+) -> Result<SnippetImport, String> {
+    let inputs = vscode::parse(&content, language.as_deref())?;
+    import(snippets_dir()?, profile.as_deref(), inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(prefix: &str, body: &str, scope: &[&str]) -> SnippetInput {
+        SnippetInput {
+            id: None,
+            name: String::new(),
+            prefix: prefix.to_string(),
+            body: body.to_string(),
+            description: None,
+            scope: scope.iter().map(|language| language.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn stores_snippets_per_profile() {
+        let dir = std::env::temp_dir().join(format!("voidesk-snippets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let log = save(
+            &dir,
+            None,
+            input("log", "console.log(${1:value});$0", &["javascript"]),
+        )
+        .unwrap();
+        assert_eq!(log.name, "log");
+        save(&dir, None, input("todo", "// TODO: $0", &[])).unwrap();
+        save(&dir, Some("work"), input("fn", "fn $1() {}", &["rust"])).unwrap();
+
+        let javascript = list(&dir, None, Some("JavaScript")).unwrap();
+        let prefixes: Vec<&str> = javascript.iter().map(|s| s.prefix.as_str()).collect();
+        assert_eq!(prefixes, vec!["log", "todo"]);
+        assert_eq!(list(&dir, None, Some("rust")).unwrap().len(), 1);
+        assert_eq!(list(&dir, Some("work"), None).unwrap().len(), 1);
+
+        let mut edit = input("log", "console.log($1);", &["javascript"]);
+        edit.id = Some(log.id.clone());
+        save(&dir, None, edit).unwrap();
+        let stored = list(&dir, None, None).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].body, "console.log($1);");
+
+        delete(&dir, None, &log.id).unwrap();
+        assert!(delete(&dir, None, &log.id).is_err());
+        assert!(save(&dir, None, input("bad", "${1:open", &[])).is_err());
+        assert!(save(&dir, None, input("two words", "x", &[])).is_err());
+        assert!(profile_path(&dir, Some("../escape")).is_err());
+
+        let vscode = r#"{ "Todo": { "prefix": "todo", "body": "// TODO: $0" } }"#;
+        let inputs = vscode::parse(vscode, None).unwrap();
+        let first = import(&dir, None, inputs).unwrap();
+        assert_eq!((first.imported, first.replaced), (1, 0));
+        let again = import(&dir, None, vscode::parse(vscode, None).unwrap()).unwrap();
+        assert_eq!((again.imported, again.replaced), (1, 1));
+        assert_eq!(list(&dir, None, None).unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,174 @@
+// VS Code snippets
+// Reads the snippet files VS Code writes: JSON with comments and trailing commas, an
+// object of named snippets whose prefix and body are a string or a list of them. Bodies
+// use the same TextMate syntax we store, so they are imported as they are.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::SnippetInput;
+
+#[derive(Debug, Deserialize)]
+struct VsCodeSnippet {
+    #[serde(default)]
+    prefix: Option<StringOrList>,
+    body: StringOrList,
+    #[serde(default)]
+    description: Option<StringOrList>,
+    /// Comma-separated language ids, only in global `.code-snippets` files
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(text) => vec![text],
+            Self::Many(list) => list,
+        }
+    }
+}
+
+/// Drop `//` and `/* */` comments and trailing commas, leaving strings alone
+pub(crate) fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '}' | ']' => {
+                // Comments are gone from `out`, so a comma before them is still spotted
+                let content = out.trim_end().len();
+                if out[..content].ends_with(',') {
+                    out.remove(content - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Snippets in a VS Code snippet file; `language` scopes those that name no languages
+/// themselves, as a `javascript.json` file's do. A snippet with several prefixes is
+/// imported once per prefix.
+pub fn parse(content: &str, language: Option<&str>) -> Result<Vec<SnippetInput>, String> {
+    let value: Value = serde_json::from_str(&strip_jsonc(content))
+        .map_err(|e| format!("Invalid snippet file: {}", e))?;
+    let snippets: BTreeMap<String, Value> =
+        serde_json::from_value(value).map_err(|_| "Snippet file is not an object".to_string())?;
+
+    let mut inputs = Vec::new();
+    for (name, value) in snippets {
+        // Anything else in the file, such as a $schema key, is not a snippet
+        let Ok(snippet) = serde_json::from_value::<VsCodeSnippet>(value) else {
+            continue;
+        };
+        let body = snippet.body.into_vec().join("\n");
+        let description = snippet
+            .description
+            .map(|description| description.into_vec().join("\n"));
+        let scope = match snippet.scope {
+            Some(scope) => scope
+                .split(',')
+                .map(|language| language.trim().to_string())
+                .filter(|language| !language.is_empty())
+                .collect(),
+            None => language
+                .map(|language| vec![language.to_string()])
+                .unwrap_or_default(),
+        };
+        let prefixes = snippet
+            .prefix
+            .map(StringOrList::into_vec)
+            .unwrap_or_default();
+        for prefix in prefixes {
+            inputs.push(SnippetInput {
+                id: None,
+                name: name.clone(),
+                prefix,
+                body: body.clone(),
+                description: description.clone(),
+                scope: scope.clone(),
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_vscode_snippet_files() {
+        let content = r#"{
+            // Logs a value
+            "Print to console": {
+                "prefix": ["log", "cl"],
+                "body": ["console.log('$1');", "$0"],
+                "description": "Log output", /* trailing comma next */
+            },
+            "Region": {
+                "prefix": "region",
+                "body": "// #region ${1:name} \"x\"\n$0\n// #endregion",
+                "scope": "typescript, javascript"
+            },
+            "No prefix": { "body": "ignored" },
+        }"#;
+        let snippets = parse(content, Some("javascript")).unwrap();
+        assert_eq!(snippets.len(), 3);
+
+        let log = &snippets[0];
+        assert_eq!(
+            (log.name.as_str(), log.prefix.as_str()),
+            ("Print to console", "log")
+        );
+        assert_eq!(log.body, "console.log('$1');\n$0");
+        assert_eq!(log.scope, vec!["javascript"]);
+        assert_eq!(snippets[1].prefix, "cl");
+
+        let region = &snippets[2];
+        assert!(region.body.starts_with("// #region ${1:name} \"x\""));
+        assert_eq!(region.scope, vec!["typescript", "javascript"]);
+
+        assert!(parse("[1, 2]", None).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// Matching brackets
+// Pairs come from the syntax tree, so brackets inside strings and comments never match
+// code ones
+
+use serde::Serialize;
+use tree_sitter::Node;
+
+use super::Document;
+use crate::lsp::manager::LspRange;
+
+const PAIRS: &[(&str, &str)] = &[("(", ")"), ("[", "]"), ("{", "}")];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BracketPair {
+    pub open: LspRange,
+    pub close: LspRange,
+}
+
+fn bracket_at(document: &Document, byte: usize) -> Option<Node<'_>> {
+    let root = document.tree.root_node();
+    let is_bracket = |node: &Node| {
+        PAIRS
+            .iter()
+            .any(|(open, close)| node.kind() == *open || node.kind() == *close)
+    };
+    // The bracket after the cursor, else the one just before it
+    root.descendant_for_byte_range(byte, byte + 1)
+        .filter(|node| node.start_byte() == byte && is_bracket(node))
+        .or_else(|| {
+            let before = byte.checked_sub(1)?;
+            root.descendant_for_byte_range(before, byte)
+                .filter(|node| node.start_byte() == before && is_bracket(node))
+        })
+}
+
+/// The counterpart of `bracket` among its siblings, skipping nested pairs of the same kind
+fn counterpart<'tree>(bracket: Node<'tree>) -> Option<Node<'tree>> {
+    let parent = bracket.parent()?;
+    let (open, close) = PAIRS
+        .iter()
+        .find(|(open, close)| bracket.kind() == *open || bracket.kind() == *close)?;
+    let forward = bracket.kind() == *open;
+
+    let mut cursor = parent.walk();
+    let siblings: Vec<Node> = parent.children(&mut cursor).collect();
+    let index = siblings.iter().position(|sibling| *sibling == bracket)?;
+    let (same, other) = if forward {
+        (open, close)
+    } else {
+        (close, open)
+    };
+    let candidates: Box<dyn Iterator<Item = &Node>> = if forward {
+        Box::new(siblings[index + 1..].iter())
+    } else {
+        Box::new(siblings[..index].iter().rev())
+    };
+
+    let mut depth = 0usize;
+    for sibling in candidates {
+        if sibling.kind() == *same {
+            depth += 1;
+        } else if sibling.kind() == *other {
+            if depth == 0 {
+                return Some(*sibling);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// The pair containing the bracket at `byte` or just before it
+pub(super) fn matching_bracket(document: &Document, byte: usize) -> Option<BracketPair> {
+    let bracket = bracket_at(document, byte)?;
+    let other = counterpart(bracket)?;
+    let (open, close) = if bracket.start_byte() < other.start_byte() {
+        (bracket, other)
+    } else {
+        (other, bracket)
+    };
+    Some(BracketPair {
+        open: document.range(open),
+        close: document.range(close),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::manager::LspPosition;
+
+    #[test]
+    fn matches_code_brackets_and_ignores_strings() {
+        let source = "fn main() {\n    let s = \"}\";\n    call(s, [1]);\n}\n";
+        let document = Document::parse("main.rs", source.to_string()).unwrap();
+
+        let open = document.offset(&LspPosition {
+            line: 0,
+            character: 10,
+        });
+        let pair = matching_bracket(&document, open).unwrap();
+        assert_eq!((pair.close.start.line, pair.close.start.character), (3, 0));
+
+        // Cursor right after the `)` of the call
+        let after = document.offset(&LspPosition {
+            line: 2,
+            character: 16,
+        });
+        let pair = matching_bracket(&document, after).unwrap();
+        assert_eq!((pair.open.start.line, pair.open.start.character), (2, 8));
+
+        let inside_string = document.offset(&LspPosition {
+            line: 1,
+            character: 13,
+        });
+        assert!(matching_bracket(&document, inside_string).is_none());
+    }
+}
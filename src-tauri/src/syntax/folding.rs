@@ -0,0 +1,178 @@
+// Folding ranges
+// Bracketed blocks, multi-line comments, indented blocks and runs of imports
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tree_sitter::Node;
+
+use super::Document;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FoldingRange {
+    pub start_line: u32,
+    /// For a bracketed block, the line of the closing bracket
+    pub end_line: u32,
+    /// "comment" or "imports"; None for code blocks
+    pub kind: Option<&'static str>,
+}
+
+const OPEN_BRACKETS: &[&str] = &["{", "[", "("];
+const CLOSE_BRACKETS: &[&str] = &["}", "]", ")"];
+
+fn is_bracketed(node: Node) -> bool {
+    let count = node.child_count();
+    count >= 2
+        && node
+            .child(0)
+            .is_some_and(|first| OPEN_BRACKETS.contains(&first.kind()))
+        && node
+            .child(count - 1)
+            .is_some_and(|last| CLOSE_BRACKETS.contains(&last.kind()))
+}
+
+/// The lines `node` folds, if it folds at all
+fn fold_for(document: &Document, node: Node) -> Option<FoldingRange> {
+    let start = node.start_position().row as u32;
+    let end = node.end_position().row as u32;
+    let range = if node.kind().contains("comment") {
+        FoldingRange {
+            start_line: start,
+            end_line: end,
+            kind: Some("comment"),
+        }
+    } else if is_bracketed(node) {
+        FoldingRange {
+            start_line: start,
+            end_line: end,
+            kind: None,
+        }
+    } else if document.language.indented_blocks.contains(&node.kind()) {
+        // The block starts below the line that opens it, e.g. `def f():`
+        let header = node.parent()?.start_position().row as u32;
+        FoldingRange {
+            start_line: header.min(start),
+            end_line: end,
+            kind: None,
+        }
+    } else {
+        return None;
+    };
+    (range.end_line > range.start_line).then_some(range)
+}
+
+/// Runs of import statements on consecutive lines, among the children of `node`
+fn import_runs(document: &Document, node: Node, ranges: &mut Vec<FoldingRange>) {
+    let imports = document.language.imports;
+    let mut run: Option<(u32, u32)> = None;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let (start, end) = (
+            child.start_position().row as u32,
+            child.end_position().row as u32,
+        );
+        run = match run {
+            _ if !imports.contains(&child.kind()) => {
+                ranges.extend(finish_run(run));
+                None
+            }
+            Some((run_start, run_end)) if start <= run_end + 1 => Some((run_start, end)),
+            _ => {
+                ranges.extend(finish_run(run));
+                Some((start, end))
+            }
+        };
+    }
+    ranges.extend(finish_run(run));
+}
+
+fn finish_run(run: Option<(u32, u32)>) -> Option<FoldingRange> {
+    let (start, end) = run?;
+    (end > start).then_some(FoldingRange {
+        start_line: start,
+        end_line: end,
+        kind: Some("imports"),
+    })
+}
+
+/// At most one range per start line, the outermost, sorted by start line
+pub(super) fn folding_ranges(document: &Document) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    let root = document.tree.root_node();
+    if !document.language.imports.is_empty() {
+        import_runs(document, root, &mut ranges);
+    }
+
+    let mut cursor = root.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if node.is_named() {
+            ranges.extend(fold_for(document, node));
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+
+    let mut by_start = BTreeMap::<u32, FoldingRange>::new();
+    for range in ranges {
+        match by_start.get(&range.start_line) {
+            Some(existing) if existing.end_line >= range.end_line => {}
+            _ => {
+                by_start.insert(range.start_line, range);
+            }
+        }
+    }
+    by_start.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_blocks_comments_and_imports() {
+        let source = "\
+import os
+import sys
+
+\"\"\"Module
+docs\"\"\"
+
+def main():
+    if os.environ:
+        print(sys.argv)
+    return 0
+";
+        let document = Document::parse("main.py", source.to_string()).unwrap();
+        let ranges = folding_ranges(&document);
+        let imports = FoldingRange {
+            start_line: 0,
+            end_line: 1,
+            kind: Some("imports"),
+        };
+        assert_eq!(ranges[0], imports);
+        assert!(ranges
+            .iter()
+            .any(|range| range.start_line == 6 && range.end_line == 9));
+        assert!(ranges
+            .iter()
+            .any(|range| range.start_line == 7 && range.end_line == 8));
+
+        let source = "fn main() {\n    /* a\n       b */\n    let v = [\n        1,\n    ];\n}\n";
+        let document = Document::parse("main.rs", source.to_string()).unwrap();
+        let ranges = folding_ranges(&document);
+        let lines: Vec<(u32, u32, Option<&str>)> = ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![(0, 6, None), (1, 2, Some("comment")), (3, 5, None)]
+        );
+    }
+}
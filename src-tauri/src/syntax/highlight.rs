@@ -0,0 +1,126 @@
+// Highlighting
+// Code as HTML with each token wrapped in a `<span class="tok-…">`, for rendered
+// Markdown. Tokens are classed by their syntax node alone, which is coarser than an
+// editor theme but needs no per-language queries.
+
+use tree_sitter::Node;
+
+use super::{languages, Document};
+
+const TYPES: &[&str] = &[
+    "type_identifier",
+    "primitive_type",
+    "predefined_type",
+    "builtin_type",
+];
+const CONSTANTS: &[&str] = &[
+    "true",
+    "false",
+    "null",
+    "nil",
+    "none",
+    "undefined",
+    "boolean_literal",
+];
+const KEYWORDS: &[&str] = &["self", "this", "super", "crate", "mutable_specifier"];
+
+fn escape(text: &str, out: &mut String) {
+    for ch in text.chars() {
+        match ch {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+/// The class for a node highlighted as a whole, None for one to descend into
+fn class_of(node: Node, text: &str) -> Option<&'static str> {
+    let kind = node.kind();
+    if kind.contains("comment") {
+        Some("comment")
+    } else if kind.contains("string") || kind == "char_literal" || kind == "rune_literal" {
+        Some("string")
+    } else if TYPES.contains(&kind) {
+        Some("type")
+    } else if kind.contains("integer") || kind.contains("float") || kind == "number" {
+        Some("number")
+    } else if CONSTANTS.contains(&kind) {
+        Some("constant")
+    } else if KEYWORDS.contains(&kind)
+        || (!node.is_named()
+            && !text.is_empty()
+            && text.chars().all(|ch| ch.is_ascii_alphabetic() || ch == '_'))
+    {
+        Some("keyword")
+    } else {
+        None
+    }
+}
+
+fn write_node(document: &Document, node: Node, cursor: &mut usize, out: &mut String) {
+    let text = document.text(node);
+    let class = class_of(node, text);
+    if class.is_none() && node.child_count() > 0 {
+        let mut walker = node.walk();
+        for child in node.children(&mut walker) {
+            write_node(document, child, cursor, out);
+        }
+        return;
+    }
+    // Whitespace, and anything the grammar skipped, since the previous token
+    if let Some(gap) = document.source.get(*cursor..node.start_byte()) {
+        escape(gap, out);
+    }
+    match class {
+        Some(class) => {
+            out.push_str("<span class=\"tok-");
+            out.push_str(class);
+            out.push_str("\">");
+            escape(text, out);
+            out.push_str("</span>");
+        }
+        None => escape(text, out),
+    }
+    *cursor = (*cursor).max(node.end_byte());
+}
+
+/// `code` as highlighted HTML, without the surrounding `<pre>`; None when there is no
+/// grammar for `language`, a code fence name such as "rust" or "ts"
+pub(crate) fn highlight_html(language: &str, code: &str) -> Option<String> {
+    let document = Document::parse_as(languages::for_fence(language)?, code.to_string())?;
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut cursor = 0;
+    write_node(&document, document.tree.root_node(), &mut cursor, &mut out);
+    if let Some(rest) = document.source.get(cursor..) {
+        escape(rest, &mut out);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_tokens_and_keeps_the_text() {
+        let code = "// add\nfn add(a: i32) -> bool { a < 2 && \"x\" != \"y\" }\n";
+        let html = highlight_html("rust", code).unwrap();
+        assert!(html.starts_with("<span class=\"tok-comment\">// add</span>\n"));
+        assert!(html.contains("<span class=\"tok-keyword\">fn</span> add(a: "));
+        assert!(html.contains("<span class=\"tok-type\">i32</span>"));
+        assert!(html.contains("a &lt; <span class=\"tok-number\">2</span> &amp;&amp; "));
+        assert!(html.contains("<span class=\"tok-string\">&quot;x&quot;</span>"));
+
+        let stripped = regex::Regex::new("<[^>]+>").unwrap().replace_all(&html, "");
+        let unescaped = stripped
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+        assert_eq!(unescaped, code);
+        assert!(highlight_html("cobol", code).is_none());
+    }
+}
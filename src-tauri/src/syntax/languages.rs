@@ -0,0 +1,235 @@
+// Grammars
+// The embedded tree-sitter grammars, which file extensions use them, and per language
+// which nodes are outline symbols, which fold by indentation and which are imports
+
+use std::path::Path;
+use tree_sitter::Language;
+
+pub(super) struct SyntaxLanguage {
+    pub grammar: fn() -> Language,
+    /// Checked in order; the first rule matching a node makes it a symbol
+    pub symbols: &'static [SymbolRule],
+    /// Nodes without brackets whose extent is set by indentation, e.g. a Python block
+    pub indented_blocks: &'static [&'static str],
+    pub imports: &'static [&'static str],
+}
+
+pub(super) enum SymbolName {
+    /// Text of the node's field
+    Field(&'static str),
+    /// Text of the node's first child of this kind
+    Child(&'static str),
+    /// `Type`, or `Trait for Type`, of a Rust impl block
+    RustImpl,
+}
+
+pub(super) struct SymbolRule {
+    pub node: &'static str,
+    pub kind: &'static str,
+    pub name: SymbolName,
+    /// When not empty, only nodes whose `value` field is one of these
+    pub values: &'static [&'static str],
+}
+
+const fn rule(node: &'static str, kind: &'static str) -> SymbolRule {
+    SymbolRule {
+        node,
+        kind,
+        name: SymbolName::Field("name"),
+        values: &[],
+    }
+}
+
+const fn named_by(node: &'static str, kind: &'static str, name: SymbolName) -> SymbolRule {
+    SymbolRule {
+        node,
+        kind,
+        name,
+        values: &[],
+    }
+}
+
+const fn valued(
+    node: &'static str,
+    kind: &'static str,
+    values: &'static [&'static str],
+) -> SymbolRule {
+    SymbolRule {
+        node,
+        kind,
+        name: SymbolName::Field("name"),
+        values,
+    }
+}
+
+const FUNCTION_VALUES: &[&str] = &[
+    "arrow_function",
+    "function_expression",
+    "generator_function",
+];
+
+const RUST_SYMBOLS: &[SymbolRule] = &[
+    rule("function_item", "function"),
+    rule("function_signature_item", "function"),
+    rule("struct_item", "struct"),
+    rule("union_item", "struct"),
+    rule("enum_item", "enum"),
+    rule("enum_variant", "enum_member"),
+    rule("field_declaration", "field"),
+    rule("trait_item", "trait"),
+    named_by("impl_item", "impl", SymbolName::RustImpl),
+    rule("mod_item", "module"),
+    rule("const_item", "constant"),
+    rule("static_item", "constant"),
+    rule("type_item", "type"),
+    rule("associated_type", "type"),
+    rule("macro_definition", "macro"),
+];
+
+const JAVASCRIPT_SYMBOLS: &[SymbolRule] = &[
+    rule("function_declaration", "function"),
+    rule("generator_function_declaration", "function"),
+    rule("class_declaration", "class"),
+    rule("method_definition", "method"),
+    named_by("field_definition", "field", SymbolName::Field("property")),
+    valued("variable_declarator", "function", FUNCTION_VALUES),
+    valued("variable_declarator", "class", &["class"]),
+];
+
+const TYPESCRIPT_SYMBOLS: &[SymbolRule] = &[
+    rule("function_declaration", "function"),
+    rule("generator_function_declaration", "function"),
+    rule("function_signature", "function"),
+    rule("class_declaration", "class"),
+    rule("abstract_class_declaration", "class"),
+    rule("method_definition", "method"),
+    rule("method_signature", "method"),
+    rule("abstract_method_signature", "method"),
+    rule("public_field_definition", "field"),
+    rule("property_signature", "field"),
+    rule("interface_declaration", "interface"),
+    rule("type_alias_declaration", "type"),
+    rule("enum_declaration", "enum"),
+    rule("internal_module", "module"),
+    rule("module", "module"),
+    valued("variable_declarator", "function", FUNCTION_VALUES),
+    valued("variable_declarator", "class", &["class"]),
+];
+
+const PYTHON_SYMBOLS: &[SymbolRule] = &[
+    rule("function_definition", "function"),
+    rule("class_definition", "class"),
+];
+
+const GO_SYMBOLS: &[SymbolRule] = &[
+    rule("function_declaration", "function"),
+    rule("method_declaration", "method"),
+    rule("type_spec", "type"),
+    rule("type_alias", "type"),
+    rule("const_spec", "constant"),
+];
+
+const JSON_SYMBOLS: &[SymbolRule] = &[named_by("pair", "property", SymbolName::Field("key"))];
+
+const CSS_SYMBOLS: &[SymbolRule] = &[
+    named_by("rule_set", "rule", SymbolName::Child("selectors")),
+    named_by(
+        "keyframes_statement",
+        "rule",
+        SymbolName::Child("keyframes_name"),
+    ),
+];
+
+const JS_IMPORTS: &[&str] = &["import_statement"];
+
+static RUST: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_rust::LANGUAGE.into(),
+    symbols: RUST_SYMBOLS,
+    indented_blocks: &[],
+    imports: &["use_declaration", "extern_crate_declaration"],
+};
+
+static TYPESCRIPT: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+    symbols: TYPESCRIPT_SYMBOLS,
+    indented_blocks: &[],
+    imports: JS_IMPORTS,
+};
+
+static TSX: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_typescript::LANGUAGE_TSX.into(),
+    symbols: TYPESCRIPT_SYMBOLS,
+    indented_blocks: &[],
+    imports: JS_IMPORTS,
+};
+
+static JAVASCRIPT: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_javascript::LANGUAGE.into(),
+    symbols: JAVASCRIPT_SYMBOLS,
+    indented_blocks: &[],
+    imports: JS_IMPORTS,
+};
+
+static PYTHON: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_python::LANGUAGE.into(),
+    symbols: PYTHON_SYMBOLS,
+    indented_blocks: &["block"],
+    imports: &[
+        "import_statement",
+        "import_from_statement",
+        "future_import_statement",
+    ],
+};
+
+static GO: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_go::LANGUAGE.into(),
+    symbols: GO_SYMBOLS,
+    indented_blocks: &[],
+    imports: &["import_declaration"],
+};
+
+static JSON: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_json::LANGUAGE.into(),
+    symbols: JSON_SYMBOLS,
+    indented_blocks: &[],
+    imports: &[],
+};
+
+static CSS: SyntaxLanguage = SyntaxLanguage {
+    grammar: || tree_sitter_css::LANGUAGE.into(),
+    symbols: CSS_SYMBOLS,
+    indented_blocks: &[],
+    imports: &["import_statement"],
+};
+
+/// The grammar for `path`, by extension; None when none is embedded
+pub(super) fn for_path(path: &Path) -> Option<&'static SyntaxLanguage> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => &RUST,
+        "ts" | "mts" | "cts" => &TYPESCRIPT,
+        "tsx" => &TSX,
+        "js" | "jsx" | "mjs" | "cjs" => &JAVASCRIPT,
+        "py" | "pyi" => &PYTHON,
+        "go" => &GO,
+        "json" => &JSON,
+        "css" => &CSS,
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// The grammar for a Markdown code fence's language name, e.g. "rust" or "ts"
+pub(super) fn for_fence(name: &str) -> Option<&'static SyntaxLanguage> {
+    let name = name.trim().to_lowercase();
+    let extension = match name.as_str() {
+        "rust" => "rs",
+        "typescript" => "ts",
+        "javascript" | "node" => "js",
+        "python" | "python3" => "py",
+        "golang" => "go",
+        "jsonc" | "json5" => "json",
+        other => other,
+    };
+    for_path(Path::new(&format!("fence.{}", extension)))
+}
@@ -0,0 +1,157 @@
+// Syntax
+// Structural features from embedded tree-sitter grammars: document outline, folding
+// ranges, matching brackets, a workspace-wide symbol index and code highlighting. They
+// work without a language server installed, the per-file ones on unsaved editor content
+// too. Positions use the LSP convention of UTF-16 columns.
+
+mod brackets;
+mod folding;
+mod highlight;
+mod languages;
+mod outline;
+pub mod symbol_index;
+
+use std::path::Path;
+use tree_sitter::{Node, Parser, Tree};
+
+use crate::commands::workspace_roots;
+use crate::lsp::manager::{LspPosition, LspRange};
+use languages::SyntaxLanguage;
+
+pub use brackets::BracketPair;
+pub use folding::FoldingRange;
+pub(crate) use highlight::highlight_html;
+pub use outline::OutlineSymbol;
+
+/// A parsed source file
+pub(crate) struct Document {
+    source: String,
+    tree: Tree,
+    language: &'static SyntaxLanguage,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl Document {
+    /// Parse `source` with the grammar for `path`; None when there is no grammar for it
+    pub(crate) fn parse(path: &str, source: String) -> Option<Self> {
+        Self::parse_as(languages::for_path(Path::new(path))?, source)
+    }
+
+    fn parse_as(language: &'static SyntaxLanguage, source: String) -> Option<Self> {
+        let mut parser = Parser::new();
+        parser.set_language(&(language.grammar)()).ok()?;
+        let tree = parser.parse(&source, None)?;
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Some(Self {
+            source,
+            tree,
+            language,
+            line_starts,
+        })
+    }
+
+    fn text(&self, node: Node) -> &str {
+        self.source.get(node.byte_range()).unwrap_or_default()
+    }
+
+    fn position(&self, byte: usize) -> LspPosition {
+        let line = self.line_starts.partition_point(|start| *start <= byte) - 1;
+        let line_start = self.line_starts[line];
+        let character = self
+            .source
+            .get(line_start..byte)
+            .map(|text| text.encode_utf16().count())
+            .unwrap_or(0);
+        LspPosition {
+            line: line as u32,
+            character: character as u32,
+        }
+    }
+
+    fn range(&self, node: Node) -> LspRange {
+        LspRange {
+            start: self.position(node.start_byte()),
+            end: self.position(node.end_byte()),
+        }
+    }
+
+    /// Byte offset of `position`, clamped to the end of its line
+    fn offset(&self, position: &LspPosition) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return self.source.len();
+        };
+        let line = &self.source[line_start..];
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+        let mut units = 0;
+        for (index, ch) in line.char_indices() {
+            if units >= position.character as usize {
+                return line_start + index;
+            }
+            units += ch.len_utf16();
+        }
+        line_start + line.len()
+    }
+}
+
+/// Parse `content`, or the file at `path` when no content is given (which must then be in
+/// the workspace), off the async runtime and run `query` on it; None when there is no
+/// grammar for the file
+async fn with_document<T: Send + 'static>(
+    path: String,
+    content: Option<String>,
+    query: impl FnOnce(&Document) -> T + Send + 'static,
+) -> Result<Option<T>, String> {
+    tokio::task::spawn_blocking(move || {
+        if languages::for_path(Path::new(&path)).is_none() {
+            return Ok(None);
+        }
+        let source = match content {
+            Some(content) => content,
+            None => {
+                workspace_roots::ensure_in_workspace(&path)?;
+                std::fs::read_to_string(&path).map_err(|e| e.to_string())?
+            }
+        };
+        Ok(Document::parse(&path, source).map(|document| query(&document)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Symbols of the file, nested; empty for languages without a grammar
+#[tauri::command]
+pub async fn syntax_outline(
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<OutlineSymbol>, String> {
+    Ok(with_document(path, content, outline::outline)
+        .await?
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn syntax_folding_ranges(
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<FoldingRange>, String> {
+    Ok(with_document(path, content, folding::folding_ranges)
+        .await?
+        .unwrap_or_default())
+}
+
+/// The bracket pair with a bracket at `position` or just before it
+#[tauri::command]
+pub async fn syntax_matching_bracket(
+    path: String,
+    content: Option<String>,
+    position: LspPosition,
+) -> Result<Option<BracketPair>, String> {
+    Ok(with_document(path, content, move |document| {
+        brackets::matching_bracket(document, document.offset(&position))
+    })
+    .await?
+    .flatten())
+}
@@ -0,0 +1,148 @@
+// Document outline
+// Symbols found by walking the syntax tree, nested the way they are in the source
+
+use serde::Serialize;
+use tree_sitter::Node;
+
+use super::languages::{SymbolName, SymbolRule, SyntaxLanguage};
+use super::Document;
+use crate::lsp::manager::LspRange;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineSymbol {
+    pub name: String,
+    /// e.g. "function", "struct", "class", "method" or "property"
+    pub kind: &'static str,
+    /// The whole declaration
+    pub range: LspRange,
+    /// Just the name
+    pub selection_range: LspRange,
+    pub children: Vec<OutlineSymbol>,
+}
+
+/// Node text on one line, e.g. a selector list spread over several
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn name_node<'tree>(node: Node<'tree>, rule: &SymbolRule) -> Option<Node<'tree>> {
+    match rule.name {
+        SymbolName::Field(field) => node.child_by_field_name(field),
+        SymbolName::Child(kind) => {
+            let mut cursor = node.walk();
+            let child = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == kind);
+            child
+        }
+        SymbolName::RustImpl => node.child_by_field_name("type"),
+    }
+}
+
+fn symbol_for(document: &Document, language: &SyntaxLanguage, node: Node) -> Option<OutlineSymbol> {
+    let rule = language.symbols.iter().find(|rule| {
+        rule.node == node.kind()
+            && (rule.values.is_empty()
+                || node
+                    .child_by_field_name("value")
+                    .is_some_and(|value| rule.values.contains(&value.kind())))
+    })?;
+    let name_node = name_node(node, rule)?;
+    let mut name = one_line(document.text(name_node));
+    if matches!(rule.name, SymbolName::RustImpl) {
+        if let Some(trait_node) = node.child_by_field_name("trait") {
+            name = format!("{} for {}", one_line(document.text(trait_node)), name);
+        }
+    }
+    // JSON keys are string nodes, quotes included
+    if rule.kind == "property" {
+        name = name.trim_matches('"').to_string();
+    }
+    Some(OutlineSymbol {
+        name,
+        kind: rule.kind,
+        range: document.range(node),
+        selection_range: document.range(name_node),
+        children: Vec::new(),
+    })
+}
+
+fn collect(
+    document: &Document,
+    language: &SyntaxLanguage,
+    node: Node,
+    out: &mut Vec<OutlineSymbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match symbol_for(document, language, child) {
+            Some(mut symbol) => {
+                collect(document, language, child, &mut symbol.children);
+                out.push(symbol);
+            }
+            None => collect(document, language, child, out),
+        }
+    }
+}
+
+pub(super) fn outline(document: &Document) -> Vec<OutlineSymbol> {
+    let mut symbols = Vec::new();
+    collect(
+        document,
+        document.language,
+        document.tree.root_node(),
+        &mut symbols,
+    );
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nests_symbols_as_in_the_source() {
+        let source = "\
+mod config {
+    pub struct Settings {
+        pub theme: String,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            todo!()
+        }
+    }
+}
+
+const MAX: usize = 3;
+";
+        let document = Document::parse("lib.rs", source.to_string()).unwrap();
+        let symbols = outline(&document);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(
+            (symbols[0].name.as_str(), symbols[0].kind),
+            ("config", "module")
+        );
+        assert_eq!(symbols[1].name, "MAX");
+
+        let module = &symbols[0].children;
+        assert_eq!(module[0].name, "Settings");
+        assert_eq!(module[0].children[0].name, "theme");
+        assert_eq!(module[1].name, "Default for Settings");
+        assert_eq!(module[1].children[0].name, "default");
+        assert_eq!(module[1].children[0].selection_range.start.line, 6);
+
+        let document = Document::parse(
+            "app.ts",
+            "export const load = async () => {};\nconst limit = 3;\n".to_string(),
+        )
+        .unwrap();
+        let symbols = outline(&document);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(
+            (symbols[0].name.as_str(), symbols[0].kind),
+            ("load", "function")
+        );
+    }
+}
@@ -0,0 +1,333 @@
+// Symbol index
+// Definitions from every file with a grammar in the open workspaces, for a "Go to
+// Symbol" picker that works before, or without, a language server. A workspace is
+// indexed in the background when it opens and kept current by the file watcher.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use super::outline::{outline, OutlineSymbol};
+use super::{languages, Document};
+use crate::commands::file_finder::fuzzy_score;
+use crate::commands::{workspace_index, workspace_roots};
+use crate::lsp::manager::LspRange;
+
+/// Larger files are usually generated or minified
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+/// Impl blocks repeat their type's name; JSON keys and CSS rules would drown out the code
+const SKIPPED_KINDS: &[&str] = &["impl", "property", "rule"];
+
+// Added to the fuzzy score
+const BONUS_EXACT: i64 = 200;
+const BONUS_PREFIX: i64 = 60;
+
+#[derive(Debug, Clone)]
+struct IndexedSymbol {
+    name: String,
+    kind: &'static str,
+    container: Option<String>,
+    selection_range: LspRange,
+}
+
+/// Symbols per file of one workspace root
+type RootSymbols = HashMap<String, Vec<IndexedSymbol>>;
+
+#[derive(Debug, Serialize)]
+pub struct SymbolMatch {
+    pub name: String,
+    pub kind: &'static str,
+    /// The enclosing symbol, e.g. the impl or class of a method
+    pub container: Option<String>,
+    pub path: String,
+    pub relative_path: String,
+    /// The symbol's name in the file
+    pub range: LspRange,
+    pub score: i64,
+    /// Char indices into `name` that matched, for highlighting
+    pub positions: Vec<usize>,
+}
+
+static SYMBOLS: OnceLock<Mutex<HashMap<String, RootSymbols>>> = OnceLock::new();
+static BUILDING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn symbols() -> &'static Mutex<HashMap<String, RootSymbols>> {
+    SYMBOLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn building() -> &'static Mutex<HashSet<String>> {
+    BUILDING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Roots are keyed by their canonical path, as the workspace registers them
+fn root_key(root: &str) -> String {
+    Path::new(root)
+        .canonicalize()
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|_| root.to_string())
+}
+
+fn flatten(symbols: Vec<OutlineSymbol>, container: Option<&str>, out: &mut Vec<IndexedSymbol>) {
+    for symbol in symbols {
+        if !SKIPPED_KINDS.contains(&symbol.kind) {
+            out.push(IndexedSymbol {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+                container: container.map(str::to_string),
+                selection_range: symbol.selection_range,
+            });
+        }
+        flatten(symbol.children, Some(&symbol.name), out);
+    }
+}
+
+/// Symbols of the file at `path`; None when it is gone, too large or has no grammar
+fn file_symbols(path: &str) -> Option<Vec<IndexedSymbol>> {
+    languages::for_path(Path::new(path))?;
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let source = fs::read_to_string(path).ok()?;
+    let document = Document::parse(path, source)?;
+    let mut symbols = Vec::new();
+    flatten(outline(&document), None, &mut symbols);
+    Some(symbols)
+}
+
+fn index_files(paths: &[String]) -> RootSymbols {
+    paths
+        .iter()
+        .filter_map(|path| Some((path.clone(), file_symbols(path)?)))
+        .collect()
+}
+
+/// Index every file of `root` known to the workspace index, unless that is already
+/// happening on another thread
+fn build_root(root: &str) -> Result<(), String> {
+    if !building()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(root.to_string())
+    {
+        return Ok(());
+    }
+    let result = (|| {
+        let mut paths = Vec::new();
+        workspace_index::for_each_indexed_file(root, |_, path| {
+            if languages::for_path(Path::new(path)).is_some() {
+                paths.push(path.to_string());
+            }
+        })?;
+        let indexed = index_files(&paths);
+        tracing::info!("Indexed symbols of {} files in {}", indexed.len(), root);
+        symbols()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(root.to_string(), indexed);
+        Ok(())
+    })();
+    if let Ok(mut building) = building().lock() {
+        building.remove(root);
+    }
+    result
+}
+
+/// Index `root` in the background
+pub fn index_root(root: &str) {
+    let root = root_key(root);
+    tokio::task::spawn_blocking(move || {
+        if let Err(error) = build_root(&root) {
+            tracing::warn!("Could not index symbols of {}: {}", root, error);
+        }
+    });
+}
+
+/// Drop the symbols of a closed workspace
+pub fn forget_root(root: &str) {
+    if let Ok(mut symbols) = symbols().lock() {
+        symbols.remove(&root_key(root));
+    }
+}
+
+/// Re-read changed, created and removed paths of an indexed root in the background
+pub fn update_files(root: &str, paths: &[String]) {
+    let root = root_key(root);
+    let indexed = symbols()
+        .lock()
+        .map(|symbols| symbols.contains_key(&root))
+        .unwrap_or(false);
+    if !indexed {
+        return;
+    }
+    let paths = paths.to_vec();
+    tokio::task::spawn_blocking(move || {
+        let updates: Vec<(String, Option<Vec<IndexedSymbol>>)> = paths
+            .into_iter()
+            .map(|path| {
+                let symbols = file_symbols(&path);
+                (path, symbols)
+            })
+            .collect();
+        let Ok(mut symbols) = symbols().lock() else {
+            return;
+        };
+        let Some(files) = symbols.get_mut(&root) else {
+            return;
+        };
+        for (path, file_symbols) in updates {
+            match file_symbols {
+                Some(file_symbols) => {
+                    files.insert(path, file_symbols);
+                }
+                None => {
+                    // Also everything under a removed folder
+                    let folder = format!("{}/", path.trim_end_matches(['/', '\\']));
+                    files.retain(|file, _| file != &path && !file.starts_with(&folder));
+                }
+            }
+        }
+    });
+}
+
+fn search(roots: &[String], query: &str, limit: usize) -> Result<Vec<SymbolMatch>, String> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let lowered: String = query.iter().collect::<String>().to_lowercase();
+
+    let symbols = symbols().lock().map_err(|e| e.to_string())?;
+    let mut matches = Vec::new();
+    for root in roots {
+        let Some(files) = symbols.get(root) else {
+            continue;
+        };
+        for (path, file_symbols) in files {
+            for symbol in file_symbols {
+                let Some((mut score, positions)) = fuzzy_score(&query, &symbol.name) else {
+                    continue;
+                };
+                let name = symbol.name.to_lowercase();
+                if name == lowered {
+                    score += BONUS_EXACT;
+                } else if name.starts_with(&lowered) {
+                    score += BONUS_PREFIX;
+                }
+                let relative_path = Path::new(path)
+                    .strip_prefix(root)
+                    .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_else(|_| path.clone());
+                matches.push(SymbolMatch {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    container: symbol.container.clone(),
+                    path: path.clone(),
+                    relative_path,
+                    range: symbol.selection_range.clone(),
+                    score,
+                    positions,
+                });
+            }
+        }
+    }
+
+    matches.sort_by(|left, right| {
+        right
+            .score
+            .cmp(&left.score)
+            .then_with(|| left.name.cmp(&right.name))
+            .then_with(|| left.relative_path.cmp(&right.relative_path))
+    });
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Definitions whose name fuzzy-matches `query`, best first, in `root` or in every open
+/// workspace. A workspace not indexed yet is indexed first.
+#[tauri::command]
+pub async fn search_symbols(
+    query: String,
+    root: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SymbolMatch>, String> {
+    let roots = match root {
+        Some(root) => {
+            workspace_roots::ensure_in_workspace(&root)?;
+            vec![root_key(&root)]
+        }
+        None => workspace_roots::list()?
+            .into_iter()
+            .map(|root| root.to_string_lossy().to_string())
+            .collect(),
+    };
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    tokio::task::spawn_blocking(move || {
+        for root in &roots {
+            let indexed = symbols()
+                .lock()
+                .map_err(|e| e.to_string())?
+                .contains_key(root);
+            if !indexed {
+                build_root(root)?;
+            }
+        }
+        search(&roots, &query, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_exact_and_prefix_matches_first() {
+        let dir = std::env::temp_dir().join(format!("voidesk-symbols-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let settings = dir.join("src/settings.rs");
+        fs::write(
+            &settings,
+            "pub struct Settings;\nimpl Settings {\n    pub fn set_theme(&self) {}\n}\n",
+        )
+        .unwrap();
+        let app = dir.join("src/app.ts");
+        fs::write(&app, "export class SettingsStore {}\n").unwrap();
+        let package = dir.join("package.json");
+        fs::write(&package, "{\"settings\": {}}\n").unwrap();
+
+        let root = dir.to_string_lossy().to_string();
+        let paths: Vec<String> = [&settings, &app, &package]
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        symbols()
+            .lock()
+            .unwrap()
+            .insert(root.clone(), index_files(&paths));
+
+        let roots = vec![root.clone()];
+        let matches = search(&roots, "settings", 10).unwrap();
+        let names: Vec<&str> = matches.iter().map(|found| found.name.as_str()).collect();
+        assert_eq!(names, vec!["Settings", "SettingsStore"]);
+        assert_eq!(matches[0].relative_path, "src/settings.rs");
+
+        let matches = search(&roots, "stthm", 10).unwrap();
+        assert_eq!(matches[0].name, "set_theme");
+        assert_eq!(matches[0].container.as_deref(), Some("Settings"));
+        assert_eq!(matches[0].range.start.line, 2);
+
+        fs::remove_file(&app).unwrap();
+        assert!(file_symbols(&paths[1]).is_none());
+
+        forget_root(&root);
+        assert!(search(&roots, "settings", 10).unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,286 @@
+// Dev server manager
+// Runs long-lived commands like `npm run dev` in managed PTYs and reports the URL
+// they start listening on so the app can offer a preview, or, when the port is taken,
+// which process already holds it
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, State};
+
+use super::shell_invocation;
+use crate::commands::port_commands::{listening_ports, ListeningPort};
+use crate::terminal::{self, strip_ansi, PtySpawnOptions, TerminalState};
+
+/// Longest partial line kept while waiting for its newline
+const MAX_PARTIAL_LINE: usize = 4096;
+
+#[derive(Clone, Serialize)]
+pub struct DevServerInfo {
+    /// Terminal id of the PTY running the server
+    pub pid: u32,
+    pub command: String,
+    pub cwd: String,
+    pub url: Option<String>,
+    pub port: Option<u16>,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+}
+
+/// Payload of the `dev-server-ready` event
+#[derive(Clone, Serialize)]
+pub struct DevServerReadyEvent {
+    pub pid: u32,
+    pub url: String,
+    pub port: u16,
+}
+
+/// Payload of the `dev-server-port-in-use` event
+#[derive(Clone, Serialize)]
+pub struct DevServerPortInUseEvent {
+    pub pid: u32,
+    pub port: u16,
+    /// What is listening on the port, when it can be told
+    pub holder: Option<ListeningPort>,
+}
+
+/// Payload of the `dev-server-exit` event
+#[derive(Clone, Serialize)]
+pub struct DevServerExitEvent {
+    pub pid: u32,
+    pub exit_code: Option<u32>,
+}
+
+pub struct DevServerState {
+    servers: Arc<Mutex<HashMap<u32, DevServerInfo>>>,
+}
+
+impl DevServerState {
+    pub fn new() -> Self {
+        Self {
+            servers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(https?)://(localhost|127\.0\.0\.1|0\.0\.0\.0|\[::1?\]|[A-Za-z0-9.-]+):(\d{2,5})",
+        )
+        .unwrap()
+    })
+}
+
+fn port_phrase_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:listening|running|started|serving|available)\b.*?\bport\s*:?\s*(\d{2,5})\b",
+        )
+        .unwrap()
+    })
+}
+
+fn port_in_use_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)EADDRINUSE|address already in use|\bport\s+\d{2,5}\s+is\s+(?:already\s+)?in use",
+        )
+        .unwrap()
+    })
+}
+
+fn port_number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)(?::|\bport\s+)(\d{2,5})\b").unwrap())
+}
+
+/// Find the port a dev server failed to bind in a line of its output
+fn detect_port_in_use(line: &str) -> Option<u16> {
+    if !port_in_use_regex().is_match(line) {
+        return None;
+    }
+    // The address comes last, e.g. "listen EADDRINUSE: address already in use :::3000"
+    let caps = port_number_regex().captures_iter(line).last()?;
+    caps[1].parse().ok()
+}
+
+/// Find the address a dev server announces in a line of its output
+fn detect_server_url(line: &str) -> Option<(String, u16)> {
+    if let Some(caps) = url_regex().captures(line) {
+        let port: u16 = caps[3].parse().ok()?;
+        // Wildcard binds are reachable through localhost
+        let host = match &caps[2] {
+            "0.0.0.0" | "[::]" | "[::1]" => "localhost",
+            host => host,
+        };
+        return Some((format!("{}://{}:{}", &caps[1], host, port), port));
+    }
+
+    let caps = port_phrase_regex().captures(line)?;
+    let port: u16 = caps[1].parse().ok()?;
+    Some((format!("http://localhost:{}", port), port))
+}
+
+/// Look up what holds `port` off the PTY reader thread, then tell the frontend
+fn report_port_in_use(app: AppHandle, pid: u32, port: u16) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let holder = listening_ports()
+            .map_err(|e| tracing::warn!("Could not list listening ports: {}", e))
+            .ok()
+            .and_then(|ports| ports.into_iter().find(|listening| listening.port == port));
+        let _ = app.emit(
+            "dev-server-port-in-use",
+            DevServerPortInUseEvent { pid, port, holder },
+        );
+    });
+}
+
+#[tauri::command]
+pub async fn start_dev_server(
+    state: State<'_, DevServerState>,
+    terminal_state: State<'_, TerminalState>,
+    app: AppHandle,
+    cwd: String,
+    command: String,
+) -> Result<DevServerInfo, String> {
+    let (program, args) = shell_invocation(&command);
+    let mut options = PtySpawnOptions::new(program);
+    options.args = args;
+    options.cwd = Some(cwd.clone());
+    options.title = Some(command.clone());
+
+    let output_servers = Arc::clone(&state.servers);
+    let output_app = app.clone();
+    let mut partial_line = String::new();
+    let mut detected = false;
+    let mut port_in_use_reported = false;
+    options.on_output = Some(Box::new(move |pid, data| {
+        if detected {
+            return;
+        }
+        partial_line.push_str(data);
+        while let Some(end) = partial_line.find('\n') {
+            let line: String = partial_line.drain(..=end).collect();
+            let line = strip_ansi(&line);
+            if !port_in_use_reported {
+                if let Some(port) = detect_port_in_use(&line) {
+                    port_in_use_reported = true;
+                    report_port_in_use(output_app.clone(), pid, port);
+                    continue;
+                }
+            }
+            let Some((url, port)) = detect_server_url(&line) else {
+                continue;
+            };
+            detected = true;
+
+            if let Ok(mut servers) = output_servers.lock() {
+                if let Some(server) = servers.get_mut(&pid) {
+                    server.url = Some(url.clone());
+                    server.port = Some(port);
+                }
+            }
+            let _ = output_app.emit("dev-server-ready", DevServerReadyEvent { pid, url, port });
+            partial_line.clear();
+            return;
+        }
+        if partial_line.len() > MAX_PARTIAL_LINE {
+            partial_line.clear();
+        }
+    }));
+
+    let exit_servers = Arc::clone(&state.servers);
+    let exit_app = app.clone();
+    options.on_exit = Some(Box::new(move |pid, status| {
+        if let Ok(mut servers) = exit_servers.lock() {
+            servers.remove(&pid);
+        }
+        let _ = exit_app.emit(
+            "dev-server-exit",
+            DevServerExitEvent {
+                pid,
+                exit_code: status.map(|status| status.exit_code()),
+            },
+        );
+    }));
+
+    // Hold the lock until the entry exists so the hooks can't run ahead of it
+    let mut servers = state.servers.lock().unwrap();
+    let pid = terminal::spawn_pty(&terminal_state, &app, options)?;
+    let info = DevServerInfo {
+        pid,
+        command,
+        cwd,
+        url: None,
+        port: None,
+        started_at: chrono::Utc::now().timestamp_millis(),
+    };
+    servers.insert(pid, info.clone());
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn stop_dev_server(
+    state: State<'_, DevServerState>,
+    terminal_state: State<'_, TerminalState>,
+    pid: u32,
+) -> Result<(), String> {
+    if state.servers.lock().unwrap().remove(&pid).is_none() {
+        return Err("Dev server not found".to_string());
+    }
+    terminal_state.close(pid);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_dev_servers(
+    state: State<'_, DevServerState>,
+) -> Result<Vec<DevServerInfo>, String> {
+    let mut servers = state
+        .servers
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    servers.sort_by_key(|server| server.pid);
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_announced_urls_and_ports() {
+        assert_eq!(
+            detect_server_url("  ➜  Local:   http://localhost:5173/"),
+            Some(("http://localhost:5173".to_string(), 5173))
+        );
+        assert_eq!(
+            detect_server_url("Server running at http://0.0.0.0:8080"),
+            Some(("http://localhost:8080".to_string(), 8080))
+        );
+        assert_eq!(
+            detect_server_url("Listening on port 3000"),
+            Some(("http://localhost:3000".to_string(), 3000))
+        );
+        assert_eq!(detect_server_url("Compiled successfully in 320ms"), None);
+
+        assert_eq!(
+            detect_port_in_use("Error: listen EADDRINUSE: address already in use :::3000"),
+            Some(3000)
+        );
+        assert_eq!(
+            detect_port_in_use("Port 5173 is in use, trying another one..."),
+            Some(5173)
+        );
+        assert_eq!(detect_port_in_use("Listening on port 3000"), None);
+    }
+}
@@ -0,0 +1,290 @@
+// Task runner
+// Detects npm scripts, cargo commands and Makefile targets in a project and runs them
+// in managed PTYs, collecting compiler problems from their output
+
+mod dev_server;
+mod problem_matcher;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::workspace_roots;
+use crate::error::VoidDeskError;
+use crate::terminal::{self, PtySpawnOptions, TerminalState};
+pub use dev_server::{list_dev_servers, start_dev_server, stop_dev_server, DevServerState};
+use problem_matcher::ProblemCollector;
+pub use problem_matcher::{ProblemMatcherKind, TaskProblem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskSource {
+    Npm,
+    Cargo,
+    Make,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDefinition {
+    /// Stable identifier such as "npm:build" or "cargo:test"
+    pub id: String,
+    pub label: String,
+    pub source: TaskSource,
+    pub command: String,
+    pub cwd: String,
+    pub problem_matchers: Vec<ProblemMatcherKind>,
+}
+
+#[derive(Serialize)]
+pub struct TaskRunInfo {
+    /// Terminal id of the PTY running the task
+    pub pid: u32,
+    pub task: TaskDefinition,
+}
+
+/// Payload of the `task-finished` event
+#[derive(Clone, Serialize)]
+pub struct TaskFinishedEvent {
+    pub pid: u32,
+    pub task_id: String,
+    pub exit_code: Option<u32>,
+    pub success: Option<bool>,
+    pub problems: Vec<TaskProblem>,
+}
+
+const CARGO_COMMANDS: &[&str] = &["build", "check", "test", "clippy", "run"];
+
+/// Find runnable tasks in the project root
+pub fn detect_tasks(root: &Path) -> Vec<TaskDefinition> {
+    let cwd = root.to_string_lossy().to_string();
+    let mut tasks = Vec::new();
+
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        let package_manager = detect_package_manager(root);
+        let scripts = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|package| package.get("scripts").and_then(|s| s.as_object()).cloned())
+            .unwrap_or_default();
+
+        for name in scripts.keys() {
+            // Script names come from the project and end up in a shell command line
+            let Some(quoted) = shell_quote(name) else {
+                continue;
+            };
+            tasks.push(TaskDefinition {
+                id: format!("npm:{}", name),
+                label: format!("{}: {}", package_manager, name),
+                source: TaskSource::Npm,
+                command: format!("{} run {}", package_manager, quoted),
+                cwd: cwd.clone(),
+                problem_matchers: vec![ProblemMatcherKind::Tsc, ProblemMatcherKind::Eslint],
+            });
+        }
+    }
+
+    if root.join("Cargo.toml").is_file() {
+        for command in CARGO_COMMANDS {
+            tasks.push(TaskDefinition {
+                id: format!("cargo:{}", command),
+                label: format!("cargo: {}", command),
+                source: TaskSource::Cargo,
+                command: format!("cargo {}", command),
+                cwd: cwd.clone(),
+                problem_matchers: vec![ProblemMatcherKind::Rustc],
+            });
+        }
+    }
+
+    let makefile = ["GNUmakefile", "makefile", "Makefile"]
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file());
+    if let Some(content) = makefile.and_then(|path| fs::read_to_string(path).ok()) {
+        for target in parse_make_targets(&content) {
+            tasks.push(TaskDefinition {
+                id: format!("make:{}", target),
+                label: format!("make: {}", target),
+                source: TaskSource::Make,
+                command: format!("make {}", target),
+                cwd: cwd.clone(),
+                problem_matchers: vec![
+                    ProblemMatcherKind::Rustc,
+                    ProblemMatcherKind::Tsc,
+                    ProblemMatcherKind::Eslint,
+                ],
+            });
+        }
+    }
+
+    tasks
+}
+
+fn detect_package_manager(root: &Path) -> &'static str {
+    if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+/// Explicit targets in definition order, skipping special (.PHONY) and pattern rules
+fn parse_make_targets(content: &str) -> Vec<String> {
+    let target_line = Regex::new(
+        r"^([A-Za-z0-9_][A-Za-z0-9_./-]*(?:\s+[A-Za-z0-9_][A-Za-z0-9_./-]*)*)\s*:([^=]|$)",
+    )
+    .unwrap();
+    let mut targets: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let Some(caps) = target_line.captures(line) else {
+            continue;
+        };
+        for target in caps[1].split_whitespace() {
+            if !targets.iter().any(|existing| existing == target) {
+                targets.push(target.to_string());
+            }
+        }
+    }
+
+    targets
+}
+
+/// `text` as a single word for the shell of shell_invocation. None when cmd.exe cannot
+/// take it literally, since it expands `%` and `!` even inside quotes.
+fn shell_quote(text: &str) -> Option<String> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.:/@+=,".contains(c);
+    if !text.is_empty() && text.chars().all(plain) {
+        return Some(text.to_string());
+    }
+    #[cfg(target_os = "windows")]
+    {
+        if text.contains(['"', '%', '!', '\r', '\n']) {
+            return None;
+        }
+        Some(format!("\"{}\"", text))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Some(format!("'{}'", text.replace('\'', r"'\''")))
+    }
+}
+
+/// Run `command` through the user's shell so PATH, aliases and quoting behave as in a terminal
+fn shell_invocation(command: &str) -> (String, Vec<String>) {
+    #[cfg(target_os = "windows")]
+    {
+        let shell = std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string());
+        (shell, vec!["/C".to_string(), command.to_string()])
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        (
+            terminal::default_shell(),
+            vec!["-c".to_string(), command.to_string()],
+        )
+    }
+}
+
+#[tauri::command]
+pub async fn list_tasks(root: String) -> Result<Vec<TaskDefinition>, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    let root = Path::new(&root);
+    if !root.is_dir() {
+        return Err(VoidDeskError::invalid_input(format!(
+            "Not a directory: {}",
+            root.display()
+        )));
+    }
+    Ok(detect_tasks(root))
+}
+
+#[tauri::command]
+pub async fn run_task(
+    state: State<'_, TerminalState>,
+    app: AppHandle,
+    root: String,
+    task_id: String,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<TaskRunInfo, VoidDeskError> {
+    workspace_roots::ensure_in_workspace(&root)?;
+    let task = detect_tasks(Path::new(&root))
+        .into_iter()
+        .find(|task| task.id == task_id)
+        .ok_or_else(|| VoidDeskError::not_found(format!("Task not found: {}", task_id)))?;
+
+    let collector = Arc::new(Mutex::new(ProblemCollector::new(
+        task.problem_matchers.clone(),
+        &task.cwd,
+    )));
+    let (program, args) = shell_invocation(&task.command);
+
+    let mut options = PtySpawnOptions::new(program);
+    options.args = args;
+    options.cwd = Some(task.cwd.clone());
+    options.title = Some(task.label.clone());
+    if let Some(cols) = cols {
+        options.cols = cols;
+    }
+    if let Some(rows) = rows {
+        options.rows = rows;
+    }
+
+    let output_collector = Arc::clone(&collector);
+    options.on_output = Some(Box::new(move |_, data| {
+        if let Ok(mut collector) = output_collector.lock() {
+            collector.feed(data);
+        }
+    }));
+
+    let exit_app = app.clone();
+    let exit_task_id = task.id.clone();
+    options.on_exit = Some(Box::new(move |pid, status| {
+        let problems = collector
+            .lock()
+            .map(|mut collector| collector.finish())
+            .unwrap_or_default();
+
+        let _ = exit_app.emit(
+            "task-finished",
+            TaskFinishedEvent {
+                pid,
+                task_id: exit_task_id,
+                exit_code: status.map(|status| status.exit_code()),
+                success: status.map(|status| status.success()),
+                problems,
+            },
+        );
+    }));
+
+    let pid = terminal::spawn_pty(&state, &app, options)?;
+    Ok(TaskRunInfo { pid, task })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_explicit_make_targets() {
+        let makefile = ".PHONY: build test\nCC := gcc\nbuild: deps\n\tcc main.c\ntest lint:\n%.o: %.c\nVERSION = 1\nbuild:\n";
+
+        assert_eq!(parse_make_targets(makefile), vec!["build", "test", "lint"]);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn quotes_script_names_for_the_shell() {
+        assert_eq!(shell_quote("build:prod").as_deref(), Some("build:prod"));
+        assert_eq!(shell_quote("x; rm -rf ~").as_deref(), Some("'x; rm -rf ~'"));
+        assert_eq!(shell_quote("it's").as_deref(), Some(r"'it'\''s'"));
+        assert_eq!(shell_quote("").as_deref(), Some("''"));
+    }
+}
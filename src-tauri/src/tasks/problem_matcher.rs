@@ -0,0 +1,278 @@
+// Problem matchers
+// Turn compiler and linter output (rustc, tsc, eslint) into structured problems
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::terminal::strip_ansi;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemMatcherKind {
+    Rustc,
+    Tsc,
+    Eslint,
+}
+
+/// A diagnostic found in task output, ready for the Problems panel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskProblem {
+    pub source: ProblemMatcherKind,
+    /// Absolute when the tool printed a path relative to the task directory
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    /// "error", "warning" or "info"
+    pub severity: String,
+    pub message: String,
+    pub code: Option<String>,
+}
+
+/// rustc prints the message first and the location on a following `-->` line
+struct PendingRustcProblem {
+    severity: String,
+    message: String,
+    code: Option<String>,
+}
+
+/// Collects problems from output chunks as they arrive
+pub struct ProblemCollector {
+    matchers: Vec<ProblemMatcherKind>,
+    cwd: PathBuf,
+    partial_line: String,
+    rustc_pending: Option<PendingRustcProblem>,
+    eslint_file: Option<String>,
+    problems: Vec<TaskProblem>,
+}
+
+fn rustc_message_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(error|warning)(?:\[(\w+)\])?: (.+)$").unwrap())
+}
+
+fn rustc_location_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").unwrap())
+}
+
+fn tsc_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // `file(1,2): error TS123: ...` and the pretty `file:1:2 - error TS123: ...`
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^(.+?)(?:\((\d+),(\d+)\): |:(\d+):(\d+) - )(error|warning|message) (TS\d+): (.+)$",
+        )
+        .unwrap()
+    })
+}
+
+fn eslint_problem_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\s+(\d+):(\d+)\s+(error|warning)\s+(.+?)(?:\s{2,}(\S+))?$").unwrap()
+    })
+}
+
+impl ProblemCollector {
+    pub fn new(matchers: Vec<ProblemMatcherKind>, cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            matchers,
+            cwd: cwd.into(),
+            partial_line: String::new(),
+            rustc_pending: None,
+            eslint_file: None,
+            problems: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, data: &str) {
+        self.partial_line.push_str(data);
+        while let Some(end) = self.partial_line.find('\n') {
+            let line: String = self.partial_line.drain(..=end).collect();
+            self.match_line(&line);
+        }
+    }
+
+    /// Match whatever is left and return every problem seen
+    pub fn finish(&mut self) -> Vec<TaskProblem> {
+        let rest = std::mem::take(&mut self.partial_line);
+        if !rest.is_empty() {
+            self.match_line(&rest);
+        }
+        std::mem::take(&mut self.problems)
+    }
+
+    fn match_line(&mut self, raw: &str) {
+        let line = strip_ansi(raw);
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        for matcher in self.matchers.clone() {
+            let matched = match matcher {
+                ProblemMatcherKind::Rustc => self.match_rustc(line),
+                ProblemMatcherKind::Tsc => self.match_tsc(line),
+                ProblemMatcherKind::Eslint => self.match_eslint(line),
+            };
+            if matched {
+                break;
+            }
+        }
+    }
+
+    fn match_rustc(&mut self, line: &str) -> bool {
+        if let Some(caps) = rustc_message_regex().captures(line) {
+            // Summary lines like "warning: `app` generated 2 warnings" never get a
+            // location and are simply replaced by the next message
+            self.rustc_pending = Some(PendingRustcProblem {
+                severity: caps[1].to_string(),
+                message: caps[3].to_string(),
+                code: caps.get(2).map(|code| code.as_str().to_string()),
+            });
+            return true;
+        }
+
+        if let Some(caps) = rustc_location_regex().captures(line) {
+            if let Some(pending) = self.rustc_pending.take() {
+                let file = self.resolve(&caps[1]);
+                self.problems.push(TaskProblem {
+                    source: ProblemMatcherKind::Rustc,
+                    file,
+                    line: caps[2].parse().unwrap_or(1),
+                    column: caps[3].parse().unwrap_or(1),
+                    severity: pending.severity,
+                    message: pending.message,
+                    code: pending.code,
+                });
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn match_tsc(&mut self, line: &str) -> bool {
+        let Some(caps) = tsc_regex().captures(line) else {
+            return false;
+        };
+
+        let line_number = caps.get(2).or_else(|| caps.get(4));
+        let column = caps.get(3).or_else(|| caps.get(5));
+        let severity = match &caps[6] {
+            "message" => "info".to_string(),
+            other => other.to_string(),
+        };
+        let file = self.resolve(caps[1].trim());
+        self.problems.push(TaskProblem {
+            source: ProblemMatcherKind::Tsc,
+            file,
+            line: line_number
+                .and_then(|m| m.as_str().parse().ok())
+                .unwrap_or(1),
+            column: column.and_then(|m| m.as_str().parse().ok()).unwrap_or(1),
+            severity,
+            message: caps[8].to_string(),
+            code: Some(caps[7].to_string()),
+        });
+        true
+    }
+
+    fn match_eslint(&mut self, line: &str) -> bool {
+        // The stylish formatter prints the file on its own line, then indented problems
+        if let Some(caps) = eslint_problem_regex().captures(line) {
+            if let Some(file) = self.eslint_file.clone() {
+                self.problems.push(TaskProblem {
+                    source: ProblemMatcherKind::Eslint,
+                    file,
+                    line: caps[1].parse().unwrap_or(1),
+                    column: caps[2].parse().unwrap_or(1),
+                    severity: caps[3].to_string(),
+                    message: caps[4].trim().to_string(),
+                    code: caps.get(5).map(|rule| rule.as_str().to_string()),
+                });
+                return true;
+            }
+            return false;
+        }
+
+        let candidate = line.trim();
+        if !line.starts_with(char::is_whitespace) && looks_like_source_path(candidate) {
+            self.eslint_file = Some(self.resolve(candidate));
+        } else if candidate.is_empty() {
+            self.eslint_file = None;
+        }
+        false
+    }
+
+    fn resolve(&self, file: &str) -> String {
+        let path = Path::new(file);
+        if path.is_absolute() {
+            file.to_string()
+        } else {
+            self.cwd.join(path).to_string_lossy().to_string()
+        }
+    }
+}
+
+fn looks_like_source_path(candidate: &str) -> bool {
+    !candidate.contains(' ')
+        && (candidate.contains('/') || candidate.contains('\\'))
+        && Path::new(candidate).extension().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(matchers: Vec<ProblemMatcherKind>, output: &str) -> Vec<TaskProblem> {
+        let mut collector = ProblemCollector::new(matchers, "/repo");
+        // Split mid-line to exercise buffering
+        let mut middle = output.len() / 2;
+        while !output.is_char_boundary(middle) {
+            middle += 1;
+        }
+        let (head, tail) = output.split_at(middle);
+        collector.feed(head);
+        collector.feed(tail);
+        collector.finish()
+    }
+
+    #[test]
+    fn matches_rustc_diagnostics() {
+        let output = "\x1b[1m\x1b[31merror[E0308]\x1b[0m: mismatched types\r\n  --> src/main.rs:4:18\r\n   |\r\nwarning: unused variable: `x`\r\n --> src/lib.rs:10:9\r\nwarning: `app` (bin \"app\") generated 1 warning\r\n";
+        let problems = collect(vec![ProblemMatcherKind::Rustc], output);
+
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].file, "/repo/src/main.rs");
+        assert_eq!((problems[0].line, problems[0].column), (4, 18));
+        assert_eq!(problems[0].code.as_deref(), Some("E0308"));
+        assert_eq!(problems[1].severity, "warning");
+        assert_eq!(problems[1].message, "unused variable: `x`");
+    }
+
+    #[test]
+    fn matches_tsc_and_eslint_output() {
+        let output = concat!(
+            "src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\n",
+            "src/util.ts:12:1 - warning TS6133: 'x' is declared but never read.\n",
+            "\n",
+            "/repo/src/App.tsx\n",
+            "  10:5  error    'foo' is not defined  no-undef\n",
+            "  12:1  warning  Unexpected console statement  no-console\n",
+            "\n",
+            "✖ 2 problems (1 error, 1 warning)\n",
+        );
+        let problems = collect(
+            vec![ProblemMatcherKind::Tsc, ProblemMatcherKind::Eslint],
+            output,
+        );
+
+        assert_eq!(problems.len(), 4);
+        assert_eq!(problems[0].file, "/repo/src/app.ts");
+        assert_eq!(problems[0].code.as_deref(), Some("TS2322"));
+        assert_eq!((problems[1].line, problems[1].column), (12, 1));
+        assert_eq!(problems[2].file, "/repo/src/App.tsx");
+        assert_eq!(problems[2].message, "'foo' is not defined");
+        assert_eq!(problems[3].code.as_deref(), Some("no-console"));
+    }
+}
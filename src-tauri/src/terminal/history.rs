@@ -0,0 +1,238 @@
+// Terminal command history
+// Records commands reported by shell integration, per project, in the app database
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::error::VoidDeskError;
+
+static COMMAND_HISTORY_DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Runs kept per project; older ones are pruned as new commands come in
+const MAX_HISTORY_PER_PROJECT: i64 = 5000;
+
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// A distinct command line with its most recent run
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandHistoryEntry {
+    pub command: String,
+    pub project_root: String,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Unix timestamp in milliseconds
+    pub last_run_at: i64,
+    pub run_count: i64,
+}
+
+pub fn initialize_persistence(db_path: PathBuf) -> Result<(), String> {
+    let registered_path = COMMAND_HISTORY_DB_PATH.get_or_init(|| db_path);
+    initialize_database(registered_path)
+}
+
+/// Store a finished command; silently skipped until persistence is initialized
+pub fn record_command(
+    project_root: &str,
+    command: &str,
+    cwd: Option<&str>,
+    exit_code: Option<i32>,
+) -> Result<(), String> {
+    let Some(db_path) = COMMAND_HISTORY_DB_PATH.get() else {
+        return Ok(());
+    };
+    insert_command(db_path, project_root, command, cwd, exit_code)
+}
+
+/// Most recently run commands, optionally restricted to one project
+pub fn recent_commands(
+    project_root: Option<&str>,
+    query: Option<&str>,
+    limit: usize,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let db_path = COMMAND_HISTORY_DB_PATH
+        .get()
+        .ok_or_else(|| "Command history has not been initialized".to_string())?;
+    query_commands(db_path, project_root, query, limit)
+}
+
+fn open_connection(db_path: &Path) -> Result<Connection, String> {
+    let connection = Connection::open(db_path).map_err(|e| {
+        format!(
+            "failed to open command history database at {}: {}",
+            db_path.display(),
+            e
+        )
+    })?;
+    connection
+        .busy_timeout(Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    connection
+        .execute_batch(
+            r#"
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(connection)
+}
+
+fn initialize_database(db_path: &Path) -> Result<(), String> {
+    let connection = open_connection(db_path)?;
+    connection
+        .execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS terminal_command_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                project_root TEXT NOT NULL,
+                command TEXT NOT NULL,
+                cwd TEXT,
+                exit_code INTEGER,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_terminal_command_history_project
+                ON terminal_command_history(project_root, created_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn insert_command(
+    db_path: &Path,
+    project_root: &str,
+    command: &str,
+    cwd: Option<&str>,
+    exit_code: Option<i32>,
+) -> Result<(), String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Ok(());
+    }
+
+    let connection = open_connection(db_path)?;
+    connection
+        .execute(
+            "INSERT INTO terminal_command_history (project_root, command, cwd, exit_code, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                project_root,
+                command,
+                cwd,
+                exit_code,
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    connection
+        .execute(
+            "DELETE FROM terminal_command_history
+             WHERE project_root = ?1 AND id NOT IN (
+                 SELECT id FROM terminal_command_history
+                 WHERE project_root = ?1
+                 ORDER BY id DESC
+                 LIMIT ?2
+             )",
+            params![project_root, MAX_HISTORY_PER_PROJECT],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn query_commands(
+    db_path: &Path,
+    project_root: Option<&str>,
+    query: Option<&str>,
+    limit: usize,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let pattern = query
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(|query| {
+            let escaped = query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        });
+
+    let connection = open_connection(db_path)?;
+    // SQLite takes the bare columns from the row that produced MAX(id)
+    let mut statement = connection
+        .prepare(
+            "SELECT command, project_root, cwd, exit_code, created_at, MAX(id), COUNT(*)
+             FROM terminal_command_history
+             WHERE (?1 IS NULL OR project_root = ?1)
+               AND (?2 IS NULL OR command LIKE ?2 ESCAPE '\\')
+             GROUP BY project_root, command
+             ORDER BY MAX(id) DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = statement
+        .query_map(params![project_root, pattern, limit as i64], |row| {
+            Ok(CommandHistoryEntry {
+                command: row.get(0)?,
+                project_root: row.get(1)?,
+                cwd: row.get(2)?,
+                exit_code: row.get(3)?,
+                last_run_at: row.get(4)?,
+                run_count: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_command_history(
+    query: Option<String>,
+    project_root: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandHistoryEntry>, VoidDeskError> {
+    Ok(recent_commands(
+        project_root.as_deref(),
+        query.as_deref(),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_repeated_commands_and_filters_by_query() {
+        let db_path = std::env::temp_dir().join(format!(
+            "voidesk-command-history-{}.sqlite",
+            uuid::Uuid::new_v4()
+        ));
+        initialize_database(&db_path).expect("schema should be created");
+
+        insert_command(&db_path, "/repo", "cargo test", Some("/repo"), Some(101)).unwrap();
+        insert_command(&db_path, "/repo", "npm run dev_server", None, Some(0)).unwrap();
+        insert_command(&db_path, "/repo", "cargo test", Some("/repo"), Some(0)).unwrap();
+        insert_command(&db_path, "/other", "cargo build", None, Some(0)).unwrap();
+
+        let all = query_commands(&db_path, Some("/repo"), None, 10).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].command, "cargo test");
+        assert_eq!(all[0].run_count, 2);
+        assert_eq!(all[0].exit_code, Some(0));
+
+        let underscore = query_commands(&db_path, None, Some("v_s"), 10).unwrap();
+        assert_eq!(underscore.len(), 1);
+        assert_eq!(underscore[0].command, "npm run dev_server");
+
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_file(db_path.with_extension("sqlite-wal"));
+        let _ = std::fs::remove_file(db_path.with_extension("sqlite-shm"));
+    }
+}
@@ -0,0 +1,1003 @@
+mod history;
+mod output;
+mod scrollback;
+mod shell_integration;
+mod title;
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, ExitStatus, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::VoidDeskError;
+use crate::remote;
+use crate::settings;
+
+pub use history::{
+    get_command_history, initialize_persistence as initialize_history, recent_commands,
+};
+pub use output::strip_ansi;
+use output::{spawn_output_emitter, PauseGate};
+pub(crate) use output::{PtyOutputEvent, Utf8Decoder};
+use scrollback::{BufferSearchResult, ScrollbackBuffer, DEFAULT_SCROLLBACK_BYTES};
+use shell_integration::{ShellIntegrationParser, ShellMarker};
+use title::PtyTitle;
+
+#[derive(Serialize, Deserialize)]
+pub struct PtyInfo {
+    pub pid: u32,
+}
+
+/// How `create_pty` starts the shell; every field is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CreatePtyOptions {
+    /// Program to run instead of a profile
+    pub shell: Option<String>,
+    /// Terminal profile from the settings; the default profile when None
+    pub profile: Option<String>,
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+    /// Defaults to true
+    pub shell_integration: Option<bool>,
+    pub restart_on_exit: bool,
+}
+
+/// Metadata about a live terminal, used to rebuild the tab list after a reload
+#[derive(Serialize, Deserialize)]
+pub struct PtyListEntry {
+    pub pid: u32,
+    pub shell: String,
+    pub cwd: Option<String>,
+    pub title: String,
+    pub paused: bool,
+    /// Unix timestamp in milliseconds
+    pub created_at: i64,
+}
+
+/// Payload of the `pty-exit` event
+#[derive(Clone, Serialize)]
+pub struct PtyExitEvent {
+    pub pid: u32,
+    /// `None` when the exit status could not be collected
+    pub exit_code: Option<u32>,
+    pub success: Option<bool>,
+}
+
+/// Payload of the `pty-shell-event` event, derived from shell integration markers
+#[derive(Clone, Serialize)]
+pub struct ShellIntegrationEvent {
+    pub pid: u32,
+    /// One of "prompt", "command_started", "command_finished" or "cwd_changed"
+    pub kind: String,
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+    pub cwd: Option<String>,
+}
+
+/// Payload of the `pty-title` event
+#[derive(Clone, Serialize)]
+pub struct PtyTitleEvent {
+    pub pid: u32,
+    pub title: String,
+}
+
+/// What shell integration has told us about the session so far
+#[derive(Default)]
+struct ShellState {
+    cwd: Option<String>,
+    /// Command line reported for the command that is about to run or running
+    pending_command: Option<String>,
+    last_command: Option<String>,
+}
+
+type PtyChild = Arc<Mutex<Box<dyn Child + Send + Sync>>>;
+
+/// Processes that exit sooner than this after starting are not restarted
+const MIN_UPTIME_FOR_RESTART: Duration = Duration::from_secs(1);
+
+struct PtySession {
+    master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    child: PtyChild,
+    scrollback: Arc<Mutex<ScrollbackBuffer>>,
+    shell_state: Arc<Mutex<ShellState>>,
+    restart_on_exit: Arc<AtomicBool>,
+    pause: Arc<PauseGate>,
+    shell: String,
+    initial_cwd: Option<String>,
+    title: Arc<Mutex<PtyTitle>>,
+    created_at: i64,
+}
+
+pub struct TerminalState {
+    ptys: Arc<Mutex<HashMap<u32, PtySession>>>,
+    next_id: Arc<Mutex<u32>>,
+}
+
+impl TerminalState {
+    pub fn new() -> Self {
+        Self {
+            ptys: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Kill a terminal's process tree and forget it; returns false if it was unknown
+    pub(crate) fn close(&self, pid: u32) -> bool {
+        let session = self.ptys.lock().unwrap().remove(&pid);
+        match session {
+            Some(session) => {
+                let mut child = session.child.lock().unwrap();
+                kill_process_tree(&mut child);
+                // A paused reader would never see EOF and finish up
+                session.pause.set_paused(false);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// An id for a new terminal; remote terminals take theirs from the same sequence
+    pub(crate) fn next_pid(&self) -> u32 {
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    }
+
+    /// Number of live terminals
+    pub(crate) fn count(&self) -> usize {
+        self.ptys.lock().map(|ptys| ptys.len()).unwrap_or(0)
+    }
+
+    /// Kill every shell (and what it started); called when the app exits
+    pub fn kill_all(&self) {
+        let sessions = match self.ptys.lock() {
+            Ok(mut ptys) => ptys.drain().map(|(_, session)| session).collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+
+        for session in sessions {
+            if let Ok(mut child) = session.child.lock() {
+                kill_process_tree(&mut child);
+            }
+            session.pause.set_paused(false);
+        }
+    }
+}
+
+/// Terminate the shell together with the processes it started
+fn kill_process_tree(child: &mut Box<dyn Child + Send + Sync>) {
+    if let Ok(Some(_)) = child.try_wait() {
+        return;
+    }
+
+    if let Some(process_id) = child.process_id() {
+        #[cfg(unix)]
+        unsafe {
+            // The shell leads its own session and process group inside the PTY.
+            // SIGHUP makes interactive shells forward the hangup to their jobs as well.
+            libc::killpg(process_id as libc::pid_t, libc::SIGHUP);
+        }
+        #[cfg(windows)]
+        {
+            let _ = std::process::Command::new("taskkill")
+                .args(["/PID", &process_id.to_string(), "/T", "/F"])
+                .output();
+        }
+    }
+
+    let _ = child.kill();
+}
+
+/// Signals the frontend can deliver to a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PtySignal {
+    Interrupt,
+    Terminate,
+    Kill,
+}
+
+impl PtySignal {
+    fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim().to_ascii_uppercase();
+        match name.strip_prefix("SIG").unwrap_or(&name) {
+            "INT" => Ok(Self::Interrupt),
+            "TERM" => Ok(Self::Terminate),
+            "KILL" => Ok(Self::Kill),
+            _ => Err(format!("Unsupported signal: {}", name)),
+        }
+    }
+}
+
+/// Deliver `signal` to whatever is running in the foreground of the terminal
+fn send_signal(session: &PtySession, signal: PtySignal) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        // Job-control shells move the running command into its own process group,
+        // so target the PTY's foreground group rather than the shell itself
+        let process_group = session
+            .master
+            .lock()
+            .map_err(|e| e.to_string())?
+            .process_group_leader()
+            .or_else(|| {
+                let child = session.child.lock().ok()?;
+                child.process_id().map(|id| id as libc::pid_t)
+            })
+            .ok_or_else(|| "Terminal process is not running".to_string())?;
+
+        let signal = match signal {
+            PtySignal::Interrupt => libc::SIGINT,
+            PtySignal::Terminate => libc::SIGTERM,
+            PtySignal::Kill => libc::SIGKILL,
+        };
+        if unsafe { libc::killpg(process_group, signal) } != 0 {
+            return Err(format!(
+                "Failed to signal process group {}: {}",
+                process_group,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        match signal {
+            PtySignal::Interrupt => {
+                // ConPTY turns ^C into a CTRL_C_EVENT for the attached console processes
+                let mut master = session.master.lock().map_err(|e| e.to_string())?;
+                master
+                    .write_all(b"\x03")
+                    .map_err(|e| format!("Write failed: {}", e))?;
+                master.flush().map_err(|e| format!("Flush failed: {}", e))
+            }
+            PtySignal::Terminate | PtySignal::Kill => {
+                let process_id = session
+                    .child
+                    .lock()
+                    .map_err(|e| e.to_string())?
+                    .process_id()
+                    .ok_or_else(|| "Terminal process is not running".to_string())?;
+                let mut args = vec!["/PID".to_string(), process_id.to_string(), "/T".to_string()];
+                if signal == PtySignal::Kill {
+                    args.push("/F".to_string());
+                }
+                let output = std::process::Command::new("taskkill")
+                    .args(&args)
+                    .output()
+                    .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+                if !output.status.success() {
+                    return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Fold a marker into the session's shell state; returns the event to emit, if any
+fn apply_shell_marker(
+    pid: u32,
+    shell_state: &Mutex<ShellState>,
+    marker: ShellMarker,
+) -> Option<ShellIntegrationEvent> {
+    let mut state = shell_state.lock().ok()?;
+    let event = |kind: &str, command: Option<String>, exit_code, cwd| ShellIntegrationEvent {
+        pid,
+        kind: kind.to_string(),
+        command,
+        exit_code,
+        cwd,
+    };
+
+    match marker {
+        ShellMarker::PromptStart => Some(event("prompt", None, None, state.cwd.clone())),
+        ShellMarker::CommandInputStart => None,
+        ShellMarker::CommandLine(command) => {
+            state.pending_command = Some(command);
+            None
+        }
+        ShellMarker::CommandExecuted => Some(event(
+            "command_started",
+            state.pending_command.clone(),
+            None,
+            state.cwd.clone(),
+        )),
+        ShellMarker::CommandFinished(exit_code) => {
+            let command = state.pending_command.take();
+            if let Some(command) = command.as_ref().filter(|c| !c.trim().is_empty()) {
+                state.last_command = Some(command.clone());
+            }
+            Some(event(
+                "command_finished",
+                command,
+                exit_code,
+                state.cwd.clone(),
+            ))
+        }
+        ShellMarker::Title(_) => None,
+        ShellMarker::Cwd(cwd) => {
+            if state.cwd.as_deref() == Some(cwd.as_str()) {
+                return None;
+            }
+            state.cwd = Some(cwd.clone());
+            Some(event("cwd_changed", None, None, Some(cwd)))
+        }
+    }
+}
+
+/// Everything a marker found in the output can update
+struct MarkerTargets<'a> {
+    pid: u32,
+    app: &'a AppHandle,
+    shell_state: &'a Mutex<ShellState>,
+    title: &'a Mutex<PtyTitle>,
+    project_root: Option<&'a str>,
+}
+
+fn handle_marker(targets: &MarkerTargets, marker: ShellMarker) {
+    let title_changed = match marker {
+        ShellMarker::Title(title) => targets
+            .title
+            .lock()
+            .map(|mut current| current.set_program_title(title))
+            .unwrap_or(false),
+        marker => {
+            let Some(event) = apply_shell_marker(targets.pid, targets.shell_state, marker) else {
+                return;
+            };
+            if let (Some(root), "command_finished", Some(command)) =
+                (targets.project_root, event.kind.as_str(), &event.command)
+            {
+                if let Err(e) =
+                    history::record_command(root, command, event.cwd.as_deref(), event.exit_code)
+                {
+                    tracing::warn!("Failed to record command: {}", e);
+                }
+            }
+            let title_changed = targets
+                .title
+                .lock()
+                .map(|mut current| current.apply_shell_event(&event))
+                .unwrap_or(false);
+            let _ = targets.app.emit("pty-shell-event", event);
+            title_changed
+        }
+    };
+
+    if title_changed {
+        emit_title(targets.app, targets.pid, targets.title);
+    }
+}
+
+fn emit_title(app: &AppHandle, pid: u32, title: &Mutex<PtyTitle>) {
+    if let Ok(title) = title.lock() {
+        let _ = app.emit(
+            "pty-title",
+            PtyTitleEvent {
+                pid,
+                title: title.current(),
+            },
+        );
+    }
+}
+
+/// Wait for the child to exit without holding its lock, so close_pty can still kill it
+fn reap_child(child: &PtyChild) -> Option<ExitStatus> {
+    loop {
+        match child.lock() {
+            Ok(mut child) => match child.try_wait() {
+                Ok(None) => {}
+                Ok(Some(status)) => return Some(status),
+                Err(_) => return None,
+            },
+            Err(_) => return None,
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Shell used for new terminals when the frontend doesn't ask for one
+pub(crate) fn default_shell() -> String {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "powershell.exe".to_string())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+}
+
+/// Decoded output callback for PTYs started by other subsystems (tasks, dev servers),
+/// called with the terminal id
+pub(crate) type OutputHook = Box<dyn FnMut(u32, &str) + Send>;
+
+/// Runs with the terminal id once the process has exited and all of its output was emitted
+pub(crate) type ExitHook = Box<dyn FnOnce(u32, Option<&ExitStatus>) + Send>;
+
+/// Everything needed to start a process in a new PTY
+pub(crate) struct PtySpawnOptions {
+    pub cols: u16,
+    pub rows: u16,
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+    pub shell_integration: bool,
+    /// Start the program again in the same terminal when it exits
+    pub restart_on_exit: bool,
+    /// Tab title; defaults to the program name
+    pub title: Option<String>,
+    pub on_output: Option<OutputHook>,
+    pub on_exit: Option<ExitHook>,
+}
+
+impl PtySpawnOptions {
+    pub fn new(program: String) -> Self {
+        Self {
+            cols: 80,
+            rows: 24,
+            program,
+            args: Vec::new(),
+            cwd: None,
+            env: HashMap::new(),
+            shell_integration: false,
+            restart_on_exit: false,
+            title: None,
+            on_output: None,
+            on_exit: None,
+        }
+    }
+}
+
+/// How to (re)start the process behind a terminal
+struct PtyLaunch {
+    program: String,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    env: HashMap<String, String>,
+    shell_integration: bool,
+}
+
+impl PtyLaunch {
+    fn start(
+        &self,
+        size: PtySize,
+    ) -> Result<(Box<dyn MasterPty + Send>, Box<dyn Child + Send + Sync>), String> {
+        let pair = native_pty_system()
+            .openpty(size)
+            .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+        let mut cmd = CommandBuilder::new(&self.program);
+        cmd.args(&self.args);
+        if self.shell_integration {
+            // Integration is best effort: the terminal still works without it
+            if let Err(e) = shell_integration::inject(&mut cmd, &self.program, &self.env) {
+                tracing::warn!("Shell integration unavailable: {}", e);
+            }
+        }
+        if let Some(dir) = &self.cwd {
+            cmd.cwd(dir);
+        }
+
+        cmd.env("TERM_PROGRAM", "VoiDesk");
+        cmd.env("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION"));
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+
+        let child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| format!("Failed to spawn command: {}", e))?;
+        // Only the child needs the slave side; keeping it open would hide EOF from the reader
+        drop(pair.slave);
+
+        Ok((pair.master, child))
+    }
+}
+
+/// Start `options.program` in a new PTY tracked by `state`; returns its terminal id
+pub(crate) fn spawn_pty(
+    state: &TerminalState,
+    app: &AppHandle,
+    options: PtySpawnOptions,
+) -> Result<u32, String> {
+    let PtySpawnOptions {
+        cols,
+        rows,
+        program,
+        args,
+        cwd,
+        env,
+        shell_integration,
+        restart_on_exit,
+        title,
+        mut on_output,
+        on_exit,
+    } = options;
+
+    let size = PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    };
+
+    let title = title.unwrap_or_else(|| {
+        Path::new(&program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| program.clone())
+    });
+
+    // Prefer the requested directory (usually the workspace root), else the app directory
+    let initial_cwd = match cwd.map(PathBuf::from) {
+        Some(dir) if dir.is_dir() => Some(dir),
+        Some(dir) => {
+            return Err(format!(
+                "Working directory does not exist: {}",
+                dir.display()
+            ))
+        }
+        None => std::env::current_dir().ok(),
+    };
+
+    let launch = PtyLaunch {
+        program,
+        args,
+        cwd: initial_cwd,
+        env,
+        shell_integration,
+    };
+    let (master, child) = launch.start(size)?;
+
+    let pid = state.next_pid();
+
+    let master = Arc::new(Mutex::new(master));
+    let child: PtyChild = Arc::new(Mutex::new(child));
+    let scrollback = Arc::new(Mutex::new(ScrollbackBuffer::new(DEFAULT_SCROLLBACK_BYTES)));
+    let shell_state = Arc::new(Mutex::new(ShellState::default()));
+    let title = Arc::new(Mutex::new(PtyTitle::new(title)));
+    let restart_on_exit = Arc::new(AtomicBool::new(restart_on_exit));
+    let pause = Arc::new(PauseGate::default());
+
+    // Commands are recorded against the directory the terminal was opened in
+    let project_root = launch
+        .cwd
+        .as_ref()
+        .map(|dir| dir.to_string_lossy().to_string());
+
+    // Store PTY
+    state.ptys.lock().unwrap().insert(
+        pid,
+        PtySession {
+            master: Arc::clone(&master),
+            child: Arc::clone(&child),
+            scrollback: Arc::clone(&scrollback),
+            shell_state: Arc::clone(&shell_state),
+            restart_on_exit: Arc::clone(&restart_on_exit),
+            pause: Arc::clone(&pause),
+            shell: launch.program.clone(),
+            initial_cwd: project_root.clone(),
+            title: Arc::clone(&title),
+            created_at: chrono::Utc::now().timestamp_millis(),
+        },
+    );
+
+    // Spawn reader thread
+    let app_clone = app.clone();
+    let ptys_clone = Arc::clone(&state.ptys);
+    let master_clone = Arc::clone(&master);
+    let child_clone = Arc::clone(&child);
+    let scrollback_clone = Arc::clone(&scrollback);
+    let shell_state_clone = Arc::clone(&shell_state);
+    let title_clone = Arc::clone(&title);
+    let pause_clone = Arc::clone(&pause);
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        // Bounded so a stalled emitter pushes back on the reader instead of queueing forever
+        let (output_tx, output_rx) = std::sync::mpsc::sync_channel::<String>(256);
+        let emitter = spawn_output_emitter(app_clone.clone(), pid, output_rx);
+        let mut handle_output = |data: String| {
+            if let Some(hook) = on_output.as_mut() {
+                hook(pid, &data);
+            }
+            if let Ok(mut scrollback) = scrollback_clone.lock() {
+                scrollback.push(&data);
+            }
+            output_tx.send(data).is_ok()
+        };
+
+        let marker_targets = MarkerTargets {
+            pid,
+            app: &app_clone,
+            shell_state: &shell_state_clone,
+            title: &title_clone,
+            project_root: project_root.as_deref(),
+        };
+
+        // One iteration per process; restart_on_exit starts another in the same terminal
+        let status = loop {
+            let started_at = Instant::now();
+            let mut parser = ShellIntegrationParser::new();
+            let mut decoder = Utf8Decoder::new();
+            let reader = master_clone.lock().unwrap().try_clone_reader();
+
+            if let Ok(mut reader) = reader {
+                loop {
+                    pause_clone.wait();
+                    match reader.read(&mut buf) {
+                        Ok(0) => break, // EOF
+                        Ok(n) => {
+                            for marker in parser.feed(&buf[..n]) {
+                                handle_marker(&marker_targets, marker);
+                            }
+
+                            let data = decoder.decode(&buf[..n]);
+                            if !data.is_empty() && !handle_output(data) {
+                                break;
+                            }
+                        }
+                        Err(_) => {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let tail = decoder.finish();
+            if !tail.is_empty() {
+                handle_output(tail);
+            }
+
+            // Reap the shell so it doesn't linger as a zombie
+            let status = reap_child(&child_clone);
+
+            // A shell that dies right after starting would only loop, so give up on it
+            if !restart_on_exit.load(Ordering::Relaxed)
+                || started_at.elapsed() < MIN_UPTIME_FOR_RESTART
+            {
+                break status;
+            }
+
+            // Swap under the map lock so close_pty either sees the new process or stops the restart
+            let ptys = ptys_clone.lock().unwrap();
+            if !ptys.contains_key(&pid) {
+                break status;
+            }
+            let size = master_clone
+                .lock()
+                .ok()
+                .and_then(|master| master.get_size().ok())
+                .unwrap_or(size);
+            match launch.start(size) {
+                Ok((master, child)) => {
+                    *master_clone.lock().unwrap() = master;
+                    *child_clone.lock().unwrap() = child;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to restart terminal {}: {}", pid, e);
+                    break status;
+                }
+            }
+            drop(ptys);
+
+            if let Ok(mut shell_state) = shell_state_clone.lock() {
+                shell_state.cwd = None;
+                shell_state.pending_command = None;
+            }
+            let title_reset = title_clone
+                .lock()
+                .map(|mut title| title.set_program_title(String::new()))
+                .unwrap_or(false);
+            if title_reset {
+                emit_title(&app_clone, pid, &title_clone);
+            }
+            let _ = app_clone.emit(
+                "pty-restarted",
+                PtyExitEvent {
+                    pid,
+                    exit_code: status.as_ref().map(|status| status.exit_code()),
+                    success: status.as_ref().map(|status| status.success()),
+                },
+            );
+        };
+
+        // Let the emitter flush what is left so pty-exit arrives after the last output
+        drop(output_tx);
+        let _ = emitter.join();
+
+        // Clean up on exit
+        let _ = app_clone.emit(
+            "pty-exit",
+            PtyExitEvent {
+                pid,
+                exit_code: status.as_ref().map(|status| status.exit_code()),
+                success: status.as_ref().map(|status| status.success()),
+            },
+        );
+
+        if let Some(on_exit) = on_exit {
+            on_exit(pid, status.as_ref());
+        }
+    });
+
+    Ok(pid)
+}
+
+fn pty_not_found() -> VoidDeskError {
+    VoidDeskError::not_found("PTY not found")
+}
+
+#[tauri::command]
+pub async fn create_pty(
+    state: State<'_, TerminalState>,
+    app: AppHandle,
+    cols: u16,
+    rows: u16,
+    options: Option<CreatePtyOptions>,
+) -> Result<PtyInfo, VoidDeskError> {
+    let CreatePtyOptions {
+        shell,
+        profile,
+        cwd,
+        env,
+        shell_integration,
+        restart_on_exit,
+    } = options.unwrap_or_default();
+
+    // An explicit shell wins; otherwise the named or default profile from the settings
+    let terminal_settings = settings::for_path(cwd.as_deref()).terminal;
+    let profile = match &shell {
+        Some(_) => None,
+        None => terminal_settings
+            .profile(profile.as_deref())
+            .map_err(VoidDeskError::invalid_input)?,
+    };
+    let mut options = match (shell, profile) {
+        (Some(shell), _) => PtySpawnOptions::new(shell),
+        (None, Some(profile)) => {
+            let mut options = PtySpawnOptions::new(profile.program.clone());
+            options.args = profile.args.clone();
+            options.env = profile.env.clone();
+            options.title = Some(profile.name.clone());
+            options
+        }
+        (None, None) => PtySpawnOptions::new(default_shell()),
+    };
+    options.cols = cols;
+    options.rows = rows;
+    options.cwd = cwd;
+    options.env.extend(env);
+    options.shell_integration = shell_integration.unwrap_or(true);
+    options.restart_on_exit = restart_on_exit;
+
+    let pid = spawn_pty(&state, &app, options)?;
+    Ok(PtyInfo { pid })
+}
+
+#[tauri::command]
+pub async fn write_to_pty(
+    state: State<'_, TerminalState>,
+    pid: u32,
+    data: String,
+) -> Result<(), VoidDeskError> {
+    if let Some(result) = remote::pty::write(pid, &data) {
+        return Ok(result?);
+    }
+    let ptys = state.ptys.lock().unwrap();
+    if let Some(session) = ptys.get(&pid) {
+        let mut master = session.master.lock().unwrap();
+        master
+            .write_all(data.as_bytes())
+            .map_err(|e| format!("Write failed: {}", e))?;
+        master.flush().map_err(|e| format!("Flush failed: {}", e))?;
+        Ok(())
+    } else {
+        Err(pty_not_found())
+    }
+}
+
+#[tauri::command]
+pub async fn resize_pty(
+    state: State<'_, TerminalState>,
+    pid: u32,
+    cols: u16,
+    rows: u16,
+) -> Result<(), VoidDeskError> {
+    if let Some(result) = remote::pty::resize(pid, cols, rows) {
+        return Ok(result?);
+    }
+    let ptys = state.ptys.lock().unwrap();
+    if let Some(session) = ptys.get(&pid) {
+        let master = session.master.lock().unwrap();
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        master
+            .resize(size)
+            .map_err(|e| format!("Resize failed: {}", e))?;
+        Ok(())
+    } else {
+        Err(pty_not_found())
+    }
+}
+
+/// All live terminals, oldest first
+#[tauri::command]
+pub async fn list_ptys(
+    state: State<'_, TerminalState>,
+) -> Result<Vec<PtyListEntry>, VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let mut entries = ptys
+        .iter()
+        .map(|(pid, session)| {
+            // Prefer the live directory reported by shell integration
+            let cwd = session
+                .shell_state
+                .lock()
+                .ok()
+                .and_then(|shell_state| shell_state.cwd.clone())
+                .or_else(|| session.initial_cwd.clone());
+            PtyListEntry {
+                pid: *pid,
+                shell: session.shell.clone(),
+                cwd,
+                title: session
+                    .title
+                    .lock()
+                    .map(|title| title.current())
+                    .unwrap_or_default(),
+                paused: session.pause.is_paused(),
+                created_at: session.created_at,
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.pid);
+    Ok(entries)
+}
+
+/// Recent output of a terminal, for restoring a re-mounted view
+#[tauri::command]
+pub async fn get_pty_buffer(
+    state: State<'_, TerminalState>,
+    pid: u32,
+) -> Result<String, VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    let scrollback = session.scrollback.lock().map_err(|e| e.to_string())?;
+    Ok(scrollback.contents())
+}
+
+/// Find text in a terminal's scrollback without sending the whole buffer to the frontend
+#[tauri::command]
+pub async fn search_pty_buffer(
+    state: State<'_, TerminalState>,
+    pid: u32,
+    query: String,
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+) -> Result<BufferSearchResult, VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    let scrollback = session.scrollback.lock().map_err(|e| e.to_string())?;
+    scrollback
+        .search(
+            &query,
+            regex.unwrap_or(false),
+            case_sensitive.unwrap_or(false),
+        )
+        .map_err(VoidDeskError::invalid_input)
+}
+
+/// Run the last command reported by shell integration again
+#[tauri::command]
+pub async fn rerun_last_command(
+    state: State<'_, TerminalState>,
+    pid: u32,
+) -> Result<(), VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    let command = session
+        .shell_state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .last_command
+        .clone()
+        .ok_or_else(|| VoidDeskError::not_found("No command has run in this terminal yet"))?;
+
+    let mut master = session.master.lock().unwrap();
+    master
+        .write_all(format!("{}\r", command).as_bytes())
+        .map_err(|e| format!("Write failed: {}", e))?;
+    master.flush()?;
+    Ok(())
+}
+
+/// Stop reading a terminal's output while its view is hidden or busy
+#[tauri::command]
+pub async fn pause_pty(state: State<'_, TerminalState>, pid: u32) -> Result<(), VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    session.pause.set_paused(true);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_pty(state: State<'_, TerminalState>, pid: u32) -> Result<(), VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    session.pause.set_paused(false);
+    Ok(())
+}
+
+/// Pin a tab title for a terminal; an empty title goes back to automatic titles
+#[tauri::command]
+pub async fn set_pty_title(
+    state: State<'_, TerminalState>,
+    app: AppHandle,
+    pid: u32,
+    title: String,
+) -> Result<(), VoidDeskError> {
+    let title_state = {
+        let ptys = state.ptys.lock().unwrap();
+        let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+        Arc::clone(&session.title)
+    };
+
+    let title = title.trim();
+    let changed = title_state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_user_title((!title.is_empty()).then(|| title.to_string()));
+    if changed {
+        emit_title(&app, pid, &title_state);
+    }
+    Ok(())
+}
+
+/// Turn respawning the shell when it exits on or off for a terminal
+#[tauri::command]
+pub async fn set_pty_restart_on_exit(
+    state: State<'_, TerminalState>,
+    pid: u32,
+    enabled: bool,
+) -> Result<(), VoidDeskError> {
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    session.restart_on_exit.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Send SIGINT, SIGTERM or SIGKILL to the terminal's foreground process
+#[tauri::command]
+pub async fn signal_pty(
+    state: State<'_, TerminalState>,
+    pid: u32,
+    signal: String,
+) -> Result<(), VoidDeskError> {
+    let signal = PtySignal::parse(&signal).map_err(VoidDeskError::invalid_input)?;
+    let ptys = state.ptys.lock().unwrap();
+    let session = ptys.get(&pid).ok_or_else(pty_not_found)?;
+    Ok(send_signal(session, signal)?)
+}
+
+#[tauri::command]
+pub async fn close_pty(state: State<'_, TerminalState>, pid: u32) -> Result<(), VoidDeskError> {
+    if !remote::pty::close(pid) {
+        state.close(pid);
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+// Terminal output batching
+// Coalesces PTY reads into a few events per frame so bursts like `cat bigfile`
+// don't flood the IPC channel
+
+use regex::Regex;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How long output is collected before it is sent to the frontend
+pub const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(12);
+
+/// Most output held for a single flush; anything older is dropped
+pub const MAX_PENDING_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Payload of the `pty-output` event
+#[derive(Clone, Serialize)]
+pub struct PtyOutputEvent {
+    pub pid: u32,
+    pub data: String,
+    /// Bytes discarded since the previous event because the UI fell behind
+    pub dropped_bytes: usize,
+}
+
+/// Output waiting to be flushed, bounded by size with a drop-oldest policy
+pub struct OutputBatch {
+    pending: String,
+    dropped_bytes: usize,
+    capacity: usize,
+}
+
+impl OutputBatch {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            pending: String::new(),
+            dropped_bytes: 0,
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, data: &str) {
+        self.pending.push_str(data);
+
+        if self.pending.len() > self.capacity {
+            let mut excess = self.pending.len() - self.capacity;
+            while !self.pending.is_char_boundary(excess) {
+                excess += 1;
+            }
+            self.pending.drain(..excess);
+            self.dropped_bytes += excess;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.dropped_bytes == 0
+    }
+
+    /// Take the pending output and the number of bytes dropped before it
+    pub fn take(&mut self) -> (String, usize) {
+        let dropped_bytes = std::mem::take(&mut self.dropped_bytes);
+        (std::mem::take(&mut self.pending), dropped_bytes)
+    }
+}
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[()][0-9A-Za-z]|\x1b[=>78]")
+            .unwrap()
+    })
+}
+
+/// Remove color and other escape sequences programs print when attached to a PTY
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    ansi_regex().replace_all(text, "")
+}
+
+/// Turns raw PTY reads into text without splitting multi-byte characters. Bytes
+/// of a sequence cut off at the end of a read are held until the next one.
+#[derive(Default)]
+pub struct Utf8Decoder {
+    incomplete: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.incomplete);
+        input.extend_from_slice(bytes);
+
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    output.push_str(valid);
+                    break;
+                }
+                Err(error) => {
+                    let (valid, after) = rest.split_at(error.valid_up_to());
+                    // `valid_up_to` marks a prefix that is known to be valid
+                    output.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match error.error_len() {
+                        Some(invalid_len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[invalid_len..];
+                        }
+                        None => {
+                            // Truncated sequence: wait for the rest of it
+                            self.incomplete = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Flush whatever is left once the PTY is closed
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.incomplete);
+        String::from_utf8_lossy(&rest).to_string()
+    }
+}
+
+/// Lets the frontend pause a terminal's reader. While paused nothing is read from the
+/// master, so the kernel buffer fills up and the child blocks on write.
+#[derive(Default)]
+pub struct PauseGate {
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl PauseGate {
+    pub fn set_paused(&self, paused: bool) {
+        if let Ok(mut current) = self.paused.lock() {
+            *current = paused;
+        }
+        if !paused {
+            self.resumed.notify_all();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.lock().map(|paused| *paused).unwrap_or(false)
+    }
+
+    /// Block the calling thread until the gate is open
+    pub fn wait(&self) {
+        if let Ok(paused) = self.paused.lock() {
+            drop(self.resumed.wait_while(paused, |paused| *paused));
+        }
+    }
+}
+
+/// Forward output received on `rx` as `pty-output` events, at most one per flush
+/// interval. The thread ends once the sender is dropped and the last batch is sent.
+pub fn spawn_output_emitter(app: AppHandle, pid: u32, rx: Receiver<String>) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut batch = OutputBatch::new(MAX_PENDING_OUTPUT_BYTES);
+
+        // Block until output arrives, then keep collecting until the interval is up
+        while let Ok(data) = rx.recv() {
+            batch.push(&data);
+            let deadline = Instant::now() + OUTPUT_FLUSH_INTERVAL;
+            let mut disconnected = false;
+
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(remaining) {
+                    Ok(data) => batch.push(&data),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        disconnected = true;
+                        break;
+                    }
+                }
+            }
+
+            if !batch.is_empty() {
+                let (data, dropped_bytes) = batch.take();
+                let _ = app.emit(
+                    "pty-output",
+                    PtyOutputEvent {
+                        pid,
+                        data,
+                        dropped_bytes,
+                    },
+                );
+            }
+
+            if disconnected {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_output_past_capacity() {
+        let mut batch = OutputBatch::new(6);
+        batch.push("abcd");
+        batch.push("efé");
+
+        assert_eq!(batch.take(), ("cdefé".to_string(), 2));
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn decodes_characters_split_across_reads() {
+        let mut decoder = Utf8Decoder::new();
+        let bytes = "a€b".as_bytes();
+
+        assert_eq!(decoder.decode(&bytes[..2]), "a");
+        assert_eq!(decoder.decode(&bytes[2..3]), "");
+        assert_eq!(decoder.decode(&bytes[3..]), "€b");
+        assert_eq!(decoder.decode(b"\xffok\xe2"), "\u{fffd}ok");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+}
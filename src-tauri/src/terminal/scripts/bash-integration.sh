@@ -0,0 +1,60 @@
+# VoiDesk shell integration for bash.
+# Loaded through --init-file, so the user's regular startup files are sourced first.
+
+if [ -z "$VOIDESK_SHELL_INTEGRATION" ]; then
+    VOIDESK_SHELL_INTEGRATION=1
+
+    if [ -r ~/.bashrc ]; then
+        . ~/.bashrc
+    fi
+
+    __voidesk_escape() {
+        local value="$1"
+        value="${value//\\/\\\\}"
+        value="${value//;/\\x3b}"
+        value="${value//$'\n'/\\x0a}"
+        printf '%s' "$value"
+    }
+
+    __voidesk_at_prompt=0
+    __voidesk_ran_command=0
+
+    __voidesk_preexec() {
+        # Ignore the DEBUG trap firing for PROMPT_COMMAND and completions
+        [ "$__voidesk_at_prompt" = 1 ] || return
+        [ -z "$COMP_LINE" ] || return
+        [ "$BASH_COMMAND" = "__voidesk_save_status" ] && return
+
+        __voidesk_at_prompt=0
+        __voidesk_ran_command=1
+        local command_line
+        command_line="$(HISTTIMEFORMAT= builtin history 1 | sed 's/^ *[0-9]* *//')"
+        printf '\e]633;E;%s\a' "$(__voidesk_escape "$command_line")"
+        printf '\e]633;C\a'
+    }
+
+    __voidesk_save_status() {
+        __voidesk_status=$?
+        # Keep the rest of PROMPT_COMMAND from looking like a user command
+        __voidesk_at_prompt=0
+    }
+
+    __voidesk_prompt() {
+        if [ "$__voidesk_ran_command" = 1 ]; then
+            printf '\e]633;D;%s\a' "$__voidesk_status"
+        fi
+        __voidesk_ran_command=0
+        printf '\e]633;P;Cwd=%s\a' "$(__voidesk_escape "$PWD")"
+        __voidesk_at_prompt=1
+    }
+
+    # Existing PROMPT_COMMANDs often end in ';', which would break the chained command
+    __voidesk_user_prompt_command="$PROMPT_COMMAND"
+    while [[ "$__voidesk_user_prompt_command" =~ [[:space:]\;]$ ]]; do
+        __voidesk_user_prompt_command="${__voidesk_user_prompt_command%?}"
+    done
+    PROMPT_COMMAND="__voidesk_save_status${__voidesk_user_prompt_command:+; $__voidesk_user_prompt_command}; __voidesk_prompt"
+    unset __voidesk_user_prompt_command
+    PS1="\[\e]633;A\a\]$PS1\[\e]633;B\a\]"
+    trap '__voidesk_preexec' DEBUG
+fi
@@ -0,0 +1,36 @@
+# VoiDesk shell integration for PowerShell.
+# PowerShell has no pre-exec hook, so the command line and its result are reported together.
+
+if (-not $env:VOIDESK_SHELL_INTEGRATION) {
+    $env:VOIDESK_SHELL_INTEGRATION = "1"
+
+    $global:__VoideskEsc = [char]27
+    $global:__VoideskBel = [char]7
+    $global:__VoideskLastHistoryId = -1
+    $global:__VoideskOriginalPrompt = $function:Prompt
+
+    function global:__VoideskEscape([string]$value) {
+        return $value.Replace("\", "\\").Replace(";", "\x3b").Replace("`n", "\x0a").Replace("`r", "\x0d")
+    }
+
+    function global:Prompt {
+        $succeeded = $?
+        $exitCode = if ($succeeded) { 0 } elseif ($LASTEXITCODE) { $LASTEXITCODE } else { 1 }
+        $esc = $global:__VoideskEsc
+        $bel = $global:__VoideskBel
+        $output = ""
+
+        $lastCommand = Get-History -Count 1
+        if ($lastCommand -and $lastCommand.Id -ne $global:__VoideskLastHistoryId) {
+            $global:__VoideskLastHistoryId = $lastCommand.Id
+            $commandLine = __VoideskEscape $lastCommand.CommandLine
+            $output += "$esc]633;E;$commandLine$bel$esc]633;D;$exitCode$bel"
+        }
+
+        $cwd = __VoideskEscape $PWD.Path
+        $output += "$esc]633;P;Cwd=$cwd$bel$esc]633;A$bel"
+        $output += & $global:__VoideskOriginalPrompt
+        $output += "$esc]633;B$bel"
+        return $output
+    }
+}
@@ -0,0 +1,187 @@
+// Terminal scrollback
+// Keeps the most recent PTY output so a re-mounted terminal view can restore it
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use super::output::strip_ansi;
+
+/// Default amount of output retained per terminal
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 512 * 1024;
+
+/// Most matches returned by a single search
+const MAX_SEARCH_MATCHES: usize = 1000;
+
+/// A match in the scrollback with escape sequences removed; columns count characters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BufferMatch {
+    pub line: usize,
+    pub start_column: usize,
+    pub end_column: usize,
+    pub line_text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BufferSearchResult {
+    pub matches: Vec<BufferMatch>,
+    /// Lines in the searched text, for mapping `line` onto the terminal's own buffer
+    pub line_count: usize,
+    /// True when the search stopped at the match limit
+    pub truncated: bool,
+}
+
+/// Ring buffer of output chunks bounded by total byte size
+pub struct ScrollbackBuffer {
+    chunks: VecDeque<String>,
+    len: usize,
+    capacity: usize,
+}
+
+impl ScrollbackBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, data: &str) {
+        if data.is_empty() || self.capacity == 0 {
+            return;
+        }
+
+        // A single chunk larger than the whole buffer only keeps its tail
+        let data = if data.len() > self.capacity {
+            let mut start = data.len() - self.capacity;
+            while !data.is_char_boundary(start) {
+                start += 1;
+            }
+            &data[start..]
+        } else {
+            data
+        };
+
+        self.chunks.push_back(data.to_string());
+        self.len += data.len();
+
+        while self.len > self.capacity {
+            match self.chunks.pop_front() {
+                Some(chunk) => self.len -= chunk.len(),
+                None => break,
+            }
+        }
+    }
+
+    pub fn contents(&self) -> String {
+        let mut output = String::with_capacity(self.len);
+        for chunk in &self.chunks {
+            output.push_str(chunk);
+        }
+        output
+    }
+}
+
+impl ScrollbackBuffer {
+    pub fn search(
+        &self,
+        query: &str,
+        is_regex: bool,
+        case_sensitive: bool,
+    ) -> Result<BufferSearchResult, String> {
+        if query.is_empty() {
+            return Err("Search query is empty".to_string());
+        }
+        let pattern = if is_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let matcher: Regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+        let contents = self.contents();
+        let text = strip_ansi(&contents);
+        let mut matches = Vec::new();
+        let mut line_count = 0;
+        let mut truncated = false;
+
+        for (line_index, line) in text.split('\n').enumerate() {
+            line_count = line_index + 1;
+            if truncated {
+                continue;
+            }
+            let line = line.trim_end_matches('\r');
+            for found in matcher.find_iter(line) {
+                if found.start() == found.end() {
+                    continue;
+                }
+                if matches.len() == MAX_SEARCH_MATCHES {
+                    truncated = true;
+                    break;
+                }
+                let start_column = line[..found.start()].chars().count();
+                matches.push(BufferMatch {
+                    line: line_index,
+                    start_column,
+                    end_column: start_column + found.as_str().chars().count(),
+                    line_text: line.to_string(),
+                });
+            }
+        }
+
+        Ok(BufferSearchResult {
+            matches,
+            line_count,
+            truncated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_chunks_past_capacity() {
+        let mut buffer = ScrollbackBuffer::new(8);
+        buffer.push("abcd");
+        buffer.push("efgh");
+        buffer.push("ij");
+
+        assert_eq!(buffer.contents(), "efghij");
+    }
+
+    #[test]
+    fn keeps_tail_of_oversized_chunk_on_char_boundary() {
+        let mut buffer = ScrollbackBuffer::new(4);
+        buffer.push("abcé€");
+
+        assert_eq!(buffer.contents(), "€");
+    }
+
+    #[test]
+    fn searches_text_without_escape_sequences() {
+        let mut buffer = ScrollbackBuffer::new(1024);
+        buffer.push("$ cargo test\r\n\x1b[32mtest ok\x1b[0m\r\né Error: TEST");
+        buffer.push(" failed\r\n");
+
+        let result = buffer.search("test", false, false).unwrap();
+        assert_eq!(result.line_count, 4);
+        assert_eq!(
+            result
+                .matches
+                .iter()
+                .map(|found| (found.line, found.start_column, found.end_column))
+                .collect::<Vec<_>>(),
+            vec![(0, 8, 12), (1, 0, 4), (2, 9, 13)]
+        );
+
+        let result = buffer.search(r"TEST \w+", true, true).unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].line_text, "é Error: TEST failed");
+    }
+}
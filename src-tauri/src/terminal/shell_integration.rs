@@ -0,0 +1,324 @@
+// Terminal shell integration
+// Injects OSC 633 prompt/command markers into bash, zsh and PowerShell and parses them
+// (plus OSC 133 and OSC 7 from shells that emit them natively, and OSC 0/2 window
+// titles) out of PTY output
+
+use portable_pty::CommandBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BASH_SCRIPT: &str = include_str!("scripts/bash-integration.sh");
+const ZSHENV_SCRIPT: &str = include_str!("scripts/zshenv.zsh");
+const ZSHRC_SCRIPT: &str = include_str!("scripts/zshrc.zsh");
+const PWSH_SCRIPT: &str = include_str!("scripts/pwsh-integration.ps1");
+
+/// Longest OSC payload we keep buffering before giving up on the sequence
+const MAX_OSC_LEN: usize = 8192;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Marker reported by the shell between prompts and commands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellMarker {
+    PromptStart,
+    CommandInputStart,
+    CommandExecuted,
+    CommandFinished(Option<i32>),
+    CommandLine(String),
+    Cwd(String),
+    Title(String),
+}
+
+/// Adds the integration script for known shells to `cmd`. Returns false when the
+/// shell is not supported (cmd.exe, fish, sh, ...) and the command was left untouched.
+pub fn inject(
+    cmd: &mut CommandBuilder,
+    shell: &str,
+    user_env: &std::collections::HashMap<String, String>,
+) -> Result<bool, String> {
+    let shell_name = Path::new(shell)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let dir = scripts_dir()?;
+    match shell_name.as_str() {
+        "bash" => {
+            let script = write_script(&dir, "bash-integration.sh", BASH_SCRIPT)?;
+            cmd.arg("--init-file");
+            cmd.arg(script);
+        }
+        "zsh" => {
+            let zdotdir = dir.join("zsh");
+            fs::create_dir_all(&zdotdir).map_err(|e| e.to_string())?;
+            write_script(&zdotdir, ".zshenv", ZSHENV_SCRIPT)?;
+            write_script(&zdotdir, ".zshrc", ZSHRC_SCRIPT)?;
+
+            let user_zdotdir = user_env
+                .get("ZDOTDIR")
+                .cloned()
+                .or_else(|| std::env::var("ZDOTDIR").ok())
+                .or_else(|| std::env::var("HOME").ok())
+                .unwrap_or_default();
+            cmd.env("VOIDESK_USER_ZDOTDIR", user_zdotdir);
+            cmd.env("ZDOTDIR", zdotdir);
+        }
+        "pwsh" | "powershell" => {
+            let script = write_script(&dir, "pwsh-integration.ps1", PWSH_SCRIPT)?;
+            cmd.arg("-NoLogo");
+            cmd.arg("-NoExit");
+            cmd.arg("-Command");
+            cmd.arg(format!(
+                ". '{}'",
+                script.display().to_string().replace('\'', "''")
+            ));
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn scripts_dir() -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join("voidesk-shell-integration");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn write_script(dir: &Path, name: &str, contents: &str) -> Result<PathBuf, String> {
+    let path = dir.join(name);
+    let up_to_date = fs::read_to_string(&path)
+        .map(|existing| existing == contents)
+        .unwrap_or(false);
+    if !up_to_date {
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseState {
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Incremental OSC scanner; sequences may be split across any number of reads
+pub struct ShellIntegrationParser {
+    state: ParseState,
+    osc: Vec<u8>,
+}
+
+impl ShellIntegrationParser {
+    pub fn new() -> Self {
+        Self {
+            state: ParseState::Ground,
+            osc: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<ShellMarker> {
+        let mut markers = Vec::new();
+
+        for &byte in bytes {
+            self.state = match self.state {
+                ParseState::Ground if byte == ESC => ParseState::Escape,
+                ParseState::Ground => ParseState::Ground,
+                ParseState::Escape if byte == b']' => {
+                    self.osc.clear();
+                    ParseState::Osc
+                }
+                ParseState::Escape if byte == ESC => ParseState::Escape,
+                ParseState::Escape => ParseState::Ground,
+                ParseState::Osc if byte == BEL => {
+                    markers.extend(parse_osc(&self.osc));
+                    ParseState::Ground
+                }
+                ParseState::Osc if byte == ESC => ParseState::OscEscape,
+                ParseState::Osc if self.osc.len() >= MAX_OSC_LEN => ParseState::Ground,
+                ParseState::Osc => {
+                    self.osc.push(byte);
+                    ParseState::Osc
+                }
+                ParseState::OscEscape if byte == b'\\' => {
+                    markers.extend(parse_osc(&self.osc));
+                    ParseState::Ground
+                }
+                ParseState::OscEscape if byte == b']' => {
+                    // Unterminated sequence directly followed by a new one
+                    self.osc.clear();
+                    ParseState::Osc
+                }
+                ParseState::OscEscape => ParseState::Ground,
+            };
+        }
+
+        markers
+    }
+}
+
+impl Default for ShellIntegrationParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_osc(payload: &[u8]) -> Option<ShellMarker> {
+    let payload = String::from_utf8_lossy(payload);
+    let (code, rest) = payload.split_once(';')?;
+
+    match code {
+        "633" | "133" => {
+            let mut parts = rest.splitn(2, ';');
+            let kind = parts.next()?;
+            let argument = parts.next();
+            match kind {
+                "A" => Some(ShellMarker::PromptStart),
+                "B" => Some(ShellMarker::CommandInputStart),
+                "C" => Some(ShellMarker::CommandExecuted),
+                "D" => {
+                    Some(ShellMarker::CommandFinished(argument.and_then(|value| {
+                        value.split(';').next()?.trim().parse().ok()
+                    })))
+                }
+                "E" if code == "633" => {
+                    // An optional nonce follows the command line
+                    let command = argument.unwrap_or("").split(';').next().unwrap_or("");
+                    Some(ShellMarker::CommandLine(unescape_633(command)))
+                }
+                "P" if code == "633" => {
+                    let value = argument?.strip_prefix("Cwd=")?;
+                    Some(ShellMarker::Cwd(unescape_633(value)))
+                }
+                _ => None,
+            }
+        }
+        "0" | "2" => Some(ShellMarker::Title(rest.to_string())),
+        "7" => {
+            // file://host/path
+            let without_scheme = rest.strip_prefix("file://")?;
+            let path_start = without_scheme.find('/')?;
+            let path = percent_decode(&without_scheme[path_start..]);
+            // file://host/C:/Users -> C:/Users
+            let path = match path.as_bytes() {
+                [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+                _ => path,
+            };
+            Some(ShellMarker::Cwd(path))
+        }
+        _ => None,
+    }
+}
+
+/// Undo the `\\` and `\xHH` escaping OSC 633 uses for values
+fn unescape_633(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'\\' {
+            if bytes.get(index + 1) == Some(&b'\\') {
+                output.push(b'\\');
+                index += 2;
+                continue;
+            }
+            if bytes.get(index + 1) == Some(&b'x') {
+                if let Some(byte) = bytes
+                    .get(index + 2..index + 4)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    output.push(byte);
+                    index += 4;
+                    continue;
+                }
+            }
+        }
+        output.push(bytes[index]);
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&output).to_string()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(byte) = bytes
+                .get(index + 1..index + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                output.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        output.push(bytes[index]);
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&output).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_markers_split_across_reads() {
+        let mut parser = ShellIntegrationParser::new();
+        let mut markers = parser.feed(b"output\x1b]633;E;ls -la\x07\x1b]633;C\x07\x1b]63");
+        markers.extend(parser.feed(b"3;D;2\x07\x1b]633;P;Cwd=/tmp/a\\x3bb\x1b\\"));
+
+        assert_eq!(
+            markers,
+            vec![
+                ShellMarker::CommandLine("ls -la".to_string()),
+                ShellMarker::CommandExecuted,
+                ShellMarker::CommandFinished(Some(2)),
+                ShellMarker::Cwd("/tmp/a;b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_command_line_and_osc7_cwd() {
+        let mut parser = ShellIntegrationParser::new();
+        let markers = parser.feed(
+            b"\x1b]633;E;echo \\\\ done\x07\x1b]7;file://host/home/me/My%20Project\x07\x1b]133;A\x07",
+        );
+
+        assert_eq!(
+            markers,
+            vec![
+                ShellMarker::CommandLine("echo \\ done".to_string()),
+                ShellMarker::Cwd("/home/me/My Project".to_string()),
+                ShellMarker::PromptStart,
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_window_titles() {
+        let mut parser = ShellIntegrationParser::new();
+        let markers = parser.feed(b"\x1b]0;me@host: ~/app\x07\x1b]2;vim main.rs\x1b\\");
+
+        assert_eq!(
+            markers,
+            vec![
+                ShellMarker::Title("me@host: ~/app".to_string()),
+                ShellMarker::Title("vim main.rs".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+// Terminal titles
+// Picks the tab title from a user override, OSC 0/2 sequences and shell integration
+
+use std::path::Path;
+
+use super::ShellIntegrationEvent;
+
+/// Title state of one terminal; the user's choice always wins
+pub struct PtyTitle {
+    default: String,
+    user: Option<String>,
+    /// Set by programs through OSC 0/2 or derived from the running command
+    dynamic: Option<String>,
+}
+
+impl PtyTitle {
+    pub fn new(default: String) -> Self {
+        Self {
+            default,
+            user: None,
+            dynamic: None,
+        }
+    }
+
+    pub fn current(&self) -> String {
+        self.user
+            .clone()
+            .or_else(|| self.dynamic.clone())
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Pin a title, or go back to automatic titles with `None`; returns true if it changed
+    pub fn set_user_title(&mut self, title: Option<String>) -> bool {
+        self.update(|titles| titles.user = title)
+    }
+
+    pub fn set_program_title(&mut self, title: String) -> bool {
+        let title = title.trim().to_string();
+        self.update(|titles| titles.dynamic = (!title.is_empty()).then_some(title))
+    }
+
+    /// Show the running command, e.g. "npm run dev — my-app", until it finishes
+    pub fn apply_shell_event(&mut self, event: &ShellIntegrationEvent) -> bool {
+        match event.kind.as_str() {
+            "command_started" => {
+                let Some(command) = event.command.as_deref().map(str::trim) else {
+                    return false;
+                };
+                if command.is_empty() {
+                    return false;
+                }
+                let title = match event.cwd.as_deref().and_then(directory_name) {
+                    Some(directory) => format!("{} — {}", command, directory),
+                    None => command.to_string(),
+                };
+                self.update(|titles| titles.dynamic = Some(title))
+            }
+            "command_finished" => self.update(|titles| titles.dynamic = None),
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> bool {
+        let before = self.current();
+        change(self);
+        self.current() != before
+    }
+}
+
+fn directory_name(path: &str) -> Option<String> {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_event(kind: &str, command: Option<&str>) -> ShellIntegrationEvent {
+        ShellIntegrationEvent {
+            pid: 0,
+            kind: kind.to_string(),
+            command: command.map(str::to_string),
+            exit_code: None,
+            cwd: Some("/home/me/my-app".to_string()),
+        }
+    }
+
+    #[test]
+    fn user_title_overrides_running_command() {
+        let mut title = PtyTitle::new("zsh".to_string());
+
+        assert!(title.apply_shell_event(&shell_event("command_started", Some("npm run dev"))));
+        assert_eq!(title.current(), "npm run dev — my-app");
+
+        assert!(title.set_user_title(Some("server".to_string())));
+        assert!(!title.apply_shell_event(&shell_event("command_finished", None)));
+        assert_eq!(title.current(), "server");
+
+        assert!(title.set_user_title(None));
+        assert_eq!(title.current(), "zsh");
+    }
+}
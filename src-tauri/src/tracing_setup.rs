@@ -2,24 +2,66 @@ use std::path::PathBuf;
 use std::sync::OnceLock;
 
 use tracing_subscriber::fmt::format::FmtSpan;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::log_buffer::LogBufferLayer;
+
+/// Directives added to RUST_LOG when logging to the log file
+const FILE_LOG_DIRECTIVES: &[&str] = &["info", "async_openai=trace", "adk=info"];
 
 static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
 
-fn resolve_log_path() -> Option<PathBuf> {
+/// The filter installed at startup, swappable while the app runs
+struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    defaults: &'static [&'static str],
+}
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Where the log file goes: logs/ai-debug.log next to the executable
+pub fn log_file_path() -> Option<PathBuf> {
     std::env::current_exe().ok().and_then(|exe| {
         exe.parent()
             .map(|dir| dir.join("logs").join("ai-debug.log"))
     })
 }
 
+/// RUST_LOG with `defaults` and then the comma-separated `directives` added
+fn build_filter(defaults: &[&str], directives: &str) -> Result<EnvFilter, String> {
+    let mut filter = EnvFilter::from_default_env();
+    for directive in defaults
+        .iter()
+        .copied()
+        .chain(directives.split(','))
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+    {
+        let parsed = directive
+            .parse()
+            .map_err(|e| format!("Invalid log directive \"{}\": {}", directive, e))?;
+        filter = filter.add_directive(parsed);
+    }
+    Ok(filter)
+}
+
+fn reloadable_filter(defaults: &'static [&'static str]) -> reload::Layer<EnvFilter, Registry> {
+    let filter = build_filter(defaults, "").unwrap_or_else(|_| EnvFilter::from_default_env());
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(LogFilter { handle, defaults });
+    layer
+}
+
 pub fn init_logging() {
-    let log_path = match resolve_log_path() {
+    let log_path = match log_file_path() {
         Some(path) => path,
         None => {
-            tracing_subscriber::fmt()
-                .with_env_filter(EnvFilter::from_default_env())
-                .with_span_events(FmtSpan::CLOSE)
+            tracing_subscriber::registry()
+                .with(reloadable_filter(&[]))
+                .with(LogBufferLayer)
+                .with(fmt::layer().with_span_events(FmtSpan::CLOSE))
                 .try_init()
                 .ok();
             return;
@@ -42,15 +84,62 @@ pub fn init_logging() {
 
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
     let _ = LOG_GUARD.set(guard);
-    let filter = EnvFilter::from_default_env()
-        .add_directive("info".parse().unwrap_or_default())
-        .add_directive("async_openai=trace".parse().unwrap_or_default())
-        .add_directive("adk=info".parse().unwrap_or_default());
-
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_span_events(FmtSpan::CLOSE)
-        .with_writer(non_blocking)
+
+    tracing_subscriber::registry()
+        .with(reloadable_filter(FILE_LOG_DIRECTIVES))
+        .with(LogBufferLayer)
+        .with(
+            fmt::layer()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(non_blocking),
+        )
         .try_init()
         .ok();
 }
+
+fn log_filter() -> Result<&'static LogFilter, String> {
+    LOG_FILTER
+        .get()
+        .ok_or_else(|| "Logging has not been initialized".to_string())
+}
+
+/// The log filter in effect, in RUST_LOG syntax
+#[tauri::command]
+pub async fn get_log_level() -> Result<String, String> {
+    log_filter()?
+        .handle
+        .with_current(|filter| filter.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Log with the startup filter plus `directive`, e.g. "debug" or
+/// "voidesk_lib::lsp=trace,voidesk_lib::git=debug", until the app exits. An empty
+/// directive goes back to the startup filter.
+#[tauri::command]
+pub async fn set_log_level(directive: String) -> Result<String, String> {
+    let log_filter = log_filter()?;
+    let filter = build_filter(log_filter.defaults, &directive)?;
+    log_filter
+        .handle
+        .reload(filter)
+        .map_err(|e| e.to_string())?;
+    tracing::info!("Log filter set to \"{}\"", directive);
+    get_log_level().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_are_added_after_the_defaults() {
+        let filter = build_filter(FILE_LOG_DIRECTIVES, "voidesk_lib::lsp=debug, git2=warn")
+            .unwrap()
+            .to_string();
+        assert!(filter.contains("voidesk_lib::lsp=debug"), "{}", filter);
+        assert!(filter.contains("async_openai=trace"), "{}", filter);
+
+        let error = build_filter(FILE_LOG_DIRECTIVES, "lsp=loud").unwrap_err();
+        assert!(error.contains("lsp=loud"), "{}", error);
+    }
+}
@@ -6,6 +6,7 @@ import { useSessionRestore } from "./hooks/useSessionRestore";
 import { useWorkspaceIndexPersistence } from "./hooks/useWorkspaceIndexPersistence";
 import { useLspDiagnostics } from "./hooks/useLspDiagnostics";
 import { useLspExtensions } from "./hooks/useLspExtensions";
+import { useEditorContextSync } from "./hooks/useEditorContextSync";
 
 function App() {
     // Register global keyboard shortcuts
@@ -25,6 +26,9 @@ function App() {
 
     useLspExtensions();
 
+    // Share the open files and selection with the agent
+    useEditorContextSync();
+
     return <MainLayout />;
 }
 
@@ -60,6 +60,20 @@ import { normalizePath, pathsEqual } from "@/utils/path";
 import { useLspExtensionsStore } from "@/stores/lspExtensionsStore";
 import { FileCode, Loader2 } from "lucide-react";
 
+// Lines on each side of the cursor reported to the agent as editor context
+const EXCERPT_RADIUS = 30;
+
+/**
+ * The lines around the main cursor, for the agent's editor context
+ */
+function cursorExcerpt(state: EditorState): { startLine: number; text: string } {
+    const line = state.doc.lineAt(state.selection.main.head).number;
+    const startLine = Math.max(1, line - EXCERPT_RADIUS);
+    const endLine = Math.min(state.doc.lines, line + EXCERPT_RADIUS);
+    const text = state.sliceDoc(state.doc.line(startLine).from, state.doc.line(endLine).to);
+    return { startLine, text };
+}
+
 /**
  * Get the appropriate CodeMirror language extension based on file extension
  */
@@ -183,9 +197,11 @@ export function CodeEditor() {
             updateFileContent: state.updateFileContent,
         }))
     );
-    const { setCursor, pendingNavigation, navigateTo } = useEditorStore(
+    const { setCursor, setSelection, setExcerpt, pendingNavigation, navigateTo } = useEditorStore(
         useShallow((state) => ({
             setCursor: state.setCursor,
+            setSelection: state.setSelection,
+            setExcerpt: state.setExcerpt,
             pendingNavigation: state.pendingNavigation,
             navigateTo: state.navigateTo,
         }))
@@ -526,6 +542,22 @@ export function CodeEditor() {
             const pos = update.state.selection.main.head;
             const line = update.state.doc.lineAt(pos);
             setCursor(line.number, pos - line.from + 1);
+
+            if (update.docChanged || update.selectionSet) {
+                const selection = update.state.selection.main;
+                if (selection.empty) {
+                    setSelection(null, null);
+                } else {
+                    const fromLine = update.state.doc.lineAt(selection.from);
+                    const toLine = update.state.doc.lineAt(selection.to);
+                    setSelection(
+                        { line: fromLine.number, column: selection.from - fromLine.from + 1 },
+                        { line: toLine.number, column: selection.to - toLine.from + 1 },
+                        update.state.sliceDoc(selection.from, selection.to)
+                    );
+                }
+                setExcerpt(cursorExcerpt(update.state));
+            }
         });
 
         const state = EditorState.create({
@@ -654,6 +686,8 @@ export function CodeEditor() {
         });
 
         viewRef.current = view;
+        setSelection(null, null);
+        setExcerpt(cursorExcerpt(state));
 
         return () => {
             view.destroy();
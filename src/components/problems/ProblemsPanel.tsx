@@ -1,8 +1,28 @@
-import { AlertCircle, AlertTriangle, ChevronRight, Info } from "lucide-react";
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useShallow } from "zustand/react/shallow";
+import { AlertCircle, AlertTriangle, ChevronRight, Info, Loader2, Sparkles } from "lucide-react";
 
-import { useDiagnosticsStore, getDiagnosticSeverityBucket } from "@/stores/diagnosticsStore";
+import { useDiagnosticsStore, getDiagnosticSeverityBucket, LspDiagnostic } from "@/stores/diagnosticsStore";
 import { useFileStore } from "@/stores/fileStore";
+import { selectActiveAISettings, useSettingsStore } from "@/stores/settingsStore";
 import { useFileSystem } from "@/hooks/useFileSystem";
+import { errorMessage } from "@/utils/errors";
+import { pathsEqual } from "@/utils/path";
+
+interface DiagnosticExplanation {
+    explanation: string;
+    fix: {
+        description: string;
+        old_text: string;
+        new_text: string;
+    } | null;
+}
+
+type ExplanationState =
+    | { status: "loading" }
+    | { status: "done"; result: DiagnosticExplanation }
+    | { status: "error"; message: string };
 
 function getRelativePath(fullPath: string, rootPath: string | null) {
     if (!rootPath) return fullPath;
@@ -19,7 +39,48 @@ function getRelativePath(fullPath: string, rootPath: string | null) {
 export function ProblemsPanel() {
     const diagnosticsByPath = useDiagnosticsStore((state) => state.diagnosticsByPath);
     const rootPath = useFileStore((state) => state.rootPath);
+    const openFiles = useFileStore((state) => state.openFiles);
+    const updateFileContent = useFileStore((state) => state.updateFileContent);
     const { openFileAtLocation } = useFileSystem();
+    const activeAISettings = useSettingsStore(useShallow(selectActiveAISettings));
+    const [explanations, setExplanations] = useState<Record<string, ExplanationState>>({});
+
+    const explain = async (key: string, diagnostic: LspDiagnostic) => {
+        setExplanations((current) => ({ ...current, [key]: { status: "loading" } }));
+        // The editor's buffer, with unsaved edits, when the file is open
+        const openFile = openFiles.find((file) => pathsEqual(file.path, diagnostic.path));
+        const surroundingCode = openFile
+            ? openFile.content
+                  .split("\n")
+                  .slice(Math.max(0, diagnostic.range.start.line - 15), diagnostic.range.end.line + 16)
+                  .join("\n")
+            : null;
+        try {
+            const result = await invoke<DiagnosticExplanation>("explain_diagnostic", {
+                path: diagnostic.path,
+                diagnostic,
+                surroundingCode,
+                providerType: activeAISettings.providerType,
+                apiKey: activeAISettings.apiKey,
+                baseUrl: activeAISettings.baseUrl,
+                modelId: activeAISettings.selectedModelId || activeAISettings.aiModels[0]?.id || "gpt-4o",
+            });
+            setExplanations((current) => ({ ...current, [key]: { status: "done", result } }));
+        } catch (error) {
+            setExplanations((current) => ({
+                ...current,
+                [key]: { status: "error", message: errorMessage(error) },
+            }));
+        }
+    };
+
+    const applyFix = (path: string, fix: NonNullable<DiagnosticExplanation["fix"]>) => {
+        const openFile = openFiles.find((file) => pathsEqual(file.path, path));
+        if (openFile && openFile.content.includes(fix.old_text)) {
+            // A replacer function, so `$` in the fix is not read as a pattern
+            updateFileContent(openFile.path, openFile.content.replace(fix.old_text, () => fix.new_text));
+        }
+    };
 
     const files = Object.entries(diagnosticsByPath).sort(([left], [right]) => left.localeCompare(right));
     const totalProblems = files.reduce((sum, [, diagnostics]) => sum + diagnostics.length, 0);
@@ -57,41 +118,95 @@ export function ProblemsPanel() {
                                       ? AlertTriangle
                                       : Info;
 
+                            const key = `${path}-${index}-${diagnostic.message}`;
+                            const explanation = explanations[key];
+                            const canApply =
+                                explanation?.status === "done" &&
+                                explanation.result.fix !== null &&
+                                openFiles.some(
+                                    (file) =>
+                                        pathsEqual(file.path, path) &&
+                                        file.content.includes(explanation.result.fix!.old_text)
+                                );
+
                             return (
-                                <button
-                                    key={`${path}-${index}-${diagnostic.message}`}
-                                    className="w-full flex items-start gap-2 px-3 py-2 text-left hover:bg-[var(--color-void-800)] transition-colors"
-                                    onClick={() =>
-                                        openFileAtLocation(
-                                            path,
-                                            name,
-                                            diagnostic.range.start.line + 1,
-                                            diagnostic.range.start.character + 1,
-                                            diagnostic.range.end.line + 1,
-                                            diagnostic.range.end.character + 1
-                                        )
-                                    }
-                                >
-                                    <Icon
-                                        className={`w-3.5 h-3.5 mt-0.5 flex-shrink-0 ${
-                                            severity === "error"
-                                                ? "text-[var(--color-accent-error)]"
-                                                : severity === "warning"
-                                                  ? "text-[var(--color-accent-warning)]"
-                                                  : "text-[var(--color-accent-info)]"
-                                        }`}
-                                    />
-                                    <div className="min-w-0 flex-1">
-                                        <div className="text-xs text-[var(--color-text-primary)] leading-relaxed">
-                                            {diagnostic.message}
+                                <div key={key} className="group relative">
+                                    <button
+                                        className="w-full flex items-start gap-2 px-3 py-2 pr-8 text-left hover:bg-[var(--color-void-800)] transition-colors"
+                                        onClick={() =>
+                                            openFileAtLocation(
+                                                path,
+                                                name,
+                                                diagnostic.range.start.line + 1,
+                                                diagnostic.range.start.character + 1,
+                                                diagnostic.range.end.line + 1,
+                                                diagnostic.range.end.character + 1
+                                            )
+                                        }
+                                    >
+                                        <Icon
+                                            className={`w-3.5 h-3.5 mt-0.5 flex-shrink-0 ${
+                                                severity === "error"
+                                                    ? "text-[var(--color-accent-error)]"
+                                                    : severity === "warning"
+                                                      ? "text-[var(--color-accent-warning)]"
+                                                      : "text-[var(--color-accent-info)]"
+                                            }`}
+                                        />
+                                        <div className="min-w-0 flex-1">
+                                            <div className="text-xs text-[var(--color-text-primary)] leading-relaxed">
+                                                {diagnostic.message}
+                                            </div>
+                                            <div className="mt-1 text-[10px] text-[var(--color-text-muted)] font-mono">
+                                                Ln {diagnostic.range.start.line + 1}, Col {diagnostic.range.start.character + 1}
+                                                {diagnostic.source ? ` • ${diagnostic.source}` : ""}
+                                                {diagnostic.code ? ` • ${diagnostic.code}` : ""}
+                                            </div>
+                                        </div>
+                                    </button>
+                                    <button
+                                        className="absolute right-2 top-2 p-1 rounded opacity-0 group-hover:opacity-100 text-[var(--color-text-muted)] hover:text-[var(--color-accent-primary)] transition-opacity"
+                                        title="Explain with AI"
+                                        disabled={explanation?.status === "loading"}
+                                        onClick={() => void explain(key, diagnostic)}
+                                    >
+                                        {explanation?.status === "loading" ? (
+                                            <Loader2 className="w-3.5 h-3.5 animate-spin" />
+                                        ) : (
+                                            <Sparkles className="w-3.5 h-3.5" />
+                                        )}
+                                    </button>
+                                    {explanation?.status === "error" && (
+                                        <div className="px-8 pb-2 text-[11px] text-[var(--color-accent-error)]">
+                                            {explanation.message}
                                         </div>
-                                        <div className="mt-1 text-[10px] text-[var(--color-text-muted)] font-mono">
-                                            Ln {diagnostic.range.start.line + 1}, Col {diagnostic.range.start.character + 1}
-                                            {diagnostic.source ? ` • ${diagnostic.source}` : ""}
-                                            {diagnostic.code ? ` • ${diagnostic.code}` : ""}
+                                    )}
+                                    {explanation?.status === "done" && (
+                                        <div className="px-8 pb-3 space-y-2 text-xs text-[var(--color-text-secondary)]">
+                                            <div className="whitespace-pre-wrap leading-relaxed">
+                                                {explanation.result.explanation}
+                                            </div>
+                                            {explanation.result.fix && (
+                                                <div className="space-y-1">
+                                                    <div className="text-[var(--color-text-primary)]">
+                                                        {explanation.result.fix.description}
+                                                    </div>
+                                                    <pre className="p-2 rounded bg-black/20 font-mono text-[11px] overflow-x-auto">
+                                                        {explanation.result.fix.new_text}
+                                                    </pre>
+                                                    {canApply && (
+                                                        <button
+                                                            className="px-2 py-1 rounded text-[11px] bg-[var(--color-accent-primary)]/15 text-[var(--color-accent-primary)] hover:bg-[var(--color-accent-primary)]/25"
+                                                            onClick={() => applyFix(path, explanation.result.fix!)}
+                                                        >
+                                                            Apply fix
+                                                        </button>
+                                                    )}
+                                                </div>
+                                            )}
                                         </div>
-                                    </div>
-                                </button>
+                                    )}
+                                </div>
                             );
                         })}
                     </div>
@@ -42,6 +42,7 @@ import {
     ActivityBarAlignment,
 } from "@/stores/settingsStore";
 import { useLspExtensions } from "@/hooks/useLspExtensions";
+import { errorMessage } from "@/utils/errors";
 
 interface SettingsCategoryItem {
     id: SettingsCategory;
@@ -88,6 +89,7 @@ interface PendingSettings {
     inlineCompletionsEnabled?: boolean;
     chatContextWindow?: number;
     persistentWorkspaceIndexEnabled?: boolean;
+    fileWatcherPollingEnabled?: boolean;
     // Editor
     tabSize?: number;
     wordWrap?: boolean;
@@ -145,6 +147,7 @@ export function SettingsPage() {
             inlineCompletionsEnabled: state.inlineCompletionsEnabled,
             chatContextWindow: state.chatContextWindow,
             persistentWorkspaceIndexEnabled: state.persistentWorkspaceIndexEnabled,
+            fileWatcherPollingEnabled: state.fileWatcherPollingEnabled,
             editorFontSize: state.editorFontSize,
             editorFontFamily: state.editorFontFamily,
             uiScale: state.uiScale,
@@ -167,6 +170,7 @@ export function SettingsPage() {
             setInlineCompletionsEnabled: state.setInlineCompletionsEnabled,
             setChatContextWindow: state.setChatContextWindow,
             setPersistentWorkspaceIndexEnabled: state.setPersistentWorkspaceIndexEnabled,
+            setFileWatcherPollingEnabled: state.setFileWatcherPollingEnabled,
             setEditorFontSize: state.setEditorFontSize,
             setEditorFontFamily: state.setEditorFontFamily,
             setUIScale: state.setUIScale,
@@ -225,7 +229,7 @@ export function SettingsPage() {
                 }
             } catch (error) {
                 if (isActive) {
-                    setWorkspaceCacheError(String(error));
+                    setWorkspaceCacheError(errorMessage(error));
                 }
             } finally {
                 if (isActive) {
@@ -269,6 +273,7 @@ export function SettingsPage() {
         if (pending.inlineCompletionsEnabled !== undefined) settings.setInlineCompletionsEnabled(pending.inlineCompletionsEnabled);
         if (pending.chatContextWindow !== undefined) settings.setChatContextWindow(pending.chatContextWindow);
         if (pending.persistentWorkspaceIndexEnabled !== undefined) settings.setPersistentWorkspaceIndexEnabled(pending.persistentWorkspaceIndexEnabled);
+        if (pending.fileWatcherPollingEnabled !== undefined) settings.setFileWatcherPollingEnabled(pending.fileWatcherPollingEnabled);
         if (pending.tabSize !== undefined) settings.setTabSize(pending.tabSize);
         if (pending.wordWrap !== undefined) settings.setWordWrap(pending.wordWrap);
         if (pending.lineNumbers !== undefined) settings.setLineNumbers(pending.lineNumbers);
@@ -308,7 +313,7 @@ export function SettingsPage() {
             const summary = await invoke<WorkspaceIndexCacheSummary>("get_workspace_index_cache_summary");
             setWorkspaceCacheSummary(summary);
         } catch (error) {
-            setWorkspaceCacheError(String(error));
+            setWorkspaceCacheError(errorMessage(error));
         } finally {
             setIsClearingWorkspaceCache(false);
         }
@@ -465,6 +470,7 @@ export function SettingsPage() {
                                 currentLineNumbers={getValue("lineNumbers", settings.lineNumbers)}
                                 currentMinimap={getValue("minimap", settings.minimap)}
                                 currentPersistentWorkspaceIndexEnabled={getValue("persistentWorkspaceIndexEnabled", settings.persistentWorkspaceIndexEnabled)}
+                                currentFileWatcherPollingEnabled={getValue("fileWatcherPollingEnabled", settings.fileWatcherPollingEnabled)}
                                 workspaceRootPath={rootPath}
                                 workspaceCacheSummary={workspaceCacheSummary}
                                 workspaceCacheError={workspaceCacheError}
@@ -475,6 +481,7 @@ export function SettingsPage() {
                                 onLineNumbersChange={(v) => updatePending("lineNumbers", v)}
                                 onMinimapChange={(v) => updatePending("minimap", v)}
                                 onPersistentWorkspaceIndexEnabledChange={(v) => updatePending("persistentWorkspaceIndexEnabled", v)}
+                                onFileWatcherPollingEnabledChange={(v) => updatePending("fileWatcherPollingEnabled", v)}
                                 onClearWorkspaceCache={handleClearWorkspaceCache}
                             />
                         )}
@@ -852,7 +859,7 @@ function AISettings({
                 authenticated: false,
                 login_in_progress: false,
             });
-            setTestResult({ success: false, message: String(error) });
+            setTestResult({ success: false, message: errorMessage(error) });
         }
     };
 
@@ -968,7 +975,7 @@ function AISettings({
             await invoke("codex_start_login");
         } catch (error) {
             setIsAuthBusy(false);
-            setTestResult({ success: false, message: String(error) });
+            setTestResult({ success: false, message: errorMessage(error) });
         }
     };
 
@@ -978,7 +985,7 @@ function AISettings({
             await invoke("codex_logout");
             await refreshCodexAuthStatus();
         } catch (error) {
-            setTestResult({ success: false, message: String(error) });
+            setTestResult({ success: false, message: errorMessage(error) });
         } finally {
             setIsAuthBusy(false);
         }
@@ -996,7 +1003,7 @@ function AISettings({
             });
             setTestResult({ success: true, message: result });
         } catch (error) {
-            setTestResult({ success: false, message: String(error) });
+            setTestResult({ success: false, message: errorMessage(error) });
         } finally {
             setIsTesting(false);
         }
@@ -1640,6 +1647,7 @@ interface EditorSettingsProps {
     currentLineNumbers: boolean;
     currentMinimap: boolean;
     currentPersistentWorkspaceIndexEnabled: boolean;
+    currentFileWatcherPollingEnabled: boolean;
     workspaceRootPath: string | null;
     workspaceCacheSummary: WorkspaceIndexCacheSummary | null;
     workspaceCacheError: string | null;
@@ -1650,6 +1658,7 @@ interface EditorSettingsProps {
     onLineNumbersChange: (enabled: boolean) => void;
     onMinimapChange: (enabled: boolean) => void;
     onPersistentWorkspaceIndexEnabledChange: (enabled: boolean) => void;
+    onFileWatcherPollingEnabledChange: (enabled: boolean) => void;
     onClearWorkspaceCache: () => Promise<void>;
 }
 
@@ -1659,6 +1668,7 @@ function EditorSettings({
     currentLineNumbers,
     currentMinimap,
     currentPersistentWorkspaceIndexEnabled,
+    currentFileWatcherPollingEnabled,
     workspaceRootPath,
     workspaceCacheSummary,
     workspaceCacheError,
@@ -1669,6 +1679,7 @@ function EditorSettings({
     onLineNumbersChange,
     onMinimapChange,
     onPersistentWorkspaceIndexEnabledChange,
+    onFileWatcherPollingEnabledChange,
     onClearWorkspaceCache,
 }: EditorSettingsProps) {
     const cachedRoot = workspaceCacheSummary?.cached_roots.find((root) => root.root_path === workspaceRootPath) || null;
@@ -1715,6 +1726,16 @@ function EditorSettings({
                         onChange={onPersistentWorkspaceIndexEnabledChange}
                     />
                 </SettingRow>
+
+                <SettingRow
+                    label="Poll for File Changes"
+                    description="Scan the project for changes every few seconds. Use this when edits on network drives or WSL mounts don't show up."
+                >
+                    <Toggle
+                        checked={currentFileWatcherPollingEnabled}
+                        onChange={onFileWatcherPollingEnabledChange}
+                    />
+                </SettingRow>
             </SettingSection>
 
             <SettingSection title="Workspace Cache" description="Persisted workspace indexes are stored on disk to speed up reopening projects.">
@@ -7,6 +7,7 @@ import "@xterm/xterm/css/xterm.css";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useTerminalStore } from "@/stores/terminalStore";
+import { useFileStore } from "@/stores/fileStore";
 
 interface TerminalPaneProps {
     paneId: string;
@@ -82,6 +83,7 @@ export function TerminalPane({ paneId, isActive, onFocus }: TerminalPaneProps) {
 
         let unlistenOutput: (() => void) | null = null;
         let unlistenExit: (() => void) | null = null;
+        let unlistenRestart: (() => void) | null = null;
 
         const initializePty = async () => {
             try {
@@ -89,6 +91,7 @@ export function TerminalPane({ paneId, isActive, onFocus }: TerminalPaneProps) {
                 const result = await invoke<{ pid: number }>("create_pty", {
                     cols: dims?.cols || 80,
                     rows: dims?.rows || 24,
+                    options: { cwd: useFileStore.getState().rootPath },
                 });
 
                 pidRef.current = result.pid;
@@ -100,9 +103,22 @@ export function TerminalPane({ paneId, isActive, onFocus }: TerminalPaneProps) {
                     }
                 });
 
-                unlistenExit = await listen<{ pid: number }>("pty-exit", (event) => {
+                unlistenExit = await listen<{ pid: number; exit_code: number | null; success: boolean | null }>(
+                    "pty-exit",
+                    (event) => {
+                        if (event.payload.pid !== pidRef.current) return;
+                        const { exit_code: exitCode, success } = event.payload;
+                        if (exitCode === null || success) {
+                            term.write("\r\n\x1b[33m[Process Completed]\x1b[0m\r\n");
+                        } else {
+                            term.write(`\r\n\x1b[31m[Process exited with code ${exitCode}]\x1b[0m\r\n`);
+                        }
+                    }
+                );
+
+                unlistenRestart = await listen<{ pid: number }>("pty-restarted", (event) => {
                     if (event.payload.pid === pidRef.current) {
-                        term.write("\r\n\x1b[33m[Process Completed]\x1b[0m\r\n");
+                        term.write("\r\n\x1b[33m[Shell restarted]\x1b[0m\r\n");
                     }
                 });
             } catch (error) {
@@ -118,6 +134,7 @@ export function TerminalPane({ paneId, isActive, onFocus }: TerminalPaneProps) {
             if (resizeTimeout) clearTimeout(resizeTimeout);
             unlistenOutput?.();
             unlistenExit?.();
+            unlistenRestart?.();
             if (pidRef.current !== null) {
                 invoke("close_pty", { pid: pidRef.current });
                 useTerminalStore.getState().setPanePid(paneId, null);
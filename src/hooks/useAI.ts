@@ -4,6 +4,7 @@ import { useShallow } from "zustand/react/shallow";
 import { selectActiveAISettings, useSettingsStore } from "@/stores/settingsStore";
 import { useFileStore } from "@/stores/fileStore";
 import { useFileSystem } from "@/hooks/useFileSystem";
+import { errorMessage } from "@/utils/errors";
 import {
     useChatStore,
     ToolOperation,
@@ -13,6 +14,7 @@ import {
 } from "@/stores/chatStore";
 
 export interface AIResponseChunk {
+    request_id: string;
     content?: string;
     tool_call?: string;
     tool_operation?: ToolOperation;
@@ -460,6 +462,9 @@ export function useAI() {
                 const onEvent = new Channel<AIResponseChunk>();
 
                 onEvent.onmessage = (chunk: AIResponseChunk) => {
+                    // Chunks of an earlier run can still arrive after a retry starts
+                    if (chunk.request_id !== runId) return;
+
                     // Check abort flag
                     if (abortRef.current) {
                         setIsStreaming(false);
@@ -635,7 +640,7 @@ export function useAI() {
                 addDebugLog({
                     timestamp: Date.now(),
                     type: "error",
-                    message: `Backend invocation failed before streaming for request ${runId}: ${String(error)}`,
+                    message: `Backend invocation failed before streaming for request ${runId}: ${errorMessage(error)}`,
                 });
                 setIsStreaming(false);
                 abortRef.current = false;
@@ -0,0 +1,76 @@
+import { useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useEditorStore } from "@/stores/editorStore";
+import { useFileStore } from "@/stores/fileStore";
+
+// Cursor moves come in bursts; report once the editor settles
+const SYNC_DELAY_MS = 300;
+
+function reportEditorContext() {
+    const { openFiles, currentFilePath } = useFileStore.getState();
+    const { cursorLine, cursorColumn, selectionStart, selectionEnd, selectedText, excerpt } =
+        useEditorStore.getState();
+    const activeFile = openFiles.find((file) => file.path === currentFilePath) ?? null;
+
+    invoke("update_editor_context", {
+        context: {
+            open_files: openFiles.map((file) => ({ path: file.path, dirty: file.isDirty })),
+            active_file: activeFile?.path ?? null,
+            language: activeFile?.language ?? null,
+            cursor: activeFile ? { line: cursorLine, column: cursorColumn } : null,
+            selection:
+                activeFile && selectionStart && selectionEnd
+                    ? { start: selectionStart, end: selectionEnd, text: selectedText }
+                    : null,
+            excerpt:
+                activeFile && excerpt
+                    ? { start_line: excerpt.startLine, text: excerpt.text }
+                    : null,
+        },
+    }).catch((error) => {
+        console.error("Failed to report editor context:", error);
+    });
+}
+
+/**
+ * Keep the backend informed of the open files, cursor and selection, so the agent's
+ * get_editor_context tool sees what the user is looking at
+ */
+export function useEditorContextSync() {
+    useEffect(() => {
+        let timer: ReturnType<typeof setTimeout> | null = null;
+        const schedule = () => {
+            if (timer) clearTimeout(timer);
+            timer = setTimeout(() => {
+                timer = null;
+                reportEditorContext();
+            }, SYNC_DELAY_MS);
+        };
+
+        const unsubscribeFiles = useFileStore.subscribe((state, previous) => {
+            if (
+                state.openFiles !== previous.openFiles ||
+                state.currentFilePath !== previous.currentFilePath
+            ) {
+                schedule();
+            }
+        });
+        const unsubscribeEditor = useEditorStore.subscribe((state, previous) => {
+            if (
+                state.cursorLine !== previous.cursorLine ||
+                state.cursorColumn !== previous.cursorColumn ||
+                state.selectedText !== previous.selectedText ||
+                state.excerpt !== previous.excerpt
+            ) {
+                schedule();
+            }
+        });
+        schedule();
+
+        return () => {
+            if (timer) clearTimeout(timer);
+            unsubscribeFiles();
+            unsubscribeEditor();
+        };
+    }, []);
+}
@@ -1,8 +1,9 @@
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import { useShallow } from "zustand/react/shallow";
-import { FileNode, useFileStore } from "@/stores/fileStore";
+import { FileNode, FileVersion, useFileStore } from "@/stores/fileStore";
 import { useEditorStore } from "@/stores/editorStore";
+import { errorMessage, isVoidDeskError, VoidDeskError } from "@/utils/errors";
 import { normalizePath, pathsEqual } from "@/utils/path";
 
 interface FileEntry {
@@ -15,6 +16,8 @@ interface TauriFileNode {
     path: string;
     name: string;
     is_dir: boolean;
+    is_symlink?: boolean;
+    is_submodule?: boolean;
     children?: TauriFileNode[];
 }
 
@@ -24,6 +27,8 @@ function convertToFileNode(node: TauriFileNode): FileNode {
         path: normalizePath(node.path),
         name: node.name,
         isDir: node.is_dir,
+        isSymlink: node.is_symlink,
+        isSubmodule: node.is_submodule,
         isExpanded: false,
         children: node.children?.map(convertToFileNode),
     };
@@ -51,6 +56,7 @@ export function useFileSystem() {
 
             if (selected && typeof selected === "string") {
                 const normalizedSelected = normalizePath(selected);
+                await invoke("open_workspace", { path: normalizedSelected });
                 setRootPath(normalizedSelected);
                 await refreshFileTree(normalizedSelected);
                 useFileStore.getState().addRecentProject(normalizedSelected);
@@ -66,6 +72,7 @@ export function useFileSystem() {
     const openFolderAt = async (path: string): Promise<boolean> => {
         try {
             const normalizedPath = normalizePath(path);
+            await invoke("open_workspace", { path: normalizedPath });
             setRootPath(normalizedPath);
             await refreshFileTree(normalizedPath);
             useFileStore.getState().addRecentProject(normalizedPath);
@@ -105,7 +112,8 @@ export function useFileSystem() {
     // List directory contents
     const listDirectory = async (path: string): Promise<FileEntry[]> => {
         try {
-            return await invoke<FileEntry[]>("list_directory", { path });
+            const root = useFileStore.getState().rootPath ?? undefined;
+            return await invoke<FileEntry[]>("list_directory", { path, root });
         } catch (error) {
             console.error("Failed to list directory:", error);
             return [];
@@ -122,13 +130,36 @@ export function useFileSystem() {
         }
     };
 
-    // Write file contents
-    const writeFile = async (path: string, content: string): Promise<boolean> => {
+    // Hash and mtime of a file on disk, to detect changes made outside the editor
+    const getFileVersion = async (path: string): Promise<FileVersion | undefined> => {
         try {
-            await invoke("write_file", { path: normalizePath(path), content });
+            return await invoke<FileVersion>("get_file_version", { path: normalizePath(path) });
+        } catch (error) {
+            console.error("Failed to get file version:", error);
+            return undefined;
+        }
+    };
+
+    // Write file contents; with `expected` the write is refused if the file changed on disk since
+    const writeFile = async (path: string, content: string, expected?: FileVersion): Promise<boolean> => {
+        try {
+            await invoke("write_file", {
+                path: normalizePath(path),
+                content,
+                expectedHash: expected?.hash,
+                expectedMtime: expected?.mtime,
+            });
             await refreshCurrentFileTree();
             return true;
         } catch (error) {
+            if (
+                expected &&
+                isVoidDeskError(error) &&
+                error.code === "conflict" &&
+                window.confirm(`${path} was changed on disk since it was opened. Overwrite it?`)
+            ) {
+                return writeFile(path, content);
+            }
             console.error("Failed to write file:", error);
             return false;
         }
@@ -164,6 +195,8 @@ export function useFileSystem() {
             .getState()
             .openFiles.find((file) => pathsEqual(file.path, normalizedPath));
         const resolvedPath = existingFile?.path || normalizedPath;
+        // Taken before reading, so a change in between shows up as a conflict on save
+        const version = await getFileVersion(resolvedPath);
         const content = await readFile(resolvedPath);
         if (content !== null) {
             openFile({
@@ -172,7 +205,11 @@ export function useFileSystem() {
                 content,
                 isDirty: false,
                 language: getLanguageFromFilename(name),
+                version,
             });
+            invoke("record_file_open", { path: resolvedPath }).catch((err) =>
+                console.error("Failed to record recent item:", err)
+            );
         }
     };
 
@@ -203,17 +240,22 @@ export function useFileSystem() {
         const resolvedPath =
             useFileStore.getState().openFiles.find((file) => pathsEqual(file.path, path))?.path ||
             normalizePath(path);
+        const version = await getFileVersion(resolvedPath);
         const content = await readFile(resolvedPath);
         if (content !== null) {
             replaceFileContent(resolvedPath, content, false);
+            useFileStore.getState().setFileVersion(resolvedPath, version);
         }
     };
 
     // Save current file
     const saveFile = async (path: string, content: string): Promise<boolean> => {
-        const success = await writeFile(path, content);
+        const { openFiles, markFileSaved, setFileVersion } = useFileStore.getState();
+        const expected = openFiles.find((file) => pathsEqual(file.path, path))?.version;
+        const success = await writeFile(path, content, expected);
         if (success) {
-            useFileStore.getState().markFileSaved(path);
+            markFileSaved(path);
+            setFileVersion(path, await getFileVersion(path));
         }
         return success;
     };
@@ -261,7 +303,7 @@ export function useFileSystem() {
     interface BatchOperationResult {
         path: string;
         success: boolean;
-        error?: string;
+        error?: VoidDeskError | string;
     }
 
     const batchDeleteFiles = async (paths: string[]): Promise<BatchOperationResult[]> => {
@@ -276,7 +318,7 @@ export function useFileSystem() {
             return paths.map(path => ({
                 path,
                 success: false,
-                error: String(error)
+                error: errorMessage(error)
             }));
         }
     };
@@ -301,7 +343,7 @@ export function useFileSystem() {
             return operations.map(op => ({
                 path: op.from,
                 success: false,
-                error: String(error)
+                error: errorMessage(error)
             }));
         }
     };
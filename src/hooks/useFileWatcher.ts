@@ -3,16 +3,42 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { useShallow } from "zustand/react/shallow";
 import { useFileStore } from "@/stores/fileStore";
+import { useSettingsStore } from "@/stores/settingsStore";
+
+interface PathChange {
+    path: string;
+    kind: "create" | "modify" | "remove";
+    // Stat at report time; absent for removed paths
+    is_dir?: boolean;
+    size?: number;
+    mtime?: number;
+}
 
 interface FileChangeEvent {
-    event_type: "create" | "modify" | "remove";
+    watcher_id: string;
+    event_type: "change" | "rename";
     paths: string[];
+    changes: PathChange[];
+    from?: string;
+    to?: string;
+}
+
+interface WatcherErrorEvent {
+    watcher_id: string;
+    message: string;
+}
+
+interface WatcherResyncEvent {
+    watcher_id: string;
+    reason: string;
 }
 
 interface TauriFileNode {
     path: string;
     name: string;
     is_dir: boolean;
+    is_symlink?: boolean;
+    is_submodule?: boolean;
     children?: TauriFileNode[];
 }
 
@@ -22,6 +48,8 @@ function convertToFileNode(node: TauriFileNode): import("@/stores/fileStore").Fi
         path: node.path,
         name: node.name,
         isDir: node.is_dir,
+        isSymlink: node.is_symlink,
+        isSubmodule: node.is_submodule,
         isExpanded: false,
         children: node.children?.map(convertToFileNode),
     };
@@ -39,7 +67,9 @@ export function useFileWatcher() {
             setFileTree: state.setFileTree,
         }))
     );
-    const unlistenRef = useRef<UnlistenFn | null>(null);
+    const unlistenRef = useRef<UnlistenFn[]>([]);
+    const pollingEnabled = useSettingsStore((state) => state.fileWatcherPollingEnabled);
+    const watcherIdRef = useRef<string | null>(null);
     const isWatchingRef = useRef(false);
 
     // Refresh file tree (duplicated here to avoid circular deps)
@@ -68,32 +98,66 @@ export function useFileWatcher() {
         }
 
         try {
-            // Set up event listener first
-            unlistenRef.current = await listen<FileChangeEvent>("file-change", (event) => {
-                console.log("File change detected:", event.payload);
+            // Set up event listeners first
+            const refreshCurrentRoot = () => {
                 const currentRootPath = useFileStore.getState().rootPath;
                 if (currentRootPath) {
                     refreshFileTree(currentRootPath);
                 }
-            });
+            };
+            unlistenRef.current.push(await listen<FileChangeEvent>("file-change", (event) => {
+                if (event.payload.watcher_id !== watcherIdRef.current) {
+                    return;
+                }
+                console.log("File change detected:", event.payload);
+                const { event_type, changes, from, to } = event.payload;
+                if (event_type === "rename" && from && to) {
+                    useFileStore.getState().retargetOpenFiles(from, to);
+                }
+                // Edits to existing files leave the tree as it is
+                const treeChanged = event_type === "rename"
+                    || changes.some((change) => change.kind !== "modify" || change.is_dir);
+                if (treeChanged) {
+                    refreshCurrentRoot();
+                }
+            }));
+            unlistenRef.current.push(await listen<WatcherErrorEvent>("watcher-error", (event) => {
+                if (event.payload.watcher_id === watcherIdRef.current) {
+                    console.error("File watcher error:", event.payload.message);
+                }
+            }));
+            // Events were lost, so individual changes can't be trusted; reload everything
+            unlistenRef.current.push(await listen<WatcherResyncEvent>("watcher-resync-needed", (event) => {
+                if (event.payload.watcher_id !== watcherIdRef.current) {
+                    return;
+                }
+                console.warn("File watcher resync needed:", event.payload.reason);
+                refreshCurrentRoot();
+            }));
 
-            // Start the watcher
-            await invoke("start_file_watcher", { path });
+            // Start the watcher; registering is idempotent and covers a restored rootPath
+            await invoke("register_workspace_root", { path });
+            watcherIdRef.current = await invoke<string>("start_file_watcher", {
+                path,
+                options: { poll: pollingEnabled },
+            });
             isWatchingRef.current = true;
             console.log("File watcher started for:", path);
         } catch (error) {
             console.error("Failed to start file watcher:", error);
         }
-    }, [refreshFileTree]);
+    }, [refreshFileTree, pollingEnabled]);
 
     // Stop watching
     const stopWatching = useCallback(async () => {
         try {
-            if (unlistenRef.current) {
-                unlistenRef.current();
-                unlistenRef.current = null;
+            unlistenRef.current.forEach((unlisten) => unlisten());
+            unlistenRef.current = [];
+            const watcherId = watcherIdRef.current;
+            watcherIdRef.current = null;
+            if (watcherId) {
+                await invoke("stop_file_watcher", { id: watcherId });
             }
-            await invoke("stop_file_watcher");
             isWatchingRef.current = false;
             console.log("File watcher stopped");
         } catch (error) {
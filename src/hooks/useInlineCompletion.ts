@@ -4,6 +4,7 @@ import { useShallow } from "zustand/react/shallow";
 import { selectActiveAISettings, useSettingsStore } from "@/stores/settingsStore";
 
 export interface InlineCompletionResult {
+    request_id: string;
     text: string;
     done: boolean;
     error?: string;
@@ -23,6 +24,8 @@ export function useInlineCompletion() {
     const debounceRef = useRef<ReturnType<typeof setTimeout>>();
     const abortRef = useRef(false);
     const requestIdRef = useRef(0);
+    // Backend request id of the completion still streaming, so it can be cancelled
+    const inFlightRef = useRef<string | null>(null);
     
     // Keep a ref to the current completion for synchronous access in keymaps
     const completionRef = useRef<string | null>(null);
@@ -32,14 +35,23 @@ export function useInlineCompletion() {
         completionRef.current = completion;
     }, [completion]);
 
+    const cancelInFlight = useCallback(() => {
+        const requestId = inFlightRef.current;
+        inFlightRef.current = null;
+        if (requestId) {
+            invoke<boolean>("cancel_ai_stream", { requestId }).catch(() => {});
+        }
+    }, []);
+
     const clearCompletion = useCallback(() => {
+        cancelInFlight();
         setCompletion(null);
         completionRef.current = null;
         abortRef.current = true;
         if (debounceRef.current) {
             clearTimeout(debounceRef.current);
         }
-    }, []);
+    }, [cancelInFlight]);
 
     const requestCompletion = useCallback(
         (content: string, cursorPos: number, filePath: string, language: string) => {
@@ -51,11 +63,14 @@ export function useInlineCompletion() {
                 clearTimeout(debounceRef.current);
             }
             abortRef.current = true;
+            cancelInFlight();
             setCompletion(null);
             completionRef.current = null;
 
             debounceRef.current = setTimeout(async () => {
                 const currentRequestId = ++requestIdRef.current;
+                const backendRequestId = globalThis.crypto?.randomUUID?.() ?? `inline-${Date.now()}-${currentRequestId}`;
+                inFlightRef.current = backendRequestId;
                 abortRef.current = false;
                 setIsLoading(true);
 
@@ -64,7 +79,7 @@ export function useInlineCompletion() {
                     let fullText = "";
 
                     onEvent.onmessage = (chunk) => {
-                        if (abortRef.current || requestIdRef.current !== currentRequestId) {
+                        if (abortRef.current || chunk.request_id !== backendRequestId) {
                             return;
                         }
 
@@ -95,6 +110,7 @@ export function useInlineCompletion() {
                         apiKey: activeAISettings.apiKey,
                         baseUrl: activeAISettings.baseUrl,
                         modelId: activeModelId,
+                        options: { requestId: backendRequestId },
                         onEvent,
                     });
                 } catch (error) {
@@ -102,10 +118,14 @@ export function useInlineCompletion() {
                         console.error("Inline completion failed:", error);
                         setIsLoading(false);
                     }
+                } finally {
+                    if (inFlightRef.current === backendRequestId) {
+                        inFlightRef.current = null;
+                    }
                 }
             }, 500);
         },
-        [activeAISettings, inlineCompletionsEnabled]
+        [activeAISettings, inlineCompletionsEnabled, cancelInFlight]
     );
 
     // These functions use the ref for synchronous access
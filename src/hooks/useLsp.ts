@@ -2,8 +2,7 @@
 // Connects CodeMirror editor to Rust LSP backend
 
 import { invoke } from "@tauri-apps/api/core";
-import { useEffect, useCallback, useRef } from "react";
-import { useFileStore } from "@/stores/fileStore";
+import { useCallback } from "react";
 
 interface CompletionItem {
     label: string;
@@ -12,6 +11,13 @@ interface CompletionItem {
     insertText?: string;
 }
 
+interface LspCompletionItem {
+    label: string;
+    kind: string | null;
+    detail: string | null;
+    insert_text: string | null;
+}
+
 interface HoverInfo {
     contents: string;
     range?: {
@@ -63,20 +69,6 @@ function getLanguageFromPath(path: string): string {
 }
 
 export function useLsp() {
-    const rootPath = useFileStore((state) => state.rootPath);
-    const initializedRootRef = useRef<string | null>(null);
-
-    // Initialize LSP when workspace opens
-    useEffect(() => {
-        if (rootPath && initializedRootRef.current !== rootPath) {
-            initializedRootRef.current = rootPath;
-            console.log("[LSP] Setting root path:", rootPath);
-            invoke("lsp_set_root", { rootPath })
-                .then(() => console.log("[LSP] Root path set successfully"))
-                .catch((err) => console.error("[LSP] Failed to set root:", err));
-        }
-    }, [rootPath]);
-
     // Notify LSP when a file is opened
     const didOpen = useCallback(async (path: string, content: string) => {
         const language = getLanguageFromPath(path);
@@ -114,22 +106,19 @@ export function useLsp() {
             if (language === "plaintext") return [];
 
             try {
-                const result = await invoke<any>("lsp_completion", {
+                const items = await invoke<LspCompletionItem[]>("lsp_completion", {
                     path,
                     line,
                     character,
                     language,
                 });
-                console.log("[LSP] completion result:", result);
+                console.log("[LSP] completion result:", items);
 
-                // Handle different completion response formats
-                if (!result) return [];
-                const items = result.items || result || [];
-                return items.map((item: any) => ({
+                return items.map((item) => ({
                     label: item.label,
-                    kind: item.kind?.toString(),
-                    detail: item.detail,
-                    insertText: item.insertText || item.label,
+                    kind: item.kind ?? undefined,
+                    detail: item.detail ?? undefined,
+                    insertText: item.insert_text || item.label,
                 }));
             } catch (err) {
                 console.error("[LSP] completion failed:", err);
@@ -147,7 +136,7 @@ export function useLsp() {
             if (language === "plaintext") return null;
 
             try {
-                const result = await invoke<any>("lsp_hover", {
+                const result = await invoke<HoverInfo | null>("lsp_hover", {
                     path,
                     line,
                     character,
@@ -156,20 +145,7 @@ export function useLsp() {
                 console.log("[LSP] hover result:", result);
 
                 if (!result || !result.contents) return null;
-
-                // Parse markdown content
-                let contents = "";
-                if (typeof result.contents === "string") {
-                    contents = result.contents;
-                } else if (result.contents.value) {
-                    contents = result.contents.value;
-                } else if (Array.isArray(result.contents)) {
-                    contents = result.contents
-                        .map((c: any) => (typeof c === "string" ? c : c.value))
-                        .join("\n");
-                }
-
-                return { contents, range: result.range };
+                return { contents: result.contents, range: result.range ?? undefined };
             } catch (err) {
                 console.error("[LSP] hover failed:", err);
                 return null;
@@ -41,6 +41,7 @@ export function useSessionRestore() {
             if (!rootPath) return;
 
             try {
+                await invoke("open_workspace", { path: rootPath });
                 const tree = await invoke<any[]>("get_project_tree", {
                     path: rootPath,
                     maxDepth: 5,
@@ -50,6 +51,8 @@ export function useSessionRestore() {
                     path: node.path,
                     name: node.name,
                     isDir: node.is_dir,
+                    isSymlink: node.is_symlink,
+                    isSubmodule: node.is_submodule,
                     isExpanded: false,
                     children: node.children?.map(convertToFileNode),
                 });
@@ -6,6 +6,9 @@ interface EditorState {
     cursorColumn: number;
     selectionStart: { line: number; column: number } | null;
     selectionEnd: { line: number; column: number } | null;
+    selectedText: string;
+    // Lines of the buffer around the cursor, reported to the agent as editor context
+    excerpt: { startLine: number; text: string } | null;
 
     // View state
     scrollTop: number;
@@ -23,8 +26,10 @@ interface EditorState {
     setCursor: (line: number, column: number) => void;
     setSelection: (
         start: { line: number; column: number } | null,
-        end: { line: number; column: number } | null
+        end: { line: number; column: number } | null,
+        text?: string
     ) => void;
+    setExcerpt: (excerpt: { startLine: number; text: string } | null) => void;
     setScrollTop: (top: number) => void;
     navigateTo: (
         target: {
@@ -42,12 +47,17 @@ export const useEditorStore = create<EditorState>((set) => ({
     cursorColumn: 1,
     selectionStart: null,
     selectionEnd: null,
+    selectedText: "",
+    excerpt: null,
     scrollTop: 0,
     pendingNavigation: null,
 
     setCursor: (line, column) => set({ cursorLine: line, cursorColumn: column }),
 
-    setSelection: (start, end) => set({ selectionStart: start, selectionEnd: end }),
+    setSelection: (start, end, text = "") =>
+        set({ selectionStart: start, selectionEnd: end, selectedText: text }),
+
+    setExcerpt: (excerpt) => set({ excerpt }),
 
     setScrollTop: (top) => set({ scrollTop: top }),
     navigateTo: (target) => set({ pendingNavigation: target }),
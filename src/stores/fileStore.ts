@@ -6,16 +6,25 @@ export interface FileNode {
     path: string;
     name: string;
     isDir: boolean;
+    isSymlink?: boolean;
+    isSubmodule?: boolean;
     children?: FileNode[];
     isExpanded?: boolean;
 }
 
+/** The file on disk as the editor last read or wrote it, from get_file_version */
+export interface FileVersion {
+    hash: string;
+    mtime: number;
+}
+
 export interface OpenFile {
     path: string;
     name: string;
     content: string;
     isDirty: boolean;
     language: string;
+    version?: FileVersion;
 }
 
 // Helper to flatten tree for range selection
@@ -66,6 +75,8 @@ interface FileState {
     updateFileContent: (path: string, content: string) => void;
     replaceFileContent: (path: string, content: string, isDirty?: boolean) => void;
     markFileSaved: (path: string) => void;
+    setFileVersion: (path: string, version: FileVersion | undefined) => void;
+    retargetOpenFiles: (from: string, to: string) => void;
 
     // Multi-select actions
     setSelectedPaths: (paths: string[]) => void;
@@ -210,6 +221,38 @@ export const useFileStore = create<FileState>()(
                 });
             },
 
+            setFileVersion: (path, version) => {
+                set({
+                    openFiles: get().openFiles.map((f) =>
+                        pathsEqual(f.path, path) ? { ...f, version } : f
+                    ),
+                });
+            },
+
+            // Follow a rename on disk: tabs for the path, or for files inside a renamed folder
+            retargetOpenFiles: (from, to) => {
+                const source = normalizePath(from);
+                const target = normalizePath(to);
+                const retarget = (path: string) => {
+                    const normalized = normalizePath(path);
+                    if (normalized === source) return target;
+                    if (normalized.startsWith(`${source}\\`)) {
+                        return target + normalized.slice(source.length);
+                    }
+                    return path;
+                };
+                const { openFiles, currentFilePath } = get();
+                set({
+                    openFiles: openFiles.map((f) => {
+                        const path = retarget(f.path);
+                        if (path === f.path) return f;
+                        const name = path.split(/[\\/]/).filter(Boolean).pop() || f.name;
+                        return { ...f, path, name };
+                    }),
+                    currentFilePath: currentFilePath ? retarget(currentFilePath) : currentFilePath,
+                });
+            },
+
             // Multi-select actions
             setSelectedPaths: (paths) => set({
                 selectedPaths: paths,
@@ -1,6 +1,7 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
 import { useFileStore } from "@/stores/fileStore";
+import { errorMessage } from "@/utils/errors";
 
 interface SearchMatch {
     id: string;
@@ -133,7 +134,7 @@ export const useSearchStore = create<SearchState>()((set, get) => ({
 
             set({ results, isSearching: false, expandedFiles, selectedMatches });
         } catch (error) {
-            set({ error: String(error), isSearching: false });
+            set({ error: errorMessage(error), isSearching: false });
         }
     },
 
@@ -205,7 +206,7 @@ export const useSearchStore = create<SearchState>()((set, get) => ({
             await invoke<ReplaceResponse>("replace_in_files", { selections });
             await get().runSearch();
         } catch (error) {
-            set({ error: String(error), isSearching: false });
+            set({ error: errorMessage(error), isSearching: false });
         }
     },
 
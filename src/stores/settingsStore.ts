@@ -112,6 +112,7 @@ interface SettingsState {
     rawStreamLoggingEnabled: boolean;
     chatContextWindow: number;
     persistentWorkspaceIndexEnabled: boolean;
+    fileWatcherPollingEnabled: boolean;
 
     // Appearance Settings
     editorFontSize: number;
@@ -147,6 +148,7 @@ interface SettingsState {
     setRawStreamLoggingEnabled: (enabled: boolean) => void;
     setChatContextWindow: (tokens: number) => void;
     setPersistentWorkspaceIndexEnabled: (enabled: boolean) => void;
+    setFileWatcherPollingEnabled: (enabled: boolean) => void;
 
     // Actions - Appearance
     setEditorFontSize: (size: number) => void;
@@ -285,6 +287,7 @@ const getDefaultState = () => ({
     rawStreamLoggingEnabled: false,
     chatContextWindow: 32000,
     persistentWorkspaceIndexEnabled: true,
+    fileWatcherPollingEnabled: false,
     editorFontSize: 14,
     editorFontFamily: "JetBrains Mono",
     uiScale: 100,
@@ -428,6 +431,7 @@ export const useSettingsStore = create<SettingsState>()(
             setRawStreamLoggingEnabled: (enabled) => set({ rawStreamLoggingEnabled: enabled }),
             setChatContextWindow: (tokens) => set({ chatContextWindow: Math.max(1024, Math.min(256000, Math.round(tokens || 1024))) }),
             setPersistentWorkspaceIndexEnabled: (enabled) => set({ persistentWorkspaceIndexEnabled: enabled }),
+            setFileWatcherPollingEnabled: (enabled) => set({ fileWatcherPollingEnabled: enabled }),
 
             // Actions - Appearance
             setEditorFontSize: (size) => set({ editorFontSize: Math.max(10, Math.min(32, size)) }),
@@ -0,0 +1,37 @@
+export type VoidDeskErrorCode =
+    | "not_found"
+    | "permission_denied"
+    | "already_exists"
+    | "invalid_input"
+    | "outside_workspace"
+    | "conflict"
+    | "unknown_host_key"
+    | "too_large"
+    | "timeout"
+    | "cancelled"
+    | "unauthorized"
+    | "rate_limited"
+    | "provider"
+    | "io"
+    | "internal";
+
+/** What a failed backend command rejects with */
+export interface VoidDeskError {
+    code: VoidDeskErrorCode;
+    message: string;
+    details?: Record<string, unknown>;
+}
+
+export function isVoidDeskError(error: unknown): error is VoidDeskError {
+    return typeof error === "object"
+        && error !== null
+        && typeof (error as VoidDeskError).code === "string"
+        && typeof (error as VoidDeskError).message === "string";
+}
+
+/** A readable message for anything a command or promise rejected with */
+export function errorMessage(error: unknown): string {
+    if (isVoidDeskError(error)) return error.message;
+    if (error instanceof Error) return error.message;
+    return String(error);
+}